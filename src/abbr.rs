@@ -0,0 +1,382 @@
+//! Abbreviations.
+//!
+//! Occurrences of defined abbreviations within text are wrapped in `<abbr>` elements with the
+//! expansion as their title, which is e.g. shown on hover and read by screen readers. The
+//! elements are emitted as raw HTML, so other renderers only output the text.
+//!
+//! Abbreviations may be defined in the document in two ways:
+//!
+//! - by a span with an `abbr` attribute, e.g. `[HTML]{abbr="HyperText Markup Language"}`, which
+//!   is itself rendered as the abbreviation,
+//! - by a description list within a div with the class `abbreviations`, which is removed from
+//!   the output.
+//!
+//! Abbreviations may also be provided with [`Abbreviations::with_definition`]. Only whole words
+//! are matched, and occurrences are not wrapped within code, verbatim, math, raw content, link
+//! definitions or image descriptions, as the latter are written as plain alt text.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! use jotdown::abbr::Abbreviations;
+//!
+//! let src = concat!(
+//!     "The [W3C]{abbr=\"World Wide Web Consortium\"} maintains CSS, not CSSOM.\n",
+//!     "\n",
+//!     "::: abbreviations\n",
+//!     ": CSS\n",
+//!     "\n",
+//!     "  Cascading Style Sheets\n",
+//!     ":::\n",
+//! );
+//! let events = Abbreviations::new(jotdown::Parser::new(src));
+//! assert_eq!(
+//!     jotdown::html::render_to_string(events),
+//!     concat!(
+//!         "<p>The <abbr title=\"World Wide Web Consortium\">W3C</abbr> maintains ",
+//!         "<abbr title=\"Cascading Style Sheets\">CSS</abbr>, not CSSOM.</p>\n",
+//!     ),
+//! );
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+
+use crate::outline::push_text;
+use crate::Attributes;
+use crate::Container;
+use crate::CowStr;
+use crate::Event;
+
+/// An iterator adapter that marks up abbreviations.
+///
+/// The events are buffered, as abbreviations may be defined after they are used.
+pub struct Abbreviations<'s> {
+    events: std::vec::IntoIter<Event<'s>>,
+    /// Abbreviations with their expansions, longest first.
+    definitions: Vec<(String, String)>,
+    /// Events to emit before the next event.
+    pending: VecDeque<Event<'s>>,
+    /// Whether each open span defines an abbreviation.
+    spans: Vec<bool>,
+    /// Nesting depth of containers whose content is not marked up.
+    skip: usize,
+}
+
+impl<'s> Abbreviations<'s> {
+    /// Collect all events and the abbreviations defined within them.
+    pub fn new<I: IntoIterator<Item = Event<'s>>>(events: I) -> Self {
+        let events: Vec<Event<'s>> = events.into_iter().collect();
+        let mut abbreviations = Self {
+            definitions: Vec::new(),
+            events: Vec::new().into_iter(),
+            pending: VecDeque::new(),
+            spans: Vec::new(),
+            skip: 0,
+        };
+        let mut list = 0;
+        // text of the current span, term or details
+        let mut text: Option<String> = None;
+        let mut term = String::new();
+        let mut title: Option<String> = None;
+        for e in &events {
+            match e {
+                Event::Start(c @ Container::Div { .. }, attrs) if list > 0 || is_list(c, attrs) => {
+                    list += 1;
+                }
+                Event::End(Container::Div { .. }) if list > 0 => list -= 1,
+                Event::Start(Container::Span, attrs) => {
+                    title = attrs.get_value("abbr").map(|v| v.to_string());
+                    text = title.as_ref().map(|_| String::new());
+                }
+                Event::End(Container::Span) => {
+                    if let (Some(abbr), Some(title)) = (text.take(), title.take()) {
+                        abbreviations = abbreviations.with_definition(&abbr, &title);
+                    }
+                }
+                Event::Start(Container::DescriptionTerm | Container::DescriptionDetails, _)
+                    if list > 0 =>
+                {
+                    text = Some(String::new());
+                }
+                Event::End(Container::DescriptionTerm) if list > 0 => {
+                    term = text.take().unwrap_or_default();
+                }
+                Event::End(Container::DescriptionDetails) if list > 0 => {
+                    let details = text.take().unwrap_or_default();
+                    abbreviations = abbreviations.with_definition(&term, &details);
+                }
+                Event::End(Container::Paragraph) => {
+                    if let Some(t) = &mut text {
+                        t.push(' ');
+                    }
+                }
+                e => {
+                    if let Some(t) = &mut text {
+                        push_text(t, e);
+                    }
+                }
+            }
+        }
+        abbreviations.events = events.into_iter();
+        abbreviations
+    }
+
+    /// Define an abbreviation, in addition to those defined within the document.
+    ///
+    /// The definition replaces any definition of the same abbreviation within the document.
+    #[must_use]
+    pub fn with_definition(mut self, abbr: &str, title: &str) -> Self {
+        let (abbr, title) = (abbr.trim(), title.trim());
+        if abbr.is_empty() {
+            return self;
+        }
+        self.definitions.retain(|(a, _)| a != abbr);
+        self.definitions.push((abbr.to_string(), title.to_string()));
+        self.definitions
+            .sort_by_key(|(a, _)| std::cmp::Reverse(a.len()));
+        self
+    }
+
+    /// Queue text with all abbreviations marked up.
+    fn mark_up(&mut self, s: &str) {
+        let mut last = 0;
+        let mut pos = 0;
+        while pos < s.len() {
+            let boundary = !s[..pos]
+                .chars()
+                .next_back()
+                .map_or(false, char::is_alphanumeric);
+            let found = if boundary {
+                self.definitions.iter().find(|(abbr, _)| {
+                    s[pos..].starts_with(abbr.as_str())
+                        && !s[pos + abbr.len()..]
+                            .chars()
+                            .next()
+                            .map_or(false, char::is_alphanumeric)
+                })
+            } else {
+                None
+            };
+            if let Some((abbr, title)) = found {
+                let (len, open) = (abbr.len(), open(title));
+                if last < pos {
+                    self.pending
+                        .push_back(Event::Str(s[last..pos].to_string().into()));
+                }
+                self.pending.extend(open);
+                self.pending.push_back(Event::Str(abbr.clone().into()));
+                self.pending.extend(close());
+                pos += len;
+                last = pos;
+            } else {
+                pos += s[pos..].chars().next().map_or(1, char::len_utf8);
+            }
+        }
+        if last < s.len() {
+            self.pending
+                .push_back(Event::Str(s[last..].to_string().into()));
+        }
+    }
+}
+
+impl<'s> Iterator for Abbreviations<'s> {
+    type Item = Event<'s>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.pending.pop_front() {
+            return Some(e);
+        }
+        match self.events.next()? {
+            Event::Start(c @ Container::Div { .. }, attrs) if is_list(&c, &attrs) => {
+                let mut depth = 0;
+                for e in self.events.by_ref() {
+                    match e {
+                        Event::Start(..) => depth += 1,
+                        Event::End(..) if depth == 0 => break,
+                        Event::End(..) => depth -= 1,
+                        _ => {}
+                    }
+                }
+                self.next()
+            }
+            Event::Start(Container::Span, attrs) => {
+                let title = attrs.get_value("abbr").map(|v| v.to_string());
+                self.spans.push(title.is_some());
+                if let Some(title) = title {
+                    self.skip += 1;
+                    self.pending.extend(open(&title));
+                    self.next()
+                } else {
+                    Some(Event::Start(Container::Span, attrs))
+                }
+            }
+            Event::End(Container::Span) => {
+                if self.spans.pop().unwrap_or(false) {
+                    self.skip -= 1;
+                    self.pending.extend(close());
+                    self.next()
+                } else {
+                    Some(Event::End(Container::Span))
+                }
+            }
+            Event::Start(c, attrs) => {
+                if is_skipped(&c) {
+                    self.skip += 1;
+                }
+                Some(Event::Start(c, attrs))
+            }
+            Event::End(c) => {
+                if is_skipped(&c) {
+                    self.skip -= 1;
+                }
+                Some(Event::End(c))
+            }
+            Event::Str(s) if self.skip == 0 && !self.definitions.is_empty() => {
+                // abbreviations may span multiple text events
+                let mut s = s.to_string();
+                while let Some(Event::Str(t)) = self.events.as_slice().first() {
+                    s.push_str(t);
+                    self.events.next();
+                }
+                self.mark_up(&s);
+                self.next()
+            }
+            e => Some(e),
+        }
+    }
+}
+
+/// Events of the start of an `<abbr>` element with the specified title.
+fn open<'s>(title: &str) -> [Event<'s>; 3] {
+    let mut html = String::from("<abbr title=\"");
+    push_escaped(&mut html, title);
+    html.push_str("\">");
+    raw(html)
+}
+
+/// Events of the end of an `<abbr>` element.
+fn close<'s>() -> [Event<'s>; 3] {
+    raw("</abbr>")
+}
+
+/// Append text to HTML, escaped for use within text or a quoted attribute value.
+pub(crate) fn push_escaped(html: &mut String, s: &str) {
+    for c in s.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            c => html.push(c),
+        }
+    }
+}
+
+/// Events of an inline raw HTML element.
+pub(crate) fn raw<'s, S: Into<CowStr<'s>>>(html: S) -> [Event<'s>; 3] {
+    let raw = Container::RawInline { format: "html" };
+    [
+        Event::Start(raw.clone(), Attributes::new()),
+        Event::Str(html.into()),
+        Event::End(raw),
+    ]
+}
+
+/// Returns `true` if the div is a list of abbreviations.
+fn is_list(div: &Container, attrs: &Attributes) -> bool {
+    matches!(
+        div,
+        Container::Div {
+            class: "abbreviations"
+        }
+    ) || attrs.get_value("class").map_or(false, |c| {
+        c.to_string().split(' ').any(|c| c == "abbreviations")
+    })
+}
+
+/// Containers whose content is not marked up.
+fn is_skipped(c: &Container) -> bool {
+    matches!(
+        c,
+        Container::CodeBlock { .. }
+            | Container::CustomBlock { .. }
+            | Container::RawBlock { .. }
+            | Container::RawInline { .. }
+            | Container::Verbatim
+            | Container::Math { .. }
+            | Container::LinkDefinition { .. }
+            | Container::Image(..)
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::Abbreviations;
+    use crate::Container;
+    use crate::Event;
+
+    /// Text of the events, with the raw HTML inlined.
+    fn text(src: &str, definitions: &[(&str, &str)]) -> String {
+        let mut abbreviations = Abbreviations::new(crate::Parser::new(src));
+        for (abbr, title) in definitions {
+            abbreviations = abbreviations.with_definition(abbr, title);
+        }
+        abbreviations
+            .filter_map(|e| match e {
+                Event::Str(s) => Some(s.to_string()),
+                Event::Start(Container::Verbatim, _) | Event::End(Container::Verbatim) => {
+                    Some("`".to_string())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn words() {
+        assert_eq!(
+            text(
+                "JS, JSON and `JS` in JSX é JS",
+                &[("JS", "Java\"Script"), ("JSON", "")]
+            ),
+            concat!(
+                "<abbr title=\"Java&quot;Script\">JS</abbr>, ",
+                "<abbr title=\"\">JSON</abbr> and `JS` in JSX é ",
+                "<abbr title=\"Java&quot;Script\">JS</abbr>",
+            ),
+        );
+    }
+
+    #[test]
+    fn image() {
+        assert_eq!(
+            text("![JS](js.png) JS", &[("JS", "JavaScript")]),
+            "JS <abbr title=\"JavaScript\">JS</abbr>",
+        );
+    }
+
+    #[test]
+    fn definitions() {
+        let src = concat!(
+            "A [B]{abbr=\"Bee\" .x} [C]{.y} B D.\n",
+            "\n",
+            "{.abbreviations}\n",
+            "::: z\n",
+            ": D\n",
+            "\n",
+            "  Dee\n",
+            "  too\n",
+            ":::\n",
+        );
+        assert_eq!(
+            text(src, &[("A", "Ay")]),
+            concat!(
+                "<abbr title=\"Ay\">A</abbr> <abbr title=\"Bee\">B</abbr> C ",
+                "<abbr title=\"Bee\">B</abbr> <abbr title=\"Dee too\">D</abbr>.",
+            ),
+        );
+    }
+}
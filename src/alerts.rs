@@ -0,0 +1,172 @@
+//! GitHub alerts.
+//!
+//! GitHub-flavored Markdown marks up notes, warnings etc. as block quotes whose first line is
+//! the kind of alert, e.g. `> [!NOTE]`. The [`Alerts`] adapter converts such block quotes to
+//! divs with the kind as class, e.g. `note`, like the `::: note` divs that would be written in
+//! Djot. The kinds are `NOTE`, `TIP`, `IMPORTANT`, `WARNING` and `CAUTION`, in any case, and the
+//! marker must be alone on its line.
+//!
+//! The divs may e.g. be rendered as admonitions, see
+//! [`html::Renderer::with_admonitions`](crate::html::Renderer::with_admonitions).
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! use jotdown::alerts::Alerts;
+//!
+//! let src = concat!(
+//!     "> [!WARNING]\n",
+//!     "> Do not feed the gremlins.\n",
+//!     "\n",
+//!     "> [!NOTE] not an alert\n",
+//! );
+//! let renderer = jotdown::html::Renderer::minified().with_admonitions();
+//! let mut html = String::new();
+//! jotdown::Render::push(&renderer, Alerts::new(jotdown::Parser::new(src)), &mut html).unwrap();
+//! assert_eq!(
+//!     html,
+//!     concat!(
+//!         "<div class=\"admonition warning\">",
+//!         "<p class=\"admonition-title\">Warning</p>",
+//!         "<p>Do not feed the gremlins.</p>",
+//!         "</div>",
+//!         "<blockquote><p>[!NOTE] not an alert</p></blockquote>",
+//!     ),
+//! );
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+
+use crate::Container;
+use crate::Event;
+
+/// An iterator adapter that converts GitHub alerts to divs.
+pub struct Alerts<'s, I> {
+    events: I,
+    /// Events that have been read ahead.
+    lookahead: VecDeque<Event<'s>>,
+    /// Class of each open block quote, if it is an alert.
+    open: Vec<Option<&'static str>>,
+}
+
+impl<'s, I> Alerts<'s, I>
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    /// Create an adapter that converts alerts.
+    pub fn new(events: I) -> Self {
+        Self {
+            events,
+            lookahead: VecDeque::new(),
+            open: Vec::new(),
+        }
+    }
+
+    fn pull(&mut self) -> Option<Event<'s>> {
+        self.lookahead.pop_front().or_else(|| self.events.next())
+    }
+
+    /// Consume the marker at the start of a block quote, returns the class of the alert.
+    fn alert(&mut self) -> Option<&'static str> {
+        let start = self.pull();
+        let marker = self.pull();
+        let class = match (&start, &marker) {
+            (Some(Event::Start(Container::Paragraph, _)), Some(Event::Str(s))) => class(s),
+            _ => None,
+        };
+        let next = if class.is_some() { self.pull() } else { None };
+        match next {
+            Some(Event::Softbreak) => {
+                self.lookahead.extend(start);
+                class
+            }
+            // the paragraph only contains the marker
+            Some(Event::End(Container::Paragraph)) => class,
+            next => {
+                for e in [next, marker, start].into_iter().flatten() {
+                    self.lookahead.push_front(e);
+                }
+                None
+            }
+        }
+    }
+}
+
+impl<'s, I> Iterator for Alerts<'s, I>
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    type Item = Event<'s>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(match self.pull()? {
+            Event::Start(Container::Blockquote, attrs) => {
+                let class = self.alert();
+                self.open.push(class);
+                match class {
+                    Some(class) => Event::Start(Container::Div { class }, attrs),
+                    None => Event::Start(Container::Blockquote, attrs),
+                }
+            }
+            Event::End(Container::Blockquote) => match self.open.pop().flatten() {
+                Some(class) => Event::End(Container::Div { class }),
+                None => Event::End(Container::Blockquote),
+            },
+            e => e,
+        })
+    }
+}
+
+/// Class of an alert, given the text of its marker.
+fn class(marker: &str) -> Option<&'static str> {
+    let kind = marker.trim().strip_prefix("[!")?.strip_suffix(']')?;
+    ["note", "tip", "important", "warning", "caution"]
+        .iter()
+        .find(|k| k.eq_ignore_ascii_case(kind))
+        .copied()
+}
+
+#[cfg(test)]
+mod test {
+    use super::Alerts;
+    use crate::Container;
+    use crate::Event;
+
+    fn containers(src: &str) -> Vec<String> {
+        Alerts::new(crate::Parser::new(src))
+            .filter_map(|e| match e {
+                Event::Start(Container::Div { class }, _) => Some(format!("div.{}", class)),
+                Event::End(Container::Div { .. }) => Some("/div".to_string()),
+                Event::Start(Container::Blockquote, _) => Some("bq".to_string()),
+                Event::End(Container::Blockquote) => Some("/bq".to_string()),
+                Event::Start(Container::Paragraph, _) => Some("p".to_string()),
+                Event::Str(s) => Some(s.to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn nested() {
+        assert_eq!(
+            containers("> [!Tip]\n>\n> > [!caution]\n> > a\n>\n> [!NOTE]\n"),
+            &[
+                "div.tip",
+                "div.caution",
+                "p",
+                "a",
+                "/div",
+                "p",
+                "[!NOTE]",
+                "/div"
+            ],
+        );
+        assert_eq!(
+            containers("> > [!x]\n\n> [!NOTE]\n\n>\n"),
+            &["bq", "bq", "p", "[!x]", "/bq", "/bq", "div.note", "/div", "bq", "/bq"],
+        );
+    }
+}
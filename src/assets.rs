@@ -0,0 +1,195 @@
+//! Rewriting of references to local files.
+//!
+//! Documents of e.g. a static site refer to images and other files by their paths relative to
+//! the document, while the published pages may need different URLs, e.g. because the files are
+//! copied to another directory or given names with content hashes. The [`Assets`] adapter hands
+//! each local destination of a link or image to a callback, which may e.g. copy the file and
+//! return its public URL.
+//!
+//! A destination is local if it is a path, i.e. it has no scheme such as `https:` and does not
+//! start with `//`. Links within the document, e.g. `#intro`, and email links are not local.
+//! The callback is given the path without any query or fragment, which are kept as is.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! use jotdown::assets::*;
+//!
+//! let src = "![cat](img/cat.png) [manual](doc/manual.pdf#page=2) [site](https://a.org/b.png)";
+//! let events = Assets::new(jotdown::Parser::new(src), |kind, path| match kind {
+//!     Kind::Image => Some(format!("/static/{}", path.replace('/', "-"))),
+//!     Kind::Link => None,
+//! });
+//! assert_eq!(
+//!     jotdown::html::render_to_string(events),
+//!     concat!(
+//!         "<p><img alt=\"cat\" src=\"/static/img-cat.png\"> ",
+//!         "<a href=\"doc/manual.pdf#page=2\">manual</a> ",
+//!         "<a href=\"https://a.org/b.png\">site</a></p>\n",
+//!     ),
+//! );
+//! # }
+//! ```
+
+use crate::Container;
+use crate::CowStr;
+use crate::Event;
+use crate::LinkType;
+use crate::SpanLinkType;
+
+/// A kind of reference to a local file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// The source of an image.
+    Image,
+    /// The destination of a link.
+    Link,
+}
+
+/// An iterator adapter that rewrites the destinations of links and images to local files.
+///
+/// The callback is given the kind of reference and the path of the file, and returns the new
+/// destination, or `None` to keep the current one.
+pub struct Assets<'s, I, F> {
+    events: I,
+    rewrite: F,
+    /// New destinations of the open links and images, if they were rewritten.
+    open: Vec<Option<CowStr<'s>>>,
+}
+
+impl<'s, I, F> Assets<'s, I, F>
+where
+    I: Iterator<Item = Event<'s>>,
+    F: FnMut(Kind, &str) -> Option<String>,
+{
+    /// Create an adapter that rewrites local destinations with the provided callback.
+    pub fn new(events: I, rewrite: F) -> Self {
+        Self {
+            events,
+            rewrite,
+            open: Vec::new(),
+        }
+    }
+
+    /// Rewrite a destination, if it is local.
+    fn rewrite(&mut self, kind: Kind, dst: &str) -> Option<CowStr<'s>> {
+        if !is_local(dst) {
+            return None;
+        }
+        let (path, suffix) =
+            dst.split_at(dst.find(|c| matches!(c, '?' | '#')).unwrap_or(dst.len()));
+        (self.rewrite)(kind, path).map(|new| format!("{}{}", new, suffix).into())
+    }
+}
+
+impl<'s, I, F> Iterator for Assets<'s, I, F>
+where
+    I: Iterator<Item = Event<'s>>,
+    F: FnMut(Kind, &str) -> Option<String>,
+{
+    type Item = Event<'s>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let e = self.events.next()?;
+        Some(match e {
+            Event::Start(Container::Link(dst, ty), attrs) => {
+                let new = match ty {
+                    LinkType::Span(SpanLinkType::Inline | SpanLinkType::Reference) => {
+                        self.rewrite(Kind::Link, &dst)
+                    }
+                    _ => None,
+                };
+                self.open.push(new.clone());
+                Event::Start(Container::Link(new.unwrap_or(dst), ty), attrs)
+            }
+            Event::Start(Container::Image(src, ty), attrs) => {
+                let new = match ty {
+                    SpanLinkType::Inline | SpanLinkType::Reference => {
+                        self.rewrite(Kind::Image, &src)
+                    }
+                    SpanLinkType::Unresolved => None,
+                };
+                self.open.push(new.clone());
+                Event::Start(Container::Image(new.unwrap_or(src), ty), attrs)
+            }
+            Event::End(Container::Link(dst, ty)) => {
+                let new = self.open.pop().flatten();
+                Event::End(Container::Link(new.unwrap_or(dst), ty))
+            }
+            Event::End(Container::Image(src, ty)) => {
+                let new = self.open.pop().flatten();
+                Event::End(Container::Image(new.unwrap_or(src), ty))
+            }
+            e => e,
+        })
+    }
+}
+
+/// Returns `true` if the destination is a path to a file.
+fn is_local(dst: &str) -> bool {
+    !crate::has_scheme(dst)
+        && !dst.starts_with("//")
+        && !dst.is_empty()
+        && !dst.starts_with(['#', '?'])
+}
+
+#[cfg(test)]
+mod test {
+    use super::Assets;
+    use super::Kind;
+    use crate::Container;
+    use crate::Event;
+
+    #[test]
+    fn local() {
+        assert!(super::is_local("a.png"));
+        assert!(super::is_local("/a/b.png"));
+        assert!(super::is_local("../a.png"));
+        assert!(!super::is_local("https://a.org"));
+        assert!(!super::is_local("data:image/png;base64,AAAA"));
+        assert!(!super::is_local("//a.org/b.png"));
+        assert!(!super::is_local("#intro"));
+        assert!(!super::is_local(""));
+    }
+
+    #[test]
+    fn nested() {
+        let src = "[![a](a.png)](b.html?x=1) <https://c.org> [d][]\n\n[d]: d.txt\n";
+        let mut paths = Vec::new();
+        let events: Vec<_> = Assets::new(crate::Parser::new(src), |kind, path| {
+            paths.push((kind, path.to_string()));
+            Some(path.to_uppercase())
+        })
+        .filter_map(|e| match e {
+            Event::Start(Container::Link(dst, _), _)
+            | Event::End(Container::Link(dst, _))
+            | Event::Start(Container::Image(dst, _), _)
+            | Event::End(Container::Image(dst, _)) => Some(dst.to_string()),
+            _ => None,
+        })
+        .collect();
+        assert_eq!(
+            events,
+            &[
+                "B.HTML?x=1",
+                "A.PNG",
+                "A.PNG",
+                "B.HTML?x=1",
+                "https://c.org",
+                "https://c.org",
+                "D.TXT",
+                "D.TXT"
+            ],
+        );
+        assert_eq!(
+            paths,
+            &[
+                (Kind::Link, "b.html".to_string()),
+                (Kind::Image, "a.png".to_string()),
+                (Kind::Link, "d.txt".to_string()),
+            ],
+        );
+    }
+}
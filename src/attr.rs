@@ -25,6 +25,12 @@ pub(crate) fn valid(src: &str) -> usize {
 /// Stores an attribute value that supports backslash escapes of ASCII punctuation upon displaying,
 /// without allocating.
 ///
+/// Within a quoted value, e.g. `key="a \"b\""`, a backslash followed by ASCII punctuation is an
+/// escape of the punctuation character, while other backslashes are kept as is. The value is
+/// stored with its escapes, and they are processed by [`AttributeValue::parts`],
+/// [`AttributeValue::unescaped`] or when the value is displayed. Renderers are responsible for
+/// escaping the unescaped value for their output format.
+///
 /// Each value is paired together with an [`AttributeKind`] in order to form an element.
 #[derive(Clone, Debug, Eq, PartialEq, Default)]
 pub struct AttributeValue<'s> {
@@ -44,6 +50,38 @@ impl<'s> AttributeValue<'s> {
         AttributeValueParts { ahead: &self.raw }
     }
 
+    /// Processes the attribute value escapes and returns the value as it should be displayed.
+    ///
+    /// The value is only copied if it contains escapes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// let attrs = Attributes::try_from(r#"{a="x" b="say \"y\""}"#).unwrap();
+    /// # use std::borrow::Cow;
+    /// assert!(matches!(attrs.get_value("a").unwrap().unescaped(), Cow::Borrowed("x")));
+    /// assert_eq!(attrs.get_value("b").unwrap().unescaped(), r#"say "y""#);
+    /// ```
+    #[must_use]
+    pub fn unescaped(&self) -> CowStr<'_> {
+        let mut parts = AttributeValueParts { ahead: &self.raw };
+        match (parts.next(), parts.clone().next()) {
+            (None, _) => CowStr::Borrowed(""),
+            (Some(first), None) => CowStr::Borrowed(first),
+            (Some(first), Some(_)) => {
+                let mut value = first.to_string();
+                parts.for_each(|part| value.push_str(part));
+                CowStr::Owned(value)
+            }
+        }
+    }
+
+    /// The value with its escapes, as written in the source.
+    pub(crate) fn raw(&self) -> &CowStr<'s> {
+        &self.raw
+    }
+
     // lifetime is 's to avoid allocation if empty value is concatenated with single value
     fn extend(&mut self, s: &'s str) {
         if s.is_empty() {
@@ -100,6 +138,7 @@ impl fmt::Display for AttributeValue<'_> {
 }
 
 /// An iterator over the parts of an [`AttributeValue`] that should be displayed.
+#[derive(Clone)]
 pub struct AttributeValueParts<'s> {
     ahead: &'s str,
 }
@@ -368,6 +407,48 @@ impl<'s> Attributes<'s> {
         }
     }
 
+    /// Returns the merged attributes, with a single element per unique key in the order that
+    /// the keys first appear, i.e. like they appear in the rendered output.
+    ///
+    /// Classes are concatenated into the first class element, other keys keep their last set
+    /// value, and comments are removed. See also [`Attributes::unique_pairs`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// let attrs = Attributes::try_from("{.a #b %c% d=e .f #g}").unwrap();
+    /// assert_eq!(
+    ///     attrs.merged().as_slice(),
+    ///     &[
+    ///         (AttributeKind::Class, "a f".into()),
+    ///         (AttributeKind::Id, "g".into()),
+    ///         (AttributeKind::Pair { key: "d" }, "e".into()),
+    ///     ]
+    /// );
+    /// ```
+    #[must_use]
+    pub fn merged(&self) -> Self {
+        let mut merged: Vec<AttributeElem<'s>> = Vec::new();
+        for (kind, value) in &self.0 {
+            let key = if let Some(key) = kind.key() {
+                key
+            } else {
+                continue;
+            };
+            if let Some((_, prev)) = merged.iter_mut().find(|(k, _)| k.key() == Some(key)) {
+                if key != "class" || prev.raw.is_empty() {
+                    *prev = value.clone();
+                } else if !value.raw.is_empty() {
+                    *prev = format!("{} {}", prev.raw, value.raw).into();
+                }
+            } else {
+                merged.push((*kind, value.clone()));
+            }
+        }
+        Self(merged)
+    }
+
     /// Returns an iterator that only emits a single key-value pair per unique key, i.e. like they
     /// appear in the rendered output.
     ///
@@ -997,6 +1078,34 @@ mod test {
         );
     }
 
+    #[test]
+    fn merge() {
+        let mut attrs = Attributes::try_from("{#a class=b key=c}").unwrap();
+        attrs.append(&mut Attributes::try_from("{.d key=e id=f}").unwrap());
+        let merged = attrs.merged();
+        assert_eq!(
+            merged.as_slice(),
+            &[
+                (Id, "f".into()),
+                (Pair { key: "class" }, "b d".into()),
+                (Pair { key: "key" }, "e".into()),
+            ],
+        );
+        assert_eq!(
+            merged.unique_pairs().collect::<Vec<_>>(),
+            attrs.unique_pairs().collect::<Vec<_>>(),
+        );
+    }
+
+    #[test]
+    fn unescaped() {
+        let attrs = Attributes::try_from(r#"{a="b\\c\"" d="e\f" g=""}"#).unwrap();
+        let value = |k| attrs.get_value(k).unwrap().unescaped().into_owned();
+        assert_eq!(value("a"), r#"b\c""#);
+        assert_eq!(value("d"), r"e\f");
+        assert_eq!(value("g"), "");
+    }
+
     #[test]
     fn escape_backslash() {
         test_attr!(
@@ -0,0 +1,169 @@
+//! Parallel processing of many documents.
+//!
+//! Static site generators and similar tools typically parse and render a large number of
+//! independent documents with the same options. A [`Batch`] distributes the documents over a
+//! number of threads and returns the results in the same order as the sources.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! use jotdown::batch::Batch;
+//!
+//! let sources = vec!["*a*".to_string(), "_b_".to_string(), "c".to_string()];
+//! let pages = Batch::new().render(sources, jotdown::html::Renderer::minified());
+//! assert_eq!(
+//!     pages,
+//!     &["<p><strong>a</strong></p>", "<p><em>b</em></p>", "<p>c</p>"],
+//! );
+//! # }
+//! ```
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::sync::Arc;
+
+use crate::Render;
+
+/// Options for processing documents in parallel.
+#[derive(Debug, Clone, Copy)]
+pub struct Batch {
+    threads: usize,
+}
+
+impl Default for Batch {
+    /// Use 4 threads.
+    fn default() -> Self {
+        Self { threads: 4 }
+    }
+}
+
+impl Batch {
+    /// Create a batch with the default number of threads.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use at most `n` threads, or a single thread if `n` is zero.
+    ///
+    /// No more threads than there are documents are spawned.
+    #[must_use]
+    pub fn with_threads(mut self, n: usize) -> Self {
+        self.threads = n.max(1);
+        self
+    }
+
+    /// Apply a function to the source of each document.
+    ///
+    /// The function is shared by all threads. The results are in the same order as the sources.
+    ///
+    /// # Panics
+    ///
+    /// If the function panics, the panic is propagated once all threads have finished.
+    ///
+    /// # Examples
+    ///
+    /// Count the number of headings in each document:
+    ///
+    /// ```
+    /// # use jotdown::batch::Batch;
+    /// use jotdown::{Container, Event};
+    ///
+    /// let sources = vec!["# a\n\n# b\n", "c\n", "# d\n"];
+    /// let headings = Batch::new().with_threads(2).map(sources, |src| {
+    ///     jotdown::Parser::new(src)
+    ///         .filter(|e| matches!(e, Event::Start(Container::Heading { .. }, _)))
+    ///         .count()
+    /// });
+    /// assert_eq!(headings, &[2, 0, 1]);
+    /// ```
+    pub fn map<S, T, F>(&self, sources: Vec<S>, f: F) -> Vec<T>
+    where
+        S: AsRef<str> + Send + Sync + 'static,
+        T: Send + 'static,
+        F: Fn(&str) -> T + Send + Sync + 'static,
+    {
+        let n = sources.len();
+        let sources = Arc::new(sources);
+        let f = Arc::new(f);
+        // index of the next document to process
+        let next = Arc::new(AtomicUsize::new(0));
+        let (tx, rx) = mpsc::channel();
+
+        let handles: Vec<_> = (0..self.threads.min(n))
+            .map(|_| {
+                let sources = Arc::clone(&sources);
+                let f = Arc::clone(&f);
+                let next = Arc::clone(&next);
+                let tx = tx.clone();
+                std::thread::spawn(move || loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    if let Some(src) = sources.get(i) {
+                        if tx.send((i, f(src.as_ref()))).is_err() {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                })
+            })
+            .collect();
+        drop(tx);
+
+        let mut results: Vec<Option<T>> = (0..n).map(|_| None).collect();
+        for (i, r) in rx {
+            results[i] = Some(r);
+        }
+        for h in handles {
+            if let Err(e) = h.join() {
+                std::panic::resume_unwind(e);
+            }
+        }
+        results.into_iter().map(Option::unwrap).collect()
+    }
+
+    /// Parse and render each document with the provided renderer.
+    ///
+    /// The results are in the same order as the sources.
+    pub fn render<S, R>(&self, sources: Vec<S>, renderer: R) -> Vec<String>
+    where
+        S: AsRef<str> + Send + Sync + 'static,
+        R: Render + Send + Sync + 'static,
+    {
+        self.map(sources, move |src| {
+            let mut s = String::new();
+            renderer.push(crate::Parser::new(src), &mut s).unwrap();
+            s
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Batch;
+
+    #[test]
+    fn order() {
+        let sources: Vec<String> = (0..100).map(|i| i.to_string()).collect();
+        let lens = Batch::new()
+            .with_threads(7)
+            .map(sources.clone(), |s| s.parse::<usize>().unwrap());
+        assert_eq!(lens, (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn empty() {
+        let sources: Vec<&str> = Vec::new();
+        assert!(Batch::new().map(sources, str::len).is_empty());
+        assert_eq!(Batch::new().with_threads(0).map(vec!["ab"], str::len), &[2]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn panic() {
+        Batch::new().map(vec!["a", "b"], |s| assert_eq!(s, "a"));
+    }
+}
@@ -0,0 +1,470 @@
+//! A BBCode renderer that takes an iterator of [`Event`]s and emits BBCode.
+//!
+//! BBCode is the markup of many forums. Only the widely supported tags are used: `b`, `i`, `u`,
+//! `s`, `url`, `email`, `img`, `code`, `quote`, `list`, `hr` and the `table` tags. Elements
+//! without such a tag, e.g. inline code or highlighted text, are written as plain text. Line
+//! breaks are significant in BBCode, so the lines of a paragraph are joined.
+//!
+//! BBCode has no escape mechanism, so text that looks like a tag is written as is. Footnotes are
+//! numbered, e.g. `[1]`, and listed after a horizontal rule at the end of the post.
+//!
+//! # Examples
+//!
+//! ```
+//! let src = concat!(
+//!     "> *Note:* see the [manual](https://djot.net)\n",
+//!     "> for details.\n",
+//!     "\n",
+//!     "1. one\n",
+//!     "2. two\n",
+//!     "\n",
+//!     "```\n",
+//!     "jotdown < a.dj\n",
+//!     "```\n",
+//! );
+//! let events = jotdown::Parser::new(src);
+//! assert_eq!(
+//!     jotdown::bbcode::render_to_string(events),
+//!     concat!(
+//!         "[quote]\n",
+//!         "[b]Note:[/b] see the [url=https://djot.net]manual[/url] for details.\n",
+//!         "[/quote]\n",
+//!         "\n",
+//!         "[list=1]\n",
+//!         "[*]one\n",
+//!         "[*]two\n",
+//!         "[/list]\n",
+//!         "\n",
+//!         "[code]jotdown < a.dj\n",
+//!         "[/code]\n",
+//!     ),
+//! );
+//! ```
+
+use crate::Container;
+use crate::Event;
+use crate::LinkType;
+use crate::ListKind;
+use crate::OrderedListNumbering;
+use crate::Render;
+use crate::SpanLinkType;
+
+/// Render events into a string.
+///
+/// This is a convenience function for using [`Renderer::push`] with fewer imports and without an
+/// intermediate variable.
+///
+/// # Examples
+///
+/// ```
+/// let events = jotdown::Parser::new("hello _world_");
+/// assert_eq!(jotdown::bbcode::render_to_string(events), "hello [i]world[/i]\n");
+/// ```
+pub fn render_to_string<'s, I>(events: I) -> String
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    let mut s = String::new();
+    Renderer::default().push(events, &mut s).unwrap();
+    s
+}
+
+/// [`Render`] implementor that writes BBCode.
+#[derive(Debug, Clone, Default)]
+pub struct Renderer {
+    code_languages: bool,
+}
+
+impl Renderer {
+    /// Write the language of code blocks as the option of the `code` tag, e.g. `[code=rust]`,
+    /// which some forums use for syntax highlighting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::bbcode::*;
+    /// let renderer = Renderer::default().with_code_languages();
+    /// let mut bbcode = String::new();
+    /// renderer
+    ///     .push(Parser::new("``` rust\nfn main() {}\n```\n"), &mut bbcode)
+    ///     .unwrap();
+    /// assert_eq!(bbcode, "[code=rust]fn main() {}\n[/code]\n");
+    /// ```
+    #[must_use]
+    pub fn with_code_languages(mut self) -> Self {
+        self.code_languages = true;
+        self
+    }
+}
+
+impl Render for Renderer {
+    fn push<'s, I, W>(&self, mut events: I, mut out: W) -> std::fmt::Result
+    where
+        I: Iterator<Item = Event<'s>>,
+        W: std::fmt::Write,
+    {
+        let mut w = Writer::new(self);
+        events.try_for_each(|e| w.render_event(&e, &mut out))?;
+        w.render_epilogue(&mut out)
+    }
+}
+
+/// Separator to write before the next block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Separator {
+    /// Nothing, at the start of the output or of a list item.
+    None,
+    /// A line break, at the start of a container.
+    Line,
+    /// A blank line, after a block.
+    Blank,
+}
+
+struct Writer<'s, 'f> {
+    renderer: &'f Renderer,
+    separator: Separator,
+    /// Whether text is written as is, e.g. within code.
+    verbatim: bool,
+    /// Depth of containers whose content is omitted, e.g. images.
+    ignore: usize,
+    /// Labels of footnotes in order of their first reference.
+    references: Vec<&'s str>,
+    /// Events of footnotes, by label.
+    footnotes: Vec<(&'s str, Vec<Event<'s>>)>,
+    /// Footnote that is being collected.
+    footnote: Option<(&'s str, Vec<Event<'s>>)>,
+}
+
+impl<'s, 'f> Writer<'s, 'f> {
+    fn new(renderer: &'f Renderer) -> Self {
+        Self {
+            renderer,
+            separator: Separator::None,
+            verbatim: false,
+            ignore: 0,
+            references: Vec::new(),
+            footnotes: Vec::new(),
+            footnote: None,
+        }
+    }
+
+    fn render_event<W>(&mut self, e: &Event<'s>, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        if let Some((label, events)) = &mut self.footnote {
+            if let Event::End(Container::Footnote { .. }) = e {
+                self.footnotes.push((label, std::mem::take(events)));
+                self.footnote = None;
+            } else {
+                events.push(e.clone());
+            }
+            return Ok(());
+        }
+
+        if self.ignore > 0 {
+            match e {
+                Event::Start(..) => self.ignore += 1,
+                Event::End(..) => self.ignore -= 1,
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        match e {
+            Event::Start(c, _) => self.render_start(c, &mut out)?,
+            Event::End(c) => self.render_end(c, &mut out)?,
+            Event::Str(s) => out.write_str(s)?,
+            Event::FootnoteReference(label) => {
+                let number = self
+                    .references
+                    .iter()
+                    .position(|l| l == label)
+                    .unwrap_or_else(|| {
+                        self.references.push(label);
+                        self.references.len() - 1
+                    })
+                    + 1;
+                write!(out, "[{}]", number)?;
+            }
+            Event::Symbol(sym) => write!(out, ":{}:", sym)?,
+            Event::LeftSingleQuote => out.write_char('‘')?,
+            Event::RightSingleQuote => out.write_char('’')?,
+            Event::LeftDoubleQuote => out.write_char('“')?,
+            Event::RightDoubleQuote => out.write_char('”')?,
+            Event::Ellipsis => out.write_char('…')?,
+            Event::EnDash => out.write_char('–')?,
+            Event::EmDash => out.write_char('—')?,
+            Event::NonBreakingSpace => out.write_char('\u{a0}')?,
+            Event::Softbreak if self.verbatim => out.write_char('\n')?,
+            Event::Softbreak => out.write_char(' ')?,
+            Event::Hardbreak => out.write_char('\n')?,
+            Event::ThematicBreak(_) => {
+                self.block(&mut out)?;
+                out.write_str("[hr]")?;
+            }
+            Event::Escape | Event::Blankline | Event::Attributes(_) => {}
+        }
+        Ok(())
+    }
+
+    fn render_start<W>(&mut self, c: &Container<'s>, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        match c {
+            Container::Blockquote => {
+                self.block(&mut out)?;
+                out.write_str("[quote]")?;
+                self.separator = Separator::Line;
+            }
+            Container::List { kind, .. } => {
+                self.block(&mut out)?;
+                match kind {
+                    ListKind::Ordered { numbering, .. } => {
+                        let option = match numbering {
+                            OrderedListNumbering::Decimal => '1',
+                            OrderedListNumbering::AlphaLower => 'a',
+                            OrderedListNumbering::AlphaUpper => 'A',
+                            OrderedListNumbering::RomanLower => 'i',
+                            OrderedListNumbering::RomanUpper => 'I',
+                        };
+                        write!(out, "[list={}]", option)?;
+                    }
+                    ListKind::Unordered(..) | ListKind::Task(..) => out.write_str("[list]")?,
+                }
+                self.separator = Separator::Line;
+            }
+            Container::ListItem | Container::TaskListItem { .. } => {
+                out.write_str("\n[*]")?;
+                if let Container::TaskListItem { checked } = c {
+                    out.write_str(if *checked { "☑ " } else { "☐ " })?;
+                }
+                self.separator = Separator::None;
+            }
+            Container::DescriptionTerm => {
+                self.block(&mut out)?;
+                out.write_str("[b]")?;
+            }
+            Container::Footnote { label } => self.footnote = Some((label, Vec::new())),
+            Container::Table => {
+                self.block(&mut out)?;
+                out.write_str("[table]")?;
+            }
+            Container::TableRow { .. } => out.write_str("\n[tr]")?,
+            Container::TableCell { head, .. } => {
+                out.write_str(if *head { "[th]" } else { "[td]" })?;
+            }
+            Container::Paragraph | Container::Heading { .. } => {
+                self.block(&mut out)?;
+                if let Container::Heading { .. } = c {
+                    out.write_str("[b]")?;
+                }
+            }
+            Container::Caption | Container::LinkDefinition { .. } => self.ignore = 1,
+            Container::RawBlock { format } | Container::RawInline { format } => {
+                if *format == "bbcode" {
+                    if c.is_block() {
+                        self.block(&mut out)?;
+                    }
+                } else {
+                    self.ignore = 1;
+                }
+            }
+            Container::CodeBlock { language } => {
+                self.block(&mut out)?;
+                if self.renderer.code_languages && !language.is_empty() {
+                    write!(out, "[code={}]", language)?;
+                } else {
+                    out.write_str("[code]")?;
+                }
+                self.verbatim = true;
+            }
+            Container::Link(dst, ty) => match ty {
+                LinkType::Span(SpanLinkType::Unresolved) => {}
+                LinkType::AutoLink => out.write_str("[url]")?,
+                LinkType::Email => out.write_str("[email]")?,
+                LinkType::Span(_) => write!(out, "[url={}]", dst)?,
+            },
+            Container::Image(src, _) => {
+                write!(out, "[img]{}[/img]", src)?;
+                self.ignore = 1;
+            }
+            Container::Math { .. } => self.verbatim = true,
+            Container::Insert => out.write_str("[u]")?,
+            Container::Delete => out.write_str("[s]")?,
+            Container::Strong => out.write_str("[b]")?,
+            Container::Emphasis => out.write_str("[i]")?,
+            Container::DescriptionList
+            | Container::DescriptionDetails
+            | Container::Section { .. }
+            | Container::Div { .. }
+            | Container::CustomBlock { .. }
+            | Container::Span
+            | Container::CustomInline { .. }
+            | Container::Verbatim
+            | Container::Subscript
+            | Container::Superscript
+            | Container::Mark => {}
+        }
+        Ok(())
+    }
+
+    fn render_end<W>(&mut self, c: &Container<'s>, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        match c {
+            Container::Blockquote => out.write_str("\n[/quote]")?,
+            Container::List { .. } => out.write_str("\n[/list]")?,
+            Container::DescriptionTerm | Container::Heading { .. } => out.write_str("[/b]")?,
+            Container::Table => out.write_str("\n[/table]")?,
+            Container::TableRow { .. } => out.write_str("[/tr]")?,
+            Container::TableCell { head, .. } => {
+                out.write_str(if *head { "[/th]" } else { "[/td]" })?;
+            }
+            Container::CodeBlock { .. } => {
+                out.write_str("[/code]")?;
+                self.verbatim = false;
+            }
+            Container::Link(_, ty) => match ty {
+                LinkType::Span(SpanLinkType::Unresolved) => {}
+                LinkType::Email => out.write_str("[/email]")?,
+                LinkType::AutoLink | LinkType::Span(_) => out.write_str("[/url]")?,
+            },
+            Container::Math { .. } => self.verbatim = false,
+            Container::Insert => out.write_str("[/u]")?,
+            Container::Delete => out.write_str("[/s]")?,
+            Container::Strong => out.write_str("[/b]")?,
+            Container::Emphasis => out.write_str("[/i]")?,
+            _ => {}
+        }
+        if c.is_block() {
+            self.separator = Separator::Blank;
+        }
+        Ok(())
+    }
+
+    fn render_epilogue<W>(&mut self, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        if !self.references.is_empty() {
+            self.block(&mut out)?;
+            out.write_str("[hr]")?;
+            let references = std::mem::take(&mut self.references);
+            for (i, label) in references.iter().enumerate() {
+                let events = self
+                    .footnotes
+                    .iter()
+                    .position(|(l, _)| l == label)
+                    .map(|i| self.footnotes.remove(i).1)
+                    .unwrap_or_default();
+                write!(out, "\n[{}] ", i + 1)?;
+                self.separator = Separator::None;
+                for e in &events {
+                    self.render_event(e, &mut out)?;
+                }
+            }
+            self.separator = Separator::Blank;
+        }
+        if self.separator != Separator::None {
+            out.write_char('\n')?;
+        }
+        Ok(())
+    }
+
+    /// Start a block, separating it from the previous one.
+    fn block<W>(&mut self, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        match self.separator {
+            Separator::None => {}
+            Separator::Line => out.write_char('\n')?,
+            Separator::Blank => out.write_str("\n\n")?,
+        }
+        self.separator = Separator::Blank;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::render_to_string;
+
+    #[test]
+    fn blocks() {
+        let src = concat!(
+            "# a\n",
+            "\n",
+            "- [ ] b\n",
+            "\n",
+            "  c\n",
+            "- [x] d\n",
+            "\n",
+            "  a) e\n",
+            "\n",
+            "***\n",
+            "\n",
+            "| f | g |\n",
+            "|---|---|\n",
+            "| h | i |\n",
+            "^ j\n",
+            "\n",
+            ": k\n",
+            "\n",
+            "  l\n",
+        );
+        assert_eq!(
+            render_to_string(crate::Parser::new(src)),
+            concat!(
+                "[b]a[/b]\n",
+                "\n",
+                "[list]\n",
+                "[*]☐ b\n",
+                "\n",
+                "c\n",
+                "[*]☑ d\n",
+                "\n",
+                "[list=a]\n",
+                "[*]e\n",
+                "[/list]\n",
+                "[/list]\n",
+                "\n",
+                "[hr]\n",
+                "\n",
+                "[table]\n",
+                "[tr][th]f[/th][th]g[/th][/tr]\n",
+                "[tr][td]h[/td][td]i[/td][/tr]\n",
+                "[/table]\n",
+                "\n",
+                "[b]k[/b]\n",
+                "\n",
+                "l\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn inline() {
+        let src = concat!(
+            "![a _b_](c.png) <https://d> <e@f> [g]{-h-}[^i] `j`\n",
+            "k\\\n",
+            "l[^i]\n",
+            "\n",
+            "[^i]: m\n",
+        );
+        assert_eq!(
+            render_to_string(crate::Parser::new(src)),
+            concat!(
+                "[img]c.png[/img] [url]https://d[/url] [email]e@f[/email] [g][s]h[/s][1] j k\n",
+                "l[1]\n",
+                "\n",
+                "[hr]\n",
+                "[1] m\n",
+            ),
+        );
+    }
+}
@@ -5,6 +5,7 @@ use crate::OrderedListNumbering::*;
 use crate::OrderedListStyle::*;
 
 use crate::attr;
+use crate::extension::BlockSyntax;
 use crate::lex;
 
 use Atom::*;
@@ -34,8 +35,10 @@ pub enum Node<'s> {
 }
 
 #[must_use]
-pub fn parse(src: &str) -> Vec<Event> {
-    TreeParser::new(src).parse()
+pub fn parse<'s>(src: &'s str, extensions: &[BlockSyntax]) -> Vec<Event<'s>> {
+    let mut p = TreeParser::new(src);
+    p.extensions = extensions.to_vec();
+    p.parse()
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -75,6 +78,10 @@ pub enum Leaf<'s> {
     CodeBlock {
         language: &'s str,
     },
+    Custom {
+        name: &'s str,
+        argument: &'s str,
+    },
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -205,6 +212,8 @@ struct TreeParser<'s> {
     /// Buffer queue for next events. Events are buffered until no modifications due to future
     /// characters are needed.
     events: Vec<Event<'s>>,
+    /// Block syntax extensions, tried before the regular syntax.
+    extensions: Vec<BlockSyntax>,
 }
 
 impl<'s> TreeParser<'s> {
@@ -220,6 +229,7 @@ impl<'s> TreeParser<'s> {
             open_sections: Vec::new(),
             open: Vec::new(),
             events: Vec::new(),
+            extensions: Vec::new(),
         }
     }
 
@@ -282,7 +292,9 @@ impl<'s> TreeParser<'s> {
             kind,
             span: span_start,
             line_count,
-        }) = MeteredBlock::new(lines.iter().map(|sp| &self.src[sp.clone()]))
+        }) = self
+            .custom_block(lines)
+            .or_else(|| MeteredBlock::new(lines.iter().map(|sp| &self.src[sp.clone()])))
         {
             let lines = &mut lines[..line_count];
             let span_start = (span_start.start + lines[0].start)..(span_start.end + lines[0].start);
@@ -291,6 +303,10 @@ impl<'s> TreeParser<'s> {
                 Kind::Fenced {
                     has_closing_fence: true,
                     ..
+                }
+                | Kind::Custom {
+                    has_closing_fence: true,
+                    ..
                 } => end_line,
                 _ => end_line.end..end_line.end,
             };
@@ -307,9 +323,12 @@ impl<'s> TreeParser<'s> {
                 lines[0].start += 1;
             }
 
-            // skip opening and closing fence of code block / div
+            // skip opening and closing fence of code block / div / custom block
             let lines = if let Kind::Fenced {
                 has_closing_fence, ..
+            }
+            | Kind::Custom {
+                has_closing_fence, ..
             } = kind
             {
                 let l = lines.len() - usize::from(has_closing_fence);
@@ -409,6 +428,7 @@ impl<'s> TreeParser<'s> {
                     _ => ListItemKind::List,
                 })),
                 Kind::Table { .. } => Block::Container(Table),
+                Kind::Custom { name, argument, .. } => Block::Leaf(Custom { name, argument }),
             };
 
             match block {
@@ -435,6 +455,41 @@ impl<'s> TreeParser<'s> {
         }
     }
 
+    /// Identify a block that is recognized by a syntax extension.
+    fn custom_block(&self, lines: &[Range<usize>]) -> Option<MeteredBlock<'s>> {
+        let src = self.src;
+        let line = &src[lines.first()?.clone()];
+        let line_t = line.trim_start_matches(|c: char| c.is_ascii_whitespace() && c != '\n');
+        let indent = line.len() - line_t.len();
+        let ext = self
+            .extensions
+            .iter()
+            .find(|ext| line_t.starts_with(&ext.prefix))?;
+        let argument = line_t[ext.prefix.len()..].trim_matches(|c: char| c.is_ascii_whitespace());
+        let closing_fence = if ext.fenced {
+            lines[1..].iter().position(|l| {
+                src[l.clone()].trim_matches(|c: char| c.is_ascii_whitespace()) == ext.prefix
+            })
+        } else {
+            None
+        };
+        let line_count = match closing_fence {
+            Some(i) => i + 2,
+            None if ext.fenced => lines.len(),
+            None => 1,
+        };
+        Some(MeteredBlock {
+            kind: Kind::Custom {
+                indent,
+                name: ext.name,
+                argument,
+                has_closing_fence: closing_fence.is_some(),
+            },
+            span: indent..line.len(),
+            line_count,
+        })
+    }
+
     fn parse_leaf(
         &mut self,
         leaf: Leaf<'s>,
@@ -443,7 +498,7 @@ impl<'s> TreeParser<'s> {
         span_end: Range<usize>,
         mut lines: &mut [Range<usize>],
     ) {
-        if let Kind::Fenced { indent, spec, .. } = k {
+        if let Kind::Fenced { indent, .. } | Kind::Custom { indent, .. } = k {
             for line in lines.iter_mut() {
                 let indent_line = self.src.as_bytes()[line.clone()]
                     .iter()
@@ -453,7 +508,7 @@ impl<'s> TreeParser<'s> {
             }
 
             // trim ending whitespace of raw block
-            if spec.starts_with('=') {
+            if matches!(k, Kind::Fenced { spec, .. } if spec.starts_with('=')) {
                 let l = lines.len();
                 if l > 0 {
                     lines[l - 1] = self.trim_end(lines[l - 1].clone());
@@ -927,6 +982,12 @@ enum Kind<'s> {
     Table {
         caption: bool,
     },
+    Custom {
+        indent: usize,
+        name: &'s str,
+        argument: &'s str,
+        has_closing_fence: bool,
+    },
 }
 
 struct IdentifiedBlock<'s> {
@@ -1185,6 +1246,7 @@ impl<'s> Kind<'s> {
     fn continues(&mut self, line: &'s str) -> bool {
         match self {
             Self::Atom(..)
+            | Self::Custom { .. }
             | Self::Fenced {
                 has_closing_fence: true,
                 ..
@@ -1831,6 +1893,78 @@ mod test {
         );
     }
 
+    #[test]
+    fn parse_custom_block() {
+        let ext = crate::extension::Extensions::new()
+            .with_fenced("x", "%%%")
+            .with_directive("y", "!y");
+        let src = concat!(
+            "> %%% a\n",
+            ">  l0\n",
+            "> %%%\n",
+            "\n",
+            "!y  b \n",
+            "%%%\n",
+            "l1\n",
+        );
+        let actual = super::parse(src, &ext.block)
+            .into_iter()
+            .map(|ev| (ev.kind, &src[ev.span]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actual,
+            &[
+                (Enter(Container(Blockquote)), ">"),
+                (
+                    Enter(Leaf(Custom {
+                        name: "x",
+                        argument: "a"
+                    })),
+                    "%%% a\n"
+                ),
+                (Inline, " l0\n"),
+                (
+                    Exit(Leaf(Custom {
+                        name: "x",
+                        argument: "a"
+                    })),
+                    "%%%\n"
+                ),
+                (Exit(Container(Blockquote)), ""),
+                (Atom(Blankline), "\n"),
+                (
+                    Enter(Leaf(Custom {
+                        name: "y",
+                        argument: "b"
+                    })),
+                    "!y  b \n"
+                ),
+                (
+                    Exit(Leaf(Custom {
+                        name: "y",
+                        argument: "b"
+                    })),
+                    ""
+                ),
+                (
+                    Enter(Leaf(Custom {
+                        name: "x",
+                        argument: ""
+                    })),
+                    "%%%\n"
+                ),
+                (Inline, "l1\n"),
+                (
+                    Exit(Leaf(Custom {
+                        name: "x",
+                        argument: ""
+                    })),
+                    ""
+                ),
+            ]
+        );
+    }
+
     #[test]
     fn parse_link_definition() {
         test_parse!(
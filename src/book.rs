@@ -0,0 +1,510 @@
+//! Assembly of multiple djot documents into a book.
+//!
+//! A [`Book`] consists of an ordered set of chapters, each a separate djot document. The book
+//! may be output either as a single combined document, or as separate chapters that link to
+//! each other.
+//!
+//! Links between chapters are resolved in both cases. A link may refer to another chapter by
+//! its path, e.g. `[](intro.dj#Motivation)`, or only to an id, e.g. `[](#Motivation)`. In the
+//! latter case, the id is first looked up in the current chapter and then in the other chapters.
+//! Reference links may also use link definitions of other chapters.
+//!
+//! When combined, ids that occur in multiple chapters are made unique, and footnotes of
+//! different chapters are kept apart even if they share a label.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! use jotdown::book::Book;
+//!
+//! let mut book = Book::new();
+//! book.push("a.dj", "# A\n\nSee [b](#B).\n");
+//! book.push("b.dj", "# B\n");
+//!
+//! let chapters = book.chapters();
+//! assert_eq!(chapters[0].0, "a.html");
+//! assert_eq!(
+//!     jotdown::html::render_to_string(chapters[0].1.clone().into_iter()),
+//!     concat!(
+//!         "<section id=\"A\">\n",
+//!         "<h1>A</h1>\n",
+//!         "<p>See <a href=\"b.html#B\">b</a>.</p>\n",
+//!         "</section>\n",
+//!     ),
+//! );
+//!
+//! assert_eq!(
+//!     jotdown::html::render_to_string(book.combined().into_iter()),
+//!     concat!(
+//!         "<section id=\"A\">\n",
+//!         "<h1>A</h1>\n",
+//!         "<p>See <a href=\"#B\">b</a>.</p>\n",
+//!         "</section>\n",
+//!         "<section id=\"B\">\n",
+//!         "<h1>B</h1>\n",
+//!         "</section>\n",
+//!     ),
+//! );
+//! # }
+//! ```
+
+use crate::outline::Counters;
+use crate::outline::Numbering;
+use crate::AttributeKind;
+use crate::Attributes;
+use crate::Container;
+use crate::CowStr;
+use crate::Event;
+use crate::LinkType;
+use crate::Map;
+use crate::Set;
+use crate::SpanLinkType;
+
+/// A single document of a book.
+struct Chapter {
+    /// Path of the source document, e.g. `intro.dj`.
+    path: String,
+    /// Djot source of the document.
+    src: String,
+    /// Labels of footnotes in the document, with a label that is unique within the book.
+    footnotes: Vec<(String, String)>,
+}
+
+/// An ordered set of djot documents.
+#[derive(Default)]
+pub struct Book {
+    chapters: Vec<Chapter>,
+    section_numbers: bool,
+}
+
+impl Book {
+    /// Create an empty book.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read each file as a chapter of a book.
+    pub fn from_files<I, P>(paths: I) -> std::io::Result<Self>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<std::path::Path>,
+    {
+        let mut book = Self::new();
+        for path in paths {
+            let path = path.as_ref();
+            let src = std::fs::read_to_string(path)?;
+            book.push(path.to_string_lossy(), src);
+        }
+        Ok(book)
+    }
+
+    /// Prefix the text of each heading with its section number, e.g. "2.1 ".
+    ///
+    /// Sections are numbered throughout the whole book, relative to the highest heading level
+    /// of any chapter, i.e. each chapter usually starts a new top-level section.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::book::Book;
+    /// # use jotdown::*;
+    /// let mut book = Book::new().with_section_numbers(true);
+    /// book.push("a.dj", "# A\n");
+    /// book.push("b.dj", "# B\n\n## C\n");
+    /// let events = book.combined();
+    /// assert_eq!(events[8], Event::Str("2 ".into()));
+    /// assert_eq!(events[14], Event::Str("2.1 ".into()));
+    /// ```
+    #[must_use]
+    pub fn with_section_numbers(mut self, enabled: bool) -> Self {
+        self.section_numbers = enabled;
+        self
+    }
+
+    /// Append a chapter to the book.
+    ///
+    /// The path is used to resolve links from other chapters, and to determine the output path
+    /// of the chapter.
+    pub fn push<P: Into<String>, S: Into<String>>(&mut self, path: P, src: S) {
+        let src = src.into();
+        let mut labels = Set::new();
+        for e in crate::Parser::new(&src) {
+            if let Event::FootnoteReference(label)
+            | Event::Start(Container::Footnote { label }, _) = e
+            {
+                labels.insert(label.to_string());
+            }
+        }
+        let n = self.chapters.len() + 1;
+        let footnotes = labels
+            .into_iter()
+            .map(|label| {
+                let unique = format!("{}:{}", n, label);
+                (label, unique)
+            })
+            .collect();
+        self.chapters.push(Chapter {
+            path: path.into(),
+            src,
+            footnotes,
+        });
+    }
+
+    /// Output paths and events of all chapters, with links between chapters pointing to the
+    /// output path of the target chapter, e.g. `intro.html`.
+    #[must_use]
+    pub fn chapters(&self) -> Vec<(String, Vec<Event<'_>>)> {
+        self.chapters
+            .iter()
+            .map(|c| {
+                std::path::Path::new(&c.path)
+                    .with_extension("html")
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .zip(self.assemble(false))
+            .collect()
+    }
+
+    /// Events of a single document that contains all chapters.
+    #[must_use]
+    pub fn combined(&self) -> Vec<Event<'_>> {
+        self.assemble(true).into_iter().flatten().collect()
+    }
+
+    /// Resolve links, ids and footnotes of all chapters.
+    fn assemble(&self, combined: bool) -> Vec<Vec<Event<'_>>> {
+        let docs: Vec<Vec<Event>> = self
+            .chapters
+            .iter()
+            .map(|c| crate::Parser::new(&c.src).collect())
+            .collect();
+
+        let mut definitions: Definitions = Map::new();
+        for events in &docs {
+            let mut definition: Option<(&str, String, &Attributes)> = None;
+            for e in events {
+                match e {
+                    Event::Start(Container::LinkDefinition { label }, attrs) => {
+                        definition = Some((label, String::new(), attrs));
+                    }
+                    Event::Str(s) => {
+                        if let Some((_, url, _)) = &mut definition {
+                            url.push_str(s);
+                        }
+                    }
+                    Event::End(Container::LinkDefinition { .. }) => {
+                        if let Some((label, url, attrs)) = definition.take() {
+                            definitions
+                                .entry(label)
+                                .or_insert_with(|| (url, attrs.clone()));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut used = Set::new();
+        let ids: Vec<Ids> = docs
+            .iter()
+            .map(|events| {
+                let mut ids = Ids::default();
+                for id in events.iter().filter_map(id) {
+                    if ids.map.contains_key(&id) {
+                        continue;
+                    }
+                    let mut unique = id.clone();
+                    let mut n = 1;
+                    while combined && used.contains(&unique) {
+                        unique = format!("{}-{}", id, n);
+                        n += 1;
+                    }
+                    used.insert(unique.clone());
+                    if ids.first.is_none() {
+                        ids.first = Some(unique.clone());
+                    }
+                    ids.map.insert(id, unique);
+                }
+                ids
+            })
+            .collect();
+
+        let top = docs
+            .iter()
+            .flatten()
+            .filter_map(|e| match e {
+                Event::Start(Container::Heading { level, .. }, _) => Some(*level),
+                _ => None,
+            })
+            .min()
+            .unwrap_or(1);
+        let numbering = Numbering::new();
+        let mut sections = Counters::default();
+
+        let mut out = Vec::with_capacity(docs.len());
+        for (i, events) in docs.into_iter().enumerate() {
+            let chapter = &self.chapters[i];
+            let footnote = |label: &str| {
+                chapter
+                    .footnotes
+                    .iter()
+                    .find(|(l, _)| l == label)
+                    .map(|(_, unique)| unique.as_str())
+            };
+            let mut doc = Vec::with_capacity(events.len());
+            for e in events {
+                let e = match e {
+                    Event::Start(c, mut attrs) => match reference(&c, &definitions) {
+                        Some((c, mut def)) => {
+                            def.append(&mut attrs);
+                            Event::Start(c, def)
+                        }
+                        None => Event::Start(c, attrs),
+                    },
+                    Event::End(c) => Event::End(reference(&c, &definitions).map_or(c, |(c, _)| c)),
+                    e => e,
+                };
+                let e = match e {
+                    Event::Start(c, mut attrs) => {
+                        if combined {
+                            for (k, v) in attrs.iter_mut() {
+                                if let AttributeKind::Id = k {
+                                    if let Some(id) = ids[i].map.get(&v.to_string()) {
+                                        *v = id.clone().into();
+                                    }
+                                }
+                            }
+                        }
+                        let c = self.container(c, i, &ids, combined, &footnote);
+                        Event::Start(c, attrs)
+                    }
+                    Event::End(c) => Event::End(self.container(c, i, &ids, combined, &footnote)),
+                    Event::FootnoteReference(label) if combined => {
+                        Event::FootnoteReference(footnote(label).unwrap_or(label))
+                    }
+                    e => e,
+                };
+                let heading = if let Event::Start(Container::Heading { level, .. }, _) = &e {
+                    Some(*level)
+                } else {
+                    None
+                };
+                doc.push(e);
+                if let (Some(level), true) = (heading, self.section_numbers) {
+                    if let Some(number) = numbering.format(sections.next(level - top + 1)) {
+                        doc.push(Event::Str(format!("{} ", number).into()));
+                    }
+                }
+            }
+            out.push(doc);
+        }
+        out
+    }
+
+    /// Rewrite the ids, link destinations and footnote labels of a container.
+    fn container<'b>(
+        &'b self,
+        c: Container<'b>,
+        chapter: usize,
+        ids: &[Ids],
+        combined: bool,
+        footnote: &dyn Fn(&str) -> Option<&'b str>,
+    ) -> Container<'b> {
+        match c {
+            Container::Section { id } if combined => Container::Section {
+                id: ids[chapter]
+                    .map
+                    .get(id.as_ref())
+                    .map_or(id, |i| i.clone().into()),
+            },
+            Container::Heading {
+                level,
+                has_section,
+                id,
+            } if combined => Container::Heading {
+                level,
+                has_section,
+                id: ids[chapter]
+                    .map
+                    .get(id.as_ref())
+                    .map_or(id, |i| i.clone().into()),
+            },
+            Container::Footnote { label } if combined => Container::Footnote {
+                label: footnote(label).unwrap_or(label),
+            },
+            Container::Link(dst, LinkType::Span(ty)) => {
+                let dst = self
+                    .resolve(&dst, chapter, ids, combined)
+                    .map_or(dst, CowStr::from);
+                Container::Link(dst, LinkType::Span(ty))
+            }
+            c => c,
+        }
+    }
+
+    /// Resolve a link destination that refers to a chapter of the book.
+    fn resolve(&self, dst: &str, chapter: usize, ids: &[Ids], combined: bool) -> Option<String> {
+        let (path, frag) = match dst.split_once('#') {
+            Some((path, frag)) => (path, Some(frag)),
+            None => (dst, None),
+        };
+        let target = if path.is_empty() {
+            let frag = frag?;
+            if ids[chapter].map.contains_key(frag) {
+                chapter
+            } else {
+                ids.iter().position(|ids| ids.map.contains_key(frag))?
+            }
+        } else {
+            self.chapters.iter().position(|c| c.path == path)?
+        };
+        let id = match frag {
+            Some(frag) => ids[target].map.get(frag).map_or(frag, String::as_str),
+            None if combined => ids[target].first.as_deref()?,
+            None => "",
+        };
+        Some(if combined || target == chapter {
+            format!("#{}", id)
+        } else {
+            let out = std::path::Path::new(&self.chapters[target].path).with_extension("html");
+            let out = out.to_string_lossy();
+            if id.is_empty() {
+                out.into_owned()
+            } else {
+                format!("{}#{}", out, id)
+            }
+        })
+    }
+}
+
+/// Ids of a chapter.
+#[derive(Default)]
+struct Ids {
+    /// Ids of the chapter, mapped to ids that are unique within the book.
+    map: Map<String, String>,
+    /// First id of the chapter, used for links to the chapter itself.
+    first: Option<String>,
+}
+
+/// Link definitions of all chapters, with their destination and attributes.
+type Definitions<'b> = Map<&'b str, (String, Attributes<'b>)>;
+
+/// Reference link or image that is resolved by a definition of another chapter, if any.
+fn reference<'b>(
+    c: &Container,
+    definitions: &Definitions<'b>,
+) -> Option<(Container<'b>, Attributes<'b>)> {
+    let (tag, image) = match c {
+        Container::Link(tag, LinkType::Span(SpanLinkType::Unresolved)) => (tag, false),
+        Container::Image(tag, SpanLinkType::Unresolved) => (tag, true),
+        _ => return None,
+    };
+    let (url, attrs) = definitions.get(tag.as_ref())?;
+    let url = CowStr::from(url.clone());
+    let c = if image {
+        Container::Image(url, SpanLinkType::Reference)
+    } else {
+        Container::Link(url, LinkType::Span(SpanLinkType::Reference))
+    };
+    Some((c, attrs.clone()))
+}
+
+/// Id defined by an event, if any.
+fn id(e: &Event) -> Option<String> {
+    match e {
+        Event::Start(Container::Section { id } | Container::Heading { id, .. }, _) => {
+            Some(id.to_string())
+        }
+        Event::Start(_, attrs) => attrs.get_value("id").map(|id| id.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Book;
+    use crate::Container::*;
+    use crate::Event;
+    use crate::Event::*;
+    use crate::LinkType;
+    use crate::SpanLinkType;
+
+    fn links<'a>(events: &[Event<'a>]) -> Vec<String> {
+        events
+            .iter()
+            .filter_map(|e| match e {
+                Start(Link(dst, _), _) => Some(dst.to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn duplicate_ids() {
+        let mut book = Book::new();
+        book.push("a.dj", "# X\n\n[](#X) [](b.dj#X) [](b.dj)\n");
+        book.push("b.dj", "# X\n\n[](#X) [](a.dj)\n");
+        let combined = book.combined();
+        assert_eq!(links(&combined), &["#X", "#X-1", "#X-1", "#X-1", "#X"]);
+        assert!(combined.contains(&Start(Section { id: "X-1".into() }, Default::default())));
+        let chapters = book.chapters();
+        assert_eq!(links(&chapters[0].1), &["#X", "b.html#X", "b.html"]);
+        assert_eq!(links(&chapters[1].1), &["#X", "a.html"]);
+    }
+
+    #[test]
+    fn unresolved() {
+        let mut book = Book::new();
+        book.push("a.dj", "[](#Y) [](c.dj) [](https://a.b)\n");
+        assert_eq!(
+            book.combined()[1],
+            Start(
+                Link("#Y".into(), LinkType::Span(SpanLinkType::Inline)),
+                Default::default()
+            )
+        );
+        assert_eq!(links(&book.combined()), &["#Y", "c.dj", "https://a.b"]);
+    }
+
+    #[test]
+    fn definitions() {
+        let mut book = Book::new();
+        book.push("a.dj", "[a][] [b][] ![c][]\n\n[b]: b.dj\n");
+        book.push(
+            "b.dj",
+            "# Y\n\n[a]: b.dj#Y\n[b]: c.dj\n\n{.d}\n[c]: c.png\n",
+        );
+        assert_eq!(links(&book.combined()), &["#Y", "#Y"]);
+        let chapters = book.chapters();
+        assert_eq!(links(&chapters[0].1), &["b.html#Y", "b.html"]);
+        let image = chapters[0].1.iter().find_map(|e| match e {
+            Start(Image(src, SpanLinkType::Reference), attrs) => Some((src, attrs)),
+            _ => None,
+        });
+        let (src, attrs) = image.unwrap();
+        assert_eq!(src.as_ref(), "c.png");
+        assert_eq!(attrs.get_value("class").unwrap().to_string(), "d");
+    }
+
+    #[test]
+    fn footnotes() {
+        let mut book = Book::new();
+        book.push("a.dj", "a[^n]\n\n[^n]: a\n");
+        book.push("b.dj", "b[^n]\n\n[^n]: b\n");
+        let combined = book.combined();
+        let labels = combined
+            .iter()
+            .filter_map(|e| match e {
+                FootnoteReference(label) | Start(Footnote { label }, _) => Some(*label),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(labels, &["1:n", "1:n", "2:n", "2:n"]);
+        let chapters = book.chapters();
+        assert_eq!(chapters[1].1[2], FootnoteReference("n"));
+    }
+}
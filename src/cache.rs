@@ -0,0 +1,177 @@
+//! Caching of rendered blocks.
+//!
+//! Live previews re-render a document after each edit, although most of its blocks are
+//! unchanged. A [`Cache`] renders each top-level block separately and reuses the HTML of blocks
+//! that were rendered before, so that only the changed blocks are rendered again. This is
+//! particularly worthwhile with expensive hooks, e.g. syntax highlighting or diagrams.
+//!
+//! Blocks are identified by a hash of their events, which covers their source as well as
+//! anything resolved from the rest of the document, e.g. link references and heading ids. Blocks
+//! that reference footnotes and footnote definitions are always rendered, as footnotes are
+//! numbered within the whole document. Fragments that are not used by the latest render are
+//! discarded.
+//!
+//! # Examples
+//!
+//! ```
+//! use jotdown::cache::Cache;
+//!
+//! let mut cache = Cache::new(jotdown::html::Renderer::default());
+//! cache.render(jotdown::Parser::new("# Title\n\nSome *text*.\n"));
+//! assert_eq!(cache.misses(), 2);
+//!
+//! let src = "# Title\n\nSome *text*.\n\nMore.\n";
+//! assert_eq!(
+//!     cache.render(jotdown::Parser::new(src)),
+//!     jotdown::html::render_to_string(jotdown::Parser::new(src)),
+//! );
+//! assert_eq!(cache.misses(), 1);
+//! ```
+
+use crate::html::Renderer;
+use crate::html::FRAGMENT;
+use crate::Attributes;
+use crate::Container;
+use crate::Event;
+use crate::Map;
+use crate::Render;
+
+/// A renderer that caches the HTML of top-level blocks.
+#[derive(Clone)]
+pub struct Cache {
+    renderer: Renderer,
+    /// Rendered blocks, by the hash of their events and their section level, which affects
+    /// their indentation.
+    fragments: Map<(u64, usize), String>,
+    /// Number of blocks rendered by the latest render.
+    misses: usize,
+}
+
+impl Cache {
+    /// Create an empty cache of blocks rendered with the provided renderer.
+    #[must_use]
+    pub fn new(renderer: Renderer) -> Self {
+        Self {
+            renderer,
+            fragments: Map::new(),
+            misses: 0,
+        }
+    }
+
+    /// Render a document to HTML, reusing previously rendered blocks.
+    pub fn render<'s, I>(&mut self, events: I) -> String
+    where
+        I: IntoIterator<Item = Event<'s>>,
+    {
+        let mut fragments = Map::new();
+        let mut output = Vec::new();
+        let mut block: Vec<Event<'s>> = Vec::new();
+        let mut depth = 0;
+        // nesting depth of sections
+        let mut level = 0;
+        self.misses = 0;
+        for e in events {
+            match &e {
+                Event::Start(Container::Section { .. }, _) => level += 1,
+                Event::End(Container::Section { .. }) => level -= 1,
+                Event::Start(c, _) if !is_transparent(c) && (depth > 0 || c.is_block()) => {
+                    depth += 1;
+                }
+                Event::End(c) if !is_transparent(c) && depth > 0 => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 && block.is_empty() {
+                output.push(e);
+                continue;
+            }
+            block.push(e);
+            if depth > 0 {
+                continue;
+            }
+            let block = std::mem::take(&mut block);
+            if block.iter().any(|e| {
+                matches!(
+                    e,
+                    Event::FootnoteReference(..) | Event::Start(Container::Footnote { .. }, _)
+                )
+            }) {
+                output.extend(block);
+                continue;
+            }
+            let key = (crate::record::hash(&block), level);
+            let html = match self.fragments.remove(&key) {
+                Some(html) => html,
+                None => {
+                    self.misses += 1;
+                    self.renderer.render_fragment(block.into_iter(), level)
+                }
+            };
+            let raw = Container::RawBlock { format: FRAGMENT };
+            output.push(Event::Start(raw.clone(), Attributes::new()));
+            output.push(Event::Str(html.clone().into()));
+            output.push(Event::End(raw));
+            fragments.insert(key, html);
+        }
+        self.fragments = fragments;
+        let mut html = String::new();
+        self.renderer.push(output.into_iter(), &mut html).unwrap();
+        html
+    }
+
+    /// Number of blocks that were not cached during the latest render.
+    #[must_use]
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    /// Number of cached blocks.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.fragments.len()
+    }
+
+    /// Returns `true` if no blocks are cached.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.fragments.is_empty()
+    }
+}
+
+/// Containers whose content is cached rather than themselves, as they span other blocks or are
+/// not rendered.
+fn is_transparent(c: &Container) -> bool {
+    matches!(
+        c,
+        Container::Section { .. } | Container::LinkDefinition { .. }
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::Cache;
+    use crate::html::Renderer;
+
+    #[test]
+    fn equivalent() {
+        let sources = [
+            "# a\n\nb\n\n## c\n\n> d\n\n- e\n- f\n",
+            "a[^1] [b][] `c`\n\n[b]: /b\n\n[^1]: d\n\n```\ne\n```\n\n| f |\n",
+            "{#x}\ntext\n\n::: warning\ny\n:::\n\n``` =html\n<br>\n```\n",
+        ];
+        for renderer in [
+            Renderer::default(),
+            Renderer::minified(),
+            Renderer::indented(crate::html::Indentation::default()),
+            Renderer::default().with_feed("https://example.com/"),
+        ] {
+            let mut cache = Cache::new(renderer.clone());
+            for src in sources {
+                let mut expected = String::new();
+                crate::Render::push(&renderer, crate::Parser::new(src), &mut expected).unwrap();
+                assert_eq!(cache.render(crate::Parser::new(src)), expected, "{:?}", src);
+                assert_eq!(cache.render(crate::Parser::new(src)), expected, "{:?}", src);
+                assert_eq!(cache.misses(), 0);
+            }
+        }
+    }
+}
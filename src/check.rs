@@ -0,0 +1,324 @@
+//! Validation of links within a document.
+//!
+//! The parser silently accepts links that do not lead anywhere, e.g. a link to a heading that
+//! has been renamed. The functions of this module find such links and report them with their
+//! location in the source, so that they can be flagged by e.g. an editor or a CI job.
+//!
+//! Internal links are checked by [`check_anchors`]. External links require e.g. network
+//! access, so they are instead handed to a user-supplied [`LinkChecker`] by [`check_links`].
+//!
+//! # Examples
+//!
+//! ```
+//! use jotdown::check::*;
+//!
+//! let src = "# Intro\n\nSee [intro](#Intro), [usage](#Usage) and [api][].\n";
+//! assert_eq!(
+//!     check_anchors(src),
+//!     &[
+//!         Issue {
+//!             span: 30..45,
+//!             kind: IssueKind::BrokenAnchor("Usage".to_string()),
+//!         },
+//!         Issue {
+//!             span: 50..57,
+//!             kind: IssueKind::UnresolvedReference("api".to_string()),
+//!         },
+//!     ],
+//! );
+//! ```
+
+use std::ops::Range;
+
+use crate::Container;
+use crate::Event;
+use crate::LinkType;
+use crate::Set;
+use crate::SpanLinkType;
+
+/// A problem found within a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    /// Location of the problem in the source, e.g. the whole link.
+    pub span: Range<usize>,
+    /// The kind of problem.
+    pub kind: IssueKind,
+}
+
+/// A kind of problem found within a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IssueKind {
+    /// A link to a fragment, e.g. `#usage`, but there is no element with the id.
+    BrokenAnchor(String),
+    /// A reference link or image, e.g. `[text][tag]`, but there is no definition of the tag.
+    UnresolvedReference(String),
+    /// A footnote reference, e.g. `[^note]`, but there is no footnote with the label.
+    UndefinedFootnote(String),
+    /// An external link that was rejected by a [`LinkChecker`].
+    DeadLink {
+        /// Destination of the link.
+        url: String,
+        /// Reason provided by the checker, e.g. "404 Not Found".
+        reason: String,
+    },
+}
+
+/// A link to an external resource, e.g. `https://example.com`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalLink {
+    /// Destination of the link.
+    pub url: String,
+    /// Location of the link in the source.
+    pub span: Range<usize>,
+}
+
+/// A checker of external links, e.g. one that sends HTTP requests.
+///
+/// The trait is implemented for closures, so a checker may also look up results that have been
+/// obtained beforehand, e.g. by resolving the URLs of [`external_links`] concurrently with an
+/// asynchronous runtime:
+///
+/// ```
+/// # use jotdown::check::*;
+/// let src = "<https://a.example> <https://b.example>";
+/// let results: std::collections::HashMap<String, Result<(), String>> = external_links(src)
+///     .into_iter()
+///     .map(|l| {
+///         let res = if l.url.contains("b.") {
+///             Err("404 Not Found".to_string())
+///         } else {
+///             Ok(())
+///         };
+///         (l.url, res)
+///     })
+///     .collect();
+/// let issues = check_links(src, |url: &str| results[url].clone());
+/// assert_eq!(
+///     issues,
+///     &[Issue {
+///         span: 20..39,
+///         kind: IssueKind::DeadLink {
+///             url: "https://b.example".to_string(),
+///             reason: "404 Not Found".to_string(),
+///         },
+///     }],
+/// );
+/// ```
+pub trait LinkChecker {
+    /// Check that the resource at the URL exists, returns the reason if it does not.
+    fn check(&mut self, url: &str) -> Result<(), String>;
+}
+
+impl<F: FnMut(&str) -> Result<(), String>> LinkChecker for F {
+    fn check(&mut self, url: &str) -> Result<(), String> {
+        self(url)
+    }
+}
+
+/// Find all internal links that do not resolve.
+///
+/// Fragment links are checked against the ids of all elements, including automatically
+/// generated section ids. Reference links and images are checked against the link
+/// definitions, and footnote references against the footnotes of the document.
+#[must_use]
+pub fn check_anchors(src: &str) -> Vec<Issue> {
+    let mut ids = Set::new();
+    let mut footnotes = Set::new();
+    // links and their span, the end is filled in when the link is closed
+    let mut links = Vec::new();
+    let mut open = Vec::new();
+    let mut references = Vec::new();
+
+    for (e, span) in crate::Parser::new(src).into_offset_iter() {
+        match e {
+            Event::Start(c, attrs) => {
+                match &c {
+                    Container::Section { id } | Container::Heading { id, .. } => {
+                        ids.insert(id.to_string());
+                    }
+                    Container::Footnote { label } => {
+                        footnotes.insert(*label);
+                    }
+                    _ => {}
+                }
+                if let Some(id) = attrs.get_value("id") {
+                    ids.insert(id.to_string());
+                }
+                if let Container::Link(..) | Container::Image(..) = c {
+                    open.push(links.len());
+                    links.push((c, span));
+                }
+            }
+            Event::End(Container::Link(..) | Container::Image(..)) => {
+                if let Some(i) = open.pop() {
+                    links[i].1.end = span.end;
+                }
+            }
+            Event::ThematicBreak(attrs) => {
+                if let Some(id) = attrs.get_value("id") {
+                    ids.insert(id.to_string());
+                }
+            }
+            Event::FootnoteReference(label) => {
+                references.push((label, span));
+            }
+            _ => {}
+        }
+    }
+
+    let mut issues = references
+        .into_iter()
+        .filter(|(label, _)| !footnotes.contains(label))
+        .map(|(label, span)| Issue {
+            span,
+            kind: IssueKind::UndefinedFootnote(label.to_string()),
+        })
+        .collect::<Vec<_>>();
+    for (c, span) in links {
+        let kind = match c {
+            Container::Link(tag, LinkType::Span(SpanLinkType::Unresolved))
+            | Container::Image(tag, SpanLinkType::Unresolved) => {
+                IssueKind::UnresolvedReference(tag.to_string())
+            }
+            Container::Link(dst, LinkType::Span(_)) => match dst.strip_prefix('#') {
+                Some(id) if !ids.contains(id) => IssueKind::BrokenAnchor(id.to_string()),
+                _ => continue,
+            },
+            _ => continue,
+        };
+        issues.push(Issue { span, kind });
+    }
+    issues.sort_by_key(|i| i.span.start);
+    issues
+}
+
+/// Find all links and images with an external destination, i.e. one with a URL scheme such as
+/// `https:`.
+#[must_use]
+pub fn external_links(src: &str) -> Vec<ExternalLink> {
+    let mut links = Vec::new();
+    let mut open = Vec::new();
+    for (e, span) in crate::Parser::new(src).into_offset_iter() {
+        match e {
+            Event::Start(
+                Container::Link(dst, LinkType::Span(_) | LinkType::AutoLink)
+                | Container::Image(dst, _),
+                _,
+            ) => {
+                let external = crate::has_scheme(&dst);
+                open.push(external.then(|| links.len()));
+                if external {
+                    links.push(ExternalLink {
+                        url: dst.to_string(),
+                        span,
+                    });
+                }
+            }
+            Event::End(
+                Container::Link(_, LinkType::Span(_) | LinkType::AutoLink) | Container::Image(..),
+            ) => {
+                if let Some(Some(i)) = open.pop() {
+                    links[i].span.end = span.end;
+                }
+            }
+            _ => {}
+        }
+    }
+    links
+}
+
+/// Check all external links of a document with the provided checker.
+///
+/// The checker is called once for each unique URL, every link to a rejected URL is reported.
+pub fn check_links<C: LinkChecker>(src: &str, mut checker: C) -> Vec<Issue> {
+    let mut results: crate::Map<String, Result<(), String>> = crate::Map::new();
+    external_links(src)
+        .into_iter()
+        .filter_map(|link| {
+            let res = results
+                .entry(link.url.clone())
+                .or_insert_with(|| checker.check(&link.url));
+            res.clone().err().map(|reason| Issue {
+                span: link.span,
+                kind: IssueKind::DeadLink {
+                    url: link.url,
+                    reason,
+                },
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::check_anchors;
+    use super::check_links;
+    use super::external_links;
+    use super::Issue;
+    use super::IssueKind::*;
+
+    #[test]
+    fn valid() {
+        let src = concat!(
+            "# A\n",
+            "\n",
+            "{#b}\n",
+            "para [^n] [c]{#d}\n",
+            "\n",
+            "[^n]: [](#A) [](#b) [](#d) [](#e) [x][y] [](#s)\n",
+            "\n",
+            "{#e}\n",
+            "***\n",
+            "\n",
+            "[y]: #b\n",
+            "\n",
+            "{#s}\n",
+            "## S\n",
+        );
+        assert_eq!(check_anchors(src), &[]);
+    }
+
+    #[test]
+    fn broken() {
+        let src = "[^n] ![a][b] [c](#d)\n";
+        assert_eq!(
+            check_anchors(src),
+            &[
+                Issue {
+                    span: 0..4,
+                    kind: UndefinedFootnote("n".to_string()),
+                },
+                Issue {
+                    span: 5..12,
+                    kind: UnresolvedReference("b".to_string()),
+                },
+                Issue {
+                    span: 13..20,
+                    kind: BrokenAnchor("d".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn external() {
+        let src = "[a](https://a.b) ![b](c.png) [d](#e) <http://f.g> <h@i.j> [k](mailto:l@m.n)\n";
+        let urls = external_links(src)
+            .into_iter()
+            .map(|l| l.url)
+            .collect::<Vec<_>>();
+        assert_eq!(urls, &["https://a.b", "http://f.g", "mailto:l@m.n"]);
+    }
+
+    #[test]
+    fn dead_once() {
+        let mut calls = 0;
+        let issues = check_links("<http://a.b> <http://a.b>", |_: &str| {
+            calls += 1;
+            Err("timeout".to_string())
+        });
+        assert_eq!(calls, 1);
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[1].span, 13..25);
+    }
+}
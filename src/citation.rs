@@ -0,0 +1,990 @@
+//! Citations of bibliographic references.
+//!
+//! Citations are written within brackets, with each cited reference identified by its key
+//! prefixed with an `@` character, e.g. `[@doe99]`. Multiple references are separated by
+//! semicolons, and each may have a prefix and a suffix, e.g. a locator: `[see @doe99, p. 3;
+//! @roe04]`.
+//!
+//! The citation syntax is not part of djot, so the brackets are parsed as regular text by the
+//! [`crate::Parser`]. The [`Citations`] adapter finds citations within the text events of a
+//! document, replaces them with formatted citations and appends a bibliography of all cited
+//! references.
+//!
+//! References are provided by a [`Bibliography`], which may be parsed from a BibTeX database.
+//! With the `serde` feature enabled, the [`Reference`] type may also be deserialized from the
+//! CSL-JSON format used by e.g. Zotero or Pandoc.
+//!
+//! Citations and bibliography entries are formatted by a [`Style`], the [`AuthorYear`] style is
+//! used by default and a [`Numeric`] style is also provided. The output consists of regular
+//! events, so it can be used with any renderer.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! use jotdown::citation::*;
+//!
+//! let mut bib = Bibliography::new();
+//! bib.push(Reference {
+//!     id: "doe99".to_string(),
+//!     title: Some("The Title".to_string()),
+//!     author: vec![Name::new("Doe", "John")],
+//!     issued: Some(Date::year(1999)),
+//!     publisher: Some("Publisher".to_string()),
+//!     ..Reference::default()
+//! });
+//!
+//! let events = jotdown::Parser::new("As shown [see @doe99, p. 3].");
+//! let html = jotdown::html::render_to_string(Citations::new(events, &bib));
+//! assert_eq!(
+//!     html,
+//!     concat!(
+//!         "<p>As shown <span class=\"citation\" data-cites=\"doe99\">",
+//!         "(see Doe 1999, p. 3)</span>.</p>\n",
+//!         "<div class=\"references\" id=\"refs\">\n",
+//!         "<div id=\"ref-doe99\">\n",
+//!         "<p>Doe, John. 1999. <em>The Title</em>. Publisher.</p>\n",
+//!         "</div>\n",
+//!         "</div>\n",
+//!     ),
+//! );
+//! # }
+//! ```
+
+use crate::AttributeKind;
+use crate::AttributeValue;
+use crate::Attributes;
+use crate::Container;
+use crate::CowStr;
+use crate::Event;
+
+mod bibtex;
+
+/// The name of a person, e.g. an author.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, rename_all = "kebab-case"))]
+pub struct Name {
+    /// Family name, e.g. "Doe".
+    pub family: Option<String>,
+    /// Given name, e.g. "John".
+    pub given: Option<String>,
+    /// A name that should not be split into parts, e.g. an organization.
+    pub literal: Option<String>,
+}
+
+impl Name {
+    /// Create a name from a family name and a given name.
+    #[must_use]
+    pub fn new(family: &str, given: &str) -> Self {
+        Self {
+            family: Some(family.to_string()),
+            given: Some(given.to_string()),
+            literal: None,
+        }
+    }
+
+    /// Name used when citing, e.g. "Doe".
+    fn short(&self) -> &str {
+        self.family
+            .as_deref()
+            .or(self.literal.as_deref())
+            .or(self.given.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// Name in the order it is displayed, e.g. "John Doe".
+    fn display(&self) -> String {
+        match (&self.family, &self.given) {
+            (Some(family), Some(given)) => format!("{} {}", given, family),
+            _ => self.short().to_string(),
+        }
+    }
+
+    /// Name in the order it is sorted, e.g. "Doe, John".
+    fn inverted(&self) -> String {
+        match (&self.family, &self.given) {
+            (Some(family), Some(given)) => format!("{}, {}", family, given),
+            _ => self.short().to_string(),
+        }
+    }
+}
+
+/// A date, e.g. when a work was issued.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, rename_all = "kebab-case"))]
+pub struct Date {
+    /// Date parts in the form `[[year, month, day]]`, a second inner list denotes the end of a
+    /// range.
+    ///
+    /// Years are limited to the range of an `i32`, negative years denote years BCE. Years that
+    /// are not numeric or out of range are kept as a literal when parsed from BibTeX.
+    pub date_parts: Vec<Vec<i32>>,
+    /// A date that should be displayed as is, e.g. "Spring 1999".
+    pub literal: Option<String>,
+}
+
+impl Date {
+    /// Create a date with only a year.
+    #[must_use]
+    pub fn year(year: i32) -> Self {
+        Self {
+            date_parts: vec![vec![year]],
+            literal: None,
+        }
+    }
+
+    /// Year as it is displayed, e.g. "1999".
+    fn display_year(&self) -> Option<String> {
+        self.date_parts
+            .first()
+            .and_then(|parts| parts.first())
+            .map(ToString::to_string)
+            .or_else(|| self.literal.clone())
+    }
+}
+
+/// A bibliographic reference, with fields named after the CSL-JSON format.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, rename_all = "kebab-case"))]
+pub struct Reference {
+    /// Key used to cite the reference, e.g. `doe99` for `[@doe99]`.
+    pub id: String,
+    /// Type of the work, e.g. "book" or "article-journal".
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
+    pub kind: Option<String>,
+    /// Title of the work.
+    pub title: Option<String>,
+    /// Authors of the work.
+    pub author: Vec<Name>,
+    /// Editors of the work.
+    pub editor: Vec<Name>,
+    /// Date when the work was issued.
+    pub issued: Option<Date>,
+    /// Title of the container of the work, e.g. the journal of an article.
+    pub container_title: Option<String>,
+    /// Publisher of the work.
+    pub publisher: Option<String>,
+    /// Volume of the container.
+    pub volume: Option<String>,
+    /// Issue of the container.
+    pub issue: Option<String>,
+    /// Page range of the work within the container.
+    pub page: Option<String>,
+    /// DOI of the work.
+    #[cfg_attr(feature = "serde", serde(rename = "DOI"))]
+    pub doi: Option<String>,
+    /// URL of the work.
+    #[cfg_attr(feature = "serde", serde(rename = "URL"))]
+    pub url: Option<String>,
+}
+
+impl Reference {
+    /// Names used when citing, authors or editors if there are no authors.
+    fn names(&self) -> &[Name] {
+        if self.author.is_empty() {
+            &self.editor
+        } else {
+            &self.author
+        }
+    }
+
+    /// Author part of a citation, e.g. "Doe and Roe".
+    fn cite_names(&self) -> String {
+        match self.names() {
+            [] => self.title.clone().unwrap_or_else(|| self.id.clone()),
+            [a] => a.short().to_string(),
+            [a, b] => format!("{} and {}", a.short(), b.short()),
+            [a, ..] => format!("{} et al.", a.short()),
+        }
+    }
+
+    /// Year part of a citation, e.g. "1999".
+    fn cite_year(&self) -> String {
+        self.issued
+            .as_ref()
+            .and_then(Date::display_year)
+            .unwrap_or_else(|| "n.d.".to_string())
+    }
+
+    /// Author part of an entry in a bibliography, e.g. "Doe, John, and Jane Roe".
+    fn entry_names(&self) -> Option<String> {
+        let names = self.names();
+        let mut s = String::new();
+        for (i, name) in names.iter().enumerate() {
+            if i == 0 {
+                s.push_str(&name.inverted());
+            } else {
+                s.push_str(if i + 1 == names.len() {
+                    if names.len() == 2 {
+                        " and "
+                    } else {
+                        ", and "
+                    }
+                } else {
+                    ", "
+                });
+                s.push_str(&name.display());
+            }
+        }
+        if !self.author.is_empty() || s.is_empty() {
+            (!s.is_empty()).then(|| s)
+        } else {
+            Some(format!(
+                "{}, ed{}",
+                s,
+                if names.len() > 1 { "s." } else { "." }
+            ))
+        }
+    }
+
+    /// Events of an entry in a bibliography, e.g. "Doe, John. 1999. _Title_. Publisher."
+    fn entry(&self) -> Vec<Event<'static>> {
+        fn sentence(s: &mut String, part: &str) {
+            s.push_str(part);
+            if !part.ends_with(['.', '?', '!'].as_ref()) {
+                s.push('.');
+            }
+            s.push(' ');
+        }
+
+        let mut events = Vec::new();
+        let mut s = String::new();
+        if let Some(names) = self.entry_names() {
+            sentence(&mut s, &names);
+        }
+        sentence(&mut s, &self.cite_year());
+        if let Some(title) = &self.title {
+            if self.container_title.is_some() {
+                s.push('“');
+                sentence(&mut s, title);
+                s.pop();
+                s.push_str("” ");
+            } else {
+                events.push(Event::Str(std::mem::take(&mut s).into()));
+                events.push(Event::Start(Container::Emphasis, Attributes::new()));
+                events.push(Event::Str(title.clone().into()));
+                events.push(Event::End(Container::Emphasis));
+                s.push_str(". ");
+            }
+        }
+        if let Some(container) = &self.container_title {
+            events.push(Event::Str(std::mem::take(&mut s).into()));
+            events.push(Event::Start(Container::Emphasis, Attributes::new()));
+            events.push(Event::Str(container.clone().into()));
+            events.push(Event::End(Container::Emphasis));
+            if let Some(volume) = &self.volume {
+                s.push(' ');
+                s.push_str(volume);
+                if let Some(issue) = &self.issue {
+                    s.push_str(&format!(" ({})", issue));
+                }
+            }
+            if let Some(page) = &self.page {
+                s.push_str(": ");
+                s.push_str(page);
+            }
+            s.push_str(". ");
+        }
+        if let Some(publisher) = &self.publisher {
+            sentence(&mut s, publisher);
+        }
+        if let Some(doi) = &self.doi {
+            s.push_str("https://doi.org/");
+            s.push_str(doi);
+            s.push(' ');
+        } else if let Some(url) = &self.url {
+            s.push_str(url);
+            s.push(' ');
+        }
+        s.pop();
+        events.push(Event::Str(s.into()));
+        events.retain(|e| !matches!(e, Event::Str(s) if s.is_empty()));
+        events
+    }
+}
+
+/// A collection of references that may be cited.
+#[derive(Debug, Clone, Default)]
+pub struct Bibliography {
+    references: Vec<Reference>,
+}
+
+impl Bibliography {
+    /// Create an empty bibliography.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a reference to the bibliography.
+    pub fn push(&mut self, reference: Reference) {
+        self.references.push(reference);
+    }
+
+    /// Obtain the reference with the specified key, if it exists.
+    #[must_use]
+    pub fn get(&self, id: &str) -> Option<&Reference> {
+        self.references.iter().find(|r| r.id == id)
+    }
+
+    /// Create a bibliography from the entries of a BibTeX database.
+    ///
+    /// Fields are converted to their CSL counterparts, e.g. `journal` is used as the
+    /// `container_title`. Braces and common TeX commands are removed from values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::citation::*;
+    /// let bib = Bibliography::from_bibtex(concat!(
+    ///     "@book{doe99,\n",
+    ///     "  author = {Doe, John and Jane Roe},\n",
+    ///     "  title = {The {BibTeX} Title},\n",
+    ///     "  year = 1999,\n",
+    ///     "}\n",
+    /// ))
+    /// .unwrap();
+    /// let r = bib.get("doe99").unwrap();
+    /// assert_eq!(r.author, &[Name::new("Doe", "John"), Name::new("Roe", "Jane")]);
+    /// assert_eq!(r.title.as_deref(), Some("The BibTeX Title"));
+    /// assert_eq!(r.issued, Some(Date::year(1999)));
+    /// ```
+    ///
+    /// When the database is invalid, the position where the parsing failed is returned:
+    ///
+    /// ```
+    /// # use jotdown::citation::*;
+    /// assert_eq!(
+    ///     Bibliography::from_bibtex("@book{a, title}").unwrap_err(),
+    ///     ParseBibtexError { pos: 14 },
+    /// );
+    /// ```
+    pub fn from_bibtex(src: &str) -> Result<Self, ParseBibtexError> {
+        bibtex::parse(src).map(|references| Self { references })
+    }
+
+    /// Returns an iterator over all references in the bibliography.
+    pub fn iter(&self) -> std::slice::Iter<'_, Reference> {
+        self.references.iter()
+    }
+}
+
+impl FromIterator<Reference> for Bibliography {
+    /// Create a bibliography from references, e.g. deserialized from CSL-JSON.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::citation::*;
+    /// let refs = vec![Reference {
+    ///     id: "doe99".to_string(),
+    ///     title: Some("The Title".to_string()),
+    ///     author: vec![Name::new("Doe", "John")],
+    ///     ..Reference::default()
+    /// }];
+    /// let bib: Bibliography = refs.into_iter().collect();
+    /// assert_eq!(bib.get("doe99").unwrap().title.as_deref(), Some("The Title"));
+    /// ```
+    fn from_iter<I: IntoIterator<Item = Reference>>(iter: I) -> Self {
+        Self {
+            references: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// Error returned by [`Bibliography::from_bibtex`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseBibtexError {
+    /// Location in input string where the database became invalid.
+    pub pos: usize,
+}
+
+/// A single cited reference within a citation, as passed to a [`Style`].
+#[derive(Debug, Clone, Copy)]
+pub struct Cite<'a> {
+    /// Text before the key, e.g. "see".
+    pub prefix: &'a str,
+    /// Key of the reference.
+    pub id: &'a str,
+    /// Text after the key, e.g. "p. 3".
+    pub suffix: &'a str,
+    /// The cited reference, if it exists in the bibliography.
+    pub reference: Option<&'a Reference>,
+    /// Number of the reference in order of first citation, starting at 1.
+    pub number: usize,
+}
+
+/// A citation style, determines how citations and bibliography entries are formatted.
+pub trait Style {
+    /// Format the text of a citation containing one or more references.
+    fn citation(&self, cites: &[Cite]) -> String;
+
+    /// Create the content of an entry of a reference in the bibliography.
+    fn entry(&self, reference: &Reference, number: usize) -> Vec<Event<'static>>;
+
+    /// Compare two references, with their numbers, to order the bibliography. By default, the
+    /// references are ordered by their numbers, i.e. in order of first citation.
+    fn order(&self, a: (&Reference, usize), b: (&Reference, usize)) -> std::cmp::Ordering {
+        a.1.cmp(&b.1)
+    }
+}
+
+/// Author-year style, e.g. "(Doe 1999, p. 3)", with the bibliography sorted by author.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AuthorYear;
+
+impl Style for AuthorYear {
+    fn citation(&self, cites: &[Cite]) -> String {
+        let mut s = String::from("(");
+        for (i, c) in cites.iter().enumerate() {
+            if i > 0 {
+                s.push_str("; ");
+            }
+            if !c.prefix.is_empty() {
+                s.push_str(c.prefix);
+                s.push(' ');
+            }
+            if let Some(r) = c.reference {
+                s.push_str(&r.cite_names());
+                s.push(' ');
+                s.push_str(&r.cite_year());
+            } else {
+                s.push_str(c.id);
+                s.push('?');
+            }
+            if !c.suffix.is_empty() {
+                s.push_str(", ");
+                s.push_str(c.suffix);
+            }
+        }
+        s.push(')');
+        s
+    }
+
+    fn entry(&self, reference: &Reference, _number: usize) -> Vec<Event<'static>> {
+        reference.entry()
+    }
+
+    fn order(&self, a: (&Reference, usize), b: (&Reference, usize)) -> std::cmp::Ordering {
+        (a.0.entry_names(), a.0.cite_year()).cmp(&(b.0.entry_names(), b.0.cite_year()))
+    }
+}
+
+/// Numeric style, e.g. "[1, p. 3]", with the bibliography in order of first citation.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Numeric;
+
+impl Style for Numeric {
+    fn citation(&self, cites: &[Cite]) -> String {
+        let mut s = String::from("[");
+        for (i, c) in cites.iter().enumerate() {
+            if i > 0 {
+                s.push_str("; ");
+            }
+            if !c.prefix.is_empty() {
+                s.push_str(c.prefix);
+                s.push(' ');
+            }
+            if c.reference.is_some() {
+                s.push_str(&c.number.to_string());
+            } else {
+                s.push_str(c.id);
+                s.push('?');
+            }
+            if !c.suffix.is_empty() {
+                s.push_str(", ");
+                s.push_str(c.suffix);
+            }
+        }
+        s.push(']');
+        s
+    }
+
+    fn entry(&self, reference: &Reference, number: usize) -> Vec<Event<'static>> {
+        let mut events = vec![Event::Str(format!("[{}] ", number).into())];
+        events.extend(reference.entry());
+        events
+    }
+}
+
+/// A single cited reference within a citation.
+struct CitationItem<'a> {
+    /// Text before the key, e.g. "see".
+    prefix: &'a str,
+    /// Key of the reference.
+    id: &'a str,
+    /// Text after the key, e.g. "p. 3".
+    suffix: &'a str,
+}
+
+/// Parse a citation, i.e. the content between brackets. Returns `None` if not a citation.
+fn parse_citation(s: &str) -> Option<Vec<CitationItem<'_>>> {
+    s.split(';')
+        .map(|item| {
+            let at = item.find('@')?;
+            let (prefix, rest) = (&item[..at], &item[at + 1..]);
+            if !(prefix.is_empty() || prefix.ends_with(char::is_whitespace)) {
+                return None;
+            }
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || "_-:.#$%&+?<>~/".contains(c)))
+                .unwrap_or(rest.len());
+            let id = rest[..len].trim_end_matches(|c: char| c.is_ascii_punctuation());
+            if id.is_empty() {
+                return None;
+            }
+            let suffix = rest[id.len()..].trim_start_matches(',').trim();
+            Some(CitationItem {
+                prefix: prefix.trim(),
+                id,
+                suffix,
+            })
+        })
+        .collect()
+}
+
+/// Find the next citation in a string, returns its range and its items.
+fn find_citation(s: &str) -> Option<(std::ops::Range<usize>, Vec<CitationItem<'_>>)> {
+    let mut pos = 0;
+    while let Some(start) = s[pos..].find("[").map(|i| pos + i) {
+        let end = start + s[start..].find(']')?;
+        if let Some(items) = parse_citation(&s[start + 1..end]) {
+            return Some((start..end + 1, items));
+        }
+        pos = start + 1;
+    }
+    None
+}
+
+/// Borrow a substring of a [`CowStr`] if possible.
+fn substr<'s>(s: &CowStr<'s>, range: std::ops::Range<usize>) -> CowStr<'s> {
+    match s {
+        CowStr::Borrowed(s) => CowStr::Borrowed(&s[range]),
+        CowStr::Owned(s) => CowStr::Owned(s[range].to_string()),
+    }
+}
+
+/// An iterator adapter that replaces citations with formatted citations and appends a
+/// bibliography.
+///
+/// The bibliography is placed inside a div with the id `refs`, if present. Otherwise, it is
+/// placed in such a div at the end of the document. Only references that are cited before the
+/// bibliography is placed are included.
+pub struct Citations<'b, 's, I> {
+    events: I,
+    bibliography: &'b Bibliography,
+    /// Keys of cited references, in order of first citation.
+    cited: Vec<&'b str>,
+    /// Events that have been created but not yet emitted.
+    pending: std::collections::VecDeque<Event<'s>>,
+    /// Style used to format citations and entries.
+    style: Box<dyn Style + 'b>,
+    /// Nesting depth of containers whose text should not be searched for citations.
+    verbatim: usize,
+    /// Event that ended the last run of text events, not yet handled.
+    peeked: Option<Event<'s>>,
+    /// The bibliography has been placed.
+    placed: bool,
+}
+
+impl<'b, 's, I> Citations<'b, 's, I>
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    /// Create an adapter that resolves citations using the provided bibliography.
+    pub fn new(events: I, bibliography: &'b Bibliography) -> Self {
+        Self {
+            events,
+            bibliography,
+            cited: Vec::new(),
+            pending: std::collections::VecDeque::new(),
+            style: Box::new(AuthorYear),
+            verbatim: 0,
+            peeked: None,
+            placed: false,
+        }
+    }
+
+    /// Use a different citation style than the default [`AuthorYear`] style.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::citation::*;
+    /// # use jotdown::*;
+    /// let bib = Bibliography::from_bibtex("@book{doe99, author = {John Doe}}").unwrap();
+    /// let events = Parser::new("[@doe99, p. 3]");
+    /// let mut events = Citations::new(events, &bib).with_style(Numeric);
+    /// assert_eq!(events.nth(2), Some(Event::Str("[1, p. 3]".into())));
+    /// ```
+    #[must_use]
+    pub fn with_style<S: Style + 'b>(mut self, style: S) -> Self {
+        self.style = Box::new(style);
+        self
+    }
+
+    /// Format a citation and mark its references as cited.
+    fn cite(&mut self, items: &[CitationItem]) -> Vec<Event<'s>> {
+        let mut cites = Vec::with_capacity(items.len());
+        for item in items {
+            let reference = self.bibliography.get(item.id);
+            let number = if let Some(r) = reference {
+                if let Some(i) = self.cited.iter().position(|id| *id == r.id) {
+                    i + 1
+                } else {
+                    self.cited.push(&r.id);
+                    self.cited.len()
+                }
+            } else {
+                0
+            };
+            cites.push(Cite {
+                prefix: item.prefix,
+                id: item.id,
+                suffix: item.suffix,
+                reference,
+                number,
+            });
+        }
+        let s = self.style.citation(&cites);
+
+        let ids = items
+            .iter()
+            .map(|item| item.id)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let attrs: Attributes = vec![
+            (AttributeKind::Class, AttributeValue::from("citation")),
+            (AttributeKind::Pair { key: "data-cites" }, ids.into()),
+        ]
+        .into();
+        vec![
+            Event::Start(Container::Span, attrs),
+            Event::Str(s.into()),
+            Event::End(Container::Span),
+        ]
+    }
+
+    /// Split a run of text events into text and citations.
+    ///
+    /// Citations are searched for in the combined text of the events, so they may contain
+    /// smart punctuation, e.g. an en dash within a page range.
+    fn split(&mut self, run: Vec<Event<'s>>) {
+        let mut text = String::new();
+        let ranges = run
+            .iter()
+            .map(|e| {
+                let start = text.len();
+                crate::outline::push_text(&mut text, e);
+                start..text.len()
+            })
+            .collect::<Vec<_>>();
+
+        let mut citations = Vec::new();
+        let mut pos = 0;
+        while let Some((range, items)) = find_citation(&text[pos..]) {
+            let range = (pos + range.start)..(pos + range.end);
+            citations.push((range.clone(), self.cite(&items)));
+            pos = range.end;
+        }
+
+        let mut citations = citations.into_iter().peekable();
+        for (e, r) in run.into_iter().zip(ranges) {
+            let mut pos = r.start;
+            while pos < r.end {
+                let (start, end) = citations
+                    .peek()
+                    .map_or((r.end, r.end), |(c, _)| (c.start, c.end));
+                if start <= pos {
+                    if end <= r.end {
+                        self.pending.extend(citations.next().unwrap().1);
+                    }
+                    pos = end.min(r.end);
+                } else {
+                    let until = start.min(r.end);
+                    self.pending.push_back(match &e {
+                        Event::Str(s) => Event::Str(substr(s, pos - r.start..until - r.start)),
+                        e => e.clone(),
+                    });
+                    pos = until;
+                }
+            }
+        }
+    }
+
+    /// Events for the entries of all cited references.
+    fn entries(&self) -> Vec<Event<'s>> {
+        let mut refs = self
+            .cited
+            .iter()
+            .filter_map(|id| self.bibliography.get(id))
+            .zip(1..)
+            .collect::<Vec<_>>();
+        refs.sort_by(|a, b| self.style.order(*a, *b));
+        let mut events = Vec::new();
+        for (r, n) in refs {
+            let attrs: Attributes =
+                vec![(AttributeKind::Id, format!("ref-{}", r.id).into())].into();
+            events.push(Event::Start(Container::Div { class: "" }, attrs));
+            events.push(Event::Start(Container::Paragraph, Attributes::new()));
+            events.extend(self.style.entry(r, n));
+            events.push(Event::End(Container::Paragraph));
+            events.push(Event::End(Container::Div { class: "" }));
+        }
+        events
+    }
+}
+
+impl<'b, 's, I> Iterator for Citations<'b, 's, I>
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    type Item = Event<'s>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.pending.pop_front() {
+            return Some(e);
+        }
+
+        match self.peeked.take().or_else(|| self.events.next()) {
+            Some(e) if self.verbatim == 0 && is_text(&e) => {
+                let mut run = vec![e];
+                loop {
+                    match self.events.next() {
+                        Some(e) if is_text(&e) => run.push(e),
+                        e => {
+                            self.peeked = e;
+                            break;
+                        }
+                    }
+                }
+                self.split(run);
+                self.next()
+            }
+            Some(Event::Start(c, attrs)) => {
+                if is_verbatim(&c) {
+                    self.verbatim += 1;
+                } else if matches!(c, Container::Div { .. })
+                    && !self.placed
+                    && attrs
+                        .get_value("id")
+                        .map_or(false, |id| id.to_string() == "refs")
+                {
+                    self.placed = true;
+                    self.pending.extend(self.entries());
+                }
+                Some(Event::Start(c, attrs))
+            }
+            Some(Event::End(c)) => {
+                if is_verbatim(&c) {
+                    self.verbatim -= 1;
+                }
+                Some(Event::End(c))
+            }
+            Some(e) => Some(e),
+            None => {
+                if self.placed || self.cited.is_empty() {
+                    return None;
+                }
+                self.placed = true;
+                let attrs: Attributes = vec![
+                    (AttributeKind::Class, "references".into()),
+                    (AttributeKind::Id, "refs".into()),
+                ]
+                .into();
+                self.pending.extend(self.entries());
+                self.pending
+                    .push_back(Event::End(Container::Div { class: "" }));
+                Some(Event::Start(Container::Div { class: "" }, attrs))
+            }
+        }
+    }
+}
+
+/// Events whose text may be part of a citation.
+fn is_text(e: &Event) -> bool {
+    matches!(
+        e,
+        Event::Str(..)
+            | Event::LeftSingleQuote
+            | Event::RightSingleQuote
+            | Event::LeftDoubleQuote
+            | Event::RightDoubleQuote
+            | Event::Ellipsis
+            | Event::EnDash
+            | Event::EmDash
+            | Event::NonBreakingSpace
+            | Event::Softbreak
+    )
+}
+
+/// Containers whose content should not be searched for citations.
+fn is_verbatim(c: &Container) -> bool {
+    matches!(
+        c,
+        Container::CodeBlock { .. }
+            | Container::CustomBlock { .. }
+            | Container::RawBlock { .. }
+            | Container::RawInline { .. }
+            | Container::Verbatim
+            | Container::Math { .. }
+            | Container::LinkDefinition { .. }
+            | Container::Link(..)
+            | Container::Image(..)
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::Bibliography;
+    use super::Citations;
+    use super::Date;
+    use super::Name;
+    use super::Reference;
+    use crate::Attributes;
+    use crate::Container::*;
+    use crate::Event;
+    use crate::Event::*;
+
+    fn bib() -> Bibliography {
+        [
+            Reference {
+                id: "a".to_string(),
+                title: Some("Title A".to_string()),
+                author: vec![Name::new("Doe", "John"), Name::new("Roe", "Jane")],
+                issued: Some(Date::year(2001)),
+                ..Reference::default()
+            },
+            Reference {
+                id: "b".to_string(),
+                title: Some("Title B".to_string()),
+                container_title: Some("Journal".to_string()),
+                author: vec![Name::new("Abe", "Al")],
+                ..Reference::default()
+            },
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    fn cite(text: &str, ids: &str) -> Vec<Event<'static>> {
+        vec![
+            Start(
+                Span,
+                vec![
+                    (crate::AttributeKind::Class, "citation".into()),
+                    (
+                        crate::AttributeKind::Pair { key: "data-cites" },
+                        ids.to_string().into(),
+                    ),
+                ]
+                .into(),
+            ),
+            Str(text.to_string().into()),
+            End(Span),
+        ]
+    }
+
+    #[test]
+    fn multiple() {
+        let bib = bib();
+        let events = Citations::new(crate::Parser::new("x [@a; see @b, p. 1] y"), &bib)
+            .take(7)
+            .collect::<Vec<_>>();
+        let mut expected = vec![Start(Paragraph, Attributes::new()), Str("x ".into())];
+        expected.extend(cite("(Doe and Roe 2001; see Abe n.d., p. 1)", "a b"));
+        expected.push(Str(" y".into()));
+        expected.push(End(Paragraph));
+        assert_eq!(events, expected);
+    }
+
+    #[test]
+    fn unresolved() {
+        let bib = bib();
+        let events = Citations::new(crate::Parser::new("[@c]"), &bib).collect::<Vec<_>>();
+        let mut expected = vec![Start(Paragraph, Attributes::new())];
+        expected.extend(cite("(c?)", "c"));
+        expected.push(End(Paragraph));
+        assert_eq!(events, expected);
+    }
+
+    #[test]
+    fn numeric() {
+        let bib = bib();
+        let events = Citations::new(crate::Parser::new("[@b] [@a; @b]"), &bib)
+            .with_style(super::Numeric)
+            .collect::<Vec<_>>();
+        let mut expected = vec![Start(Paragraph, Attributes::new())];
+        expected.extend(cite("[1]", "b"));
+        expected.push(Str(" ".into()));
+        expected.extend(cite("[2; 1]", "a b"));
+        expected.push(End(Paragraph));
+        assert_eq!(&events[..9], expected);
+        assert_eq!(events[12], Str("[1] ".into()));
+        assert_eq!(events[13], Str("Abe, Al. n.d. “Title B.” ".into()));
+    }
+
+    #[test]
+    fn punctuation() {
+        let bib = bib();
+        let src = "x [@a, pp. 3--5] [see \"B\" @b]...";
+        let events = Citations::new(crate::Parser::new(src), &bib).collect::<Vec<_>>();
+        let mut expected = vec![Start(Paragraph, Attributes::new()), Str("x ".into())];
+        expected.extend(cite("(Doe and Roe 2001, pp. 3–5)", "a"));
+        expected.push(Str(" ".into()));
+        expected.extend(cite("(see “B” Abe n.d.)", "b"));
+        expected.push(Ellipsis);
+        expected.push(End(Paragraph));
+        assert_eq!(&events[..expected.len()], expected);
+    }
+
+    #[test]
+    fn not_citation() {
+        let bib = bib();
+        let src = "[a] [email@a] `[@a]`";
+        let events = Citations::new(crate::Parser::new(src), &bib);
+        assert_eq!(
+            events.collect::<Vec<_>>(),
+            crate::Parser::new(src).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn placed() {
+        let bib = bib();
+        let src = concat!(
+            "[@b]\n", //
+            "\n",
+            "{#refs}\n",
+            ":::\n",
+            ":::\n",
+        );
+        let events = Citations::new(crate::Parser::new(src), &bib).collect::<Vec<_>>();
+        assert_eq!(
+            &events[6..],
+            &[
+                Start(
+                    Div { class: "" },
+                    vec![(crate::AttributeKind::Id, "refs".into())].into()
+                ),
+                Start(
+                    Div { class: "" },
+                    vec![(crate::AttributeKind::Id, "ref-b".into())].into()
+                ),
+                Start(Paragraph, Attributes::new()),
+                Str("Abe, Al. n.d. “Title B.” ".into()),
+                Start(Emphasis, Attributes::new()),
+                Str("Journal".into()),
+                End(Emphasis),
+                Str(".".into()),
+                End(Paragraph),
+                End(Div { class: "" }),
+                End(Div { class: "" }),
+            ]
+        );
+    }
+}
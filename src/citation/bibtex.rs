@@ -0,0 +1,457 @@
+//! Parser for BibTeX databases.
+
+use super::Date;
+use super::Name;
+use super::ParseBibtexError;
+use super::Reference;
+
+/// Parse all entries of a BibTeX database.
+pub(super) fn parse(src: &str) -> Result<Vec<Reference>, ParseBibtexError> {
+    let mut p = Parser {
+        src,
+        pos: 0,
+        strings: Vec::new(),
+    };
+    let mut refs = Vec::new();
+    while let Some(at) = src[p.pos..].find('@') {
+        p.pos += at + 1;
+        let kind = p.ident().to_ascii_lowercase();
+        p.ws();
+        let close = match p.peek() {
+            Some('{') if !kind.is_empty() => '}',
+            Some('(') if !kind.is_empty() => ')',
+            // text between entries is ignored, e.g. an email address
+            _ => continue,
+        };
+        p.pos += 1;
+        match kind.as_str() {
+            "comment" | "preamble" => {
+                p.pos -= 1;
+                p.skip_group()?;
+            }
+            "string" => {
+                let (name, value) = p.field()?;
+                p.strings.push((name, value));
+                p.ws();
+                p.expect(close)?;
+            }
+            _ => {
+                p.ws();
+                let key_len = src[p.pos..]
+                    .find(|c: char| c == ',' || c == close || c.is_whitespace())
+                    .ok_or_else(|| p.err())?;
+                let key = &src[p.pos..p.pos + key_len];
+                p.pos += key_len;
+                let mut fields = Vec::new();
+                loop {
+                    p.ws();
+                    match p.peek() {
+                        Some(',') => p.pos += 1,
+                        Some(c) if c == close => {
+                            p.pos += 1;
+                            break;
+                        }
+                        _ => return Err(p.err()),
+                    }
+                    p.ws();
+                    if p.peek() == Some(close) {
+                        p.pos += 1;
+                        break;
+                    }
+                    fields.push(p.field()?);
+                }
+                refs.push(reference(key, &kind, fields));
+            }
+        }
+    }
+    Ok(refs)
+}
+
+struct Parser<'s> {
+    src: &'s str,
+    pos: usize,
+    /// Values defined by `@string` entries.
+    strings: Vec<(String, String)>,
+}
+
+impl<'s> Parser<'s> {
+    fn err(&self) -> ParseBibtexError {
+        ParseBibtexError { pos: self.pos }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), ParseBibtexError> {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            Ok(())
+        } else {
+            Err(self.err())
+        }
+    }
+
+    fn ws(&mut self) {
+        let s = &self.src[self.pos..];
+        self.pos += s.len() - s.trim_start().len();
+    }
+
+    fn ident(&mut self) -> &'s str {
+        let s = &self.src[self.pos..];
+        let len = s
+            .find(|c: char| !(c.is_alphanumeric() || "_-:.+/'".contains(c)))
+            .unwrap_or(s.len());
+        self.pos += len;
+        &s[..len]
+    }
+
+    /// Skip a group delimited by braces or parentheses, returns its content.
+    fn skip_group(&mut self) -> Result<&'s str, ParseBibtexError> {
+        let (open, close) = match self.peek() {
+            Some('(') => ('(', ')'),
+            _ => ('{', '}'),
+        };
+        self.expect(open)?;
+        let start = self.pos;
+        let mut depth = 1;
+        for (i, c) in self.src[start..].char_indices() {
+            if c == open {
+                depth += 1;
+            } else if c == close {
+                depth -= 1;
+                if depth == 0 {
+                    self.pos = start + i + 1;
+                    return Ok(&self.src[start..start + i]);
+                }
+            }
+        }
+        self.pos = self.src.len();
+        Err(self.err())
+    }
+
+    /// Parse a field of the form `name = value # value ...`.
+    fn field(&mut self) -> Result<(String, String), ParseBibtexError> {
+        self.ws();
+        let name = self.ident().to_ascii_lowercase();
+        if name.is_empty() {
+            return Err(self.err());
+        }
+        self.ws();
+        self.expect('=')?;
+        let mut value = String::new();
+        loop {
+            self.ws();
+            match self.peek() {
+                Some('{') => value.push_str(self.skip_group()?),
+                Some('"') => {
+                    self.pos += 1;
+                    let start = self.pos;
+                    let mut depth = 0;
+                    let len = self.src[start..]
+                        .char_indices()
+                        .find(|(_, c)| {
+                            match c {
+                                '{' => depth += 1,
+                                '}' => depth -= 1,
+                                '"' if depth == 0 => return true,
+                                _ => {}
+                            }
+                            false
+                        })
+                        .map(|(i, _)| i)
+                        .ok_or_else(|| self.err())?;
+                    value.push_str(&self.src[start..start + len]);
+                    self.pos = start + len + 1;
+                }
+                Some(c) if c.is_alphanumeric() => {
+                    let ident = self.ident();
+                    if let Some((_, v)) = self
+                        .strings
+                        .iter()
+                        .find(|(n, _)| n.eq_ignore_ascii_case(ident))
+                    {
+                        value.push_str(v);
+                    } else {
+                        value.push_str(ident);
+                    }
+                }
+                _ => return Err(self.err()),
+            }
+            self.ws();
+            if self.peek() == Some('#') {
+                self.pos += 1;
+            } else {
+                return Ok((name, value));
+            }
+        }
+    }
+}
+
+/// Convert the fields of an entry to a reference.
+fn reference(key: &str, kind: &str, fields: Vec<(String, String)>) -> Reference {
+    let kind = match kind {
+        "article" => "article-journal",
+        "book" | "booklet" => "book",
+        "inbook" | "incollection" => "chapter",
+        "inproceedings" | "conference" => "paper-conference",
+        "mastersthesis" | "phdthesis" => "thesis",
+        "techreport" => "report",
+        "manual" => "book",
+        "online" => "webpage",
+        _ => "document",
+    };
+    let mut r = Reference {
+        id: key.to_string(),
+        kind: Some(kind.to_string()),
+        ..Reference::default()
+    };
+    let mut year = None;
+    let mut month = None;
+    for (name, value) in fields {
+        match name.as_str() {
+            "author" => r.author = names(&value),
+            "editor" => r.editor = names(&value),
+            "year" => year = Some(value),
+            "month" => month = Some(value),
+            "doi" => r.doi = Some(value),
+            "url" => r.url = Some(value),
+            _ => {
+                let value = Some(text(&value));
+                match name.as_str() {
+                    "title" => r.title = value,
+                    "journal" | "booktitle" | "journaltitle" => r.container_title = value,
+                    "publisher" | "school" | "institution" | "organization" => {
+                        r.publisher = r.publisher.or(value);
+                    }
+                    "volume" => r.volume = value,
+                    "number" | "issue" => r.issue = value,
+                    "pages" => r.page = value,
+                    _ => {}
+                }
+            }
+        }
+    }
+    r.issued = year.map(|year| {
+        let year = text(&year);
+        match year.trim().parse() {
+            Ok(y) => {
+                let mut parts = vec![y];
+                if let Some(m) = month.as_deref().and_then(month_number) {
+                    parts.push(m);
+                }
+                Date {
+                    date_parts: vec![parts],
+                    literal: None,
+                }
+            }
+            Err(_) => Date {
+                date_parts: Vec::new(),
+                literal: Some(year),
+            },
+        }
+    });
+    r
+}
+
+fn month_number(month: &str) -> Option<i32> {
+    let month = month.trim().to_ascii_lowercase();
+    month.parse().ok().or_else(|| {
+        [
+            "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+        ]
+        .iter()
+        .position(|m| month.starts_with(m))
+        .map(|i| i as i32 + 1)
+    })
+}
+
+/// Split a list of names separated by `and`.
+fn names(s: &str) -> Vec<Name> {
+    let mut names = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    let words = s.split_whitespace().collect::<Vec<_>>();
+    for (i, w) in words.iter().enumerate() {
+        if depth == 0 && w.eq_ignore_ascii_case("and") {
+            names.push(name(&words[start..i]));
+            start = i + 1;
+        }
+        depth += w.matches('{').count() as isize - w.matches('}').count() as isize;
+    }
+    names.push(name(&words[start..]));
+    names.retain(|n| *n != Name::default());
+    names
+}
+
+/// Parse a name in either the `First Last` or the `Last, First` form.
+fn name(words: &[&str]) -> Name {
+    let s = words.join(" ");
+    if let Some((family, given)) = s.split_once(',') {
+        Name {
+            family: Some(text(family.trim())),
+            given: Some(text(given.trim())).filter(|g| !g.is_empty()),
+            literal: None,
+        }
+    } else if is_group(&s) {
+        Name {
+            literal: Some(text(&s)),
+            ..Name::default()
+        }
+    } else if let Some((family, given)) = words.split_last() {
+        Name {
+            family: Some(text(family)),
+            given: Some(text(&given.join(" "))).filter(|g| !g.is_empty()),
+            literal: None,
+        }
+    } else {
+        Name::default()
+    }
+}
+
+/// Check if the whole string is enclosed within a single pair of braces.
+fn is_group(s: &str) -> bool {
+    let mut depth = 0;
+    s.starts_with('{')
+        && s.char_indices().all(|(i, c)| {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ => {}
+            }
+            depth > 0 || i + 1 == s.len()
+        })
+}
+
+/// Convert a BibTeX value to plain text, removing braces and common TeX commands.
+fn text(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' => {}
+            '~' => out.push('\u{a0}'),
+            '-' if chars.peek() == Some(&'-') => {
+                chars.next();
+                if chars.peek() == Some(&'-') {
+                    chars.next();
+                    out.push('—');
+                } else {
+                    out.push('–');
+                }
+            }
+            '\\' => match chars.next() {
+                Some(c) if accent(c).is_some() => {
+                    // accented letter, e.g. `\"o` or `\"{o}`
+                    let brace = chars.next_if_eq(&'{').is_some();
+                    if let Some(l) = chars.next() {
+                        out.push(l);
+                        out.extend(accent(c));
+                    }
+                    if brace {
+                        chars.next_if_eq(&'}');
+                    }
+                }
+                Some(c) if c.is_ascii_punctuation() => out.push(c),
+                Some(c) => {
+                    // skip the command name, the argument is kept
+                    let mut cmd = c.to_string();
+                    while let Some(c) = chars.next_if(|c| c.is_ascii_alphabetic()) {
+                        cmd.push(c);
+                    }
+                    if cmd == "LaTeX" || cmd == "TeX" {
+                        out.push_str(&cmd);
+                    }
+                }
+                None => {}
+            },
+            c if c.is_whitespace() => {
+                if !out.ends_with(' ') {
+                    out.push(' ');
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out.trim().to_string()
+}
+
+/// Combining character of a TeX accent command, e.g. `\"` for an umlaut.
+fn accent(c: char) -> Option<char> {
+    match c {
+        '"' => Some('\u{308}'),
+        '\'' => Some('\u{301}'),
+        '`' => Some('\u{300}'),
+        '^' => Some('\u{302}'),
+        '~' => Some('\u{303}'),
+        '=' => Some('\u{304}'),
+        '.' => Some('\u{307}'),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::Date;
+    use super::super::Name;
+    use super::super::ParseBibtexError;
+
+    #[test]
+    fn entry() {
+        let refs = super::parse(concat!(
+            "@string{jn = \"Journal\"}\n",
+            "@comment{ignored @book{x}}\n",
+            "@Article(a,\n",
+            "  author = \"{Acme Corp.} and van Doe, J{\\\"o}hn and Jane Roe\",\n",
+            "  journal = jn # { of Things},\n",
+            "  pages = {1--10},\n",
+            "  year = {2001}, month = feb,\n",
+            ")\n",
+        ))
+        .unwrap();
+        assert_eq!(refs.len(), 1);
+        let r = &refs[0];
+        assert_eq!(r.id, "a");
+        assert_eq!(r.kind.as_deref(), Some("article-journal"));
+        assert_eq!(
+            r.author,
+            &[
+                Name {
+                    literal: Some("Acme Corp.".to_string()),
+                    ..Name::default()
+                },
+                Name::new("van Doe", "Jo\u{308}hn"),
+                Name::new("Roe", "Jane"),
+            ]
+        );
+        assert_eq!(r.container_title.as_deref(), Some("Journal of Things"));
+        assert_eq!(r.page.as_deref(), Some("1–10"));
+        assert_eq!(
+            r.issued,
+            Some(Date {
+                date_parts: vec![vec![2001, 2]],
+                literal: None,
+            })
+        );
+    }
+
+    #[test]
+    fn text() {
+        let refs = super::parse(concat!(
+            "Maintained by jane@example.com, @ 2001.\n",
+            "@book{a, title = {b}}\n",
+            "@{x} @\n",
+        ))
+        .unwrap();
+        assert_eq!(refs.len(), 1);
+        assert_eq!(refs[0].id, "a");
+    }
+
+    #[test]
+    fn unterminated() {
+        assert_eq!(
+            super::parse("@book{a, title = {b}"),
+            Err(ParseBibtexError { pos: 20 })
+        );
+    }
+}
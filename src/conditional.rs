@@ -0,0 +1,160 @@
+//! Conditional content.
+//!
+//! A single source may be used to produce several variants of a document, e.g. one for the web
+//! and one for print. Elements with a class of the form `if-<target>`, e.g. `{.if-print}` or
+//! `::: if-html`, are only included when rendering for that target. An element with several
+//! such classes is included if any of its targets is active.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! use jotdown::conditional::Conditional;
+//!
+//! let src = concat!(
+//!     "::: if-html\n",
+//!     "Watch the [video](https://example.com/video).\n",
+//!     ":::\n",
+//!     "\n",
+//!     "{.if-print}\n",
+//!     "Scan the QR code.\n",
+//!     "\n",
+//!     "Read the [manual]{.if-html}[printed manual]{.if-print}.\n",
+//! );
+//! let events = Conditional::new(jotdown::Parser::new(src)).with_target("print");
+//! assert_eq!(
+//!     jotdown::html::render_to_string(events),
+//!     concat!(
+//!         "<p class=\"if-print\">Scan the QR code.</p>\n",
+//!         "<p>Read the <span class=\"if-print\">printed manual</span>.</p>\n",
+//!     ),
+//! );
+//! # }
+//! ```
+
+use crate::Attributes;
+use crate::Container;
+use crate::Event;
+
+/// An iterator adapter that drops elements whose targets are not active.
+pub struct Conditional<I> {
+    events: I,
+    targets: Vec<String>,
+    /// The previous event ended a block element that was dropped.
+    dropped_block: bool,
+}
+
+impl<'s, I> Conditional<I>
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    /// Create an adapter without any active targets, i.e. that drops all conditional elements.
+    pub fn new(events: I) -> Self {
+        Self {
+            events,
+            targets: Vec::new(),
+            dropped_block: false,
+        }
+    }
+
+    /// Include elements with the `if-<target>` class.
+    #[must_use]
+    pub fn with_target(mut self, target: &str) -> Self {
+        self.targets.push(target.to_string());
+        self
+    }
+
+    /// Returns `true` if the element is conditional and none of its targets are active.
+    fn is_excluded(&self, div_class: &str, attrs: &Attributes) -> bool {
+        let classes = attrs
+            .get_value("class")
+            .map_or_else(String::new, |c| c.to_string());
+        let mut conditional = false;
+        for class in std::iter::once(div_class).chain(classes.split(' ')) {
+            if let Some(target) = class.strip_prefix("if-") {
+                if self.targets.iter().any(|t| t == target) {
+                    return false;
+                }
+                conditional = true;
+            }
+        }
+        conditional
+    }
+}
+
+impl<'s, I> Iterator for Conditional<I>
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    type Item = Event<'s>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let e = self.events.next()?;
+            // also drop the blank line that separates a dropped block from the next one
+            if std::mem::replace(&mut self.dropped_block, false) && matches!(e, Event::Blankline) {
+                continue;
+            }
+            let excluded = match &e {
+                Event::Start(Container::Div { class }, attrs) => self.is_excluded(class, attrs),
+                Event::Start(_, attrs) | Event::ThematicBreak(attrs) => self.is_excluded("", attrs),
+                _ => false,
+            };
+            if !excluded {
+                return Some(e);
+            }
+            self.dropped_block = match &e {
+                Event::Start(c, _) => c.is_block(),
+                _ => true,
+            };
+            if let Event::Start(..) = e {
+                let mut depth = 0;
+                for e in self.events.by_ref() {
+                    match e {
+                        Event::Start(..) => depth += 1,
+                        Event::End(..) if depth == 0 => break,
+                        Event::End(..) => depth -= 1,
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Conditional;
+    use crate::Event;
+
+    fn text(src: &str, targets: &[&str]) -> String {
+        targets
+            .iter()
+            .fold(Conditional::new(crate::Parser::new(src)), |c, t| {
+                c.with_target(t)
+            })
+            .filter_map(|e| match e {
+                Event::Str(s) => Some(s.to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn targets() {
+        let src = "[a]{.if-x}[b]{.if-y .if-z}[c]{.if-x .if-y}d";
+        assert_eq!(text(src, &[]), "d");
+        assert_eq!(text(src, &["x"]), "acd");
+        assert_eq!(text(src, &["z"]), "bd");
+        assert_eq!(text(src, &["x", "y"]), "abcd");
+    }
+
+    #[test]
+    fn blocks() {
+        let src = "{.if-x}\n# a\n\nb\n\n# c\n\n{.if-x}\n***\n\n:::: if-y\n::: if-x\nd\n:::\n::::\n";
+        assert_eq!(text(src, &[]), "c");
+        assert_eq!(text(src, &["x"]), "abc");
+        assert_eq!(text(src, &["x", "y"]), "abcd");
+    }
+}
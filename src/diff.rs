@@ -0,0 +1,561 @@
+//! Structural comparison of documents.
+//!
+//! Two documents are compared block by block rather than line by line, so that e.g. a rewrapped
+//! paragraph is considered unchanged while a change of its inline formatting is not. Sections
+//! are transparent, their headings and contents are compared as separate blocks.
+//!
+//! Differences may also be applied as a [`Patch`], or combined by a three-way [`merge`].
+//!
+//! # Examples
+//!
+//! ```
+//! use jotdown::diff::*;
+//!
+//! let old = "# Title\n\nsome text\nwrapped\n\n- a\n- b\n";
+//! let new = "# Title\n\nsome text wrapped\n\n- a\n- c\n\nmore\n";
+//! assert_eq!(
+//!     diff(old, new),
+//!     &[
+//!         Change::Changed {
+//!             old: 28..36,
+//!             new: 28..37,
+//!         },
+//!         Change::Inserted { new: 37..42 },
+//!     ],
+//! );
+//! ```
+
+use std::ops::Range;
+
+use crate::Container;
+use crate::Event;
+
+/// A difference between two documents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// A block that only exists in the new document.
+    Inserted {
+        /// Location of the block in the new document.
+        new: Range<usize>,
+    },
+    /// A block that only exists in the old document.
+    Removed {
+        /// Location of the block in the old document.
+        old: Range<usize>,
+    },
+    /// A block of the old document that has been replaced by a block of the new document.
+    Changed {
+        /// Location of the block in the old document.
+        old: Range<usize>,
+        /// Location of the block in the new document.
+        new: Range<usize>,
+    },
+}
+
+/// A top-level block of a document, excluding sections.
+#[derive(Debug, Clone)]
+struct Block<'s> {
+    /// Events of the block, with adjacent text merged.
+    events: Vec<Event<'s>>,
+    /// Location of the block in the source.
+    span: Range<usize>,
+    /// Location of the block in the source, including any preceding attributes and any
+    /// following blank lines.
+    text: Range<usize>,
+}
+
+/// Split a document into its top-level blocks.
+fn blocks(src: &str) -> Vec<Block<'_>> {
+    let mut blocks = Vec::new();
+    let mut current: Option<Block> = None;
+    let mut depth = 0;
+    // start of the text of the next block
+    let mut next = 0;
+    for (e, span) in crate::Parser::new(src).into_offset_iter() {
+        match e {
+            Event::Start(Container::Section { .. }, _) | Event::End(Container::Section { .. })
+                if depth == 0 => {}
+            Event::Blankline if depth == 0 => next = span.end,
+            Event::Start(..) => {
+                depth += 1;
+                current
+                    .get_or_insert_with(|| Block {
+                        events: Vec::new(),
+                        span: span.clone(),
+                        text: next..next,
+                    })
+                    .events
+                    .push(e);
+            }
+            Event::End(..) => {
+                depth -= 1;
+                if let Some(block) = &mut current {
+                    block.events.push(e);
+                    block.span.end = span.end;
+                }
+                if depth == 0 {
+                    blocks.extend(current.take());
+                    next = span.end;
+                }
+            }
+            e => {
+                if let Some(block) = &mut current {
+                    // merge text so that rewrapped paragraphs are considered equal
+                    let e = if let Event::Softbreak = e {
+                        Event::Str(" ".into())
+                    } else {
+                        e
+                    };
+                    match (block.events.last_mut(), e) {
+                        (Some(Event::Str(prev)), Event::Str(s)) => {
+                            *prev = format!("{}{}", prev, s).into();
+                        }
+                        (_, e) => block.events.push(e),
+                    }
+                } else {
+                    blocks.push(Block {
+                        events: vec![e],
+                        text: next..next,
+                        span: span.clone(),
+                    });
+                    next = span.end;
+                }
+            }
+        }
+    }
+    // extend each block until the next one, so that the whole source is covered
+    let mut end = src.len();
+    for (i, b) in blocks.iter_mut().enumerate().rev() {
+        b.text = if i == 0 { 0 } else { b.text.start }..end;
+        end = b.text.start;
+    }
+    blocks
+}
+
+/// An operation that transforms a sequence of blocks into another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    /// Block of both sequences, by old and new index.
+    Keep(usize, usize),
+    /// Block of the old sequence, by index.
+    Remove(usize),
+    /// Block of the new sequence, by index.
+    Insert(usize),
+}
+
+/// Determine a minimal sequence of operations that transforms `old` into `new`, using the longest
+/// common subsequence of blocks.
+fn ops(old: &[Block], new: &[Block]) -> Vec<Op> {
+    let (n, m) = (old.len(), new.len());
+    // length of the longest common subsequence of old[i..] and new[j..]
+    let mut lcs = vec![vec![0; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old[i].events == new[j].events {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && old[i].events == new[j].events {
+            ops.push(Op::Keep(i, j));
+            i += 1;
+            j += 1;
+        } else if j < m && (i == n || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            ops.push(Op::Insert(j));
+            j += 1;
+        } else {
+            ops.push(Op::Remove(i));
+            i += 1;
+        }
+    }
+    ops
+}
+
+/// Compare two documents and list the blocks that differ.
+///
+/// Adjacent removed and inserted blocks are paired up as changed blocks.
+#[must_use]
+pub fn diff(old: &str, new: &str) -> Vec<Change> {
+    let (old, new) = (blocks(old), blocks(new));
+    let mut changes = Vec::new();
+    let mut removed = Vec::new();
+    let mut inserted = Vec::new();
+    let mut flush = |removed: &mut Vec<usize>, inserted: &mut Vec<usize>| {
+        let paired = removed.len().min(inserted.len());
+        for (i, j) in removed.iter().zip(inserted.iter()) {
+            changes.push(Change::Changed {
+                old: old[*i].span.clone(),
+                new: new[*j].span.clone(),
+            });
+        }
+        for i in &removed[paired..] {
+            changes.push(Change::Removed {
+                old: old[*i].span.clone(),
+            });
+        }
+        for j in &inserted[paired..] {
+            changes.push(Change::Inserted {
+                new: new[*j].span.clone(),
+            });
+        }
+        removed.clear();
+        inserted.clear();
+    };
+    for op in ops(&old, &new) {
+        match op {
+            Op::Keep(..) => flush(&mut removed, &mut inserted),
+            Op::Remove(i) => removed.push(i),
+            Op::Insert(j) => inserted.push(j),
+        }
+    }
+    flush(&mut removed, &mut inserted);
+    changes
+}
+
+/// A replacement of a range of blocks of the base document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Edit {
+    /// Index of the first replaced block.
+    start: usize,
+    /// Index after the last replaced block.
+    end: usize,
+    /// Source of the replaced blocks.
+    removed: String,
+    /// Source of the blocks that replace them.
+    inserted: String,
+}
+
+/// Group the operations into edits of the old document.
+fn edits(old_src: &str, old: &[Block], new_src: &str, new: &[Block]) -> Vec<Edit> {
+    let mut edits: Vec<Edit> = Vec::new();
+    let mut i = 0;
+    let mut open = false;
+    for op in ops(old, new) {
+        let edit = match op {
+            Op::Keep(..) => {
+                open = false;
+                i += 1;
+                continue;
+            }
+            Op::Remove(..) | Op::Insert(..) => {
+                if !open {
+                    edits.push(Edit {
+                        start: i,
+                        end: i,
+                        removed: String::new(),
+                        inserted: String::new(),
+                    });
+                    open = true;
+                }
+                edits.last_mut().unwrap()
+            }
+        };
+        match op {
+            Op::Remove(r) => {
+                edit.end += 1;
+                edit.removed.push_str(&old_src[old[r].text.clone()]);
+                i += 1;
+            }
+            Op::Insert(j) => edit.inserted.push_str(&new_src[new[j].text.clone()]),
+            Op::Keep(..) => unreachable!(),
+        }
+    }
+    edits
+}
+
+/// Append the source of blocks, separated by a blank line from any preceding blocks.
+fn push_blocks(out: &mut String, text: &str) {
+    if !out.is_empty() && !text.is_empty() && !out.ends_with("\n\n") {
+        out.push_str(if out.ends_with('\n') { "\n" } else { "\n\n" });
+    }
+    out.push_str(text);
+}
+
+/// Apply non-overlapping edits, sorted by position, to a document.
+fn assemble(src: &str, blocks: &[Block], edits: &[&Edit]) -> String {
+    let mut out = String::with_capacity(src.len());
+    let mut edits = edits.iter().peekable();
+    let mut i = 0;
+    while i <= blocks.len() {
+        if let Some(edit) = edits.next_if(|e| e.start == i) {
+            push_blocks(&mut out, &edit.inserted);
+            if edit.end > i {
+                i = edit.end;
+                continue;
+            }
+        }
+        if let Some(b) = blocks.get(i) {
+            push_blocks(&mut out, &src[b.text.clone()]);
+        }
+        i += 1;
+    }
+    if blocks.is_empty() {
+        out.push_str(src);
+    }
+    out
+}
+
+/// A set of changes that transforms one document into another.
+///
+/// # Examples
+///
+/// ```
+/// # use jotdown::diff::*;
+/// let patch = Patch::new("a\n\nb\n", "a\n\n_b_\n");
+/// assert_eq!(patch.apply("a\n\nb\n").unwrap(), "a\n\n_b_\n");
+/// assert_eq!(patch.apply("a\n\nc\n"), Err(PatchError { block: 1 }));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Patch {
+    edits: Vec<Edit>,
+}
+
+/// Error returned by [`Patch::apply`] when a document does not match the patch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PatchError {
+    /// Index of the first top-level block that differs from the expected block.
+    pub block: usize,
+}
+
+impl Patch {
+    /// Create a patch that transforms the `old` document into the `new` document.
+    #[must_use]
+    pub fn new(old: &str, new: &str) -> Self {
+        Self {
+            edits: edits(old, &blocks(old), new, &blocks(new)),
+        }
+    }
+
+    /// Check if the patch has no effect.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    /// Apply the patch to a document.
+    ///
+    /// The blocks removed or replaced by the patch must be structurally equal to the
+    /// corresponding blocks of the document, any other blocks may differ.
+    pub fn apply(&self, src: &str) -> Result<String, PatchError> {
+        let blocks = blocks(src);
+        for edit in &self.edits {
+            let removed = self::blocks(&edit.removed);
+            let target = blocks.get(edit.start..edit.end).unwrap_or_default();
+            if let Some(k) = (0..removed.len())
+                .find(|k| target.get(*k).map(|b| &b.events) != Some(&removed[*k].events))
+            {
+                return Err(PatchError {
+                    block: edit.start + k,
+                });
+            }
+        }
+        Ok(assemble(
+            src,
+            &blocks,
+            &self.edits.iter().collect::<Vec<_>>(),
+        ))
+    }
+}
+
+/// A range of blocks that was changed differently by both sides of a [`merge`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// Location of the blocks in the base document.
+    pub base: Range<usize>,
+    /// Source of the blocks as changed by our side.
+    pub ours: String,
+    /// Source of the blocks as changed by their side.
+    pub theirs: String,
+}
+
+/// Combine the changes that two sides have made to a common base document.
+///
+/// Changes to different blocks are both applied. If both sides change the same blocks in
+/// different ways, the conflicting changes are returned instead.
+///
+/// # Examples
+///
+/// ```
+/// # use jotdown::diff::*;
+/// let base = "# A\n\na\n\n# B\n\nb\n";
+/// let ours = "# A\n\n_a_\n\n# B\n\nb\n";
+/// let theirs = "# A\n\na\n\n# B\n\n*b*\n";
+/// assert_eq!(
+///     merge(base, ours, theirs).unwrap(),
+///     "# A\n\n_a_\n\n# B\n\n*b*\n",
+/// );
+///
+/// let conflicts = merge(base, ours, "# A\n\n*a*\n\n# B\n\nb\n").unwrap_err();
+/// assert_eq!(conflicts[0].ours, "_a_\n\n");
+/// assert_eq!(conflicts[0].theirs, "*a*\n\n");
+/// ```
+pub fn merge(base: &str, ours: &str, theirs: &str) -> Result<String, Vec<Conflict>> {
+    let base_blocks = blocks(base);
+    let our_edits = edits(base, &base_blocks, ours, &blocks(ours));
+    let their_edits = edits(base, &base_blocks, theirs, &blocks(theirs));
+
+    let mut all = our_edits
+        .iter()
+        .map(|e| (e, true))
+        .chain(their_edits.iter().map(|e| (e, false)))
+        .collect::<Vec<_>>();
+    all.sort_by_key(|(e, _)| (e.start, e.end));
+
+    let mut merged: Vec<Edit> = Vec::new();
+    let mut conflicts = Vec::new();
+    let mut k = 0;
+    while k < all.len() {
+        // cluster of edits that overlap or touch the same position
+        let (start, mut end) = (all[k].0.start, all[k].0.end);
+        let mut n = k + 1;
+        while n < all.len() && (all[n].0.start < end || all[n].0.start == start) {
+            end = end.max(all[n].0.end);
+            n += 1;
+        }
+        let cluster = &all[k..n];
+        k = n;
+
+        let side = |ours: bool| {
+            let edits = cluster
+                .iter()
+                .filter(|(_, o)| *o == ours)
+                .map(|(e, _)| *e)
+                .collect::<Vec<_>>();
+            let blocks = &base_blocks[start..end];
+            let mut text = String::new();
+            let mut i = start;
+            let mut edits = edits.into_iter().peekable();
+            while i <= end {
+                if let Some(e) = edits.next_if(|e| e.start == i) {
+                    push_blocks(&mut text, &e.inserted);
+                    if e.end > i {
+                        i = e.end;
+                        continue;
+                    }
+                }
+                if i < end {
+                    push_blocks(&mut text, &base[blocks[i - start].text.clone()]);
+                }
+                i += 1;
+            }
+            text
+        };
+        let removed = base_blocks[start..end]
+            .iter()
+            .map(|b| &base[b.text.clone()])
+            .collect::<String>();
+        let (o, t) = (side(true), side(false));
+        let inserted = if cluster.iter().all(|(_, o)| *o) || o == t {
+            o
+        } else if cluster.iter().all(|(_, o)| !*o) {
+            t
+        } else {
+            let span = |b: &Block| b.span.clone();
+            let base_span = match (base_blocks.get(start), base_blocks[..end].last()) {
+                (Some(s), Some(e)) if start < end => span(s).start..span(e).end,
+                _ => {
+                    let pos = base_blocks.get(start).map_or(base.len(), |b| b.span.start);
+                    pos..pos
+                }
+            };
+            conflicts.push(Conflict {
+                base: base_span,
+                ours: o,
+                theirs: t,
+            });
+            continue;
+        };
+        merged.push(Edit {
+            start,
+            end,
+            removed,
+            inserted,
+        });
+    }
+
+    if conflicts.is_empty() {
+        Ok(assemble(
+            base,
+            &base_blocks,
+            &merged.iter().collect::<Vec<_>>(),
+        ))
+    } else {
+        Err(conflicts)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::diff;
+    use super::Change::*;
+
+    #[test]
+    fn equal() {
+        assert_eq!(diff("# a\n\nb\n\n> c\n", "# a\n\n\nb\n\n> c"), &[]);
+    }
+
+    #[test]
+    fn removed() {
+        assert_eq!(diff("a\n\nb\n\nc\n", "a\n\nc\n"), &[Removed { old: 3..5 }]);
+    }
+
+    #[test]
+    fn inline_change() {
+        assert_eq!(
+            diff("a *b* c\n", "a _b_ c\n"),
+            &[Changed {
+                old: 0..8,
+                new: 0..8
+            }]
+        );
+    }
+
+    #[test]
+    fn heading_change() {
+        assert_eq!(
+            diff("# a\n\nb\n", "# c\n\nb\n"),
+            &[Changed {
+                old: 0..4,
+                new: 0..4
+            }]
+        );
+    }
+
+    #[test]
+    fn patch_roundtrip() {
+        let old = "{#x}\npara\n\n# a\n\n- b\n- c\n\n> d\n";
+        let new = "# a\n\n{.y}\npara\n\n- b\n- c\n\ne\n";
+        let patch = super::Patch::new(old, new);
+        assert!(!patch.is_empty());
+        assert_eq!(patch.apply(old).unwrap(), new);
+        assert!(super::Patch::new(old, old).is_empty());
+    }
+
+    #[test]
+    fn patch_other() {
+        let patch = super::Patch::new("a\n\nb\n", "a\n\nc\n");
+        assert_eq!(patch.apply("x\n\nb\n").unwrap(), "x\n\nc\n");
+    }
+
+    #[test]
+    fn merge_insertions() {
+        let base = "a\n\nb\n";
+        assert_eq!(
+            super::merge(base, "a\n\nx\n\nb\n", "a\n\nb\n\ny\n").unwrap(),
+            "a\n\nx\n\nb\n\ny\n"
+        );
+        assert_eq!(
+            super::merge(base, "a\n\nx\n\nb\n", "a\n\nx\n\nb\n").unwrap(),
+            "a\n\nx\n\nb\n"
+        );
+        let conflicts = super::merge(base, "a\n\nx\n\nb\n", "a\n\ny\n\nb\n").unwrap_err();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].base, 3..3);
+    }
+}
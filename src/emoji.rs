@@ -0,0 +1,298 @@
+//! Emoji shortcodes.
+//!
+//! Djot parses symbols such as `:smile:` but leaves their interpretation to the application, the
+//! HTML renderer outputs them unchanged. The [`Emoji`] adapter replaces symbols that are emoji
+//! shortcodes with the emoji themselves, using a table bundled with the crate. Unknown symbols
+//! are left as is, so that they may still be handled by other means.
+//!
+//! The shortcodes are those of [gemoji](https://github.com/github/gemoji), as used by GitHub,
+//! Slack and many other applications, including aliases such as `+1` and `thumbsup`. Only the
+//! commonly used subset of the emoji is included.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! use jotdown::emoji::Emoji;
+//!
+//! let src = "Released :tada: :+1: :unknown:";
+//! assert_eq!(
+//!     jotdown::html::render_to_string(Emoji::new(jotdown::Parser::new(src))),
+//!     "<p>Released 🎉 👍 :unknown:</p>\n",
+//! );
+//! # }
+//! ```
+
+use crate::Event;
+
+/// Look up the emoji of a gemoji shortcode, without the surrounding colons.
+///
+/// ```
+/// assert_eq!(jotdown::emoji::lookup("rocket"), Some("🚀"));
+/// assert_eq!(jotdown::emoji::lookup(":rocket:"), None);
+/// ```
+#[must_use]
+pub fn lookup(shortcode: &str) -> Option<&'static str> {
+    EMOJI
+        .binary_search_by(|(s, _)| (*s).cmp(shortcode))
+        .ok()
+        .map(|i| EMOJI[i].1)
+}
+
+/// An iterator adapter that replaces emoji shortcodes with emoji.
+pub struct Emoji<I> {
+    events: I,
+}
+
+impl<'s, I> Emoji<I>
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    /// Create an adapter that replaces emoji shortcodes.
+    pub fn new(events: I) -> Self {
+        Self { events }
+    }
+}
+
+impl<'s, I> Iterator for Emoji<I>
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    type Item = Event<'s>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(match self.events.next()? {
+            Event::Symbol(sym) => match lookup(&sym) {
+                Some(emoji) => Event::Str(emoji.into()),
+                None => Event::Symbol(sym),
+            },
+            e => e,
+        })
+    }
+}
+
+/// Shortcodes and their emoji, sorted by shortcode.
+static EMOJI: &[(&str, &str)] = &[
+    ("+1", "👍"),
+    ("-1", "👎"),
+    ("100", "💯"),
+    ("alarm_clock", "⏰"),
+    ("alien", "👽"),
+    ("angry", "😠"),
+    ("apple", "🍎"),
+    ("arrow_down", "⬇\u{fe0f}"),
+    ("arrow_left", "⬅\u{fe0f}"),
+    ("arrow_right", "➡\u{fe0f}"),
+    ("arrow_up", "⬆\u{fe0f}"),
+    ("art", "🎨"),
+    ("bear", "🐻"),
+    ("bee", "🐝"),
+    ("beer", "🍺"),
+    ("beers", "🍻"),
+    ("bell", "🔔"),
+    ("bird", "🐦"),
+    ("birthday", "🎂"),
+    ("black_heart", "🖤"),
+    ("blue_heart", "💙"),
+    ("blush", "😊"),
+    ("book", "📖"),
+    ("bookmark", "🔖"),
+    ("books", "📚"),
+    ("boom", "💥"),
+    ("broken_heart", "💔"),
+    ("bug", "🐛"),
+    ("bulb", "💡"),
+    ("cake", "🍰"),
+    ("calendar", "📆"),
+    ("cat", "🐱"),
+    ("checkered_flag", "🏁"),
+    ("chicken", "🐔"),
+    ("clap", "👏"),
+    ("cloud", "☁\u{fe0f}"),
+    ("clown_face", "🤡"),
+    ("coffee", "☕"),
+    ("collision", "💥"),
+    ("computer", "💻"),
+    ("confetti_ball", "🎊"),
+    ("confused", "😕"),
+    ("construction", "🚧"),
+    ("copyright", "©\u{fe0f}"),
+    ("cow", "🐮"),
+    ("crab", "🦀"),
+    ("crescent_moon", "🌙"),
+    ("cry", "😢"),
+    ("date", "📅"),
+    ("disappointed", "😞"),
+    ("dog", "🐶"),
+    ("e-mail", "📧"),
+    ("earth_africa", "🌍"),
+    ("earth_americas", "🌎"),
+    ("earth_asia", "🌏"),
+    ("email", "📧"),
+    ("exclamation", "❗"),
+    ("expressionless", "😑"),
+    ("eyes", "👀"),
+    ("facepunch", "👊"),
+    ("fire", "🔥"),
+    ("fist", "✊"),
+    ("fist_oncoming", "👊"),
+    ("fist_raised", "✊"),
+    ("flushed", "😳"),
+    ("frog", "🐸"),
+    ("gear", "⚙\u{fe0f}"),
+    ("ghost", "👻"),
+    ("gift", "🎁"),
+    ("globe_with_meridians", "🌐"),
+    ("green_heart", "💚"),
+    ("grin", "😁"),
+    ("grinning", "😀"),
+    ("hammer", "🔨"),
+    ("handshake", "🤝"),
+    ("hankey", "💩"),
+    ("hear_no_evil", "🙉"),
+    ("heart", "❤\u{fe0f}"),
+    ("heart_eyes", "😍"),
+    ("heavy_check_mark", "✔\u{fe0f}"),
+    ("heavy_exclamation_mark", "❗"),
+    ("heavy_minus_sign", "➖"),
+    ("heavy_plus_sign", "➕"),
+    ("honeybee", "🐝"),
+    ("hourglass", "⌛"),
+    ("hugs", "🤗"),
+    ("information_source", "ℹ\u{fe0f}"),
+    ("innocent", "😇"),
+    ("joy", "😂"),
+    ("key", "🔑"),
+    ("kissing_heart", "😘"),
+    ("laughing", "😆"),
+    ("link", "🔗"),
+    ("lipstick", "💄"),
+    ("lock", "🔒"),
+    ("mag", "🔍"),
+    ("mask", "😷"),
+    ("memo", "📝"),
+    ("monkey_face", "🐵"),
+    ("mouse", "🐭"),
+    ("muscle", "💪"),
+    ("musical_note", "🎵"),
+    ("nerd_face", "🤓"),
+    ("neutral_face", "😐"),
+    ("no_entry", "⛔"),
+    ("no_entry_sign", "🚫"),
+    ("notes", "🎶"),
+    ("octopus", "🐙"),
+    ("ok_hand", "👌"),
+    ("open_book", "📖"),
+    ("orange_heart", "🧡"),
+    ("package", "📦"),
+    ("panda_face", "🐼"),
+    ("pencil", "📝"),
+    ("penguin", "🐧"),
+    ("phone", "☎\u{fe0f}"),
+    ("pig", "🐷"),
+    ("pizza", "🍕"),
+    ("point_down", "👇"),
+    ("point_left", "👈"),
+    ("point_right", "👉"),
+    ("point_up", "☝\u{fe0f}"),
+    ("poop", "💩"),
+    ("pray", "🙏"),
+    ("punch", "👊"),
+    ("purple_heart", "💜"),
+    ("question", "❓"),
+    ("rabbit", "🐰"),
+    ("rage", "😡"),
+    ("rainbow", "🌈"),
+    ("raised_hands", "🙌"),
+    ("recycle", "♻\u{fe0f}"),
+    ("registered", "®\u{fe0f}"),
+    ("relaxed", "☺\u{fe0f}"),
+    ("robot", "🤖"),
+    ("rocket", "🚀"),
+    ("rofl", "🤣"),
+    ("roll_eyes", "🙄"),
+    ("satisfied", "😆"),
+    ("scream", "😱"),
+    ("see_no_evil", "🙈"),
+    ("shit", "💩"),
+    ("skull", "💀"),
+    ("sleeping", "😴"),
+    ("slightly_smiling_face", "🙂"),
+    ("smile", "😄"),
+    ("smiley", "😃"),
+    ("smirk", "😏"),
+    ("snake", "🐍"),
+    ("snowflake", "❄\u{fe0f}"),
+    ("sob", "😭"),
+    ("sparkles", "✨"),
+    ("sparkling_heart", "💖"),
+    ("speak_no_evil", "🙊"),
+    ("star", "⭐"),
+    ("star2", "🌟"),
+    ("stuck_out_tongue", "😛"),
+    ("stuck_out_tongue_winking_eye", "😜"),
+    ("sunglasses", "😎"),
+    ("sunny", "☀\u{fe0f}"),
+    ("sweat_smile", "😅"),
+    ("tada", "🎉"),
+    ("telephone", "☎\u{fe0f}"),
+    ("thinking", "🤔"),
+    ("thumbsdown", "👎"),
+    ("thumbsup", "👍"),
+    ("tiger", "🐯"),
+    ("tm", "™\u{fe0f}"),
+    ("triangular_flag_on_post", "🚩"),
+    ("trophy", "🏆"),
+    ("turtle", "🐢"),
+    ("two_hearts", "💕"),
+    ("umbrella", "☔"),
+    ("unamused", "😒"),
+    ("unicorn", "🦄"),
+    ("unlock", "🔓"),
+    ("upside_down_face", "🙃"),
+    ("v", "✌\u{fe0f}"),
+    ("warning", "⚠\u{fe0f}"),
+    ("wave", "👋"),
+    ("whale", "🐳"),
+    ("white_check_mark", "✅"),
+    ("white_flag", "🏳\u{fe0f}"),
+    ("wink", "😉"),
+    ("worried", "😟"),
+    ("wrench", "🔧"),
+    ("x", "❌"),
+    ("yellow_heart", "💛"),
+    ("yum", "😋"),
+    ("zap", "⚡"),
+    ("zipper_mouth_face", "🤐"),
+];
+
+#[cfg(test)]
+mod test {
+    use super::Emoji;
+    use super::EMOJI;
+    use crate::Event;
+
+    #[test]
+    fn sorted() {
+        assert!(EMOJI.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    #[test]
+    fn symbols() {
+        let events: Vec<_> = Emoji::new(crate::Parser::new(":heart: `:heart:` :x:y:")).collect();
+        assert_eq!(
+            &events[1..events.len() - 1],
+            &[
+                Event::Str("❤\u{fe0f}".into()),
+                Event::Str(" ".into()),
+                Event::Start(crate::Container::Verbatim, crate::Attributes::new()),
+                Event::Str(":heart:".into()),
+                Event::End(crate::Container::Verbatim),
+                Event::Str(" ".into()),
+                Event::Str("❌".into()),
+                Event::Str("y:".into()),
+            ],
+        );
+    }
+}
@@ -0,0 +1,271 @@
+//! Excerpts of documents.
+//!
+//! An excerpt is a short summary of a document, e.g. for a listing page or a meta description.
+//! It consists of the text of the first paragraphs of the document with all markup stripped,
+//! limited by a number of paragraphs and/or a number of characters. When limited by characters,
+//! the excerpt ends at a sentence boundary if possible.
+//!
+//! For single-line text, e.g. for a meta tag or a notification, runs of whitespace within the
+//! excerpt may be collapsed to single spaces, see [`Excerpt::with_collapsed_whitespace`].
+//!
+//! # Examples
+//!
+//! ```
+//! use jotdown::excerpt::Excerpt;
+//!
+//! let src = "# Title\n\nThe _first_ sentence. The second one.\n\nAnother paragraph.\n";
+//! let excerpt = Excerpt::new().with_chars(30);
+//! assert_eq!(excerpt.text(jotdown::Parser::new(src)), "The first sentence.");
+//! ```
+
+use crate::Attributes;
+use crate::Container;
+use crate::Event;
+
+/// Limits of an excerpt.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Excerpt {
+    paragraphs: Option<usize>,
+    chars: Option<usize>,
+    collapse: bool,
+}
+
+impl Excerpt {
+    /// Create an excerpt that includes all paragraphs.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Include at most `n` paragraphs.
+    #[must_use]
+    pub fn with_paragraphs(mut self, n: usize) -> Self {
+        self.paragraphs = Some(n);
+        self
+    }
+
+    /// Include at most `n` characters, excluding separators between paragraphs.
+    ///
+    /// The text is cut at the end of the last complete sentence within the limit. If not even
+    /// the first sentence fits, it is cut at a word boundary and an ellipsis is appended.
+    #[must_use]
+    pub fn with_chars(mut self, n: usize) -> Self {
+        self.chars = Some(n);
+        self
+    }
+
+    /// Collapse each run of whitespace, including line breaks, to a single space, and separate
+    /// paragraphs of the text by a single space rather than blank lines.
+    ///
+    /// Non-breaking spaces are retained.
+    ///
+    /// ```
+    /// # use jotdown::excerpt::Excerpt;
+    /// let src = "Some   text\\\nwith  breaks.\n\nMore\ttext.\n";
+    /// assert_eq!(
+    ///     Excerpt::new().with_collapsed_whitespace().text(jotdown::Parser::new(src)),
+    ///     "Some text with breaks. More text.",
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_collapsed_whitespace(mut self) -> Self {
+        self.collapse = true;
+        self
+    }
+
+    /// Create the text of each paragraph of the excerpt.
+    #[must_use]
+    pub fn paragraphs<'s, I: IntoIterator<Item = Event<'s>>>(&self, events: I) -> Vec<String> {
+        let mut paragraphs = Vec::new();
+        let mut current: Option<String> = None;
+        // depth of containers whose content is excluded
+        let mut skip = 0;
+        for e in events {
+            if self.paragraphs.map_or(false, |n| paragraphs.len() >= n) {
+                break;
+            }
+            match e {
+                Event::Start(c, _) if is_skipped(&c) => skip += 1,
+                Event::End(c) if is_skipped(&c) => skip -= 1,
+                _ if skip > 0 => {}
+                Event::Start(Container::Paragraph, _) => current = Some(String::new()),
+                Event::End(Container::Paragraph) => {
+                    if let Some(p) = current.take() {
+                        let p = if self.collapse {
+                            collapse(&p)
+                        } else {
+                            p.trim().to_string()
+                        };
+                        if !p.is_empty() {
+                            paragraphs.push(p);
+                        }
+                    }
+                }
+                e => {
+                    if let Some(p) = &mut current {
+                        if let Event::Symbol(sym) = e {
+                            p.push(':');
+                            p.push_str(&sym);
+                            p.push(':');
+                        } else {
+                            crate::outline::push_text(p, &e);
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(limit) = self.chars {
+            let mut remaining = limit;
+            let mut out = Vec::new();
+            for p in paragraphs {
+                let len = p.chars().count();
+                if len <= remaining {
+                    remaining -= len;
+                    out.push(p);
+                } else {
+                    if let Some(p) = truncate(&p, remaining, out.is_empty()) {
+                        out.push(p);
+                    }
+                    break;
+                }
+            }
+            out
+        } else {
+            paragraphs
+        }
+    }
+
+    /// Create a plain text excerpt, with paragraphs separated by blank lines, or by spaces if
+    /// whitespace is collapsed.
+    #[must_use]
+    pub fn text<'s, I: IntoIterator<Item = Event<'s>>>(&self, events: I) -> String {
+        let separator = if self.collapse { " " } else { "\n\n" };
+        self.paragraphs(events).join(separator)
+    }
+
+    /// Create the events of an excerpt, a paragraph with plain text for each paragraph.
+    ///
+    /// The events may be rendered to produce e.g. an HTML excerpt:
+    ///
+    /// ```
+    /// # #[cfg(feature = "html")]
+    /// # {
+    /// # use jotdown::excerpt::Excerpt;
+    /// let src = "Some *text* <with> [a link](url).\n\nMore text.\n";
+    /// let excerpt = Excerpt::new().with_paragraphs(1).events(jotdown::Parser::new(src));
+    /// assert_eq!(
+    ///     jotdown::html::render_to_string(excerpt.into_iter()),
+    ///     "<p>Some text &lt;with&gt; a link.</p>\n",
+    /// );
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn events<'s, I: IntoIterator<Item = Event<'s>>>(&self, events: I) -> Vec<Event<'s>> {
+        self.paragraphs(events)
+            .into_iter()
+            .flat_map(|p| {
+                [
+                    Event::Start(Container::Paragraph, Attributes::new()),
+                    Event::Str(p.into()),
+                    Event::End(Container::Paragraph),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Cut a paragraph to at most `n` characters at a sentence boundary. If there is no sentence
+/// boundary, it is cut at a word boundary if `force` is set.
+fn truncate(p: &str, n: usize, force: bool) -> Option<String> {
+    let end = p.char_indices().nth(n).map_or(p.len(), |(i, _)| i);
+    let within = &p[..end];
+    let sentence = within
+        .char_indices()
+        .rev()
+        .find(|(i, c)| {
+            matches!(c, '.' | '!' | '?' | '…')
+                && p[i + c.len_utf8()..]
+                    .chars()
+                    .next()
+                    .map_or(true, char::is_whitespace)
+        })
+        .map(|(i, c)| i + c.len_utf8());
+    if let Some(i) = sentence {
+        Some(p[..i].to_string())
+    } else if force {
+        let word = if p[end..].chars().next().map_or(true, char::is_whitespace) {
+            end
+        } else {
+            within.rfind(char::is_whitespace).unwrap_or(0)
+        };
+        Some(format!("{}…", p[..word].trim_end()))
+    } else {
+        None
+    }
+}
+
+/// Replace each run of whitespace other than non-breaking spaces with a single space, and
+/// remove leading and trailing whitespace.
+fn collapse(p: &str) -> String {
+    p.split(|c: char| c.is_whitespace() && c != '\u{a0}')
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Containers whose content is not part of an excerpt.
+fn is_skipped(c: &Container) -> bool {
+    matches!(
+        c,
+        Container::Footnote { .. }
+            | Container::LinkDefinition { .. }
+            | Container::RawInline { .. }
+            | Container::Math { .. }
+            | Container::Table
+            | Container::Image(..)
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::Excerpt;
+
+    fn text(excerpt: Excerpt, src: &str) -> String {
+        excerpt.text(crate::Parser::new(src))
+    }
+
+    #[test]
+    fn paragraphs() {
+        let src = "a\n\n# b\n\n> c\n\n```\nd\n```\n\ne\n";
+        assert_eq!(text(Excerpt::new(), src), "a\n\nc\n\ne");
+        assert_eq!(text(Excerpt::new().with_paragraphs(2), src), "a\n\nc");
+    }
+
+    #[test]
+    fn skipped() {
+        let src = "a[^b] $`c` ![d](e) `f`{=html}\"g\"\n\n[^b]: note\n";
+        assert_eq!(text(Excerpt::new(), src), "a   “g”");
+        assert_eq!(
+            text(Excerpt::new().with_collapsed_whitespace(), src),
+            "a “g”"
+        );
+    }
+
+    #[test]
+    fn chars() {
+        let src = "One. Two.\n\nThree is long.\n";
+        assert_eq!(text(Excerpt::new().with_chars(4), src), "One.");
+        assert_eq!(text(Excerpt::new().with_chars(8), src), "One.");
+        assert_eq!(text(Excerpt::new().with_chars(12), src), "One. Two.");
+        assert_eq!(text(Excerpt::new().with_chars(3), src), "…");
+        assert_eq!(
+            text(Excerpt::new().with_chars(10), "a sentence without end"),
+            "a sentence…"
+        );
+        assert_eq!(
+            text(Excerpt::new().with_chars(9), "a sentence without end"),
+            "a…"
+        );
+    }
+}
@@ -0,0 +1,328 @@
+//! Extensions of the Djot syntax.
+//!
+//! Dialects of Djot may be experimented with by registering additional syntax with an
+//! [`Extensions`] object and passing it to [`Parser::with_extensions`]. Elements that are
+//! recognized by an extension are emitted as custom containers, with the name of the extension.
+//!
+//! Inline extensions are triggered by a string, e.g. an opening delimiter. Whenever the trigger
+//! occurs within inline content, outside of verbatim and attributes, the extension is given the
+//! rest of the line and may claim a part of it. The content of the element is emitted as text,
+//! it is not parsed further. Extensions are tried before the regular syntax, in the order they
+//! were registered, and an escaped trigger is ignored.
+//!
+//! Block extensions are recognized by the start of a line, at the beginning of a block. A fenced
+//! block extends until a line consisting only of its fence, while a directive occupies a single
+//! line. The rest of the first line is the argument of the block, and the content of a fenced
+//! block is emitted as text, like that of a code block, while a directive has no content. Custom
+//! blocks may be rendered by hooks, see [`html::Renderer::with_custom_block`], or else as divs
+//! with the argument as a `data-argument` attribute.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! use jotdown::extension::Extensions;
+//! use jotdown::*;
+//!
+//! let extensions = Extensions::new()
+//!     .with_delimited("highlight", "==", "==")
+//!     .with_inline("issue", "#", |s: &str| {
+//!         let len = s[1..].find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len() - 1);
+//!         (len > 0).then(|| (len + 1, 1..len + 1))
+//!     });
+//! let src = "Fixed ==again== in #123, not \\==here==.";
+//! let events: Vec<_> = Parser::with_extensions(src, &extensions).collect();
+//! assert_eq!(
+//!     &events[2..5],
+//!     &[
+//!         Event::Start(Container::CustomInline { name: "highlight" }, Attributes::new()),
+//!         Event::Str("again".into()),
+//!         Event::End(Container::CustomInline { name: "highlight" }),
+//!     ],
+//! );
+//! assert_eq!(
+//!     html::render_to_string(events.into_iter()),
+//!     concat!(
+//!         "<p>Fixed <span class=\"highlight\">again</span> in ",
+//!         "<span class=\"issue\">123</span>, not ==here==.</p>\n",
+//!     ),
+//! );
+//! # }
+//! ```
+//!
+//! Block extensions, with an HTML hook for one of them:
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! use jotdown::extension::Extensions;
+//! use jotdown::html::Renderer;
+//! use jotdown::*;
+//!
+//! let extensions = Extensions::new()
+//!     .with_fenced("aside", "%%%")
+//!     .with_directive("include", "!include");
+//! let src = concat!(
+//!     "%%% Note\n",
+//!     "Not *emphasized*.\n",
+//!     "%%%\n",
+//!     "\n",
+//!     "!include chapter.dj\n",
+//! );
+//! let events: Vec<_> = Parser::with_extensions(src, &extensions).collect();
+//! assert_eq!(
+//!     &events[..3],
+//!     &[
+//!         Event::Start(
+//!             Container::CustomBlock { name: "aside", argument: "Note" },
+//!             Attributes::new(),
+//!         ),
+//!         Event::Str("Not *emphasized*.\n".into()),
+//!         Event::End(Container::CustomBlock { name: "aside", argument: "Note" }),
+//!     ],
+//! );
+//! let renderer = Renderer::default().with_custom_block("include", |path, _| {
+//!     Ok::<_, ()>(format!("<p>Contents of {}.</p>", path))
+//! });
+//! let mut html = String::new();
+//! renderer.push(events.into_iter(), &mut html).unwrap();
+//! assert_eq!(
+//!     html,
+//!     concat!(
+//!         "<div class=\"aside\" data-argument=\"Note\">Not *emphasized*.\n",
+//!         "</div>\n",
+//!         "<p>Contents of chapter.dj.</p>\n",
+//!     ),
+//! );
+//! # }
+//! ```
+//!
+//! [`Parser::with_extensions`]: crate::Parser::with_extensions
+//! [`html::Renderer::with_custom_block`]: crate::html::Renderer::with_custom_block
+
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Function that attempts to parse an inline element at the start of a string.
+type InlineParse = Arc<dyn Fn(&str) -> Option<(usize, Range<usize>)> + Send + Sync>;
+
+/// An inline extension.
+#[derive(Clone)]
+pub(crate) struct InlineSyntax {
+    /// Name of the custom container.
+    pub name: &'static str,
+    /// String that the element starts with.
+    pub trigger: String,
+    /// The element must not be preceded by an alphanumeric character.
+    pub word: bool,
+    pub parse: InlineParse,
+}
+
+/// A block extension.
+#[derive(Debug, Clone)]
+pub(crate) struct BlockSyntax {
+    /// Name of the custom container.
+    pub name: &'static str,
+    /// String that the first line of the block starts with.
+    pub prefix: String,
+    /// The block is closed by a line with only the prefix, as opposed to ending after one line.
+    pub fenced: bool,
+}
+
+/// A set of syntax extensions.
+#[derive(Clone, Default)]
+pub struct Extensions {
+    pub(crate) inline: Vec<InlineSyntax>,
+    pub(crate) block: Vec<BlockSyntax>,
+    /// Tags of inline containers whose syntax is disabled.
+    pub(crate) disabled: Vec<u8>,
+}
+
+impl Extensions {
+    /// Create an empty set of extensions, i.e. regular Djot.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Recognize inline elements starting with `trigger` using the provided function.
+    ///
+    /// The function is given the rest of the line, starting with the trigger. If it recognizes an
+    /// element, it returns the length of the element and the range of its content, both relative
+    /// to the start of the string. Invalid lengths and ranges are ignored.
+    #[must_use]
+    pub fn with_inline<F>(mut self, name: &'static str, trigger: &str, parse: F) -> Self
+    where
+        F: Fn(&str) -> Option<(usize, Range<usize>)> + Send + Sync + 'static,
+    {
+        self.inline.push(InlineSyntax {
+            name,
+            trigger: trigger.to_string(),
+            word: false,
+            parse: Arc::new(parse),
+        });
+        self
+    }
+
+    /// Recognize non-empty inline elements enclosed by `open` and `close` on a single line.
+    #[must_use]
+    pub fn with_delimited(self, name: &'static str, open: &str, close: &str) -> Self {
+        let (n_open, close) = (open.len(), close.to_string());
+        self.with_inline(name, open, move |s| {
+            s[n_open..]
+                .find(&close)
+                .filter(|len| *len > 0)
+                .map(|len| (n_open + len + close.len(), n_open..n_open + len))
+        })
+    }
+
+    /// Recognize hashtags, e.g. `#rust`, as custom inline containers named `hashtag`.
+    ///
+    /// A hashtag consists of letters, digits, underscores and hyphens, at least one of which is
+    /// not a digit, and it must not be preceded by a letter or digit. The content of the element
+    /// includes the `#`. See [`social`](crate::social) for resolving the destinations of
+    /// hashtags.
+    #[must_use]
+    pub fn with_hashtags(self) -> Self {
+        self.with_word("hashtag", "#", |s| {
+            let len = s[1..]
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+                .unwrap_or(s.len() - 1);
+            let tag = s[1..len + 1].trim_end_matches('-');
+            (!tag.chars().all(|c| c.is_ascii_digit())).then(|| tag.len() + 1)
+        })
+    }
+
+    /// Recognize mentions, e.g. `@alice`, as custom inline containers named `mention`.
+    ///
+    /// A mention consists of letters, digits, underscores, hyphens and inner periods, and it must
+    /// not be preceded by a letter or digit, e.g. within an email address. The content of the
+    /// element includes the `@`. See [`social`](crate::social) for resolving the destinations of
+    /// mentions.
+    #[must_use]
+    pub fn with_mentions(self) -> Self {
+        self.with_word("mention", "@", |s| {
+            let len = s[1..]
+                .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '-' | '.')))
+                .unwrap_or(s.len() - 1);
+            let user = s[1..len + 1].trim_end_matches(|c| c == '.' || c == '-');
+            (!user.is_empty()).then(|| user.len() + 1)
+        })
+    }
+
+    /// Recognize wiki links, e.g. `[[Page Name]]` or `[[target|text]]`, as custom inline
+    /// containers named `wikilink`.
+    ///
+    /// The content of the element is the text between the brackets, including any `|`. See
+    /// [`wiki`](crate::wiki) for resolving the destinations of wiki links.
+    #[must_use]
+    pub fn with_wiki_links(self) -> Self {
+        self.with_delimited("wikilink", "[[", "]]")
+    }
+
+    /// Recognize elements that start a word and whose content is the whole element.
+    fn with_word<F>(mut self, name: &'static str, trigger: &str, len: F) -> Self
+    where
+        F: Fn(&str) -> Option<usize> + Send + Sync + 'static,
+    {
+        self.inline.push(InlineSyntax {
+            name,
+            trigger: trigger.to_string(),
+            word: true,
+            parse: Arc::new(move |s| len(s).map(|len| (len, 0..len))),
+        });
+        self
+    }
+
+    /// Recognize blocks starting with a line that begins with `fence` and ending with a line
+    /// that only contains `fence`, or at the end of the enclosing container.
+    #[must_use]
+    pub fn with_fenced(mut self, name: &'static str, fence: &str) -> Self {
+        self.block.push(BlockSyntax {
+            name,
+            prefix: fence.to_string(),
+            fenced: true,
+        });
+        self
+    }
+
+    /// Recognize single-line blocks that begin with `prefix`.
+    #[must_use]
+    pub fn with_directive(mut self, name: &'static str, prefix: &str) -> Self {
+        self.block.push(BlockSyntax {
+            name,
+            prefix: prefix.to_string(),
+            fenced: false,
+        });
+        self
+    }
+
+    /// Do not recognize the syntax of inline containers of the same kind as `container`, e.g.
+    /// subscript in chat messages where `~` is commonly used on its own.
+    ///
+    /// The delimiters of a disabled container are treated as text. Only the containers of inline
+    /// formatting may be disabled, i.e. subscript, superscript, insert, delete, strong, emphasis
+    /// and mark, other containers are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::extension::Extensions;
+    /// let extensions = Extensions::new()
+    ///     .with_disabled(Container::Subscript)
+    ///     .with_disabled(Container::Superscript);
+    /// let src = "~2 ^_^ ~x~ {~y~} _z_";
+    /// let events: Vec<_> = Parser::with_extensions(src, &extensions).collect();
+    /// assert_eq!(
+    ///     events,
+    ///     &[
+    ///         Event::Start(Container::Paragraph, Attributes::new()),
+    ///         Event::Str("~2 ^_^ ~x~ {~y~} ".into()),
+    ///         Event::Start(Container::Emphasis, Attributes::new()),
+    ///         Event::Str("z".into()),
+    ///         Event::End(Container::Emphasis),
+    ///         Event::End(Container::Paragraph),
+    ///     ],
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_disabled(mut self, container: crate::Container) -> Self {
+        if matches!(
+            container,
+            crate::Container::Subscript
+                | crate::Container::Superscript
+                | crate::Container::Insert
+                | crate::Container::Delete
+                | crate::Container::Strong
+                | crate::Container::Emphasis
+                | crate::Container::Mark
+        ) {
+            self.disabled.push(container.tag());
+        }
+        self
+    }
+
+    /// Returns `true` if no extensions have been registered and no syntax has been disabled.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.inline.is_empty() && self.block.is_empty() && self.disabled.is_empty()
+    }
+}
+
+impl std::fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Extensions")
+            .field(
+                "inline",
+                &self.inline.iter().map(|e| e.name).collect::<Vec<_>>(),
+            )
+            .field(
+                "block",
+                &self.block.iter().map(|e| e.name).collect::<Vec<_>>(),
+            )
+            .field("disabled", &self.disabled)
+            .finish()
+    }
+}
@@ -0,0 +1,548 @@
+//! Front matter of documents.
+//!
+//! Documents used by e.g. static site generators often start with metadata such as a title and
+//! a date, separated from the markup by lines containing only `---`. A [`Document`] splits such
+//! a source into its front matter, deserialized into a user-defined type, and its body.
+//!
+//! The front matter is written in a subset of YAML:
+//!
+//! - a `key: value` pair on each line,
+//! - values are quoted strings, booleans, numbers, `null` or plain text until the end of the
+//!   line or a ` #` comment,
+//! - lists of values are written either inline, `[a, b]`, or as one `- value` per line
+//!   following the key,
+//! - lines starting with `#` are comments.
+//!
+//! Other formats may be used with [`Document::parse_with`], e.g. with a function from a TOML or
+//! YAML crate.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! use jotdown::front_matter::Document;
+//!
+//! #[derive(serde::Deserialize)]
+//! struct Meta {
+//!     title: String,
+//!     draft: bool,
+//!     tags: Vec<String>,
+//!     summary: Option<String>,
+//! }
+//!
+//! let src = concat!(
+//!     "---\n",
+//!     "title: \"Hello: world\"\n",
+//!     "draft: false\n",
+//!     "tags:\n",
+//!     "  - djot\n",
+//!     "  - rust\n",
+//!     "---\n",
+//!     "Some _text_.\n",
+//! );
+//! let doc = Document::<Meta>::parse(src).unwrap();
+//! assert_eq!(doc.front_matter.title, "Hello: world");
+//! assert!(!doc.front_matter.draft);
+//! assert_eq!(doc.front_matter.tags, &["djot", "rust"]);
+//! assert_eq!(doc.front_matter.summary, None);
+//! assert_eq!(
+//!     jotdown::html::render_to_string(doc.events()),
+//!     "<p>Some <em>text</em>.</p>\n",
+//! );
+//! # }
+//! ```
+
+use serde::de;
+use serde::de::IntoDeserializer;
+
+/// A document split into its front matter and body.
+#[derive(Debug, Clone)]
+pub struct Document<'s, M> {
+    /// The deserialized front matter.
+    pub front_matter: M,
+    /// Source of the body, following the front matter.
+    pub body: &'s str,
+    /// Location of the body in the source of the document.
+    pub offset: usize,
+}
+
+impl<'s, M> Document<'s, M> {
+    /// Split a document and deserialize its front matter with the provided function.
+    ///
+    /// The function is given the content of the front matter, without the delimiting lines. If
+    /// the document has no front matter, it is given an empty string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::front_matter::Document;
+    /// let src = "---\na = 1\n---\nbody\n";
+    /// let doc = Document::parse_with(src, |s| Ok::<_, ()>(s.to_string())).unwrap();
+    /// assert_eq!(doc.front_matter, "a = 1\n");
+    /// assert_eq!(doc.body, "body\n");
+    /// assert_eq!(doc.offset, 14);
+    /// ```
+    pub fn parse_with<F, E>(src: &'s str, f: F) -> Result<Self, E>
+    where
+        F: FnOnce(&str) -> Result<M, E>,
+    {
+        let (front_matter, offset) = split(src).map_or(("", 0), |(fm, body)| (&src[fm], body));
+        Ok(Self {
+            front_matter: f(front_matter)?,
+            body: &src[offset..],
+            offset,
+        })
+    }
+
+    /// Parse the body of the document.
+    ///
+    /// Note that spans of the events are relative to the body, see [`Document::offset`].
+    #[must_use]
+    pub fn events(&self) -> crate::Parser<'s> {
+        crate::Parser::new(self.body)
+    }
+}
+
+impl<'s, M: de::DeserializeOwned> Document<'s, M> {
+    /// Split a document and deserialize its front matter.
+    ///
+    /// A document without front matter is deserialized as if the front matter was empty, which
+    /// fails if `M` has any required fields.
+    pub fn parse(src: &'s str) -> Result<Self, ParseFrontMatterError> {
+        let (fm, offset) = split(src).unwrap_or((0..0, 0));
+        let entries = entries(&src[fm.clone()], fm.start)?;
+        let front_matter = M::deserialize(MapDeserializer {
+            entries: &entries,
+            pos: fm.start,
+        })?;
+        Ok(Self {
+            front_matter,
+            body: &src[offset..],
+            offset,
+        })
+    }
+}
+
+/// Error returned by [`Document::parse`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseFrontMatterError {
+    /// Location in the source of the value that could not be parsed or deserialized.
+    pub pos: usize,
+    /// Description of the error.
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseFrontMatterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "invalid front matter at {}: {}", self.pos, self.message)
+    }
+}
+
+impl std::error::Error for ParseFrontMatterError {}
+
+impl de::Error for ParseFrontMatterError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Self {
+            pos: 0,
+            message: msg.to_string(),
+        }
+    }
+}
+
+/// Find the content of the front matter and the start of the body, if the document has front
+/// matter.
+fn split(src: &str) -> Option<(std::ops::Range<usize>, usize)> {
+    let mut lines = src.split_inclusive('\n').scan(0, |pos, l| {
+        let start = *pos;
+        *pos += l.len();
+        Some((start, l))
+    });
+    if lines.next()?.1.trim_end() != "---" {
+        return None;
+    }
+    let start = src.find('\n')? + 1;
+    lines
+        .find(|(_, l)| l.trim_end() == "---")
+        .map(|(end, l)| (start..end, end + l.len()))
+}
+
+/// Value of a key in the front matter.
+#[derive(Debug)]
+enum Value {
+    Null,
+    Scalar { text: String, quoted: bool },
+    List(Vec<(usize, Value)>),
+}
+
+/// Parse the key-value pairs of the front matter, with the location of each value.
+fn entries(src: &str, offset: usize) -> Result<Vec<(&str, usize, Value)>, ParseFrontMatterError> {
+    let mut entries = Vec::new();
+    let mut lines = src
+        .split_inclusive('\n')
+        .scan(offset, |pos, l| {
+            let start = *pos;
+            *pos += l.len();
+            Some((start, l.trim_end()))
+        })
+        .filter(|(_, l)| !l.trim_start().is_empty() && !l.trim_start().starts_with('#'))
+        .peekable();
+    while let Some((pos, line)) = lines.next() {
+        let (key, value) = match line.split_once(':') {
+            Some((key, value)) if !line.starts_with(char::is_whitespace) => (key.trim(), value),
+            _ => return Err(error(pos, "expected key")),
+        };
+        let value_pos = pos + line.len() - value.trim_start().len();
+        let value = if value.trim().is_empty() {
+            let mut items = Vec::new();
+            while let Some((pos, line)) = lines.next_if(|(_, l)| is_item(l)) {
+                let item = line.trim_start()[1..].trim_start();
+                let item_pos = pos + line.len() - item.len();
+                items.push((item_pos, value_of(item, item_pos)?));
+            }
+            if items.is_empty() {
+                Value::Null
+            } else {
+                Value::List(items)
+            }
+        } else {
+            value_of(value.trim(), value_pos)?
+        };
+        entries.push((key, value_pos, value));
+    }
+    Ok(entries)
+}
+
+/// Returns `true` if the line is an item of a block list.
+fn is_item(line: &str) -> bool {
+    let l = line.trim_start();
+    l == "-" || l.starts_with("- ")
+}
+
+fn value_of(s: &str, pos: usize) -> Result<Value, ParseFrontMatterError> {
+    if let Some(inner) = s.strip_prefix('[') {
+        let inner = inner
+            .strip_suffix(']')
+            .ok_or_else(|| error(pos, "unterminated list"))?;
+        let mut items = Vec::new();
+        let mut push = |start: usize, end: usize| {
+            let item = &inner[start..end];
+            if !item.trim().is_empty() {
+                let item_pos = pos + 1 + start + item.len() - item.trim_start().len();
+                items.push((item_pos, scalar(item.trim(), item_pos)?));
+            }
+            Ok(())
+        };
+        let mut start = 0;
+        let mut quote = None;
+        for (i, c) in inner.char_indices() {
+            match c {
+                '"' | '\'' if quote.is_none() => quote = Some(c),
+                c if quote == Some(c) => quote = None,
+                ',' if quote.is_none() => {
+                    push(start, i)?;
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        push(start, inner.len())?;
+        Ok(Value::List(items))
+    } else {
+        scalar(s, pos)
+    }
+}
+
+fn scalar(s: &str, pos: usize) -> Result<Value, ParseFrontMatterError> {
+    if let Some(inner) = s.strip_prefix('"') {
+        let inner = inner
+            .strip_suffix('"')
+            .ok_or_else(|| error(pos, "unterminated string"))?;
+        let mut text = String::new();
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            if c == '\\' {
+                text.push(match chars.next() {
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some(c @ ('"' | '\\')) => c,
+                    _ => return Err(error(pos, "invalid escape")),
+                });
+            } else {
+                text.push(c);
+            }
+        }
+        Ok(Value::Scalar { text, quoted: true })
+    } else if let Some(inner) = s.strip_prefix('\'') {
+        let inner = inner
+            .strip_suffix('\'')
+            .ok_or_else(|| error(pos, "unterminated string"))?;
+        Ok(Value::Scalar {
+            text: inner.replace("''", "'"),
+            quoted: true,
+        })
+    } else {
+        let s = s.find(" #").map_or(s, |i| &s[..i]).trim_end();
+        if matches!(s, "" | "~" | "null") {
+            Ok(Value::Null)
+        } else {
+            Ok(Value::Scalar {
+                text: s.to_string(),
+                quoted: false,
+            })
+        }
+    }
+}
+
+fn error(pos: usize, message: &str) -> ParseFrontMatterError {
+    ParseFrontMatterError {
+        pos,
+        message: message.to_string(),
+    }
+}
+
+/// Deserializer of the entire front matter, a map.
+struct MapDeserializer<'a> {
+    entries: &'a [(&'a str, usize, Value)],
+    pos: usize,
+}
+
+impl<'de, 'a> de::Deserializer<'de> for MapDeserializer<'a> {
+    type Error = ParseFrontMatterError;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor
+            .visit_map(MapAccess {
+                entries: self.entries.iter(),
+                value: None,
+            })
+            .map_err(|e| at(e, self.pos))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct MapAccess<'a> {
+    entries: std::slice::Iter<'a, (&'a str, usize, Value)>,
+    value: Option<(usize, &'a Value)>,
+}
+
+impl<'de, 'a> de::MapAccess<'de> for MapAccess<'a> {
+    type Error = ParseFrontMatterError;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        if let Some((key, pos, value)) = self.entries.next() {
+            self.value = Some((*pos, value));
+            seed.deserialize(key.into_deserializer()).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let (pos, value) = self.value.take().unwrap();
+        seed.deserialize(ValueDeserializer { value, pos })
+    }
+}
+
+/// Deserializer of a single value.
+struct ValueDeserializer<'a> {
+    value: &'a Value,
+    pos: usize,
+}
+
+impl<'a> ValueDeserializer<'a> {
+    fn parse<T: std::str::FromStr>(&self) -> Result<T, ParseFrontMatterError> {
+        if let Value::Scalar { text, .. } = self.value {
+            if let Ok(v) = text.parse() {
+                return Ok(v);
+            }
+        }
+        Err(error(self.pos, "invalid value"))
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                visitor.$visit(self.parse()?).map_err(|e| at(e, self.pos))
+            }
+        )*
+    };
+}
+
+impl<'de, 'a> de::Deserializer<'de> for ValueDeserializer<'a> {
+    type Error = ParseFrontMatterError;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Null => visitor.visit_unit(),
+            Value::Scalar {
+                text,
+                quoted: false,
+            } => {
+                if let Ok(b) = text.parse() {
+                    visitor.visit_bool(b)
+                } else if let Ok(n) = text.parse() {
+                    visitor.visit_i64(n)
+                } else if let Ok(n) = text.parse() {
+                    visitor.visit_f64(n)
+                } else {
+                    visitor.visit_str(text)
+                }
+            }
+            Value::Scalar { text, .. } => visitor.visit_str(text),
+            Value::List(items) => visitor.visit_seq(SeqAccess {
+                items: items.iter(),
+            }),
+        }
+        .map_err(|e| at(e, self.pos))
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_str<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Scalar { text, .. } => visitor.visit_str(text).map_err(|e| at(e, self.pos)),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_string<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Scalar { text, .. } => visitor
+                .visit_enum(text.as_str().into_deserializer())
+                .map_err(|e| at(e, self.pos)),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        i128 u128 char bytes byte_buf unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct SeqAccess<'a> {
+    items: std::slice::Iter<'a, (usize, Value)>,
+}
+
+impl<'de, 'a> de::SeqAccess<'de> for SeqAccess<'a> {
+    type Error = ParseFrontMatterError;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        self.items
+            .next()
+            .map(|(pos, value)| seed.deserialize(ValueDeserializer { value, pos: *pos }))
+            .transpose()
+    }
+}
+
+/// Locate an error at the value, unless it has already been located at a nested value.
+fn at(mut e: ParseFrontMatterError, pos: usize) -> ParseFrontMatterError {
+    if e.pos == 0 {
+        e.pos = pos;
+    }
+    e
+}
+
+#[cfg(test)]
+mod test {
+    use super::Document;
+    use super::ParseFrontMatterError;
+
+    #[derive(Debug, Default, PartialEq, serde::Deserialize)]
+    #[serde(default)]
+    struct Meta {
+        title: String,
+        weight: Option<u32>,
+        ratio: f64,
+        tags: Vec<String>,
+    }
+
+    fn parse(src: &str) -> Result<Meta, ParseFrontMatterError> {
+        Document::<Meta>::parse(src).map(|doc| doc.front_matter)
+    }
+
+    #[test]
+    fn values() {
+        let src = concat!(
+            "---\n",
+            "# comment\n",
+            "title: a 'b' # c\n",
+            "weight: ~\n",
+            "ratio: 0.5\n",
+            "tags: [\"x, y\", 'z''', w]\n",
+            "---\n",
+        );
+        assert_eq!(
+            parse(src),
+            Ok(Meta {
+                title: "a 'b'".to_string(),
+                weight: None,
+                ratio: 0.5,
+                tags: vec!["x, y".to_string(), "z'".to_string(), "w".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn no_front_matter() {
+        let doc = Document::<Meta>::parse("---\ntitle: a\n").unwrap();
+        assert_eq!(doc.front_matter, Meta::default());
+        assert_eq!(doc.offset, 0);
+        assert!(Document::<Meta>::parse("").is_ok());
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse("---\nweight: -1\n---\n").map_err(|e| e.pos), Err(12));
+        assert_eq!(
+            parse("---\ntags: [a, \"b]\n---\n").map_err(|e| e.pos),
+            Err(14)
+        );
+        assert_eq!(parse("---\n  a\n---\n").map_err(|e| e.pos), Err(4));
+    }
+}
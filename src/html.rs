@@ -1,6 +1,9 @@
 //! An HTML renderer that takes an iterator of [`Event`]s and emits HTML.
 
+use crate::has_scheme;
 use crate::Alignment;
+use crate::AttributeKind;
+use crate::Attributes;
 use crate::Container;
 use crate::Event;
 use crate::LinkType;
@@ -142,6 +145,297 @@ impl Default for Indentation {
     }
 }
 
+/// Syntax of the output, see [`Renderer::with_syntax`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    /// HTML5, e.g. `<br>`.
+    Html,
+    /// XML, i.e. XHTML, e.g. `<br/>`.
+    Xml,
+}
+
+/// Output of smart punctuation, see [`Renderer::with_entities`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entities {
+    /// Unicode characters, e.g. `…`.
+    Literal,
+    /// Numeric character references, e.g. `&#x2026;`.
+    Numeric,
+    /// Named character references, e.g. `&hellip;`.
+    Named,
+}
+
+/// Options for the output of footnotes, see [`Renderer::with_footnotes`].
+///
+/// # Examples
+///
+/// ```
+/// # use jotdown::*;
+/// # use jotdown::html::*;
+/// let src = "a[^x] b[^y]\n\n[^x]: x\n\n[^y]: y\n";
+/// let renderer = Renderer::minified().with_footnotes(FootnoteStyle {
+///     numbering: FootnoteNumbering::Symbols,
+///     backlink: "back".to_string(),
+///     heading: Some("Notes".to_string()),
+///     ..FootnoteStyle::default()
+/// });
+/// let mut html = String::new();
+/// renderer.push(Parser::new(src), &mut html).unwrap();
+/// assert_eq!(
+///     html,
+///     concat!(
+///         r##"<p>a<a id="fnref1" href="#fn1" role="doc-noteref"><sup>*</sup></a> "##,
+///         r##"b<a id="fnref2" href="#fn2" role="doc-noteref"><sup>†</sup></a></p>"##,
+///         r##"<section role="doc-endnotes"><h2>Notes</h2><ol>"##,
+///         r##"<li id="fn1" style="list-style-type: '* ';">"##,
+///         r##"<p>x<a href="#fnref1" role="doc-backlink">back</a></p></li>"##,
+///         r##"<li id="fn2" style="list-style-type: '† ';">"##,
+///         r##"<p>y<a href="#fnref2" role="doc-backlink">back</a></p></li>"##,
+///         r##"</ol></section>"##,
+///     ),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct FootnoteStyle {
+    /// Numbering of the footnotes, numeric by default.
+    pub numbering: FootnoteNumbering,
+    /// Text of the links from the footnotes back to their references.
+    pub backlink: String,
+    /// Heading of the footnote section, in place of the thematic break that precedes the
+    /// footnotes by default.
+    pub heading: Option<String>,
+    /// Placement of the footnote section, at the end of the document by default.
+    pub placement: FootnotePlacement,
+    /// Prefix of the ids of footnotes and their references, empty by default. May be used to
+    /// keep the ids unique when multiple documents are placed on the same page.
+    pub id_prefix: String,
+}
+
+impl Default for FootnoteStyle {
+    fn default() -> Self {
+        Self {
+            numbering: FootnoteNumbering::Numeric,
+            backlink: "\u{21A9}\u{FE0E}".to_string(),
+            heading: None,
+            placement: FootnotePlacement::End,
+            id_prefix: String::new(),
+        }
+    }
+}
+
+/// Numbering of footnotes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FootnoteNumbering {
+    /// Decimal numbers, e.g. 1, 2, 3.
+    Numeric,
+    /// Typographical symbols, i.e. *, †, ‡, §, ‖, ¶, which are doubled, tripled etc. after the
+    /// sixth footnote.
+    Symbols,
+}
+
+impl FootnoteNumbering {
+    /// The marker of the footnote with the specified number.
+    fn marker(self, number: usize) -> String {
+        match self {
+            Self::Numeric => number.to_string(),
+            Self::Symbols => {
+                const SYMBOLS: [&str; 6] = ["*", "†", "‡", "§", "‖", "¶"];
+                SYMBOLS[(number - 1) % SYMBOLS.len()].repeat((number - 1) / SYMBOLS.len() + 1)
+            }
+        }
+    }
+}
+
+/// Placement of the footnote section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FootnotePlacement {
+    /// A single section at the end of the document.
+    End,
+    /// A section at the end of each top-level section of the document, with the footnotes that
+    /// have been referenced within it. Footnotes referenced outside of sections are placed at
+    /// the end of the document.
+    ///
+    /// The whole document is buffered, as footnotes may be defined after the end of the section
+    /// they are referenced in.
+    Sections,
+    /// Within the text, at the first reference to each footnote, as a
+    /// `<span class="footnote">` element that contains the content of the footnote.
+    ///
+    /// This is intended for print CSS, where the element may be floated to the bottom of the
+    /// page or into the margin, which also generates the marker of the reference. Only the inline
+    /// content of the footnote is written, with its blocks, e.g. paragraphs, list items or code
+    /// blocks, joined by spaces, as a paragraph may not contain block elements. The whole
+    /// document is buffered, as footnotes are usually defined after their references.
+    Inline,
+}
+
+/// Options for the markup of code blocks, see [`Renderer::with_code_blocks`].
+///
+/// # Examples
+///
+/// ```
+/// # use jotdown::*;
+/// # use jotdown::html::*;
+/// let renderer = Renderer::minified().with_code_blocks(CodeBlockStyle {
+///     language_prefix: "lang-".to_string(),
+///     language_on_pre: true,
+///     data_lang: true,
+///     wrapper: Some("div".to_string()),
+///     wrapper_class: Some("highlight".to_string()),
+///     code_class: None,
+/// });
+/// let mut html = String::new();
+/// renderer
+///     .push(Parser::new("``` rust\nfn f() {}\n```"), &mut html)
+///     .unwrap();
+/// assert_eq!(
+///     html,
+///     concat!(
+///         r#"<div class="highlight"><pre class="lang-rust" data-lang="rust">"#,
+///         "<code>fn f() {}\n</code></pre></div>",
+///     ),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct CodeBlockStyle {
+    /// Prefix of the class with the language of the code block, `language-` by default.
+    pub language_prefix: String,
+    /// Place the class with the language on the `<pre>` element instead of the `<code>` element.
+    pub language_on_pre: bool,
+    /// Add the language as a `data-lang` attribute of the `<pre>` element.
+    pub data_lang: bool,
+    /// Element that wraps the `<pre>` element, e.g. `div`.
+    pub wrapper: Option<String>,
+    /// Class of the wrapping element.
+    pub wrapper_class: Option<String>,
+    /// Class of the `<code>` element, written before the class with the language, e.g. `hljs`.
+    pub code_class: Option<String>,
+}
+
+impl Default for CodeBlockStyle {
+    fn default() -> Self {
+        Self {
+            language_prefix: "language-".to_string(),
+            language_on_pre: false,
+            data_lang: false,
+            wrapper: None,
+            wrapper_class: None,
+            code_class: None,
+        }
+    }
+}
+
+impl CodeBlockStyle {
+    /// Classes expected by highlight.js, e.g. `<code class="hljs language-rust">`.
+    ///
+    /// The default classes, e.g. `language-rust`, are expected by most other highlighters such
+    /// as Prism, while a `language_prefix` of `lang-` is expected by e.g. Prettify.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let renderer = Renderer::minified().with_code_blocks(CodeBlockStyle::highlight_js());
+    /// let mut html = String::new();
+    /// renderer
+    ///     .push(Parser::new("``` rust\nfn f() {}\n```"), &mut html)
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     html,
+    ///     "<pre><code class=\"hljs language-rust\">fn f() {}\n</code></pre>",
+    /// );
+    /// ```
+    #[must_use]
+    pub fn highlight_js() -> Self {
+        Self {
+            code_class: Some("hljs".to_string()),
+            ..Self::default()
+        }
+    }
+}
+
+/// Output of an executed code block, see [`Renderer::with_code_execution`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodeOutput {
+    /// HTML written after the code block.
+    After(String),
+    /// HTML written in place of the code block.
+    Replace(String),
+}
+
+/// Output of raw content of a format, see [`Renderer::with_raw_format`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RawOutput {
+    /// Written as is, like raw HTML by default.
+    Html,
+    /// Escaped and written like code, i.e. within a `<code>` element, or a `<pre>` element for
+    /// raw blocks.
+    Code,
+    /// Omitted, like raw content of formats other than HTML by default.
+    Omit,
+}
+
+/// Options for permalink anchors within headings, see [`Renderer::with_permalinks`].
+#[derive(Debug, Clone)]
+pub struct Permalinks {
+    /// Text of the anchors, `¶` by default.
+    pub symbol: String,
+    /// Placement of the anchors within the headings, after the text by default.
+    pub placement: PermalinkPlacement,
+}
+
+impl Default for Permalinks {
+    fn default() -> Self {
+        Self {
+            symbol: "¶".to_string(),
+            placement: PermalinkPlacement::After,
+        }
+    }
+}
+
+/// Placement of a permalink anchor within a heading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermalinkPlacement {
+    /// Before the text of the heading.
+    Before,
+    /// After the text of the heading.
+    After,
+}
+
+/// Hook used to render the content of a math container, see [`Renderer::with_math`].
+type MathHook = std::sync::Arc<dyn Fn(&str, bool) -> Option<String> + Send + Sync>;
+
+/// Hook used to render the content of a code block, see [`Renderer::with_diagrams`].
+type CodeBlockHook = std::sync::Arc<dyn Fn(&str, &str) -> Option<String> + Send + Sync>;
+
+/// Hook used to execute a code block, see [`Renderer::with_code_execution`].
+type ExecutionHook = std::sync::Arc<dyn Fn(&str, &str) -> Option<CodeOutput> + Send + Sync>;
+
+/// Hook used to render a custom block, see [`Renderer::with_custom_block`].
+type CustomBlockHook = std::sync::Arc<dyn Fn(&str, &str) -> Option<String> + Send + Sync>;
+
+/// Hook used to look up the dimensions of an image, see [`Renderer::with_image_sizes`].
+type ImageSizeHook = std::sync::Arc<dyn Fn(&str) -> Option<(u32, u32)> + Send + Sync>;
+
+/// Hook used to add attributes to containers, see [`Renderer::with_attributes_fn`].
+type AttributesHook =
+    std::sync::Arc<dyn Fn(&Container) -> Option<Attributes<'static>> + Send + Sync>;
+
+/// Hook used to rewrite the URLs of links and images, see [`Renderer::with_url_rewrite`].
+type UrlHook = std::sync::Arc<dyn Fn(&str) -> Option<String> + Send + Sync>;
+
+/// Hook used to read the content of a local image, see [`Renderer::with_embedded_images`].
+type ImageDataHook = std::sync::Arc<dyn Fn(&str) -> Option<Vec<u8>> + Send + Sync>;
+
+/// Element used to render an inline container, see [`Renderer::with_element`].
+#[derive(Clone)]
+struct Element {
+    container: Container<'static>,
+    tag: String,
+    class: Option<String>,
+}
+
 /// [`Render`] implementor that writes HTML output.
 ///
 /// By default, block elements are placed on separate lines. To configure the formatting of the
@@ -149,10 +443,723 @@ impl Default for Indentation {
 #[derive(Clone)]
 pub struct Renderer {
     indent: Option<Indentation>,
+    math: Option<MathHook>,
+    diagrams: Option<(Vec<String>, CodeBlockHook)>,
+    execution: Option<ExecutionHook>,
+    custom_blocks: Vec<(String, CustomBlockHook)>,
+    elements: Vec<Element>,
+    /// Attributes added to all containers of a kind.
+    attributes: Vec<(Container<'static>, Attributes<'static>)>,
+    /// Hooks that add attributes to containers, depending on their fields.
+    attributes_fn: Vec<AttributesHook>,
+    /// Classes of divs rendered as admonitions, with their titles.
+    admonitions: Vec<(String, String)>,
+    figures: bool,
+    footnotes: FootnoteStyle,
+    /// Prefix of the classes added by the renderer.
+    class_prefix: String,
+    syntax: Syntax,
+    entities: Entities,
+    comments: bool,
+    cjk_breaks: bool,
+    permalinks: Option<Permalinks>,
+    line_numbers: bool,
+    diff_lines: bool,
+    tab_width: Option<usize>,
+    code_blocks: CodeBlockStyle,
+    /// Output of raw content, by format.
+    raw_formats: Vec<(String, RawOutput)>,
+    image_sizes: Option<ImageSizeHook>,
+    url_rewrite: Option<UrlHook>,
+    /// Maximum size of embedded images, with the hook used to read them.
+    embedded_images: Option<(usize, ImageDataHook)>,
+    obfuscate_emails: bool,
+    print: bool,
+    /// Base URL of the document, if rendering for a feed.
+    feed: Option<String>,
 }
 
-impl Renderer {
-    /// Create a renderer that emits no whitespace between elements.
+impl Renderer {
+    /// Create a renderer that emits no whitespace between elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let src = concat!(
+    ///     "- a\n",
+    ///     "\n",
+    ///     "  - b\n",
+    ///     "\n",
+    ///     "  - c\n",
+    /// );
+    /// let mut actual = String::new();
+    /// let renderer = Renderer::minified();
+    /// renderer.push(Parser::new(src), &mut actual).unwrap();
+    /// let expected =
+    ///     "<ul><li>a<ul><li><p>b</p></li><li><p>c</p></li></ul></li></ul>";
+    /// assert_eq!(actual, expected);
+    /// ```
+    #[must_use]
+    pub fn minified() -> Self {
+        Self {
+            indent: None,
+            ..Self::default()
+        }
+    }
+
+    /// Create a renderer that indents lines based on their block element depth.
+    ///
+    /// See the [`Indentation`] struct for indentation options.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let src = concat!(
+    ///     "- a\n",
+    ///     "\n",
+    ///     "  - b\n",
+    ///     "\n",
+    ///     "  - c\n",
+    /// );
+    /// let mut actual = String::new();
+    /// let renderer = Renderer::indented(Indentation::default());
+    /// renderer.push(Parser::new(src), &mut actual).unwrap();
+    /// let expected = concat!(
+    ///     "<ul>\n",
+    ///     "\t<li>\n",
+    ///     "\t\ta\n",
+    ///     "\t\t<ul>\n",
+    ///     "\t\t\t<li>\n",
+    ///     "\t\t\t\t<p>b</p>\n",
+    ///     "\t\t\t</li>\n",
+    ///     "\t\t\t<li>\n",
+    ///     "\t\t\t\t<p>c</p>\n",
+    ///     "\t\t\t</li>\n",
+    ///     "\t\t</ul>\n",
+    ///     "\t</li>\n",
+    ///     "</ul>\n",
+    /// );
+    /// assert_eq!(actual, expected);
+    /// ```
+    #[must_use]
+    pub fn indented(indent: Indentation) -> Self {
+        Self {
+            indent: Some(indent),
+            ..Self::default()
+        }
+    }
+
+    /// Render the content of math containers with the provided function, e.g. in order to
+    /// pre-render it on the server with a KaTeX or MathJax binding.
+    ///
+    /// The function is given the raw math source and whether it is display math. The returned
+    /// HTML is written as is, in place of the default `<span class="math">` element. Attributes of
+    /// the math are kept on a `<span>` element wrapping the returned HTML. If the function returns
+    /// an error, the math is rendered as if no function was provided.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let src = "$`x^2` and $$`\\frac{1}{2}`\n";
+    /// let renderer = Renderer::default().with_math(|math, display| {
+    ///     if display {
+    ///         Err("display math not supported")
+    ///     } else {
+    ///         Ok(format!("<math><mi>{}</mi></math>", math))
+    ///     }
+    /// });
+    /// let mut html = String::new();
+    /// renderer.push(Parser::new(src), &mut html).unwrap();
+    /// assert_eq!(
+    ///     html,
+    ///     concat!(
+    ///         "<p><math><mi>x^2</mi></math> and ",
+    ///         "<span class=\"math display\">\\[\\frac{1}{2}\\]</span></p>\n",
+    ///     ),
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_math<F, E>(mut self, f: F) -> Self
+    where
+        F: Fn(&str, bool) -> Result<String, E> + Send + Sync + 'static,
+    {
+        self.math = Some(std::sync::Arc::new(move |math, display| {
+            f(math, display).ok()
+        }));
+        self
+    }
+
+    /// Render code blocks with any of the specified languages with the provided function, e.g. in
+    /// order to convert diagram descriptions (mermaid, graphviz, plantuml, ...) to SVG.
+    ///
+    /// The function is given the language and the content of the code block. The returned HTML
+    /// is written as is, in place of the default `<pre>` element. If the function returns an
+    /// error, the code block is rendered as if no function was provided.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let src = concat!(
+    ///     "``` mermaid\n",
+    ///     "graph TD; A-->B;\n",
+    ///     "```\n",
+    ///     "\n",
+    ///     "``` dot\n",
+    ///     "digraph {\n",
+    ///     "```\n",
+    /// );
+    /// let renderer = Renderer::default().with_diagrams(["mermaid", "dot"], |lang, src| {
+    ///     if src.starts_with("graph") {
+    ///         Ok(format!("<svg data-lang=\"{}\"></svg>", lang))
+    ///     } else {
+    ///         Err("syntax error")
+    ///     }
+    /// });
+    /// let mut html = String::new();
+    /// renderer.push(Parser::new(src), &mut html).unwrap();
+    /// assert_eq!(
+    ///     html,
+    ///     concat!(
+    ///         "<svg data-lang=\"mermaid\"></svg>\n",
+    ///         "<pre><code class=\"language-dot\">digraph {\n",
+    ///         "</code></pre>\n",
+    ///     ),
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_diagrams<I, S, F, E>(mut self, languages: I, f: F) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+        F: Fn(&str, &str) -> Result<String, E> + Send + Sync + 'static,
+    {
+        self.diagrams = Some((
+            languages.into_iter().map(Into::into).collect(),
+            std::sync::Arc::new(move |lang, src| f(lang, src).ok()),
+        ));
+        self
+    }
+
+    /// Execute code blocks with the provided function, e.g. to include the output of the
+    /// examples of a document.
+    ///
+    /// The function is given the language and the content of each code block, and returns the
+    /// HTML to write after the code block or in place of it, or `None` to only render the code
+    /// block. Code blocks rendered as diagrams are not executed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let src = concat!(
+    ///     "``` sum\n",
+    ///     "1 2 3\n",
+    ///     "```\n",
+    ///     "\n",
+    ///     "``` total\n",
+    ///     "4 5\n",
+    ///     "```\n",
+    /// );
+    /// let renderer = Renderer::minified().with_code_execution(|lang, code| {
+    ///     let sum: i64 = code.split_whitespace().filter_map(|n| n.parse::<i64>().ok()).sum();
+    ///     let output = format!("<pre class=\"output\">{}</pre>", sum);
+    ///     match lang {
+    ///         "sum" => Some(CodeOutput::After(output)),
+    ///         "total" => Some(CodeOutput::Replace(output)),
+    ///         _ => None,
+    ///     }
+    /// });
+    /// let mut html = String::new();
+    /// renderer.push(Parser::new(src), &mut html).unwrap();
+    /// assert_eq!(
+    ///     html,
+    ///     concat!(
+    ///         "<pre><code class=\"language-sum\">1 2 3\n</code></pre>",
+    ///         "<pre class=\"output\">6</pre>",
+    ///         "<pre class=\"output\">9</pre>",
+    ///     ),
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_code_execution<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str, &str) -> Option<CodeOutput> + Send + Sync + 'static,
+    {
+        self.execution = Some(std::sync::Arc::new(f));
+        self
+    }
+
+    /// Render custom blocks with the specified name with the provided function, see the
+    /// [`extension`](crate::extension) module.
+    ///
+    /// The function is given the argument and the content of the block. The returned HTML is
+    /// written as is, in place of the default `<div>` element. If the function returns an error,
+    /// the block is rendered as if no function was provided.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// # use jotdown::extension::Extensions;
+    /// let src = concat!(
+    ///     "$$$ 5\n",
+    ///     "Jon Doe\n",
+    ///     "$$$\n",
+    /// );
+    /// let extensions = Extensions::new().with_fenced("rating", "$$$");
+    /// let renderer = Renderer::default().with_custom_block("rating", |stars, author| {
+    ///     let n: usize = stars.parse().map_err(|_| "invalid rating")?;
+    ///     Ok::<_, &str>(format!("<p>{} by {}</p>", "★".repeat(n), author.trim()))
+    /// });
+    /// let mut html = String::new();
+    /// renderer
+    ///     .push(Parser::with_extensions(src, &extensions), &mut html)
+    ///     .unwrap();
+    /// assert_eq!(html, "<p>★★★★★ by Jon Doe</p>\n");
+    /// ```
+    #[must_use]
+    pub fn with_custom_block<F, E>(mut self, name: &str, f: F) -> Self
+    where
+        F: Fn(&str, &str) -> Result<String, E> + Send + Sync + 'static,
+    {
+        self.custom_blocks.push((
+            name.to_string(),
+            std::sync::Arc::new(move |argument, content| f(argument, content).ok()),
+        ));
+        self
+    }
+
+    /// Render containers of the same kind as `container` with the specified element and class,
+    /// instead of the default element.
+    ///
+    /// Only the kind of the container is considered, not its fields. The containers that may be
+    /// remapped are those rendered as a single element without any additional markup:
+    ///
+    /// - spans, verbatim and the containers of inline formatting, i.e. subscript, superscript,
+    ///   insert, delete, strong, emphasis and mark,
+    /// - blockquotes, divs, sections, tables, captions and the elements of description lists.
+    ///
+    /// Other containers are rendered as usual.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let src = "The butler did {=it=}, {+surprisingly+}.";
+    /// let renderer = Renderer::default()
+    ///     .with_element(Container::Mark, "span", Some("spoiler"))
+    ///     .with_element(Container::Insert, "u", None);
+    /// let mut html = String::new();
+    /// renderer.push(Parser::new(src), &mut html).unwrap();
+    /// assert_eq!(
+    ///     html,
+    ///     "<p>The butler did <span class=\"spoiler\">it</span>, <u>surprisingly</u>.</p>\n",
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_element(
+        mut self,
+        container: Container<'static>,
+        tag: &str,
+        class: Option<&str>,
+    ) -> Self {
+        self.elements
+            .retain(|e| std::mem::discriminant(&e.container) != std::mem::discriminant(&container));
+        self.elements.push(Element {
+            container,
+            tag: tag.to_string(),
+            class: class.map(ToString::to_string),
+        });
+        self
+    }
+
+    /// Add the specified attributes to all containers of the same kind as `container`.
+    ///
+    /// Only the kind of the container is considered, not its fields. The attributes are merged
+    /// with the attributes of each container: classes are combined, while any other attribute
+    /// specified in the document takes precedence. Calling this again for the same kind of
+    /// container replaces the previous attributes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let src = "{.wide}\n| a |\n\n{title=x}\n| b |\n";
+    /// let renderer = Renderer::minified().with_attributes(
+    ///     Container::Table,
+    ///     Attributes::try_from("{.table title=table}").unwrap(),
+    /// );
+    /// let mut html = String::new();
+    /// renderer.push(Parser::new(src), &mut html).unwrap();
+    /// assert_eq!(
+    ///     html,
+    ///     concat!(
+    ///         "<table class=\"table wide\" title=\"table\"><tr><td>a</td></tr></table>",
+    ///         "<table class=\"table\" title=\"x\"><tr><td>b</td></tr></table>",
+    ///     ),
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_attributes(
+        mut self,
+        container: Container<'static>,
+        attributes: Attributes<'static>,
+    ) -> Self {
+        self.attributes
+            .retain(|(c, _)| std::mem::discriminant(c) != std::mem::discriminant(&container));
+        self.attributes.push((container, attributes));
+        self
+    }
+
+    /// Add the attributes returned by the provided function to each container.
+    ///
+    /// Unlike [`Renderer::with_attributes`], the function is given the whole container, so the
+    /// attributes may depend on its fields, e.g. the destination and type of a link. The
+    /// attributes are merged in the same way, after those of [`Renderer::with_attributes`].
+    /// Calling this again adds another function, whose attributes are merged after those of the
+    /// previous ones.
+    ///
+    /// # Examples
+    ///
+    /// Mark links to other sites, i.e. with an absolute URL:
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let src = "[a](https://a.org) [b](#b) [c](c.html) <https://d.org>";
+    /// let renderer = Renderer::minified().with_attributes_fn(|c| match c {
+    ///     Container::Link(dst, _) if dst.contains("://") => {
+    ///         Some(Attributes::try_from("{rel=noopener .external}").unwrap())
+    ///     }
+    ///     _ => None,
+    /// });
+    /// let mut html = String::new();
+    /// renderer.push(Parser::new(src), &mut html).unwrap();
+    /// assert_eq!(
+    ///     html,
+    ///     concat!(
+    ///         "<p><a href=\"https://a.org\" rel=\"noopener\" class=\"external\">a</a> ",
+    ///         "<a href=\"#b\">b</a> <a href=\"c.html\">c</a> ",
+    ///         "<a href=\"https://d.org\" rel=\"noopener\" class=\"external\">https://d.org</a></p>",
+    ///     ),
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_attributes_fn<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Container) -> Option<Attributes<'static>> + Send + Sync + 'static,
+    {
+        self.attributes_fn.push(std::sync::Arc::new(f));
+        self
+    }
+
+    /// Render divs with the specified class as admonitions with the specified title.
+    ///
+    /// An admonition is a div with the additional class `admonition`, whose content is preceded
+    /// by a title paragraph with the class `admonition-title`. If a div has several classes that
+    /// are admonitions, the one that was registered first is used.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let src = concat!(
+    ///     "::: warning\n",
+    ///     "Do not feed the gremlins.\n",
+    ///     ":::\n",
+    /// );
+    /// let renderer = Renderer::default().with_admonition("warning", "Achtung!");
+    /// let mut html = String::new();
+    /// renderer.push(Parser::new(src), &mut html).unwrap();
+    /// assert_eq!(
+    ///     html,
+    ///     concat!(
+    ///         "<div class=\"admonition warning\">\n",
+    ///         "<p class=\"admonition-title\">Achtung!</p>\n",
+    ///         "<p>Do not feed the gremlins.</p>\n",
+    ///         "</div>\n",
+    ///     ),
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_admonition(mut self, class: &str, title: &str) -> Self {
+        self.admonitions
+            .push((class.to_string(), title.to_string()));
+        self
+    }
+
+    /// Render divs with the classes `note`, `tip`, `important`, `warning` and `caution` as
+    /// admonitions, see [`Renderer::with_admonition`].
+    ///
+    /// The titles are the class names, capitalized. Other classes may be added, or the titles
+    /// changed, by registering them before calling this method.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let src = "{.note}\n:::\nRead the manual.\n:::\n";
+    /// let renderer = Renderer::minified().with_admonitions();
+    /// let mut html = String::new();
+    /// renderer.push(Parser::new(src), &mut html).unwrap();
+    /// assert_eq!(
+    ///     html,
+    ///     concat!(
+    ///         "<div class=\"note admonition\">",
+    ///         "<p class=\"admonition-title\">Note</p>",
+    ///         "<p>Read the manual.</p>",
+    ///         "</div>",
+    ///     ),
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_admonitions(self) -> Self {
+        [
+            ("note", "Note"),
+            ("tip", "Tip"),
+            ("important", "Important"),
+            ("warning", "Warning"),
+            ("caution", "Caution"),
+        ]
+        .iter()
+        .fold(self, |r, (class, title)| r.with_admonition(class, title))
+    }
+
+    /// Render paragraphs that only contain an image as figures.
+    ///
+    /// The caption of a figure is given by a line starting with `^ ` after the image, or by the
+    /// alt text of the image otherwise. Attributes of the paragraph are placed on the `<figure>`
+    /// element.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let src = concat!(
+    ///     "![A cat](cat.jpg)\n",
+    ///     "\n",
+    ///     "{#dog}\n",
+    ///     "![A dog](dog.jpg)\n",
+    ///     "^ The _good_ boy.\n",
+    ///     "\n",
+    ///     "Not ![alone](a.jpg).\n",
+    /// );
+    /// let renderer = Renderer::default().with_figures();
+    /// let mut html = String::new();
+    /// renderer.push(Parser::new(src), &mut html).unwrap();
+    /// assert_eq!(
+    ///     html,
+    ///     concat!(
+    ///         "<figure>\n",
+    ///         "<img alt=\"A cat\" src=\"cat.jpg\">\n",
+    ///         "<figcaption>A cat</figcaption>\n",
+    ///         "</figure>\n",
+    ///         "<figure id=\"dog\">\n",
+    ///         "<img alt=\"A dog\" src=\"dog.jpg\">\n",
+    ///         "<figcaption>The <em>good</em> boy.</figcaption>\n",
+    ///         "</figure>\n",
+    ///         "<p>Not <img alt=\"alone\" src=\"a.jpg\">.</p>\n",
+    ///     ),
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_figures(mut self) -> Self {
+        self.figures = true;
+        self
+    }
+
+    /// Render footnotes with the provided style, see [`FootnoteStyle`].
+    #[must_use]
+    pub fn with_footnotes(mut self, style: FootnoteStyle) -> Self {
+        self.footnotes = style;
+        self
+    }
+
+    /// Prefix the classes that are added by the renderer, e.g. `math` or `admonition`, with the
+    /// specified string, to avoid collisions with the classes of a stylesheet.
+    ///
+    /// The names of custom containers are also prefixed, while classes specified in the document
+    /// or by [`Renderer::with_element`] are not.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let src = "{.x}\n$`a`\n\n``` rust\nb\n```\n";
+    /// let renderer = Renderer::minified().with_class_prefix("dj-");
+    /// let mut html = String::new();
+    /// renderer.push(Parser::new(src), &mut html).unwrap();
+    /// assert_eq!(
+    ///     html,
+    ///     concat!(
+    ///         r#"<p class="x"><span class="dj-math dj-inline">\(a\)</span></p>"#,
+    ///         r#"<pre><code class="dj-language-rust">b"#,
+    ///         "\n",
+    ///         "</code></pre>",
+    ///     ),
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_class_prefix(mut self, prefix: &str) -> Self {
+        self.class_prefix = prefix.to_string();
+        self
+    }
+
+    /// Render with the specified syntax, HTML5 by default.
+    ///
+    /// With the XML syntax, void elements are closed, e.g. `<br/>`, boolean attributes are given
+    /// values, e.g. `checked="checked"`, and no named character references are used. Attribute
+    /// values are always enclosed in double quotes, which both syntaxes accept. Raw HTML is
+    /// written as is, regardless of the syntax.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let src = "a\\\nb\\ ![c](d.png)\n\n***\n";
+    /// let renderer = Renderer::minified().with_syntax(Syntax::Xml);
+    /// let mut html = String::new();
+    /// renderer.push(Parser::new(src), &mut html).unwrap();
+    /// assert_eq!(
+    ///     html,
+    ///     "<p>a<br/>b&#xa0;<img alt=\"c\" src=\"d.png\"/></p><hr/>",
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_syntax(mut self, syntax: Syntax) -> Self {
+        self.syntax = syntax;
+        self
+    }
+
+    /// Write smart punctuation, i.e. quotes, ellipses, dashes and non-breaking spaces, as the
+    /// specified kind of characters or references, Unicode characters by default.
+    ///
+    /// Named references are not defined in XML, numeric references are written instead with
+    /// [`Syntax::Xml`] or [`Renderer::with_feed`]. Non-breaking spaces are always written as
+    /// references, named by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let src = "\"Wait\"... 1--2";
+    /// let mut html = String::new();
+    /// let renderer = Renderer::minified().with_entities(Entities::Named);
+    /// renderer.push(Parser::new(src), &mut html).unwrap();
+    /// assert_eq!(html, "<p>&ldquo;Wait&rdquo;&hellip; 1&ndash;2</p>");
+    /// let mut html = String::new();
+    /// let renderer = Renderer::minified().with_entities(Entities::Numeric);
+    /// renderer.push(Parser::new(src), &mut html).unwrap();
+    /// assert_eq!(html, "<p>&#x201c;Wait&#x201d;&#x2026; 1&#x2013;2</p>");
+    /// ```
+    #[must_use]
+    pub fn with_entities(mut self, entities: Entities) -> Self {
+        self.entities = entities;
+        self
+    }
+
+    /// Render comments, e.g. `{% TODO: rephrase %}`, as HTML comments instead of dropping them.
+    ///
+    /// Comments within the attributes of an element are placed before the element. Any `--`
+    /// within a comment is written as `- -`, as it may not occur within an HTML comment.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let src = "{% draft %}\nSome [text]{% check this %}.\n";
+    /// let renderer = Renderer::minified().with_comments();
+    /// let mut html = String::new();
+    /// renderer.push(Parser::new(src), &mut html).unwrap();
+    /// assert_eq!(
+    ///     html,
+    ///     "<!-- draft --><p>Some <!-- check this --><span>text</span>.</p>",
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_comments(mut self) -> Self {
+        self.comments = true;
+        self
+    }
+
+    /// Join lines without a line break when both sides of a soft break are Chinese or Japanese
+    /// characters, which are not separated by spaces.
+    ///
+    /// Browsers render a line break as a space, which is only appropriate within text that
+    /// separates words with spaces.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let src = "日本語の\n文章。\nLatin\ntext";
+    /// let renderer = Renderer::minified().with_cjk_breaks();
+    /// let mut html = String::new();
+    /// renderer.push(Parser::new(src), &mut html).unwrap();
+    /// assert_eq!(html, "<p>日本語の文章。\nLatin\ntext</p>");
+    /// ```
+    #[must_use]
+    pub fn with_cjk_breaks(mut self) -> Self {
+        self.cjk_breaks = true;
+        self
+    }
+
+    /// Add a permalink anchor, i.e. a link to the heading itself, to each heading.
+    ///
+    /// The anchors have the class `anchor`, so that they may e.g. only be shown when the heading
+    /// is hovered. They are omitted in feeds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let renderer = Renderer::minified().with_permalinks(Permalinks {
+    ///     symbol: "#".to_string(),
+    ///     placement: PermalinkPlacement::Before,
+    /// });
+    /// let mut html = String::new();
+    /// renderer.push(Parser::new("## Usage"), &mut html).unwrap();
+    /// assert_eq!(
+    ///     html,
+    ///     r##"<section id="Usage"><h2><a class="anchor" href="#Usage">#</a>Usage</h2></section>"##,
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_permalinks(mut self, permalinks: Permalinks) -> Self {
+        self.permalinks = Some(permalinks);
+        self
+    }
+
+    /// Wrap each line of code blocks in an element with its line number, and highlight lines
+    /// that are specified by attributes of the code block.
+    ///
+    /// Each line is wrapped in a `<span class="line">` with the line number in a `data-line`
+    /// attribute, which may be displayed with CSS, e.g. `content: attr(data-line)`, so that the
+    /// numbers are not copied together with the code. The lines are numbered from the value of a
+    /// `startline` attribute, or from 1. Lines listed in a `hl_lines` attribute, e.g. `"2 4-6"`
+    /// for the second, fourth, fifth and sixth line of the code block, additionally have the
+    /// class `hl`. Neither attribute is written to the output.
     ///
     /// # Examples
     ///
@@ -160,27 +1167,364 @@ impl Renderer {
     /// # use jotdown::*;
     /// # use jotdown::html::*;
     /// let src = concat!(
-    ///     "- a\n",
+    ///     "{startline=5 hl_lines=2}\n",
+    ///     "```\n",
+    ///     "a\n",
+    ///     "b\n",
+    ///     "```\n",
+    /// );
+    /// let renderer = Renderer::minified().with_line_numbers();
+    /// let mut html = String::new();
+    /// renderer.push(Parser::new(src), &mut html).unwrap();
+    /// assert_eq!(
+    ///     html,
+    ///     concat!(
+    ///         "<pre><code>",
+    ///         "<span class=\"line\" data-line=\"5\">a</span>\n",
+    ///         "<span class=\"line hl\" data-line=\"6\">b</span>\n",
+    ///         "</code></pre>",
+    ///     ),
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_line_numbers(mut self) -> Self {
+        self.line_numbers = true;
+        self
+    }
+
+    /// Wrap each line of diffs in an element, with a class for added and removed lines.
+    ///
+    /// Code blocks with the language `diff`, or with a `diff` class, e.g. a patch of Rust code
+    /// with `{.diff}` attributes, are considered diffs. Each line is wrapped in a
+    /// `<span class="line">` that additionally has the class `added` if it starts with a `+`, or
+    /// `removed` if it starts with a `-`, so that the changes may be colored with CSS. The
+    /// elements are the same as those of [`Renderer::with_line_numbers`], which may also be
+    /// enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let src = concat!(
+    ///     "``` diff\n",
+    ///     " fn main() {\n",
+    ///     "-    println!(\"hello\");\n",
+    ///     "+    println!(\"hello, world\");\n",
+    ///     "```\n",
+    /// );
+    /// let renderer = Renderer::minified().with_diff_lines();
+    /// let mut html = String::new();
+    /// renderer.push(Parser::new(src), &mut html).unwrap();
+    /// assert_eq!(
+    ///     html,
+    ///     concat!(
+    ///         "<pre><code class=\"language-diff\">",
+    ///         "<span class=\"line\"> fn main() {</span>\n",
+    ///         "<span class=\"line removed\">-    println!(\"hello\");</span>\n",
+    ///         "<span class=\"line added\">+    println!(\"hello, world\");</span>\n",
+    ///         "</code></pre>",
+    ///     ),
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_diff_lines(mut self) -> Self {
+        self.diff_lines = true;
+        self
+    }
+
+    /// Expand tabs within code blocks and verbatim to spaces, up to the next multiple of `width`
+    /// columns, or of a single column if `width` is zero.
+    ///
+    /// Browsers render tabs with a width of 8 columns by default, which may be changed by CSS,
+    /// so the alignment of code that mixes tabs and spaces depends on the styling of the page.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let src = "```\na\tb\n\tc\n```\n\n`ab\tc`";
+    /// let renderer = Renderer::minified().with_tab_width(4);
+    /// let mut html = String::new();
+    /// renderer.push(Parser::new(src), &mut html).unwrap();
+    /// assert_eq!(
+    ///     html,
+    ///     "<pre><code>a   b\n    c\n</code></pre><p><code>ab  c</code></p>",
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_tab_width(mut self, width: usize) -> Self {
+        self.tab_width = Some(width.max(1));
+        self
+    }
+
+    /// Render code blocks with the provided markup, see [`CodeBlockStyle`], e.g. in order to
+    /// match the output expected by a syntax highlighter or a script.
+    #[must_use]
+    pub fn with_code_blocks(mut self, style: CodeBlockStyle) -> Self {
+        self.code_blocks = style;
+        self
+    }
+
+    /// Output raw blocks and inlines of the specified format, e.g. `=html` or `=latex`, as
+    /// specified instead of the default.
+    ///
+    /// By default, raw HTML is written as is and raw content of other formats is omitted. Raw
+    /// HTML is omitted in feeds regardless, see [`Renderer::with_feed`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let src = "`<b>`{=html} `\\LaTeX`{=latex} `<i>`{=xhtml}";
+    /// let renderer = Renderer::minified()
+    ///     .with_raw_format("html", RawOutput::Code)
+    ///     .with_raw_format("latex", RawOutput::Code)
+    ///     .with_raw_format("xhtml", RawOutput::Html);
+    /// let mut html = String::new();
+    /// renderer.push(Parser::new(src), &mut html).unwrap();
+    /// assert_eq!(html, "<p><code>&lt;b&gt;</code> <code>\\LaTeX</code> <i></p>");
+    /// ```
+    ///
+    /// The class of inline verbatim, like the attributes of any other container, may be set
+    /// with [`Renderer::with_attributes`]:
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let renderer = Renderer::minified()
+    ///     .with_attributes(Container::Verbatim, Attributes::try_from("{.code}").unwrap());
+    /// let mut html = String::new();
+    /// renderer.push(Parser::new("`a`"), &mut html).unwrap();
+    /// assert_eq!(html, "<p><code class=\"code\">a</code></p>");
+    /// ```
+    #[must_use]
+    pub fn with_raw_format(mut self, format: &str, output: RawOutput) -> Self {
+        self.raw_formats.retain(|(f, _)| f != format);
+        self.raw_formats.push((format.to_string(), output));
+        self
+    }
+
+    /// Add `width` and `height` attributes to images, with the intrinsic dimensions returned by
+    /// the provided function.
+    ///
+    /// The function is given the source of the image and may e.g. read the header of the file or
+    /// look it up in a manifest. Knowing the dimensions in advance, browsers can reserve space
+    /// for images before they are loaded, so that the layout does not shift. Images that already
+    /// have a `width` or `height` attribute are left as is, as are images for which the function
+    /// returns `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let src = "![a](a.png) ![b](b.png){width=50} ![c](c.png)\n";
+    /// let renderer = Renderer::default().with_image_sizes(|src| match src {
+    ///     "a.png" | "b.png" => Some((640, 480)),
+    ///     _ => None,
+    /// });
+    /// let mut html = String::new();
+    /// renderer.push(Parser::new(src), &mut html).unwrap();
+    /// assert_eq!(
+    ///     html,
+    ///     concat!(
+    ///         "<p><img width=\"640\" height=\"480\" alt=\"a\" src=\"a.png\"> ",
+    ///         "<img width=\"50\" alt=\"b\" src=\"b.png\"> ",
+    ///         "<img alt=\"c\" src=\"c.png\"></p>\n",
+    ///     ),
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_image_sizes<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str) -> Option<(u32, u32)> + Send + Sync + 'static,
+    {
+        self.image_sizes = Some(std::sync::Arc::new(f));
+        self
+    }
+
+    /// Rewrite the URLs of links and images with the provided function, e.g. in order to append
+    /// content hashes for cache busting.
+    ///
+    /// The function is given the destination of each inline or reference link and the source of
+    /// each image, and returns the URL to render instead, or `None` to keep it. Autolinks and
+    /// email links are not rewritten. The function may e.g. look up the URL in a manifest
+    /// produced by an asset pipeline, as in the example below.
+    ///
+    /// Other hooks, e.g. [`Renderer::with_image_sizes`], are given the original URL.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let manifest: std::collections::HashMap<String, String> = [
+    ///     ("img/logo.png", "img/logo.3f2a91.png"),
+    ///     ("guide.pdf", "guide.pdf?v=7c01e4"),
+    /// ]
+    /// .iter()
+    /// .map(|(k, v)| (k.to_string(), v.to_string()))
+    /// .collect();
+    /// let src = "![logo](img/logo.png) [guide](guide.pdf) [home](index.html)\n";
+    /// let renderer = Renderer::default().with_url_rewrite(move |url| manifest.get(url).cloned());
+    /// let mut html = String::new();
+    /// renderer.push(Parser::new(src), &mut html).unwrap();
+    /// assert_eq!(
+    ///     html,
+    ///     concat!(
+    ///         "<p><img alt=\"logo\" src=\"img/logo.3f2a91.png\"> ",
+    ///         "<a href=\"guide.pdf?v=7c01e4\">guide</a> ",
+    ///         "<a href=\"index.html\">home</a></p>\n",
+    ///     ),
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_url_rewrite<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+    {
+        self.url_rewrite = Some(std::sync::Arc::new(f));
+        self
+    }
+
+    /// Embed local images of at most `max_size` bytes as `data:` URIs, e.g. in order to export a
+    /// self-contained HTML file.
+    ///
+    /// The provided function is given the source of each local image, i.e. one without a URL
+    /// scheme, and returns the content of the file, or `None` if it could not be read. Only PNG,
+    /// JPEG, GIF, WebP, AVIF and SVG images are embedded, as determined by the file extension.
+    /// Images that are not embedded are linked as usual.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let src = "![dot](dot.gif) ![photo](photo.jpg)\n";
+    /// let renderer = Renderer::default()
+    ///     .with_embedded_images(1024, |path| match path {
+    ///         "dot.gif" => Some(b"GIF89a".to_vec()),
+    ///         _ => Some(vec![0; 4096]),
+    ///     });
+    /// let mut html = String::new();
+    /// renderer.push(Parser::new(src), &mut html).unwrap();
+    /// assert_eq!(
+    ///     html,
+    ///     concat!(
+    ///         "<p><img alt=\"dot\" src=\"data:image/gif;base64,R0lGODlh\"> ",
+    ///         "<img alt=\"photo\" src=\"photo.jpg\"></p>\n",
+    ///     ),
+    /// );
+    /// ```
+    ///
+    /// A function that reads images relative to the directory of the document may look like:
+    ///
+    /// ```no_run
+    /// # use jotdown::html::*;
+    /// let dir = std::path::PathBuf::from("docs");
+    /// let renderer = Renderer::default()
+    ///     .with_embedded_images(16 * 1024, move |path| std::fs::read(dir.join(path)).ok());
+    /// ```
+    #[must_use]
+    pub fn with_embedded_images<F>(mut self, max_size: usize, f: F) -> Self
+    where
+        F: Fn(&str) -> Option<Vec<u8>> + Send + Sync + 'static,
+    {
+        self.embedded_images = Some((max_size, std::sync::Arc::new(f)));
+        self
+    }
+
+    /// Write email addresses of autolinks and `mailto:` links as numeric character references.
+    ///
+    /// Browsers display the addresses as usual, but scrapers that search the HTML source for
+    /// addresses or `mailto:` will not find them. No JavaScript is required.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let renderer = Renderer::default().with_obfuscated_emails();
+    /// let mut html = String::new();
+    /// renderer.push(Parser::new("<a@b.c>"), &mut html).unwrap();
+    /// assert_eq!(
+    ///     html,
+    ///     concat!(
+    ///         "<p><a href=\"&#x6d;&#x61;&#x69;&#x6c;&#x74;&#x6f;&#x3a;",
+    ///         "&#x61;&#x40;&#x62;&#x2e;&#x63;\">&#x61;&#x40;&#x62;&#x2e;&#x63;</a></p>\n",
+    ///     ),
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_obfuscated_emails(mut self) -> Self {
+        self.obfuscate_emails = true;
+        self
+    }
+
+    /// Render HTML intended to be printed, or converted to PDF with print CSS.
+    ///
+    /// - the URLs of links are appended to their text in parentheses, within a
+    ///   `<span class="url">` element, unless they point within the document,
+    /// - blocks with a `page-break` class are given a `break-before: page` style.
+    ///
+    /// Footnotes are rendered as endnotes by default. To render them as footnotes at the bottom
+    /// of each page or as margin notes, place them within the text with
+    /// [`FootnotePlacement::Inline`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::html::*;
+    /// let src = concat!(
+    ///     "See [the site](https://example.com)[^1] and [below](#Part-2).\n",
     ///     "\n",
-    ///     "  - b\n",
+    ///     "[^1]: Visited in May.\n",
     ///     "\n",
-    ///     "  - c\n",
+    ///     "{.page-break}\n",
+    ///     "# Part 2\n",
+    /// );
+    /// let renderer = Renderer::minified()
+    ///     .with_print()
+    ///     .with_footnotes(FootnoteStyle {
+    ///         placement: FootnotePlacement::Inline,
+    ///         ..FootnoteStyle::default()
+    ///     });
+    /// let mut html = String::new();
+    /// renderer.push(Parser::new(src), &mut html).unwrap();
+    /// assert_eq!(
+    ///     html,
+    ///     concat!(
+    ///         "<p>See <a href=\"https://example.com\">the site</a> ",
+    ///         "<span class=\"url\">(https://example.com)</span>",
+    ///         "<span class=\"footnote\" role=\"doc-footnote\">Visited in May.</span> ",
+    ///         "and <a href=\"#Part-2\">below</a>.</p>",
+    ///         "<section class=\"page-break\" style=\"break-before: page;\" id=\"Part-2\">",
+    ///         "<h1>Part 2</h1></section>",
+    ///     ),
     /// );
-    /// let mut actual = String::new();
-    /// let renderer = Renderer::minified();
-    /// renderer.push(Parser::new(src), &mut actual).unwrap();
-    /// let expected =
-    ///     "<ul><li>a<ul><li><p>b</p></li><li><p>c</p></li></ul></li></ul>";
-    /// assert_eq!(actual, expected);
     /// ```
     #[must_use]
-    pub fn minified() -> Self {
-        Self { indent: None }
+    pub fn with_print(mut self) -> Self {
+        self.print = true;
+        self
     }
 
-    /// Create a renderer that indents lines based on their block element depth.
+    /// Render HTML that is safe to embed in an RSS or Atom feed, for a document located at the
+    /// provided base URL.
     ///
-    /// See the [`Indentation`] struct for indentation options.
+    /// Feed readers display the content out of the context of the original page, so the output
+    /// is adjusted accordingly:
+    ///
+    /// - relative link and image URLs are resolved against the base URL, unless it is empty,
+    /// - raw HTML is omitted,
+    /// - id attributes are omitted, and footnote references are not linked,
+    /// - all non-ASCII characters and quotes are escaped as numeric character references, so
+    ///   the output is valid regardless of the encoding of the feed and contains no entities
+    ///   that are undefined in XML.
     ///
     /// # Examples
     ///
@@ -188,37 +1532,59 @@ impl Renderer {
     /// # use jotdown::*;
     /// # use jotdown::html::*;
     /// let src = concat!(
-    ///     "- a\n",
-    ///     "\n",
-    ///     "  - b\n",
+    ///     "{#cafe}\n",
+    ///     "# Café\n",
     ///     "\n",
-    ///     "  - c\n",
+    ///     "See [the menu](menu.html) and ![a map](/img/map.png).`<script>`{=html}\n",
     /// );
-    /// let mut actual = String::new();
-    /// let renderer = Renderer::indented(Indentation::default());
-    /// renderer.push(Parser::new(src), &mut actual).unwrap();
-    /// let expected = concat!(
-    ///     "<ul>\n",
-    ///     "\t<li>\n",
-    ///     "\t\ta\n",
-    ///     "\t\t<ul>\n",
-    ///     "\t\t\t<li>\n",
-    ///     "\t\t\t\t<p>b</p>\n",
-    ///     "\t\t\t</li>\n",
-    ///     "\t\t\t<li>\n",
-    ///     "\t\t\t\t<p>c</p>\n",
-    ///     "\t\t\t</li>\n",
-    ///     "\t\t</ul>\n",
-    ///     "\t</li>\n",
-    ///     "</ul>\n",
+    /// let renderer = Renderer::default().with_feed("https://example.com/blog/post.html");
+    /// let mut html = String::new();
+    /// renderer.push(Parser::new(src), &mut html).unwrap();
+    /// assert_eq!(
+    ///     html,
+    ///     concat!(
+    ///         "<section>\n",
+    ///         "<h1>Caf&#xe9;</h1>\n",
+    ///         "<p>See <a href=\"https://example.com/blog/menu.html\">the menu</a> and ",
+    ///         "<img alt=\"a map\" src=\"https://example.com/img/map.png\">.</p>\n",
+    ///         "</section>\n",
+    ///     ),
     /// );
-    /// assert_eq!(actual, expected);
     /// ```
     #[must_use]
-    pub fn indented(indent: Indentation) -> Self {
-        Self {
-            indent: Some(indent),
+    pub fn with_feed(mut self, base_url: impl Into<String>) -> Self {
+        self.feed = Some(base_url.into());
+        self
+    }
+
+    /// Append to the prefix of the ids of footnotes, see [`FootnoteStyle::id_prefix`].
+    pub(crate) fn with_footnote_id_prefix(mut self, prefix: &str) -> Self {
+        self.footnotes.id_prefix.push_str(prefix);
+        self
+    }
+
+    /// Render a block nested within the specified number of levels, to be written as a raw
+    /// block of the [`FRAGMENT`] format.
+    ///
+    /// The indentation of the first line and the final newline are omitted, as they are written
+    /// around raw blocks.
+    pub(crate) fn render_fragment<'s, I>(&self, events: I, level: usize) -> String
+    where
+        I: Iterator<Item = Event<'s>>,
+    {
+        let mut renderer = self.clone();
+        if let Some(indent) = &mut renderer.indent {
+            indent.initial_level += level;
         }
+        let mut html = String::new();
+        renderer.push(events, &mut html).unwrap();
+        let start = match &self.indent {
+            Some(indent) if !indent.string.is_empty() => {
+                html.len() - html.trim_start_matches(indent.string.as_str()).len()
+            }
+            _ => 0,
+        };
+        html[start..].trim_end_matches('\n').to_string()
     }
 }
 
@@ -265,6 +1631,33 @@ impl Default for Renderer {
                 string: String::new(),
                 initial_level: 0,
             }),
+            math: None,
+            diagrams: None,
+            execution: None,
+            custom_blocks: Vec::new(),
+            elements: Vec::new(),
+            attributes: Vec::new(),
+            attributes_fn: Vec::new(),
+            admonitions: Vec::new(),
+            figures: false,
+            footnotes: FootnoteStyle::default(),
+            class_prefix: String::new(),
+            syntax: Syntax::Html,
+            entities: Entities::Literal,
+            comments: false,
+            cjk_breaks: false,
+            permalinks: None,
+            line_numbers: false,
+            diff_lines: false,
+            tab_width: None,
+            code_blocks: CodeBlockStyle::default(),
+            raw_formats: vec![("html".to_string(), RawOutput::Html)],
+            image_sizes: None,
+            url_rewrite: None,
+            embedded_images: None,
+            obfuscate_emails: false,
+            print: false,
+            feed: None,
         }
     }
 }
@@ -275,8 +1668,25 @@ impl Render for Renderer {
         I: Iterator<Item = Event<'s>>,
         W: std::fmt::Write,
     {
-        let mut w = Writer::new(&self.indent);
-        events.try_for_each(|e| w.render_event(&e, &mut out))?;
+        let mut w = Writer::new(self);
+        if self.footnotes.placement != FootnotePlacement::End {
+            let events: Vec<_> = events.collect();
+            events.iter().for_each(|e| {
+                w.collect_footnote(e);
+            });
+            // the definitions have been collected, skip them instead of collecting them again
+            let mut footnote = 0;
+            for e in &events {
+                match e {
+                    Event::Start(Container::Footnote { .. }, _) => footnote += 1,
+                    Event::End(Container::Footnote { .. }) => footnote -= 1,
+                    e if footnote == 0 => w.render_event(e, &mut out)?,
+                    _ => {}
+                }
+            }
+        } else {
+            events.try_for_each(|e| w.render_event(&e, &mut out))?;
+        }
         w.render_epilogue(&mut out)
     }
 }
@@ -284,6 +1694,9 @@ impl Render for Renderer {
 enum Raw {
     None,
     Html,
+    /// Output rendered by the renderer itself, always written as is.
+    Fragment,
+    Code,
     Other,
 }
 
@@ -293,8 +1706,86 @@ impl Default for Raw {
     }
 }
 
+/// Content of a container that is buffered in order to be passed to a hook.
+struct Capture<'s> {
+    /// Start event of the container, replayed if the hook fails.
+    start: Event<'s>,
+    /// Text content of the container.
+    content: String,
+}
+
+/// Lines of a code block that are wrapped in elements, see [`Renderer::with_line_numbers`].
+struct CodeLines {
+    /// Number of the first line.
+    start: usize,
+    /// Index of the current line within the code block, starting at 0.
+    index: usize,
+    /// Ranges of highlighted lines, by their position within the code block starting at 1.
+    highlight: Vec<(usize, usize)>,
+    /// Write the line numbers, see [`Renderer::with_line_numbers`].
+    numbers: bool,
+    /// Classify added and removed lines, see [`Renderer::with_diff_lines`].
+    diff: bool,
+    /// The element of the current line has been written.
+    open: bool,
+}
+
+impl CodeLines {
+    fn new(attrs: &Attributes, numbers: bool, diff: bool) -> Self {
+        if !numbers {
+            return Self {
+                start: 1,
+                index: 0,
+                highlight: Vec::new(),
+                numbers,
+                diff,
+                open: false,
+            };
+        }
+        let start = attrs
+            .get_value("startline")
+            .and_then(|v| v.to_string().trim().parse().ok())
+            .unwrap_or(1);
+        let highlight = attrs.get_value("hl_lines").map_or_else(Vec::new, |v| {
+            v.to_string()
+                .split_whitespace()
+                .filter_map(|r| {
+                    let (a, b) = r.split_once('-').unwrap_or((r, r));
+                    Some((a.parse().ok()?, b.parse().ok()?))
+                })
+                .collect()
+        });
+        Self {
+            start,
+            index: 0,
+            highlight,
+            numbers,
+            diff,
+            open: false,
+        }
+    }
+
+    fn is_highlighted(&self) -> bool {
+        let n = self.index + 1;
+        self.highlight.iter().any(|(a, b)| (*a..=*b).contains(&n))
+    }
+}
+
+/// A paragraph that is buffered until it is known whether it is a figure.
+struct Figure<'s> {
+    /// Start event of the paragraph.
+    start: Event<'s>,
+    /// Events within the paragraph, starting with an image.
+    events: Vec<Event<'s>>,
+}
+
+/// Format of raw blocks that contain HTML rendered by this renderer, e.g. cached fragments,
+/// which are written as is regardless of the configured raw formats.
+pub(crate) const FRAGMENT: &str = "\0fragment";
+
 struct Writer<'s, 'f> {
     indent: &'f Option<Indentation>,
+    renderer: &'f Renderer,
     depth: usize,
     raw: Raw,
     img_alt_text: usize,
@@ -302,17 +1793,36 @@ struct Writer<'s, 'f> {
     first_line: bool,
     ignore: bool,
     footnotes: Footnotes<'s>,
+    capture: Option<Capture<'s>>,
+    figure: Option<Figure<'s>>,
+    /// Nesting depth of sections.
+    sections: usize,
+    /// Nesting depth of written containers that are not block containers.
+    inline_depth: usize,
+    /// Last character of the text written so far.
+    last_char: Option<char>,
+    /// A soft break that is written unless it is followed by a CJK character.
+    pending_break: bool,
+    /// Id of the current heading, if a permalink is written at its end.
+    permalink: Option<String>,
+    /// Lines of the current code block, if they are wrapped in elements.
+    code_lines: Option<CodeLines>,
+    /// Column within the current code block or verbatim, if its tabs are expanded.
+    column: Option<usize>,
+    /// Whether text is within a link to an email address that is obfuscated.
+    email: bool,
 }
 
 impl<'s, 'f> Writer<'s, 'f> {
-    fn new(indent: &'f Option<Indentation>) -> Self {
-        let depth = if let Some(indent) = indent {
+    fn new(renderer: &'f Renderer) -> Self {
+        let depth = if let Some(indent) = &renderer.indent {
             indent.initial_level
         } else {
             0
         };
         Self {
-            indent,
+            indent: &renderer.indent,
+            renderer,
             depth,
             raw: Raw::default(),
             img_alt_text: 0,
@@ -320,6 +1830,16 @@ impl<'s, 'f> Writer<'s, 'f> {
             first_line: true,
             ignore: false,
             footnotes: Footnotes::default(),
+            capture: None,
+            figure: None,
+            sections: 0,
+            inline_depth: 0,
+            last_char: None,
+            pending_break: false,
+            permalink: None,
+            code_lines: None,
+            column: None,
+            email: false,
         }
     }
 
@@ -361,22 +1881,40 @@ impl<'s, 'f> Writer<'s, 'f> {
         Ok(())
     }
 
-    fn render_event<W>(&mut self, e: &Event<'s>, mut out: W) -> std::fmt::Result
-    where
-        W: std::fmt::Write,
-    {
+    /// Collect the event if it is part of a footnote definition, returning `true` if it is.
+    fn collect_footnote(&mut self, e: &Event<'s>) -> bool {
         if let Event::Start(Container::Footnote { label }, ..) = e {
             self.footnotes.start(label, Vec::new());
-            return Ok(());
         } else if let Some(events) = self.footnotes.current() {
             if matches!(e, Event::End(Container::Footnote { .. })) {
                 self.footnotes.end();
             } else {
                 events.push(e.clone());
             }
+        } else {
+            return false;
+        }
+        true
+    }
+
+    fn render_event<W>(&mut self, e: &Event<'s>, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        if self.collect_footnote(e) {
             return Ok(());
         }
 
+        // keep the break pending through opening inline tags, until the next character is known
+        if self.pending_break && !matches!(e, Event::Start(c, ..) if !c.is_block()) {
+            self.pending_break = false;
+            let joined = matches!(e, Event::Str(s) if s.chars().next().map_or(false, is_cjk));
+            if !joined {
+                out.write_char('\n')?;
+                self.indent(&mut out)?;
+            }
+        }
+
         if matches!(&e, Event::Start(Container::LinkDefinition { .. }, ..)) {
             self.ignore = true;
             return Ok(());
@@ -391,15 +1929,243 @@ impl<'s, 'f> Writer<'s, 'f> {
             return Ok(());
         }
 
+        if let Some(figure) = &mut self.figure {
+            if figure.events.is_empty() && !matches!(e, Event::Start(Container::Image(..), ..)) {
+                let figure = self.figure.take().unwrap();
+                self.write_event(&figure.start, &mut out)?;
+            } else if matches!(e, Event::End(Container::Paragraph)) {
+                let figure = self.figure.take().unwrap();
+                return self.write_figure(figure, e, &mut out);
+            } else {
+                figure.events.push(e.clone());
+                return Ok(());
+            }
+        } else if self.renderer.figures && !self.footnotes.in_epilogue() {
+            if let Event::Start(Container::Paragraph, ..) = e {
+                self.figure = Some(Figure {
+                    start: e.clone(),
+                    events: Vec::new(),
+                });
+                return Ok(());
+            }
+        }
+
+        match e {
+            Event::Start(Container::Section { .. }, ..) => self.sections += 1,
+            Event::End(Container::Section { .. }) => {
+                self.sections -= 1;
+                if self.sections == 0
+                    && self.renderer.footnotes.placement == FootnotePlacement::Sections
+                {
+                    self.render_notes(&mut out)?;
+                }
+            }
+            _ => {}
+        }
+
+        if let Some(capture) = &mut self.capture {
+            match e {
+                Event::Str(s) => capture.content.push_str(s),
+                Event::End(c) => {
+                    let capture = self.capture.take().unwrap();
+                    let (html, after) = match self.render_hook(c, &capture.content) {
+                        Some(html) => (Some(html), None),
+                        None => match self.execute(c, &capture.content) {
+                            Some(CodeOutput::Replace(html)) => (Some(html), None),
+                            Some(CodeOutput::After(html)) => (None, Some(html)),
+                            None => (None, None),
+                        },
+                    };
+                    if let Some(html) = html {
+                        if c.is_block() {
+                            self.block(&mut out, 0)?;
+                        }
+                        match &capture.start {
+                            Event::Start(Container::Math { .. }, attrs) if !attrs.is_empty() => {
+                                // keep attributes of math spans on a wrapping element
+                                out.write_str("<span")?;
+                                for (a, v) in attrs.unique_pairs() {
+                                    write!(out, r#" {}=""#, a)?;
+                                    v.parts()
+                                        .try_for_each(|part| self.write_attr(part, &mut out))?;
+                                    out.write_char('"')?;
+                                }
+                                out.write_char('>')?;
+                                out.write_str(&html)?;
+                                out.write_str("</span>")?;
+                            }
+                            _ => out.write_str(&html)?,
+                        }
+                        self.first_line = false;
+                    } else {
+                        self.write_event(&capture.start, &mut out)?;
+                        self.write_event(&Event::Str(capture.content.into()), &mut out)?;
+                        self.write_event(e, &mut out)?;
+                    }
+                    if let Some(html) = after {
+                        self.block(&mut out, 0)?;
+                        out.write_str(&html)?;
+                    }
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        if let Event::Start(c, ..) = e {
+            if self.has_hook(c) {
+                self.capture = Some(Capture {
+                    start: e.clone(),
+                    content: String::new(),
+                });
+                return Ok(());
+            }
+        }
+
+        self.write_event(e, out)
+    }
+
+    /// Write a buffered paragraph as a figure, or as a regular paragraph if it contains more
+    /// than an image and a caption.
+    fn write_figure(
+        &mut self,
+        figure: Figure<'s>,
+        end: &Event<'s>,
+        mut out: &mut dyn std::fmt::Write,
+    ) -> std::fmt::Result {
+        let mut depth = 0;
+        let image_len = 1 + figure
+            .events
+            .iter()
+            .position(|e| {
+                match e {
+                    Event::Start(..) => depth += 1,
+                    Event::End(..) => depth -= 1,
+                    _ => {}
+                }
+                depth == 0
+            })
+            .unwrap_or(figure.events.len() - 1);
+        let (image, rest) = figure.events.split_at(image_len);
+        let caption = match rest {
+            [] => image[1..image.len() - 1].to_vec(),
+            [Event::Softbreak, Event::Str(s), caption @ ..] if s.starts_with("^ ") => {
+                std::iter::once(Event::Str(s[2..].to_string().into()))
+                    .chain(caption.iter().cloned())
+                    .collect()
+            }
+            _ => {
+                self.write_event(&figure.start, &mut out)?;
+                for e in &figure.events {
+                    self.render_event(e, &mut out)?;
+                }
+                return self.render_event(end, out);
+            }
+        };
+
+        self.block(&mut out, 1)?;
+        out.write_str("<figure")?;
+        if let Event::Start(_, attrs) = &figure.start {
+            for (a, v) in attrs.unique_pairs() {
+                if a == "id" && self.renderer.feed.is_some() {
+                    continue;
+                }
+                write!(out, r#" {}=""#, a)?;
+                v.parts()
+                    .try_for_each(|part| self.write_attr(part, &mut out))?;
+                out.write_char('"')?;
+            }
+        }
+        out.write_char('>')?;
+        self.first_line = false;
+        self.block(&mut out, 0)?;
+        for e in image {
+            self.render_event(e, &mut out)?;
+        }
+        if !caption.is_empty() {
+            self.block(&mut out, 0)?;
+            out.write_str("<figcaption>")?;
+            for e in &caption {
+                self.render_event(e, &mut out)?;
+            }
+            out.write_str("</figcaption>")?;
+        }
+        self.block(&mut out, -1)?;
+        out.write_str("</figure>")
+    }
+
+    fn write_event<W>(&mut self, e: &Event<'s>, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
         match e {
             Event::Start(c, attrs) => {
+                if !c.is_block_container() {
+                    self.inline_depth += 1;
+                }
                 if c.is_block() {
                     self.block(&mut out, c.is_block_container().into())?;
                 }
                 if self.img_alt_text > 0 && !matches!(c, Container::Image(..)) {
                     return Ok(());
                 }
+                if self.renderer.tab_width.is_some()
+                    && matches!(c, Container::CodeBlock { .. } | Container::Verbatim)
+                {
+                    self.column = Some(0);
+                }
+                let merged: Attributes;
+                let defaults = self
+                    .renderer
+                    .attributes
+                    .iter()
+                    .find(|(d, _)| std::mem::discriminant(d) == std::mem::discriminant(c))
+                    .map(|(_, defaults)| defaults);
+                let added = self
+                    .renderer
+                    .attributes_fn
+                    .iter()
+                    .filter_map(|f| f(c))
+                    .collect::<Vec<_>>();
+                let attrs = if defaults.is_some() || !added.is_empty() {
+                    merged = defaults
+                        .into_iter()
+                        .chain(&added)
+                        .flat_map(|a| a.iter())
+                        .chain(attrs.iter())
+                        .cloned()
+                        .collect();
+                    &merged
+                } else {
+                    attrs
+                };
+                if self.img_alt_text == 0 {
+                    self.write_comments(attrs, &mut out)?;
+                }
+                let prefix = self.renderer.class_prefix.as_str();
+                let element = self.element(c);
+                let admonition = self.admonition(c, attrs);
+                let admonition_class = admonition.map(|_| format!("{}admonition", prefix));
+                let code_blocks = &self.renderer.code_blocks;
+                let language_class = match c {
+                    Container::CodeBlock { language }
+                        if code_blocks.language_on_pre && !language.is_empty() =>
+                    {
+                        let mut class = format!("{}{}", prefix, code_blocks.language_prefix);
+                        self.write_attr(language, &mut class)?;
+                        Some(class)
+                    }
+                    _ => None,
+                };
+                let extra_class = element
+                    .and_then(|e| e.class.as_deref())
+                    .or(admonition_class.as_deref())
+                    .or(language_class.as_deref());
+                if let Some(element) = element {
+                    write!(out, "<{}", element.tag)?;
+                }
                 match &c {
+                    _ if element.is_some() => {}
                     Container::Blockquote => out.write_str("<blockquote")?,
                     Container::List { kind, tight } => {
                         self.list_tightness.push(*tight);
@@ -433,7 +2199,9 @@ impl<'s, 'f> Writer<'s, 'f> {
                     Container::Table => out.write_str("<table")?,
                     Container::TableRow { .. } => out.write_str("<tr")?,
                     Container::Section { .. } => out.write_str("<section")?,
-                    Container::Div { .. } => out.write_str("<div")?,
+                    Container::Div { .. } | Container::CustomBlock { .. } => {
+                        out.write_str("<div")?;
+                    }
                     Container::Paragraph => {
                         if matches!(self.list_tightness.last(), Some(true)) {
                             return Ok(());
@@ -445,17 +2213,49 @@ impl<'s, 'f> Writer<'s, 'f> {
                     Container::TableCell { head: true, .. } => out.write_str("<th")?,
                     Container::Caption => out.write_str("<caption")?,
                     Container::DescriptionTerm => out.write_str("<dt")?,
-                    Container::CodeBlock { .. } => out.write_str("<pre")?,
-                    Container::Span | Container::Math { .. } => out.write_str("<span")?,
+                    Container::CodeBlock { .. } => {
+                        if let Some(wrapper) = &code_blocks.wrapper {
+                            write!(out, "<{}", wrapper)?;
+                            if let Some(class) = &code_blocks.wrapper_class {
+                                out.write_str(r#" class=""#)?;
+                                self.write_attr(class, &mut out)?;
+                                out.write_char('"')?;
+                            }
+                            out.write_char('>')?;
+                        }
+                        out.write_str("<pre")?;
+                    }
+                    Container::Span | Container::Math { .. } | Container::CustomInline { .. } => {
+                        out.write_str("<span")?;
+                    }
                     Container::Link(dst, ty) => {
                         if matches!(ty, LinkType::Span(SpanLinkType::Unresolved)) {
                             out.write_str("<a")?;
                         } else {
                             out.write_str(r#"<a href=""#)?;
-                            if matches!(ty, LinkType::Email) {
-                                out.write_str("mailto:")?;
+                            let mailto = dst
+                                .get(..7)
+                                .map_or(false, |s| s.eq_ignore_ascii_case("mailto:"));
+                            self.email = self.renderer.obfuscate_emails
+                                && (matches!(ty, LinkType::Email) || mailto);
+                            match ty {
+                                LinkType::Email if self.email => {
+                                    write_obfuscated("mailto:", &mut out)?;
+                                    write_obfuscated(dst, &mut out)?;
+                                }
+                                LinkType::Span(_) if self.email => {
+                                    write_obfuscated(dst, &mut out)?;
+                                }
+                                LinkType::Span(_) => {
+                                    let dst = self.rewrite_url(dst);
+                                    self.write_attr(&self.url(&dst), &mut out)?;
+                                }
+                                LinkType::AutoLink => self.write_attr(&self.url(dst), &mut out)?,
+                                LinkType::Email => {
+                                    out.write_str("mailto:")?;
+                                    self.write_attr(dst, &mut out)?;
+                                }
                             }
-                            write_attr(dst, &mut out)?;
                             out.write_char('"')?;
                         }
                     }
@@ -469,10 +2269,27 @@ impl<'s, 'f> Writer<'s, 'f> {
                     }
                     Container::Verbatim => out.write_str("<code")?,
                     Container::RawBlock { format } | Container::RawInline { format } => {
-                        self.raw = if format == &"html" {
-                            Raw::Html
-                        } else {
-                            Raw::Other
+                        if *format == FRAGMENT {
+                            self.raw = Raw::Fragment;
+                            return Ok(());
+                        }
+                        let output = self
+                            .renderer
+                            .raw_formats
+                            .iter()
+                            .find(|(f, _)| f == format)
+                            .map_or(RawOutput::Omit, |(_, output)| *output);
+                        self.raw = match output {
+                            RawOutput::Html => Raw::Html,
+                            RawOutput::Code => {
+                                out.write_str(if c.is_block() {
+                                    "<pre><code>"
+                                } else {
+                                    "<code>"
+                                })?;
+                                Raw::Code
+                            }
+                            RawOutput::Omit => Raw::Other,
                         };
                         return Ok(());
                     }
@@ -486,21 +2303,48 @@ impl<'s, 'f> Writer<'s, 'f> {
                     Container::LinkDefinition { .. } => return Ok(()),
                 }
 
+                let page_break = self.renderer.print
+                    && c.is_block()
+                    && (matches!(c, Container::Div { class } if class == &"page-break")
+                        || attrs.get_value("class").map_or(false, |v| {
+                            v.to_string().split_whitespace().any(|c| c == "page-break")
+                        }));
                 let mut id_written = false;
                 let mut class_written = false;
                 for (a, v) in attrs.unique_pairs() {
+                    if a == "id" && self.renderer.feed.is_some() {
+                        id_written = true;
+                        continue;
+                    }
+                    if matches!(c, Container::CodeBlock { .. })
+                        && self.renderer.line_numbers
+                        && matches!(a, "startline" | "hl_lines")
+                    {
+                        continue;
+                    }
                     write!(out, r#" {}=""#, a)?;
-                    v.parts().try_for_each(|part| write_attr(part, &mut out))?;
+                    v.parts()
+                        .try_for_each(|part| self.write_attr(part, &mut out))?;
                     match a {
                         "class" => {
                             class_written = true;
-                            write_class(c, true, &mut out)?;
+                            write_class(c, prefix, extra_class, true, &mut out)?;
                         }
                         "id" => id_written = true,
+                        "style" if page_break => {
+                            let style = v.to_string();
+                            if !style.trim_end().is_empty() && !style.trim_end().ends_with(';') {
+                                out.write_char(';')?;
+                            }
+                            out.write_str(" break-before: page;")?;
+                        }
                         _ => {}
                     }
                     out.write_char('"')?;
                 }
+                if page_break && !attrs.contains_key("style") {
+                    out.write_str(r#" style="break-before: page;""#)?;
+                }
 
                 if let Container::Heading {
                     id,
@@ -509,15 +2353,18 @@ impl<'s, 'f> Writer<'s, 'f> {
                 }
                 | Container::Section { id } = &c
                 {
-                    if !id_written {
+                    if !id_written && self.renderer.feed.is_none() {
                         out.write_str(r#" id=""#)?;
-                        write_attr(id, &mut out)?;
+                        self.write_attr(id, &mut out)?;
                         out.write_char('"')?;
                     }
                 } else if (matches!(c, Container::Div { class } if !class.is_empty())
+                    || extra_class.is_some()
                     || matches!(
                         c,
                         Container::Math { .. }
+                            | Container::CustomInline { .. }
+                            | Container::CustomBlock { .. }
                             | Container::List {
                                 kind: ListKind::Task(..),
                                 ..
@@ -526,7 +2373,7 @@ impl<'s, 'f> Writer<'s, 'f> {
                     && !class_written
                 {
                     out.write_str(r#" class=""#)?;
-                    write_class(c, false, &mut out)?;
+                    write_class(c, prefix, extra_class, false, &mut out)?;
                     out.write_char('"')?;
                 }
 
@@ -543,41 +2390,119 @@ impl<'s, 'f> Writer<'s, 'f> {
                         write!(out, r#" style="text-align: {};">"#, a)?;
                     }
                     Container::CodeBlock { language } => {
-                        if language.is_empty() {
-                            out.write_str("><code>")?;
-                        } else {
-                            out.write_str(r#"><code class="language-"#)?;
-                            write_attr(language, &mut out)?;
+                        let diff = self.renderer.diff_lines
+                            && (*language == "diff"
+                                || attrs.get_value("class").map_or(false, |v| {
+                                    v.to_string().split_whitespace().any(|c| c == "diff")
+                                }));
+                        if self.renderer.line_numbers || diff {
+                            self.code_lines =
+                                Some(CodeLines::new(attrs, self.renderer.line_numbers, diff));
+                        }
+                        if code_blocks.data_lang && !language.is_empty() {
+                            out.write_str(r#" data-lang=""#)?;
+                            self.write_attr(language, &mut out)?;
+                            out.write_char('"')?;
+                        }
+                        let language_class = !language.is_empty() && !code_blocks.language_on_pre;
+                        if language_class || code_blocks.code_class.is_some() {
+                            out.write_str(r#"><code class=""#)?;
+                            if let Some(class) = &code_blocks.code_class {
+                                self.write_attr(class, &mut out)?;
+                                if language_class {
+                                    out.write_char(' ')?;
+                                }
+                            }
+                            if language_class {
+                                write!(out, "{}{}", prefix, code_blocks.language_prefix)?;
+                                self.write_attr(language, &mut out)?;
+                            }
                             out.write_str(r#"">"#)?;
+                        } else {
+                            out.write_str("><code>")?;
                         }
                     }
-                    Container::Image(..) => {
+                    Container::Image(src, ..) => {
                         if self.img_alt_text == 1 {
+                            if let Some(f) = &self.renderer.image_sizes {
+                                if !attrs.contains_key("width") && !attrs.contains_key("height") {
+                                    if let Some((width, height)) = f(src) {
+                                        write!(out, r#" width="{}" height="{}""#, width, height)?;
+                                    }
+                                }
+                            }
                             out.write_str(r#" alt=""#)?;
                         }
                     }
                     Container::Math { display } => {
                         out.write_str(if *display { r#">\["# } else { r#">\("# })?;
                     }
+                    Container::CustomBlock { argument, .. } => {
+                        if !argument.is_empty() && !attrs.contains_key("data-argument") {
+                            out.write_str(r#" data-argument=""#)?;
+                            self.write_attr(argument, &mut out)?;
+                            out.write_char('"')?;
+                        }
+                        out.write_char('>')?;
+                    }
                     Container::TaskListItem { checked } => {
                         out.write_char('>')?;
                         self.block(&mut out, 0)?;
-                        if *checked {
-                            out.write_str(r#"<input disabled="" type="checkbox" checked=""/>"#)?;
+                        let xml = self.renderer.syntax == Syntax::Xml;
+                        out.write_str(if xml {
+                            r#"<input disabled="disabled" type="checkbox""#
                         } else {
-                            out.write_str(r#"<input disabled="" type="checkbox"/>"#)?;
+                            r#"<input disabled="" type="checkbox""#
+                        })?;
+                        if *checked {
+                            out.write_str(if xml {
+                                r#" checked="checked""#
+                            } else {
+                                r#" checked="""#
+                            })?;
                         }
+                        out.write_str("/>")?;
                     }
                     _ => out.write_char('>')?,
                 }
+
+                if let Some(title) = admonition {
+                    self.first_line = false;
+                    self.block(&mut out, 0)?;
+                    write!(out, r#"<p class="{}admonition-title">"#, prefix)?;
+                    self.write_text(title, &mut out)?;
+                    out.write_str("</p>")?;
+                }
+
+                if let (Container::Heading { id, .. }, Some(permalinks)) =
+                    (c, &self.renderer.permalinks)
+                {
+                    let id = attrs
+                        .get_value("id")
+                        .map_or_else(|| id.to_string(), |v| v.to_string());
+                    if !id.is_empty() && self.renderer.feed.is_none() {
+                        match permalinks.placement {
+                            PermalinkPlacement::Before => self.write_permalink(&id, &mut out)?,
+                            PermalinkPlacement::After => self.permalink = Some(id),
+                        }
+                    }
+                }
             }
             Event::End(c) => {
                 if c.is_block_container() {
                     self.block(&mut out, -1)?;
+                } else {
+                    self.inline_depth -= 1;
+                }
+                if matches!(c, Container::CodeBlock { .. } | Container::Verbatim) {
+                    self.column = None;
                 }
                 if self.img_alt_text > 0 && !matches!(c, Container::Image(..)) {
                     return Ok(());
                 }
+                if let Some(element) = self.element(c) {
+                    return write!(out, "</{}>", element.tag);
+                }
                 match c {
                     Container::Blockquote => out.write_str("</blockquote>")?,
                     Container::List { kind, .. } => {
@@ -598,7 +2523,9 @@ impl<'s, 'f> Writer<'s, 'f> {
                     Container::Table => out.write_str("</table>")?,
                     Container::TableRow { .. } => out.write_str("</tr>")?,
                     Container::Section { .. } => out.write_str("</section>")?,
-                    Container::Div { .. } => out.write_str("</div>")?,
+                    Container::Div { .. } | Container::CustomBlock { .. } => {
+                        out.write_str("</div>")?;
+                    }
                     Container::Paragraph => {
                         if matches!(self.list_tightness.last(), Some(true)) {
                             return Ok(());
@@ -607,21 +2534,56 @@ impl<'s, 'f> Writer<'s, 'f> {
                             out.write_str("</p>")?;
                         }
                     }
-                    Container::Heading { level, .. } => write!(out, "</h{}>", level)?,
+                    Container::Heading { level, .. } => {
+                        if let Some(id) = self.permalink.take() {
+                            self.write_permalink(&id, &mut out)?;
+                        }
+                        write!(out, "</h{}>", level)?;
+                    }
                     Container::TableCell { head: false, .. } => out.write_str("</td>")?,
                     Container::TableCell { head: true, .. } => out.write_str("</th>")?,
                     Container::Caption => out.write_str("</caption>")?,
                     Container::DescriptionTerm => out.write_str("</dt>")?,
-                    Container::CodeBlock { .. } => out.write_str("</code></pre>")?,
-                    Container::Span => out.write_str("</span>")?,
-                    Container::Link(..) => out.write_str("</a>")?,
+                    Container::CodeBlock { .. } => {
+                        if self.code_lines.take().map_or(false, |lines| lines.open) {
+                            out.write_str("</span>")?;
+                        }
+                        out.write_str("</code></pre>")?;
+                        if let Some(wrapper) = &self.renderer.code_blocks.wrapper {
+                            write!(out, "</{}>", wrapper)?;
+                        }
+                    }
+                    Container::Span | Container::CustomInline { .. } => out.write_str("</span>")?,
+                    Container::Link(dst, ty) => {
+                        self.email = false;
+                        out.write_str("</a>")?;
+                        let mailto = dst
+                            .get(..7)
+                            .map_or(false, |s| s.eq_ignore_ascii_case("mailto:"));
+                        if self.renderer.print
+                            && matches!(
+                                ty,
+                                LinkType::Span(SpanLinkType::Inline | SpanLinkType::Reference)
+                            )
+                            && !(dst.is_empty() || dst.starts_with('#') || mailto)
+                        {
+                            write!(out, r#" <span class="{}url">("#, self.renderer.class_prefix)?;
+                            let dst = self.rewrite_url(dst);
+                            self.write_text(&self.url(&dst), &mut out)?;
+                            out.write_str(")</span>")?;
+                        }
+                    }
                     Container::Image(src, ..) => {
                         if self.img_alt_text == 1 {
                             if !src.is_empty() {
                                 out.write_str(r#"" src=""#)?;
-                                write_attr(src, &mut out)?;
+                                if !self.write_embedded_image(src, &mut out)? {
+                                    let src = self.rewrite_url(src);
+                                    self.write_attr(&self.url(&src), &mut out)?;
+                                }
                             }
-                            out.write_str(r#"">"#)?;
+                            out.write_char('"')?;
+                            out.write_str(self.void_end())?;
                         }
                         self.img_alt_text -= 1;
                     }
@@ -634,7 +2596,13 @@ impl<'s, 'f> Writer<'s, 'f> {
                         })?;
                     }
                     Container::RawBlock { .. } | Container::RawInline { .. } => {
-                        self.raw = Raw::None;
+                        if let Raw::Code = std::mem::take(&mut self.raw) {
+                            out.write_str(if c.is_block() {
+                                "</code></pre>"
+                            } else {
+                                "</code>"
+                            })?;
+                        }
                     }
                     Container::Subscript => out.write_str("</sub>")?,
                     Container::Superscript => out.write_str("</sup>")?,
@@ -647,105 +2615,556 @@ impl<'s, 'f> Writer<'s, 'f> {
                 }
             }
             Event::Str(s) => match self.raw {
-                Raw::None if self.img_alt_text > 0 => write_attr(s, &mut out)?,
-                Raw::None => write_text(s, &mut out)?,
-                Raw::Html => out.write_str(s)?,
+                Raw::None if self.column.is_some() => {
+                    let s = self.expand_tabs(s);
+                    if self.code_lines.is_some() {
+                        self.write_code_lines(&s, &mut out)?;
+                    } else {
+                        self.write_text(&s, &mut out)?;
+                    }
+                }
+                Raw::None if self.img_alt_text > 0 => self.write_attr(s, &mut out)?,
+                Raw::None if self.code_lines.is_some() => self.write_code_lines(s, &mut out)?,
+                Raw::None if self.email => write_obfuscated(s, &mut out)?,
+                Raw::None => {
+                    self.write_text(s, &mut out)?;
+                    self.last_char = s.chars().last().or(self.last_char);
+                }
+                Raw::Html if self.renderer.feed.is_none() => out.write_str(s)?,
+                Raw::Html => {}
+                Raw::Fragment => out.write_str(s)?,
+                Raw::Code => self.write_text(s, &mut out)?,
                 Raw::Other => {}
             },
             Event::FootnoteReference(label) => {
                 let number = self.footnotes.reference(label);
-                if self.img_alt_text == 0 {
+                let marker = self.renderer.footnotes.numbering.marker(number);
+                if self.renderer.footnotes.placement == FootnotePlacement::Inline {
+                    if number > self.footnotes.number && self.img_alt_text == 0 {
+                        self.footnotes.number = number;
+                        self.write_inline_note(label, &mut out)?;
+                    }
+                } else if self.img_alt_text == 0 && self.renderer.feed.is_some() {
+                    write!(out, "<sup>{}</sup>", marker)?;
+                } else if self.img_alt_text == 0 {
+                    let prefix = &self.renderer.footnotes.id_prefix;
+                    out.write_str(r#"<a id=""#)?;
+                    self.write_attr(prefix, &mut out)?;
+                    write!(out, r##"fnref{}" href="#"##, number)?;
+                    self.write_attr(prefix, &mut out)?;
                     write!(
                         out,
-                        r##"<a id="fnref{}" href="#fn{}" role="doc-noteref"><sup>{}</sup></a>"##,
-                        number, number, number
+                        r#"fn{}" role="doc-noteref"><sup>{}</sup></a>"#,
+                        number, marker
                     )?;
                 }
             }
             Event::Symbol(sym) => write!(out, ":{}:", sym)?,
-            Event::LeftSingleQuote => out.write_str("‘")?,
-            Event::RightSingleQuote => out.write_str("’")?,
-            Event::LeftDoubleQuote => out.write_str("“")?,
-            Event::RightDoubleQuote => out.write_str("”")?,
-            Event::Ellipsis => out.write_str("…")?,
-            Event::EnDash => out.write_str("–")?,
-            Event::EmDash => out.write_str("—")?,
+            Event::LeftSingleQuote => self.write_punctuation('‘', "lsquo", &mut out)?,
+            Event::RightSingleQuote => self.write_punctuation('’', "rsquo", &mut out)?,
+            Event::LeftDoubleQuote => self.write_punctuation('“', "ldquo", &mut out)?,
+            Event::RightDoubleQuote => self.write_punctuation('”', "rdquo", &mut out)?,
+            Event::Ellipsis => self.write_punctuation('…', "hellip", &mut out)?,
+            Event::EnDash => self.write_punctuation('–', "ndash", &mut out)?,
+            Event::EmDash => self.write_punctuation('—', "mdash", &mut out)?,
+            Event::NonBreakingSpace
+                if self.renderer.entities == Entities::Numeric || !self.named_references() =>
+            {
+                out.write_str("&#xa0;")?;
+            }
             Event::NonBreakingSpace => out.write_str("&nbsp;")?,
             Event::Hardbreak => {
-                out.write_str("<br>")?;
+                out.write_str("<br")?;
+                out.write_str(self.void_end())?;
                 self.block(out, 0)?;
             }
+            Event::Softbreak
+                if self.renderer.cjk_breaks && self.last_char.map_or(false, is_cjk) =>
+            {
+                self.pending_break = true;
+            }
             Event::Softbreak => {
                 out.write_char('\n')?;
                 self.indent(&mut out)?;
             }
+            Event::Attributes(attrs)
+                if self.renderer.comments
+                    && self.img_alt_text == 0
+                    && attrs.iter().any(|(k, _)| *k == AttributeKind::Comment) =>
+            {
+                if self.inline_depth == 0 {
+                    self.block(&mut out, 0)?;
+                }
+                self.write_comments(attrs, &mut out)?;
+            }
             Event::Escape | Event::Blankline | Event::Attributes(..) => {}
             Event::ThematicBreak(attrs) => {
                 self.block(&mut out, 0)?;
                 out.write_str("<hr")?;
                 for (a, v) in attrs.unique_pairs() {
+                    if a == "id" && self.renderer.feed.is_some() {
+                        continue;
+                    }
                     write!(out, r#" {}=""#, a)?;
-                    v.parts().try_for_each(|part| write_attr(part, &mut out))?;
+                    v.parts()
+                        .try_for_each(|part| self.write_attr(part, &mut out))?;
                     out.write_char('"')?;
                 }
-                out.write_str(">")?;
+                out.write_str(self.void_end())?;
+            }
+        }
+        self.first_line = false;
+
+        Ok(())
+    }
+
+    /// Expand the tabs within text of a code block or verbatim.
+    fn expand_tabs<'a>(&mut self, s: &'a str) -> std::borrow::Cow<'a, str> {
+        let width = self.renderer.tab_width.unwrap_or(1);
+        let column = if let Some(column) = &mut self.column {
+            column
+        } else {
+            return s.into();
+        };
+        if !s.contains('\t') {
+            match s.rfind('\n') {
+                Some(i) => *column = s[i + 1..].chars().count(),
+                None => *column += s.chars().count(),
+            }
+            return s.into();
+        }
+        let mut expanded = String::with_capacity(s.len());
+        for c in s.chars() {
+            match c {
+                '\t' => {
+                    let n = width - *column % width;
+                    expanded.extend(std::iter::repeat(' ').take(n));
+                    *column += n;
+                }
+                '\n' => {
+                    expanded.push(c);
+                    *column = 0;
+                }
+                c => {
+                    expanded.push(c);
+                    *column += 1;
+                }
+            }
+        }
+        expanded.into()
+    }
+
+    /// Write text of a code block, with each line wrapped in an element.
+    fn write_code_lines<W>(&mut self, s: &str, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        let mut lines = if let Some(lines) = self.code_lines.take() {
+            lines
+        } else {
+            return Ok(());
+        };
+        let prefix = &self.renderer.class_prefix;
+        let mut parts = s.split('\n').peekable();
+        while let Some(line) = parts.next() {
+            let newline = parts.peek().is_some();
+            if !lines.open && (newline || !line.is_empty()) {
+                write!(out, r#"<span class="{}line"#, prefix)?;
+                if lines.is_highlighted() {
+                    write!(out, " {}hl", prefix)?;
+                }
+                if lines.diff {
+                    if line.starts_with('+') {
+                        write!(out, " {}added", prefix)?;
+                    } else if line.starts_with('-') {
+                        write!(out, " {}removed", prefix)?;
+                    }
+                }
+                if lines.numbers {
+                    write!(out, r#"" data-line="{}">"#, lines.start + lines.index)?;
+                } else {
+                    out.write_str("\">")?;
+                }
+                lines.open = true;
+            }
+            self.write_text(line, &mut out)?;
+            if newline {
+                out.write_str("</span>\n")?;
+                lines.open = false;
+                lines.index += 1;
+            }
+        }
+        self.code_lines = Some(lines);
+        Ok(())
+    }
+
+    /// Write a permalink anchor to the element with the provided id.
+    fn write_permalink<W>(&mut self, id: &str, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        let symbol = self.renderer.permalinks.as_ref().map_or("", |p| &p.symbol);
+        write!(
+            out,
+            r##"<a class="{}anchor" href="#"##,
+            self.renderer.class_prefix
+        )?;
+        self.write_attr(id, &mut out)?;
+        out.write_str(r#"">"#)?;
+        self.write_text(symbol, &mut out)?;
+        out.write_str("</a>")
+    }
+
+    /// Write the comments within attributes, if comments are rendered.
+    fn write_comments(
+        &self,
+        attrs: &Attributes,
+        out: &mut dyn std::fmt::Write,
+    ) -> std::fmt::Result {
+        if !self.renderer.comments {
+            return Ok(());
+        }
+        for (kind, value) in attrs.iter() {
+            if *kind != AttributeKind::Comment {
+                continue;
+            }
+            out.write_str("<!-- ")?;
+            let mut prev = ' ';
+            for c in value.to_string().trim().chars() {
+                if c == '-' && prev == '-' {
+                    out.write_char(' ')?;
+                }
+                out.write_char(c)?;
+                prev = c;
+            }
+            out.write_str(" -->")?;
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if named character references may be used.
+    fn named_references(&self) -> bool {
+        self.renderer.feed.is_none() && self.renderer.syntax == Syntax::Html
+    }
+
+    /// Write a punctuation character, or a reference to it.
+    fn write_punctuation(
+        &self,
+        c: char,
+        name: &str,
+        out: &mut dyn std::fmt::Write,
+    ) -> std::fmt::Result {
+        match self.renderer.entities {
+            Entities::Literal => self.write_text(c.encode_utf8(&mut [0; 4]), out),
+            Entities::Named if self.named_references() => write!(out, "&{};", name),
+            Entities::Named | Entities::Numeric => write!(out, "&#x{:x};", u32::from(c)),
+        }
+    }
+
+    /// End of the start tag of a void element, e.g. `<br>`.
+    fn void_end(&self) -> &'static str {
+        match self.renderer.syntax {
+            Syntax::Html => ">",
+            Syntax::Xml => "/>",
+        }
+    }
+
+    fn write_text<W>(&self, s: &str, out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        write_escape(s, false, self.renderer.feed.is_some(), out)
+    }
+
+    fn write_attr<W>(&self, s: &str, out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        write_escape(s, true, self.renderer.feed.is_some(), out)
+    }
+
+    /// Write the content of a footnote within the text, see [`FootnotePlacement::Inline`].
+    fn write_inline_note(
+        &mut self,
+        label: &str,
+        mut out: &mut dyn std::fmt::Write,
+    ) -> std::fmt::Result {
+        let events = self
+            .footnotes
+            .events
+            .get(label)
+            .cloned()
+            .unwrap_or_default();
+        write!(
+            out,
+            r#"<span class="{}footnote" role="doc-footnote">"#,
+            self.renderer.class_prefix
+        )?;
+        // whether the next inline content is separated from the previous by a block boundary
+        let mut separate = false;
+        let mut written = false;
+        for e in &events {
+            match e {
+                Event::Blankline | Event::Escape => {}
+                Event::Start(c, ..) | Event::End(c) if c.is_block() => separate = written,
+                Event::ThematicBreak(..) => separate = written,
+                e => {
+                    if separate {
+                        out.write_char(' ')?;
+                        separate = false;
+                    }
+                    written = true;
+                    self.render_event(e, &mut out)?;
+                }
+            }
+        }
+        out.write_str("</span>")
+    }
+
+    /// Resolve a link or image destination against the base URL of a feed.
+    fn url<'u>(&self, url: &'u str) -> std::borrow::Cow<'u, str> {
+        match &self.renderer.feed {
+            Some(base) => resolve_url(base, url),
+            None => url.into(),
+        }
+    }
+
+    /// Write the content of a local image as a `data:` URI, returning `false` if it is not
+    /// embedded.
+    fn write_embedded_image<W>(&self, src: &str, mut out: W) -> Result<bool, std::fmt::Error>
+    where
+        W: std::fmt::Write,
+    {
+        let (max_size, f) = if let Some(embed) = &self.renderer.embedded_images {
+            embed
+        } else {
+            return Ok(false);
+        };
+        if has_scheme(src) || src.starts_with("//") {
+            return Ok(false);
+        }
+        let path = &src[..src.find(|c| matches!(c, '?' | '#')).unwrap_or(src.len())];
+        let ext = path.rsplit_once('.').map_or("", |(_, ext)| ext);
+        let mime = match ext.to_ascii_lowercase().as_str() {
+            "png" => "image/png",
+            "jpg" | "jpeg" => "image/jpeg",
+            "gif" => "image/gif",
+            "webp" => "image/webp",
+            "avif" => "image/avif",
+            "svg" => "image/svg+xml",
+            _ => return Ok(false),
+        };
+        match f(path) {
+            Some(data) if data.len() <= *max_size => {
+                write!(out, "data:{};base64,", mime)?;
+                write_base64(&data, out)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// URL returned by the rewrite hook, if any.
+    fn rewrite_url<'u>(&self, url: &'u str) -> std::borrow::Cow<'u, str> {
+        self.renderer
+            .url_rewrite
+            .as_ref()
+            .and_then(|f| f(url))
+            .map_or(url.into(), Into::into)
+    }
+
+    /// Element that replaces the default element of the container, if it has been remapped.
+    fn element(&self, c: &Container<'s>) -> Option<&'f Element> {
+        if !matches!(
+            c,
+            Container::Span
+                | Container::Verbatim
+                | Container::Subscript
+                | Container::Superscript
+                | Container::Insert
+                | Container::Delete
+                | Container::Strong
+                | Container::Emphasis
+                | Container::Mark
+                | Container::Blockquote
+                | Container::Div { .. }
+                | Container::Section { .. }
+                | Container::Table
+                | Container::Caption
+                | Container::DescriptionList
+                | Container::DescriptionTerm
+                | Container::DescriptionDetails
+        ) {
+            return None;
+        }
+        self.renderer
+            .elements
+            .iter()
+            .find(|e| std::mem::discriminant(&e.container) == std::mem::discriminant(c))
+    }
+
+    /// Title of the div, if it is an admonition.
+    fn admonition(&self, c: &Container<'s>, attrs: &Attributes<'s>) -> Option<&'f str> {
+        let div_class = if let Container::Div { class } = c {
+            class
+        } else {
+            return None;
+        };
+        let classes = attrs
+            .get_value("class")
+            .map_or_else(String::new, |c| c.to_string());
+        self.renderer
+            .admonitions
+            .iter()
+            .find(|(class, _)| class == div_class || classes.split(' ').any(|c| c == class))
+            .map(|(_, title)| title.as_str())
+    }
+
+    /// Returns `true` if the content of the container should be captured and passed to a hook.
+    fn has_hook(&self, c: &Container) -> bool {
+        if self.img_alt_text > 0 {
+            return false;
+        }
+        match c {
+            Container::Math { .. } => self.renderer.math.is_some(),
+            Container::CodeBlock { language } => {
+                self.renderer.execution.is_some()
+                    || matches!(&self.renderer.diagrams, Some((langs, _)) if langs.iter().any(|l| l == language))
             }
+            Container::CustomBlock { name, .. } => {
+                self.renderer.custom_blocks.iter().any(|(n, _)| n == name)
+            }
+            _ => false,
         }
-        self.first_line = false;
+    }
 
-        Ok(())
+    /// Render captured content with a hook, returns `None` if the hook failed.
+    fn render_hook(&self, c: &Container, content: &str) -> Option<String> {
+        match c {
+            Container::Math { display } => self
+                .renderer
+                .math
+                .as_ref()
+                .and_then(|f| f(content, *display)),
+            Container::CodeBlock { language } => self
+                .renderer
+                .diagrams
+                .as_ref()
+                .and_then(|(_, f)| f(language, content)),
+            Container::CustomBlock { name, argument } => self
+                .renderer
+                .custom_blocks
+                .iter()
+                .find(|(n, _)| n == name)
+                .and_then(|(_, f)| f(argument, content)),
+            _ => None,
+        }
     }
 
-    fn render_epilogue<W>(&mut self, mut out: W) -> std::fmt::Result
-    where
-        W: std::fmt::Write,
-    {
-        if self.footnotes.reference_encountered() {
-            self.block(&mut out, 0)?;
-            out.write_str("<section role=\"doc-endnotes\">")?;
-            self.block(&mut out, 0)?;
-            out.write_str("<hr>")?;
-            self.block(&mut out, 0)?;
-            out.write_str("<ol>")?;
+    /// Execute a captured code block, if there is an execution hook.
+    fn execute(&self, c: &Container, content: &str) -> Option<CodeOutput> {
+        match c {
+            Container::CodeBlock { language } => self
+                .renderer
+                .execution
+                .as_ref()
+                .and_then(|f| f(language, content)),
+            _ => None,
+        }
+    }
 
-            while let Some((number, events)) = self.footnotes.next() {
-                self.block(&mut out, 0)?;
-                write!(out, "<li id=\"fn{}\">", number)?;
+    /// Write a section with the footnotes that have been referenced but not yet written.
+    fn render_notes(&mut self, mut out: &mut dyn std::fmt::Write) -> std::fmt::Result {
+        if !self.footnotes.has_pending() {
+            return Ok(());
+        }
+        let style = &self.renderer.footnotes;
+        self.footnotes.epilogue = true;
+        self.block(&mut out, 0)?;
+        out.write_str("<section role=\"doc-endnotes\">")?;
+        self.block(&mut out, 0)?;
+        if let Some(heading) = &style.heading {
+            out.write_str("<h2>")?;
+            self.write_text(heading, &mut out)?;
+            out.write_str("</h2>")?;
+        } else {
+            out.write_str("<hr")?;
+            out.write_str(self.void_end())?;
+        }
+        self.block(&mut out, 0)?;
+        let number_start = self.footnotes.number + 1;
+        if style.numbering == FootnoteNumbering::Numeric && number_start > 1 {
+            write!(out, "<ol start=\"{}\">", number_start)?;
+        } else {
+            out.write_str("<ol>")?;
+        }
 
-                let mut unclosed_para = false;
-                for e in events.iter().flatten() {
-                    if matches!(&e, Event::Blankline | Event::Escape) {
-                        continue;
-                    }
-                    if unclosed_para {
-                        // not a footnote, so no need to add href before para close
-                        out.write_str("</p>")?;
-                    }
-                    self.render_event(e, &mut out)?;
-                    unclosed_para = matches!(e, Event::End(Container::Paragraph { .. }))
-                        && !matches!(self.list_tightness.last(), Some(true));
-                }
-                if !unclosed_para {
-                    // create a new paragraph
-                    self.block(&mut out, 0)?;
-                    out.write_str("<p>")?;
-                }
+        while let Some((number, events)) = self.footnotes.next() {
+            self.block(&mut out, 0)?;
+            out.write_str("<li")?;
+            if self.renderer.feed.is_none() {
+                out.write_str(" id=\"")?;
+                self.write_attr(&style.id_prefix, &mut out)?;
+                write!(out, "fn{}\"", number)?;
+            }
+            if style.numbering == FootnoteNumbering::Symbols {
                 write!(
                     out,
-                    "<a href=\"#fnref{}\" role=\"doc-backlink\">\u{21A9}\u{FE0E}</a></p>",
-                    number,
+                    " style=\"list-style-type: '{} ';\"",
+                    style.numbering.marker(number)
                 )?;
+            }
+            out.write_char('>')?;
 
+            let mut unclosed_para = false;
+            for e in events.iter().flatten() {
+                if matches!(&e, Event::Blankline | Event::Escape) {
+                    continue;
+                }
+                if unclosed_para {
+                    // not a footnote, so no need to add href before para close
+                    out.write_str("</p>")?;
+                }
+                self.render_event(e, &mut out)?;
+                unclosed_para = matches!(e, Event::End(Container::Paragraph { .. }))
+                    && !matches!(self.list_tightness.last(), Some(true));
+            }
+            if self.renderer.feed.is_some() {
+                // no backlink, as the reference has no id
+                if unclosed_para {
+                    out.write_str("</p>")?;
+                }
+            } else if !unclosed_para {
+                // create a new paragraph
                 self.block(&mut out, 0)?;
-                out.write_str("</li>")?;
+                out.write_str("<p>")?;
+            }
+            if self.renderer.feed.is_none() {
+                out.write_str("<a href=\"#")?;
+                self.write_attr(&style.id_prefix, &mut out)?;
+                write!(out, "fnref{}\" role=\"doc-backlink\">", number)?;
+                self.write_text(&style.backlink, &mut out)?;
+                out.write_str("</a></p>")?;
             }
 
             self.block(&mut out, 0)?;
-            out.write_str("</ol>")?;
-            self.block(&mut out, 0)?;
-            out.write_str("</section>")?;
+            out.write_str("</li>")?;
         }
 
+        self.block(&mut out, 0)?;
+        out.write_str("</ol>")?;
+        self.block(&mut out, 0)?;
+        out.write_str("</section>")?;
+        self.footnotes.epilogue = false;
+        Ok(())
+    }
+
+    fn render_epilogue<W>(&mut self, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        self.render_notes(&mut out)?;
+
         if self.indent.is_some() {
             out.write_char('\n')?;
         }
@@ -754,20 +3173,36 @@ impl<'s, 'f> Writer<'s, 'f> {
     }
 }
 
-fn write_class<W>(c: &Container, mut first_written: bool, out: &mut W) -> std::fmt::Result
+fn write_class<W>(
+    c: &Container,
+    prefix: &str,
+    extra: Option<&str>,
+    mut first_written: bool,
+    out: &mut W,
+) -> std::fmt::Result
 where
     W: std::fmt::Write,
 {
-    if let Some(cls) = match c {
+    let (classes, prefixed): (&[&str], _) = match c {
         Container::List {
             kind: ListKind::Task(..),
             ..
-        } => Some("task-list"),
-        Container::Math { display: false } => Some("math inline"),
-        Container::Math { display: true } => Some("math display"),
-        _ => None,
-    } {
+        } => (&["task-list"], true),
+        Container::Math { display: false } => (&["math", "inline"], true),
+        Container::Math { display: true } => (&["math", "display"], true),
+        Container::CustomInline { name } | Container::CustomBlock { name, .. } => {
+            (std::slice::from_ref(name), true)
+        }
+        _ => (extra.as_ref().map_or(&[][..], std::slice::from_ref), false),
+    };
+    for cls in classes {
+        if first_written {
+            out.write_char(' ')?;
+        }
         first_written = true;
+        if prefixed {
+            out.write_str(prefix)?;
+        }
         out.write_str(cls)?;
     }
     if let Container::Div { class } = c {
@@ -781,21 +3216,64 @@ where
     Ok(())
 }
 
-fn write_text<W>(s: &str, out: W) -> std::fmt::Result
+/// Returns `true` if the character is a Chinese or Japanese character, or punctuation.
+fn is_cjk(c: char) -> bool {
+    matches!(
+        c,
+        '\u{3000}'..='\u{303f}' // symbols and punctuation
+            | '\u{3040}'..='\u{30ff}' // hiragana and katakana
+            | '\u{3100}'..='\u{312f}' // bopomofo
+            | '\u{3400}'..='\u{4dbf}' // ideographs, extension A
+            | '\u{4e00}'..='\u{9fff}' // ideographs
+            | '\u{f900}'..='\u{faff}' // compatibility ideographs
+            | '\u{ff00}'..='\u{ffef}' // halfwidth and fullwidth forms
+            | '\u{20000}'..='\u{3134f}' // ideographs, extensions B to G
+    )
+}
+
+/// Write text with all characters as numeric character references.
+fn write_obfuscated<W>(s: &str, mut out: W) -> std::fmt::Result
 where
     W: std::fmt::Write,
 {
-    write_escape(s, false, out)
+    s.chars()
+        .try_for_each(|c| write!(out, "&#x{:x};", u32::from(c)))
 }
 
-fn write_attr<W>(s: &str, out: W) -> std::fmt::Result
+/// Write data encoded as base64, with padding.
+fn write_base64<W>(data: &[u8], mut out: W) -> std::fmt::Result
 where
     W: std::fmt::Write,
 {
-    write_escape(s, true, out)
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from(b[0]) << 16 | u32::from(b[1]) << 8 | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.write_char(char::from(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize]))?;
+            } else {
+                out.write_char('=')?;
+            }
+        }
+    }
+    Ok(())
 }
 
-fn write_escape<W>(mut s: &str, escape_quotes: bool, mut out: W) -> std::fmt::Result
+/// Write text with special characters escaped.
+///
+/// If `conservative` is set, quotes and all non-ASCII characters are also escaped, using numeric
+/// character references.
+pub(crate) fn write_escape<W>(
+    mut s: &str,
+    escape_quotes: bool,
+    conservative: bool,
+    mut out: W,
+) -> std::fmt::Result
 where
     W: std::fmt::Write,
 {
@@ -805,7 +3283,9 @@ where
             '<' => Some("&lt;"),
             '>' => Some("&gt;"),
             '&' => Some("&amp;"),
-            '"' if escape_quotes => Some("&quot;"),
+            '"' if escape_quotes || conservative => Some("&quot;"),
+            '\'' if conservative => Some("&#39;"),
+            c if conservative && !c.is_ascii() => Some(""),
             _ => None,
         }
         .map_or(false, |s| {
@@ -814,12 +3294,60 @@ where
         })
     }) {
         out.write_str(&s[..i])?;
-        out.write_str(ent)?;
-        s = &s[i + 1..];
+        let c = s[i..].chars().next().unwrap();
+        if ent.is_empty() {
+            write!(out, "&#x{:x};", u32::from(c))?;
+        } else {
+            out.write_str(ent)?;
+        }
+        s = &s[i + c.len_utf8()..];
     }
     out.write_str(s)
 }
 
+/// Resolve a URL relative to a base URL.
+///
+/// Dot segments are left as is, as they are resolved by the client.
+fn resolve_url<'u>(base: &str, url: &'u str) -> std::borrow::Cow<'u, str> {
+    if base.is_empty() || url.is_empty() || has_scheme(url) || !has_scheme(base) {
+        return url.into();
+    }
+
+    // base URL without fragment, and without query unless only the fragment is replaced
+    let base = &base[..base.find('#').unwrap_or(base.len())];
+    if url.starts_with('#') {
+        return format!("{}{}", base, url).into();
+    }
+    let base = &base[..base.find('?').unwrap_or(base.len())];
+    if url.starts_with('?') {
+        return format!("{}{}", base, url).into();
+    }
+
+    let scheme_end = base.find(':').unwrap() + 1;
+    if url.starts_with("//") {
+        return format!("{}{}", &base[..scheme_end], url).into();
+    }
+    let path_start = if base[scheme_end..].starts_with("//") {
+        base[scheme_end + 2..]
+            .find('/')
+            .map_or(base.len(), |i| scheme_end + 2 + i)
+    } else {
+        scheme_end
+    };
+    if url.starts_with('/') {
+        format!("{}{}", &base[..path_start], url).into()
+    } else {
+        let dir = base[path_start..]
+            .rfind('/')
+            .map_or(path_start, |i| path_start + i + 1);
+        if dir == path_start {
+            format!("{}/{}", &base[..path_start], url).into()
+        } else {
+            format!("{}{}", &base[..dir], url).into()
+        }
+    }
+}
+
 /// Helper to aggregate footnotes for rendering at the end of the document. It will cache footnote
 /// events until they should be emitted at the end.
 ///
@@ -835,17 +3363,19 @@ struct Footnotes<'s> {
     events: Map<&'s str, Vec<Event<'s>>>,
     /// Number of last footnote that was emitted.
     number: usize,
+    /// Footnotes are currently being emitted.
+    epilogue: bool,
 }
 
 impl<'s> Footnotes<'s> {
-    /// Returns `true` if any reference has been encountered.
-    fn reference_encountered(&self) -> bool {
-        !self.references.is_empty()
+    /// Returns `true` if any footnotes have been referenced but not yet emitted.
+    fn has_pending(&self) -> bool {
+        self.references.len() > self.number
     }
 
-    /// Returns `true` if within the epilogue, i.e. if any footnotes have been pulled.
+    /// Returns `true` if within the epilogue, i.e. if footnotes are being emitted.
     fn in_epilogue(&self) -> bool {
-        self.number > 0
+        self.epilogue
     }
 
     /// Add a footnote reference.
@@ -900,7 +3430,10 @@ mod test {
             #[allow(unused)]
             let mut indent = None;
             $(indent = Some($indent);)?
-            let renderer = super::Renderer { indent };
+            let renderer = super::Renderer {
+                indent,
+                ..super::Renderer::default()
+            };
             let mut actual = String::new();
             renderer
                 .push(crate::Parser::new($src), &mut actual)
@@ -1005,4 +3538,642 @@ mod test {
             Indentation::default(),
         );
     }
+
+    #[test]
+    fn resolve_url() {
+        let base = "https://a.b/c/d.html?e#f";
+        let resolve = |url| super::resolve_url(base, url);
+        assert_eq!(resolve("g.html"), "https://a.b/c/g.html");
+        assert_eq!(resolve("/g"), "https://a.b/g");
+        assert_eq!(resolve("//h.i/g"), "https://h.i/g");
+        assert_eq!(resolve("#g"), "https://a.b/c/d.html?e#g");
+        assert_eq!(resolve("?g"), "https://a.b/c/d.html?g");
+        assert_eq!(resolve("mailto:a@b.c"), "mailto:a@b.c");
+        assert_eq!(super::resolve_url("https://a.b", "g"), "https://a.b/g");
+        assert_eq!(super::resolve_url("", "g"), "g");
+    }
+
+    #[test]
+    fn feed_footnotes() {
+        let renderer = super::Renderer::minified().with_feed("");
+        let mut actual = String::new();
+        renderer
+            .push(crate::Parser::new("a[^b] 'c'\n\n[^b]: d\n"), &mut actual)
+            .unwrap();
+        assert_eq!(
+            actual,
+            concat!(
+                "<p>a<sup>1</sup> &#x2018;c&#x2019;</p>",
+                "<section role=\"doc-endnotes\"><hr><ol><li><p>d</p></li></ol></section>",
+            ),
+        );
+    }
+
+    #[test]
+    fn admonitions() {
+        let renderer = super::Renderer::minified()
+            .with_admonition("tip", "Hint")
+            .with_admonitions();
+        let mut actual = String::new();
+        renderer
+            .push(
+                crate::Parser::new("{.caution}\n::: tip\na\n:::\n\n::: x\nb\n:::\n"),
+                &mut actual,
+            )
+            .unwrap();
+        assert_eq!(
+            actual,
+            concat!(
+                r#"<div class="caution admonition tip"><p class="admonition-title">Hint</p>"#,
+                r#"<p>a</p></div><div class="x"><p>b</p></div>"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn figures() {
+        let renderer = super::Renderer::minified().with_figures();
+        let mut actual = String::new();
+        renderer
+            .push(
+                crate::Parser::new("![](a)\n\n![b](c) d\n\n![e](f)[^g]\n\n[^g]: ![h](i)\n"),
+                &mut actual,
+            )
+            .unwrap();
+        assert_eq!(
+            actual,
+            concat!(
+                r#"<figure><img alt="" src="a"></figure>"#,
+                r#"<p><img alt="b" src="c"> d</p>"#,
+                r##"<p><img alt="e" src="f"><a id="fnref1" href="#fn1" role="doc-noteref">"##,
+                r##"<sup>1</sup></a></p><section role="doc-endnotes"><hr><ol><li id="fn1">"##,
+                r##"<p><img alt="h" src="i"><a href="#fnref1" role="doc-backlink">↩︎</a></p>"##,
+                r#"</li></ol></section>"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn footnotes_in_sections() {
+        let renderer = super::Renderer::minified().with_footnotes(super::FootnoteStyle {
+            placement: super::FootnotePlacement::Sections,
+            ..super::FootnoteStyle::default()
+        });
+        let mut actual = String::new();
+        renderer
+            .push(
+                crate::Parser::new(concat!(
+                    "# a\n\nb[^x]\n\n[^x]: x\n\n## c\n\n",
+                    "# d\n\ne[^y] f[^z]\n\n[^y]: y\n\n",
+                    "# g\n\n[^z]: z\n",
+                )),
+                &mut actual,
+            )
+            .unwrap();
+        assert_eq!(
+            actual,
+            concat!(
+                r##"<section id="a"><h1>a</h1><p>b<a id="fnref1" href="#fn1" role="doc-noteref">"##,
+                r##"<sup>1</sup></a></p><section id="c"><h2>c</h2></section>"##,
+                r##"<section role="doc-endnotes"><hr><ol><li id="fn1"><p>x"##,
+                r##"<a href="#fnref1" role="doc-backlink">↩︎</a></p></li></ol></section></section>"##,
+                r##"<section id="d"><h1>d</h1><p>e<a id="fnref2" href="#fn2" role="doc-noteref">"##,
+                r##"<sup>2</sup></a> f<a id="fnref3" href="#fn3" role="doc-noteref"><sup>3</sup></a>"##,
+                r##"</p><section role="doc-endnotes"><hr><ol start="2"><li id="fn2">"##,
+                r##"<p>y<a href="#fnref2" role="doc-backlink">↩︎</a></p></li><li id="fn3">"##,
+                r##"<p>z<a href="#fnref3" role="doc-backlink">↩︎</a></p></li></ol></section></section>"##,
+                r##"<section id="g"><h1>g</h1></section>"##,
+            ),
+        );
+    }
+
+    #[test]
+    fn class_prefix() {
+        let renderer = super::Renderer::minified()
+            .with_admonitions()
+            .with_class_prefix("p-");
+        let mut actual = String::new();
+        renderer
+            .push(
+                crate::Parser::new("::: tip\n- [ ] a\n:::\n\n$$`b`{.c}"),
+                &mut actual,
+            )
+            .unwrap();
+        assert_eq!(
+            actual,
+            concat!(
+                r#"<div class="p-admonition tip"><p class="p-admonition-title">Tip</p>"#,
+                r#"<ul class="p-task-list"><li><input disabled="" type="checkbox"/>a</li></ul>"#,
+                r#"</div><p><span class="c p-math p-display">\[b\]</span></p>"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn xml() {
+        let renderer = super::Renderer::minified().with_syntax(super::Syntax::Xml);
+        let mut actual = String::new();
+        renderer
+            .push(crate::Parser::new("- [x] a[^b]\n\n[^b]: c\n"), &mut actual)
+            .unwrap();
+        assert_eq!(
+            actual,
+            concat!(
+                r#"<ul class="task-list"><li><input disabled="disabled" type="checkbox" "#,
+                r##"checked="checked"/>a<a id="fnref1" href="#fn1" role="doc-noteref">"##,
+                r##"<sup>1</sup></a></li></ul><section role="doc-endnotes"><hr/><ol>"##,
+                r##"<li id="fn1"><p>c<a href="#fnref1" role="doc-backlink">↩︎</a></p></li>"##,
+                r#"</ol></section>"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn entities_feed() {
+        let renderer = super::Renderer::minified()
+            .with_feed("")
+            .with_entities(super::Entities::Named);
+        let mut actual = String::new();
+        renderer
+            .push(crate::Parser::new("a---b\\ c"), &mut actual)
+            .unwrap();
+        assert_eq!(actual, "<p>a&#x2014;b&#xa0;c</p>");
+    }
+
+    #[test]
+    fn comments() {
+        let renderer = super::Renderer::default().with_comments();
+        let mut actual = String::new();
+        renderer
+            .push(crate::Parser::new("a\n\n{% b--->c %}\n"), &mut actual)
+            .unwrap();
+        assert_eq!(actual, "<p>a</p>\n<!-- b- - ->c -->\n");
+    }
+
+    #[test]
+    fn attribute_escapes() {
+        let mut actual = String::new();
+        super::Renderer::minified()
+            .push(
+                crate::Parser::new(r#"[a]{title="\"b\" <c> & \\d \e"}"#),
+                &mut actual,
+            )
+            .unwrap();
+        assert_eq!(
+            actual,
+            r#"<p><span title="&quot;b&quot; &lt;c&gt; &amp; \d \e">a</span></p>"#,
+        );
+    }
+
+    #[test]
+    fn permalinks() {
+        let renderer = super::Renderer::minified().with_permalinks(super::Permalinks::default());
+        let mut actual = String::new();
+        renderer
+            .push(crate::Parser::new("# a\n\n- {#x}\n  ## b\n"), &mut actual)
+            .unwrap();
+        assert_eq!(
+            actual,
+            concat!(
+                r##"<section id="a"><h1>a<a class="anchor" href="#a">¶</a></h1>"##,
+                r##"<ul><li><h2 id="x">b<a class="anchor" href="#x">¶</a></h2></li></ul>"##,
+                "</section>",
+            ),
+        );
+    }
+
+    #[test]
+    fn line_numbers() {
+        let renderer = super::Renderer::minified().with_line_numbers();
+        let mut actual = String::new();
+        renderer
+            .push(
+                crate::Parser::new(
+                    "{hl_lines=\"1 3-4\" .x}\n```\na<\n\nb\nc\n```\n\n```\nd\n```\n",
+                ),
+                &mut actual,
+            )
+            .unwrap();
+        assert_eq!(
+            actual,
+            concat!(
+                r#"<pre class="x"><code>"#,
+                r#"<span class="line hl" data-line="1">a&lt;</span>"#,
+                "\n",
+                r#"<span class="line" data-line="2"></span>"#,
+                "\n",
+                r#"<span class="line hl" data-line="3">b</span>"#,
+                "\n",
+                r#"<span class="line hl" data-line="4">c</span>"#,
+                "\n</code></pre>",
+                "<pre><code>",
+                r#"<span class="line" data-line="1">d</span>"#,
+                "\n</code></pre>",
+            ),
+        );
+    }
+
+    #[test]
+    fn diff_lines() {
+        let renderer = super::Renderer::minified()
+            .with_diff_lines()
+            .with_line_numbers()
+            .with_class_prefix("x-");
+        let mut actual = String::new();
+        renderer
+            .push(
+                crate::Parser::new("{.diff}\n``` rust\n+a\n\n-b\n```\n\n```\n+c\n```\n"),
+                &mut actual,
+            )
+            .unwrap();
+        assert_eq!(
+            actual,
+            concat!(
+                r#"<pre class="diff"><code class="x-language-rust">"#,
+                r#"<span class="x-line x-added" data-line="1">+a</span>"#,
+                "\n",
+                r#"<span class="x-line" data-line="2"></span>"#,
+                "\n",
+                r#"<span class="x-line x-removed" data-line="3">-b</span>"#,
+                "\n</code></pre>",
+                "<pre><code>",
+                r#"<span class="x-line" data-line="1">+c</span>"#,
+                "\n</code></pre>",
+            ),
+        );
+    }
+
+    #[test]
+    fn tab_width() {
+        let renderer = super::Renderer::minified()
+            .with_tab_width(4)
+            .with_line_numbers();
+        let mut actual = String::new();
+        renderer
+            .push(
+                crate::Parser::new("```\nabcd\te\n\t\tf\n```\n\na\tb"),
+                &mut actual,
+            )
+            .unwrap();
+        assert_eq!(
+            actual,
+            concat!(
+                "<pre><code>",
+                r#"<span class="line" data-line="1">abcd    e</span>"#,
+                "\n",
+                r#"<span class="line" data-line="2">        f</span>"#,
+                "\n</code></pre>",
+                "<p>a\tb</p>",
+            ),
+        );
+    }
+
+    #[test]
+    fn code_blocks() {
+        let renderer = super::Renderer::minified()
+            .with_class_prefix("x-")
+            .with_code_blocks(super::CodeBlockStyle {
+                language_on_pre: true,
+                ..super::CodeBlockStyle::default()
+            });
+        let mut actual = String::new();
+        renderer
+            .push(
+                crate::Parser::new("{.a}\n``` c\nb\n```\n\n```\nd\n```\n"),
+                &mut actual,
+            )
+            .unwrap();
+        assert_eq!(
+            actual,
+            concat!(
+                r#"<pre class="a x-language-c"><code>b"#,
+                "\n</code></pre>",
+                "<pre><code>d\n</code></pre>",
+            ),
+        );
+    }
+
+    #[test]
+    fn highlight_js() {
+        let renderer = super::Renderer::minified()
+            .with_class_prefix("x-")
+            .with_code_blocks(super::CodeBlockStyle {
+                language_prefix: "lang-".to_string(),
+                ..super::CodeBlockStyle::highlight_js()
+            });
+        let mut actual = String::new();
+        renderer
+            .push(
+                crate::Parser::new("``` c\nb\n```\n\n```\nd\n```\n"),
+                &mut actual,
+            )
+            .unwrap();
+        assert_eq!(
+            actual,
+            concat!(
+                r#"<pre><code class="hljs x-lang-c">b"#,
+                "\n</code></pre>",
+                r#"<pre><code class="hljs">d"#,
+                "\n</code></pre>",
+            ),
+        );
+    }
+
+    #[test]
+    fn raw_format_code() {
+        let renderer = super::Renderer::default().with_raw_format("tex", super::RawOutput::Code);
+        let mut actual = String::new();
+        renderer
+            .push(
+                crate::Parser::new("``` =tex\n\\a & b\n```\n\n``` =html\n<hr>\n```\n"),
+                &mut actual,
+            )
+            .unwrap();
+        assert_eq!(actual, "<pre><code>\\a &amp; b</code></pre>\n<hr>\n");
+    }
+
+    #[test]
+    fn image_sizes() {
+        let renderer = super::Renderer::minified().with_image_sizes(|src| {
+            let n = src.strip_suffix(".png")?.len() as u32;
+            Some((n, 2 * n))
+        });
+        let mut actual = String::new();
+        renderer
+            .push(
+                crate::Parser::new("![![x](b.png)](aaa.png){height=1} ![y](aa.png) ![z](a.svg)"),
+                &mut actual,
+            )
+            .unwrap();
+        assert_eq!(
+            actual,
+            concat!(
+                r#"<p><img height="1" alt="x" src="aaa.png"> "#,
+                r#"<img width="2" height="4" alt="y" src="aa.png"> "#,
+                r#"<img alt="z" src="a.svg"></p>"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn url_rewrite() {
+        let renderer = super::Renderer::minified()
+            .with_feed("https://a.b/c/")
+            .with_url_rewrite(|url| Some(format!("{}?v=1", url)));
+        let mut actual = String::new();
+        renderer
+            .push(
+                crate::Parser::new("[d](e.html) ![f](g.png) <h@i.j> <https://k.l>"),
+                &mut actual,
+            )
+            .unwrap();
+        assert_eq!(
+            actual,
+            concat!(
+                r#"<p><a href="https://a.b/c/e.html?v=1">d</a> "#,
+                r#"<img alt="f" src="https://a.b/c/g.png?v=1"> "#,
+                r#"<a href="mailto:h@i.j">h@i.j</a> "#,
+                r#"<a href="https://k.l">https://k.l</a></p>"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn base64() {
+        let encode = |data: &[u8]| {
+            let mut s = String::new();
+            super::write_base64(data, &mut s).unwrap();
+            s
+        };
+        assert_eq!(encode(b""), "");
+        assert_eq!(encode(b"f"), "Zg==");
+        assert_eq!(encode(b"fo"), "Zm8=");
+        assert_eq!(encode(b"foo"), "Zm9v");
+        assert_eq!(encode(b"foob"), "Zm9vYg==");
+        assert_eq!(encode(&[0xff, 0xfe]), "//4=");
+    }
+
+    #[test]
+    fn embedded_images() {
+        let renderer = super::Renderer::minified()
+            .with_url_rewrite(|url| Some(format!("/{}", url)))
+            .with_embedded_images(3, |path| Some(path.as_bytes()[..path.len() - 4].to_vec()));
+        let mut actual = String::new();
+        renderer
+            .push(
+                crate::Parser::new("![](a.PNG?v) ![](bcde.png) ![](f.txt) ![](https://g/h.png)"),
+                &mut actual,
+            )
+            .unwrap();
+        assert_eq!(
+            actual,
+            concat!(
+                r#"<p><img alt="" src="data:image/png;base64,YQ=="> "#,
+                r#"<img alt="" src="/bcde.png"> "#,
+                r#"<img alt="" src="/f.txt"> "#,
+                r#"<img alt="" src="/https://g/h.png"></p>"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn obfuscated_emails() {
+        let renderer = super::Renderer::minified().with_obfuscated_emails();
+        let mut actual = String::new();
+        renderer
+            .push(
+                crate::Parser::new("[mail _me_](MAILTO:a@b) [c](d@e) <https://f>"),
+                &mut actual,
+            )
+            .unwrap();
+        assert_eq!(
+            actual,
+            concat!(
+                r#"<p><a href="&#x4d;&#x41;&#x49;&#x4c;&#x54;&#x4f;&#x3a;&#x61;&#x40;&#x62;">"#,
+                "&#x6d;&#x61;&#x69;&#x6c;&#x20;<em>&#x6d;&#x65;</em></a> ",
+                r#"<a href="d@e">c</a> <a href="https://f">https://f</a></p>"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn inline_note_blocks() {
+        let renderer = super::Renderer::minified().with_footnotes(super::FootnoteStyle {
+            placement: super::FootnotePlacement::Inline,
+            ..super::FootnoteStyle::default()
+        });
+        let src = "a[^a]\n\n[^a]: n\n\n  - list\n  - *item*\n\n  ```\n  x < y\n  ```\n";
+        let mut actual = String::new();
+        renderer.push(crate::Parser::new(src), &mut actual).unwrap();
+        assert_eq!(
+            actual,
+            concat!(
+                r#"<p>a<span class="footnote" role="doc-footnote">"#,
+                "n list <strong>item</strong> x &lt; y\n</span></p>",
+            ),
+        );
+    }
+
+    #[test]
+    fn print() {
+        let renderer = super::Renderer::minified()
+            .with_print()
+            .with_class_prefix("p-")
+            .with_footnotes(super::FootnoteStyle {
+                placement: super::FootnotePlacement::Inline,
+                ..super::FootnoteStyle::default()
+            });
+        let src = concat!(
+            "a[^n] b[^n] [c](mailto:d@e) [f][]\n",
+            "\n",
+            "[^n]: g\n",
+            "\n",
+            "    h[^m]\n",
+            "\n",
+            "[^m]: i\n",
+            "\n",
+            "[f]: j.html\n",
+            "\n",
+            "{style=\"color: red\"}\n",
+            "::: page-break\n",
+            "k\n",
+            ":::\n",
+        );
+        let mut actual = String::new();
+        renderer.push(crate::Parser::new(src), &mut actual).unwrap();
+        assert_eq!(
+            actual,
+            concat!(
+                r#"<p>a<span class="p-footnote" role="doc-footnote">g h"#,
+                r#"<span class="p-footnote" role="doc-footnote">i</span></span> b "#,
+                r#"<a href="mailto:d@e">c</a> <a href="j.html">f</a> "#,
+                r#"<span class="p-url">(j.html)</span></p>"#,
+                r#"<div style="color: red; break-before: page;" class="page-break">"#,
+                r#"<p>k</p></div>"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn code_execution() {
+        let src = "``` a\nx\ny\n```\n\n- ``` b\n  z\n  ```\n";
+        let plain = super::Renderer::default().with_line_numbers();
+        let renderer = plain.clone().with_code_execution(|lang, code| {
+            (lang == "b").then(|| super::CodeOutput::After(format!("<output>{}</output>", code)))
+        });
+        let (mut expected, mut actual) = (String::new(), String::new());
+        plain.push(crate::Parser::new(src), &mut expected).unwrap();
+        renderer.push(crate::Parser::new(src), &mut actual).unwrap();
+        assert_eq!(
+            actual,
+            expected.replace("</pre>\n</li>", "</pre>\n<output>z\n</output>\n</li>"),
+        );
+    }
+
+    #[test]
+    fn cjk_breaks() {
+        let renderer = super::Renderer::default().with_cjk_breaks();
+        let mut actual = String::new();
+        renderer
+            .push(
+                crate::Parser::new("> 中文\n> _強調_\n> 中文\n> a\n"),
+                &mut actual,
+            )
+            .unwrap();
+        assert_eq!(
+            actual,
+            "<blockquote>\n<p>中文<em>強調</em>中文\na</p>\n</blockquote>\n",
+        );
+    }
+
+    #[test]
+    fn attributes() {
+        let renderer = super::Renderer::minified()
+            .with_attributes(
+                crate::Container::Link("".into(), crate::LinkType::Email),
+                crate::Attributes::try_from("{.external rel=noopener}").unwrap(),
+            )
+            .with_attributes(
+                crate::Container::Link("".into(), crate::LinkType::Email),
+                crate::Attributes::try_from("{.link}").unwrap(),
+            );
+        let mut actual = String::new();
+        renderer
+            .push(crate::Parser::new("[a](b){#c .d} _e_"), &mut actual)
+            .unwrap();
+        assert_eq!(
+            actual,
+            r#"<p><a href="b" class="link d" id="c">a</a> <em>e</em></p>"#,
+        );
+    }
+
+    #[test]
+    fn elements() {
+        let renderer = super::Renderer::minified()
+            .with_element(crate::Container::Mark, "b", None)
+            .with_element(crate::Container::Mark, "span", Some("x"))
+            .with_element(
+                crate::Container::Link("".into(), crate::LinkType::Email),
+                "b",
+                None,
+            );
+        let mut actual = String::new();
+        renderer
+            .push(
+                crate::Parser::new("{=a=}{.y} ![{=b=}](c) <d@e>"),
+                &mut actual,
+            )
+            .unwrap();
+        assert_eq!(
+            actual,
+            concat!(
+                r#"<p><span class="y x">a</span> <img alt="b" src="c"> "#,
+                r#"<a href="mailto:d@e">d@e</a></p>"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn elements_block() {
+        let renderer = super::Renderer::minified()
+            .with_element(crate::Container::Blockquote, "aside", Some("quote"))
+            .with_element(crate::Container::Div { class: "" }, "article", None)
+            .with_element(crate::Container::Verbatim, "kbd", None);
+        let mut actual = String::new();
+        renderer
+            .push(crate::Parser::new("> `a`\n\n::: b\nc\n:::\n"), &mut actual)
+            .unwrap();
+        assert_eq!(
+            actual,
+            concat!(
+                r#"<aside class="quote"><p><kbd>a</kbd></p></aside>"#,
+                r#"<article class="b"><p>c</p></article>"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn math_hook() {
+        let renderer = super::Renderer::minified().with_math(|math, display| {
+            if display {
+                Err(())
+            } else {
+                Ok(format!("<m>{}</m>", math))
+            }
+        });
+        let mut actual = String::new();
+        renderer
+            .push(
+                crate::Parser::new("$`a<b` $$`c<d`{#e} $`f`{#g}"),
+                &mut actual,
+            )
+            .unwrap();
+        assert_eq!(
+            actual,
+            concat!(
+                r#"<p><m>a<b</m> <span id="e" class="math display">\[c&lt;d\]</span> "#,
+                r#"<span id="g"><m>f</m></span></p>"#,
+            ),
+        );
+    }
 }
@@ -0,0 +1,282 @@
+//! Detection of duplicate ids.
+//!
+//! Ids that are generated from the text of headings are always unique, but explicit ids, e.g.
+//! `{#intro}`, are used as is and may equal another explicit or generated id. A document with
+//! duplicate ids is invalid HTML, and links to such an id only lead to the first element.
+//!
+//! The [`UniqueIds`] adapter finds duplicate ids and by default makes them unique by appending a
+//! number, like the generated ids. The final id is set on the event, both in the attributes and
+//! in the id of a section or heading. Duplicates are also recorded, so that they may be reported
+//! as warnings, and strict tools may instead reject documents with [`check_ids`].
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! use jotdown::ids::*;
+//!
+//! let src = "# Usage\n\n{#Usage}\nSee below.\n";
+//! let mut events = UniqueIds::new(jotdown::Parser::new(src));
+//! assert_eq!(
+//!     jotdown::html::render_to_string(&mut events),
+//!     concat!(
+//!         "<section id=\"Usage\">\n",
+//!         "<h1>Usage</h1>\n",
+//!         "<p id=\"Usage-1\">See below.</p>\n",
+//!         "</section>\n",
+//!     ),
+//! );
+//! assert_eq!(
+//!     events.duplicates(),
+//!     &[Duplicate {
+//!         index: 5,
+//!         id: "Usage".to_string(),
+//!         renamed: Some("Usage-1".to_string()),
+//!     }],
+//! );
+//! # }
+//! ```
+
+use crate::AttributeKind;
+use crate::Container;
+use crate::CowStr;
+use crate::Event;
+use crate::Set;
+
+/// Treatment of duplicate ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Duplicates {
+    /// Append the lowest number that makes the id unique, e.g. `intro-1`.
+    Suffix,
+    /// Leave the id as is.
+    Keep,
+}
+
+impl Default for Duplicates {
+    fn default() -> Self {
+        Self::Suffix
+    }
+}
+
+/// An id that has already been used by a previous element.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Duplicate {
+    /// Index of the start event of the element.
+    pub index: usize,
+    /// The duplicate id.
+    pub id: String,
+    /// The id that replaced the duplicate, if it was made unique.
+    pub renamed: Option<String>,
+}
+
+impl std::fmt::Display for Duplicate {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "duplicate id \"{}\" at event {}", self.id, self.index)
+    }
+}
+
+impl std::error::Error for Duplicate {}
+
+/// An iterator adapter that finds and resolves duplicate ids.
+pub struct UniqueIds<'s, I: Iterator<Item = Event<'s>>> {
+    events: I,
+    strategy: Duplicates,
+    seen: Set<String>,
+    duplicates: Vec<Duplicate>,
+    /// Index of the next event.
+    index: usize,
+    /// New ids of the open containers, if they were renamed.
+    open: Vec<Option<CowStr<'s>>>,
+}
+
+impl<'s, I> UniqueIds<'s, I>
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    /// Create an adapter that makes duplicate ids unique.
+    pub fn new(events: I) -> Self {
+        Self {
+            events,
+            strategy: Duplicates::default(),
+            seen: Set::new(),
+            duplicates: Vec::new(),
+            index: 0,
+            open: Vec::new(),
+        }
+    }
+
+    /// Treat duplicate ids as specified, instead of appending a number.
+    #[must_use]
+    pub fn with_duplicates(mut self, strategy: Duplicates) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Duplicate ids that have been found, in order of occurrence.
+    #[must_use]
+    pub fn duplicates(&self) -> &[Duplicate] {
+        &self.duplicates
+    }
+
+    /// Register the id of an element, returning a new id if it is renamed.
+    fn register(&mut self, index: usize, id: String) -> Option<CowStr<'s>> {
+        if id.is_empty() || !self.seen.contains(&id) {
+            self.seen.insert(id);
+            return None;
+        }
+        let renamed = match self.strategy {
+            Duplicates::Keep => None,
+            Duplicates::Suffix => (1..)
+                .map(|n| format!("{}-{}", id, n))
+                .find(|candidate| !self.seen.contains(candidate)),
+        };
+        if let Some(r) = &renamed {
+            self.seen.insert(r.clone());
+        }
+        self.duplicates.push(Duplicate {
+            index,
+            id,
+            renamed: renamed.clone(),
+        });
+        renamed.map(CowStr::from)
+    }
+}
+
+impl<'s, I> Iterator for UniqueIds<'s, I>
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    type Item = Event<'s>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let e = self.events.next()?;
+        let index = self.index;
+        self.index += 1;
+        Some(match e {
+            Event::Start(mut c, mut attrs) => {
+                let renamed = if let Container::Heading {
+                    has_section: true, ..
+                } = c
+                {
+                    // the heading shares the id of its section
+                    self.open.last().cloned().flatten()
+                } else {
+                    let id = attrs.get_value("id").map(|v| v.to_string()).or_else(|| {
+                        if let Container::Section { id } | Container::Heading { id, .. } = &c {
+                            Some(id.to_string())
+                        } else {
+                            None
+                        }
+                    });
+                    id.and_then(|id| self.register(index, id))
+                };
+                if let Some(id) = &renamed {
+                    set_id(&mut c, id);
+                    if attrs.contains_key("id") {
+                        attrs.retain(|(k, _)| k.key() != Some("id"));
+                        attrs.push((AttributeKind::Id, id.clone().into()));
+                    }
+                }
+                self.open.push(renamed);
+                Event::Start(c, attrs)
+            }
+            Event::End(mut c) => {
+                if let Some(Some(id)) = self.open.pop() {
+                    set_id(&mut c, &id);
+                }
+                Event::End(c)
+            }
+            e => e,
+        })
+    }
+}
+
+/// Check that all ids of a document are unique, returning the first duplicate otherwise.
+///
+/// # Examples
+///
+/// ```
+/// # use jotdown::ids::*;
+/// let events = jotdown::Parser::new("[a]{#x} [b]{#x}");
+/// assert_eq!(
+///     check_ids(events),
+///     Err(Duplicate {
+///         index: 5,
+///         id: "x".to_string(),
+///         renamed: None,
+///     }),
+/// );
+/// ```
+pub fn check_ids<'s, I>(events: I) -> Result<(), Duplicate>
+where
+    I: IntoIterator<Item = Event<'s>>,
+{
+    let mut ids = UniqueIds::new(events.into_iter()).with_duplicates(Duplicates::Keep);
+    while ids.next().is_some() {
+        if let Some(duplicate) = ids.duplicates.pop() {
+            return Err(duplicate);
+        }
+    }
+    Ok(())
+}
+
+fn set_id<'s>(c: &mut Container<'s>, new: &CowStr<'s>) {
+    if let Container::Section { id } | Container::Heading { id, .. } = c {
+        *id = new.clone();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::check_ids;
+    use super::Duplicates;
+    use super::UniqueIds;
+    use crate::Attributes;
+    use crate::Container;
+    use crate::Event;
+
+    #[test]
+    fn sections() {
+        let src = "{#a}\n# A\n\n{#a}\n## B\n";
+        let events: Vec<_> = UniqueIds::new(crate::Parser::new(src))
+            .filter(|e| matches!(e, Event::Start(..) | Event::End(..)))
+            .collect();
+        let section = |id| Container::Section { id };
+        let heading = |level, id| Container::Heading {
+            level,
+            has_section: true,
+            id,
+        };
+        assert_eq!(
+            events,
+            &[
+                Event::Start(section("a".into()), Attributes::try_from("{#a}").unwrap()),
+                Event::Start(heading(1, "a".into()), Attributes::new()),
+                Event::End(heading(1, "a".into())),
+                Event::Start(
+                    section("a-1".into()),
+                    Attributes::try_from("{#a-1}").unwrap()
+                ),
+                Event::Start(heading(2, "a-1".into()), Attributes::new()),
+                Event::End(heading(2, "a-1".into())),
+                Event::End(section("a-1".into())),
+                Event::End(section("a".into())),
+            ],
+        );
+        assert_eq!(crate::validate::validate(events), Ok(()));
+    }
+
+    #[test]
+    fn keep() {
+        let src = "# a\n\n[b]{#a} [c]{#a}";
+        let mut ids = UniqueIds::new(crate::Parser::new(src)).with_duplicates(Duplicates::Keep);
+        assert!(ids.by_ref().any(|e| matches!(
+            e,
+            Event::Start(Container::Span, attrs) if attrs.get_value("id") == Some("a".into())
+        )));
+        ids.by_ref().for_each(drop);
+        assert_eq!(ids.duplicates().len(), 2);
+        assert_eq!(check_ids(crate::Parser::new(src)).unwrap_err().index, 6);
+    }
+}
@@ -45,6 +45,7 @@ pub enum Container<'s> {
     InlineLink(CowStrIndex),
     InlineImage(CowStrIndex),
     Autolink(&'s str),
+    Custom { name: &'s str },
 }
 
 type CowStrIndex = u32;
@@ -228,6 +229,10 @@ pub struct Parser<'s> {
     pub(crate) store_cowstrs: Vec<CowStr<'s>>,
     /// Storage of attributes, used to reduce size of [`EventKind`].
     pub(crate) store_attributes: Vec<attr::Attributes<'s>>,
+    /// Syntax extensions, tried before the regular syntax.
+    pub(crate) extensions: Vec<crate::extension::InlineSyntax>,
+    /// Tags of containers whose syntax is not recognized.
+    pub(crate) disabled: Vec<u8>,
 }
 
 enum ControlFlow {
@@ -252,6 +257,8 @@ impl<'s> Parser<'s> {
             attributes: None,
             store_cowstrs: Vec::new(),
             store_attributes: Vec::new(),
+            extensions: Vec::new(),
+            disabled: Vec::new(),
         }
     }
 
@@ -284,6 +291,7 @@ impl<'s> Parser<'s> {
         if let Some(first) = self.input.eat() {
             self.parse_verbatim(&first)
                 .or_else(|| self.parse_attributes(&first))
+                .or_else(|| self.parse_extension(&first))
                 .or_else(|| self.parse_autolink(&first))
                 .or_else(|| self.parse_symbol(&first))
                 .or_else(|| self.parse_footnote_reference(&first))
@@ -582,6 +590,76 @@ impl<'s> Parser<'s> {
         Some(Continue)
     }
 
+    fn parse_extension(&mut self, first: &lex::Token) -> Option<ControlFlow> {
+        if self.extensions.is_empty() {
+            return None;
+        }
+
+        let start = self.input.span.start;
+        let end_line = self.input.span_line.end;
+        let line = &self.input.src[start..end_line];
+        let backslashes = self.input.src[self.input.span_line.start..start]
+            .bytes()
+            .rev()
+            .take_while(|c| *c == b'\\')
+            .count();
+        let escaped = backslashes % 2 == 1;
+        // a trigger may occur anywhere within a text token
+        let len_first = if matches!(first.kind, lex::Kind::Text) {
+            first.len
+        } else {
+            1
+        };
+        let (i, name, len, content) = (0..len_first)
+            .filter(|i| line.is_char_boundary(*i) && !(*i == 0 && escaped))
+            .find_map(|i| {
+                let s = &line[i..];
+                self.extensions.iter().find_map(|ext| {
+                    if !s.starts_with(&ext.trigger) {
+                        return None;
+                    }
+                    if ext.word
+                        && self.input.src[..start + i]
+                            .chars()
+                            .next_back()
+                            .map_or(false, char::is_alphanumeric)
+                    {
+                        return None;
+                    }
+                    (ext.parse)(s)
+                        .filter(|(len, content)| {
+                            0 < *len
+                                && *len <= s.len()
+                                && s.is_char_boundary(*len)
+                                && content.start <= content.end
+                                && content.end <= *len
+                                && s.is_char_boundary(content.start)
+                                && s.is_char_boundary(content.end)
+                        })
+                        .map(|(len, content)| (i, ext.name, len, content))
+                })
+            })?;
+
+        let start = start + i;
+        self.input.span.end = start;
+        if i > 0 {
+            // emit the preceding text, the element is parsed as the next event
+            self.push(EventKind::Str);
+        } else {
+            let end = start + len;
+            self.push_sp(
+                EventKind::Enter(Custom { name }),
+                start..start + content.start,
+            );
+            self.push_sp(EventKind::Str, start + content.start..start + content.end);
+            self.push_sp(EventKind::Exit(Custom { name }), start + content.end..end);
+            self.input.span.end = end;
+        }
+        self.input.lexer =
+            lex::Lexer::new(&self.input.src.as_bytes()[self.input.span.end..end_line]);
+        Some(Continue)
+    }
+
     fn parse_autolink(&mut self, first: &lex::Token) -> Option<ControlFlow> {
         if first.kind == lex::Kind::Sym(Symbol::Lt) {
             let mut end = false;
@@ -853,6 +931,12 @@ impl<'s> Parser<'s> {
             })
             .or_else(|| {
                 let opener = Opener::from_token(first.kind)?;
+                if opener
+                    .formatting()
+                    .map_or(false, |c| self.disabled.contains(&c.tag()))
+                {
+                    return None;
+                }
                 let whitespace_after = self
                     .input
                     .lexer
@@ -1116,6 +1200,20 @@ impl Opener {
         }
     }
 
+    /// The container of inline formatting that is opened, if any.
+    fn formatting(&self) -> Option<crate::Container<'static>> {
+        match self {
+            Opener::Strong(..) => Some(crate::Container::Strong),
+            Opener::Emphasis(..) => Some(crate::Container::Emphasis),
+            Opener::Superscript(..) => Some(crate::Container::Superscript),
+            Opener::Subscript(..) => Some(crate::Container::Subscript),
+            Opener::Mark => Some(crate::Container::Mark),
+            Opener::Delete => Some(crate::Container::Delete),
+            Opener::Insert => Some(crate::Container::Insert),
+            _ => None,
+        }
+    }
+
     fn bidirectional(&self) -> bool {
         matches!(
             self,
@@ -1706,6 +1804,27 @@ mod test {
         );
     }
 
+    #[test]
+    fn container_cjk() {
+        // only ASCII whitespace affects the delimiters, so no spaces are needed around them
+        test_parse!(
+            "これは*強調*です",
+            (Str, "これは"),
+            (Enter(Strong), "*"),
+            (Str, "強調"),
+            (Exit(Strong), "*"),
+            (Str, "です"),
+        );
+        test_parse!(
+            "「_强调_」。",
+            (Str, "「"),
+            (Enter(Emphasis), "_"),
+            (Str, "强调"),
+            (Exit(Emphasis), "_"),
+            (Str, "」。"),
+        );
+    }
+
     #[test]
     fn container_nest() {
         test_parse!(
@@ -2044,4 +2163,77 @@ mod test {
             (Empty, "{.b}"),
         );
     }
+
+    fn parse_ext<'s>(
+        src: &'s str,
+        ext: &crate::extension::Extensions,
+    ) -> Vec<(super::EventKind<'s>, &'s str)> {
+        let mut p = super::Parser::new(src);
+        p.extensions = ext.inline.clone();
+        p.disabled = ext.disabled.clone();
+        p.feed_line(0..src.len(), true);
+        p.map(|ev| (ev.kind, &src[ev.span])).collect()
+    }
+
+    #[test]
+    fn extension() {
+        let ext = crate::extension::Extensions::new().with_delimited("x", "==", "==");
+        assert_eq!(
+            parse_ext("a==b==*c*", &ext),
+            &[
+                (Str, "a"),
+                (Enter(Custom { name: "x" }), "=="),
+                (Str, "b"),
+                (Exit(Custom { name: "x" }), "=="),
+                (Enter(Strong), "*"),
+                (Str, "c"),
+                (Exit(Strong), "*"),
+            ]
+        );
+        assert_eq!(parse_ext("`==a==`", &ext)[1], (Str, "==a=="));
+        assert_eq!(
+            parse_ext("\\\\==a==", &ext)[2],
+            (Enter(Custom { name: "x" }), "==")
+        );
+        assert_eq!(parse_ext("==a", &ext), &[(Str, "==a")]);
+    }
+
+    #[test]
+    fn disabled() {
+        let ext = crate::extension::Extensions::new()
+            .with_disabled(crate::Container::Delete)
+            .with_disabled(crate::Container::Span);
+        assert_eq!(
+            parse_ext("{-a-} [b]{.c}", &ext),
+            &[
+                (Str, "{-a-} "),
+                (
+                    Attributes {
+                        container: true,
+                        attrs: 0
+                    },
+                    "{.c}"
+                ),
+                (Enter(Span), "["),
+                (Str, "b"),
+                (Exit(Span), "]{.c}"),
+            ]
+        );
+    }
+
+    #[test]
+    fn extension_invalid() {
+        let ext = crate::extension::Extensions::new()
+            .with_inline("x", "a", |s| Some((s.len() + 1, 0..1)))
+            .with_inline("y", "a", |_| Some((1, 0..2)))
+            .with_inline("z", "b", |_| Some((3, 1..1)));
+        assert_eq!(
+            parse_ext("abcd", &ext),
+            &[
+                (Str, "a"),
+                (Enter(Custom { name: "z" }), "b"),
+                (Exit(Custom { name: "z" }), "cd"),
+            ]
+        );
+    }
 }
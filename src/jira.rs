@@ -0,0 +1,507 @@
+//! A renderer that takes an iterator of [`Event`]s and emits the wiki markup of Jira and
+//! Confluence.
+//!
+//! The output may be used e.g. as the description of a Jira issue or the content of a Confluence
+//! page in wiki format. Code blocks become `{code}` macros and block quotes `{quote}` macros.
+//! Footnotes have no equivalent, so they are numbered, e.g. `^1^`, and listed after a horizontal
+//! rule at the end of the document.
+//!
+//! Line breaks are significant in the wiki markup, so the lines of a paragraph are joined.
+//! Characters that would be interpreted as markup are escaped with a backslash.
+//!
+//! # Examples
+//!
+//! ```
+//! let src = concat!(
+//!     "## Steps\n",
+//!     "\n",
+//!     "1. Open [the site](https://djot.net).\n",
+//!     "2. Run `jotdown`:\n",
+//!     "\n",
+//!     "``` sh\n",
+//!     "jotdown a.dj\n",
+//!     "```\n",
+//! );
+//! let events = jotdown::Parser::new(src);
+//! assert_eq!(
+//!     jotdown::jira::render_to_string(events),
+//!     concat!(
+//!         "h2. Steps\n",
+//!         "\n",
+//!         "# Open [the site|https://djot.net].\n",
+//!         "# Run {{jotdown}}:\n",
+//!         "\n",
+//!         "{code:sh}\n",
+//!         "jotdown a.dj\n",
+//!         "{code}\n",
+//!     ),
+//! );
+//! ```
+
+use crate::Container;
+use crate::Event;
+use crate::LinkType;
+use crate::ListKind;
+use crate::Render;
+use crate::SpanLinkType;
+
+/// Render events into a string.
+///
+/// This is a convenience function for using [`Renderer::push`] with fewer imports and without an
+/// intermediate variable.
+///
+/// # Examples
+///
+/// ```
+/// let events = jotdown::Parser::new("hello *world*");
+/// assert_eq!(jotdown::jira::render_to_string(events), "hello *world*\n");
+/// ```
+pub fn render_to_string<'s, I>(events: I) -> String
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    let mut s = String::new();
+    Renderer::default().push(events, &mut s).unwrap();
+    s
+}
+
+/// [`Render`] implementor that writes Jira and Confluence wiki markup.
+#[derive(Debug, Clone, Default)]
+pub struct Renderer {}
+
+impl Render for Renderer {
+    fn push<'s, I, W>(&self, mut events: I, mut out: W) -> std::fmt::Result
+    where
+        I: Iterator<Item = Event<'s>>,
+        W: std::fmt::Write,
+    {
+        let mut w = Writer::default();
+        events.try_for_each(|e| w.render_event(&e, &mut out))?;
+        w.render_epilogue(&mut out)
+    }
+}
+
+/// Separator to write before the next block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Separator {
+    /// Nothing, at the start of the output or of a list item.
+    None,
+    /// A line break, at the start of a container.
+    Line,
+    /// A blank line, after a block.
+    Blank,
+}
+
+impl Default for Separator {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+#[derive(Default)]
+struct Writer<'s> {
+    separator: Separator,
+    /// Prefixes of the open lists, e.g. `*` or `#`.
+    list: String,
+    /// Whether the next character is at the start of a line.
+    line_start: bool,
+    /// Whether text is written as is, e.g. within code.
+    verbatim: bool,
+    /// Depth of containers whose content is omitted, e.g. images.
+    ignore: usize,
+    /// Labels of footnotes in order of their first reference.
+    references: Vec<&'s str>,
+    /// Events of footnotes, by label.
+    footnotes: Vec<(&'s str, Vec<Event<'s>>)>,
+    /// Footnote that is being collected.
+    footnote: Option<(&'s str, Vec<Event<'s>>)>,
+}
+
+impl<'s> Writer<'s> {
+    fn render_event<W>(&mut self, e: &Event<'s>, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        if let Some((label, events)) = &mut self.footnote {
+            if let Event::End(Container::Footnote { .. }) = e {
+                self.footnotes.push((label, std::mem::take(events)));
+                self.footnote = None;
+            } else {
+                events.push(e.clone());
+            }
+            return Ok(());
+        }
+
+        if self.ignore > 0 {
+            match e {
+                Event::Start(..) => self.ignore += 1,
+                Event::End(..) => self.ignore -= 1,
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        match e {
+            Event::Start(c, _) => self.render_start(c, &mut out)?,
+            Event::End(c) => self.render_end(c, &mut out)?,
+            Event::Str(s) if self.verbatim => out.write_str(s)?,
+            Event::Str(s) => self.write_text(s, &mut out)?,
+            Event::FootnoteReference(label) => {
+                let number = self
+                    .references
+                    .iter()
+                    .position(|l| l == label)
+                    .unwrap_or_else(|| {
+                        self.references.push(label);
+                        self.references.len() - 1
+                    })
+                    + 1;
+                write!(out, "^{}^", number)?;
+            }
+            Event::Symbol(sym) => {
+                out.write_char(':')?;
+                self.write_text(sym, &mut out)?;
+                out.write_char(':')?;
+            }
+            Event::LeftSingleQuote => out.write_char('‘')?,
+            Event::RightSingleQuote => out.write_char('’')?,
+            Event::LeftDoubleQuote => out.write_char('“')?,
+            Event::RightDoubleQuote => out.write_char('”')?,
+            Event::Ellipsis => out.write_char('…')?,
+            Event::EnDash => out.write_char('–')?,
+            Event::EmDash => out.write_char('—')?,
+            Event::NonBreakingSpace => out.write_str("&nbsp;")?,
+            Event::Softbreak if self.verbatim => out.write_char('\n')?,
+            Event::Softbreak => out.write_char(' ')?,
+            Event::Hardbreak => out.write_str("\\\\ ")?,
+            Event::ThematicBreak(_) => {
+                self.block(&mut out)?;
+                out.write_str("----")?;
+            }
+            Event::Escape | Event::Blankline | Event::Attributes(_) => {}
+        }
+        Ok(())
+    }
+
+    fn render_start<W>(&mut self, c: &Container<'s>, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        match c {
+            Container::Blockquote => {
+                self.block(&mut out)?;
+                out.write_str("{quote}")?;
+                self.separator = Separator::Line;
+            }
+            Container::List { kind, .. } => {
+                if self.list.is_empty() {
+                    self.block(&mut out)?;
+                    self.separator = Separator::None;
+                }
+                self.list.push(match kind {
+                    ListKind::Ordered { .. } => '#',
+                    ListKind::Unordered(..) | ListKind::Task(..) => '*',
+                });
+            }
+            Container::ListItem | Container::TaskListItem { .. } => {
+                if self.separator != Separator::None {
+                    out.write_char('\n')?;
+                }
+                write!(out, "{} ", self.list)?;
+                if let Container::TaskListItem { checked } = c {
+                    out.write_str(if *checked { "☑ " } else { "☐ " })?;
+                }
+                self.separator = Separator::None;
+            }
+            Container::DescriptionTerm => {
+                self.block(&mut out)?;
+                out.write_char('*')?;
+            }
+            Container::Footnote { label } => self.footnote = Some((label, Vec::new())),
+            Container::Table => {
+                self.block(&mut out)?;
+                self.separator = Separator::None;
+            }
+            Container::TableRow { .. } => {
+                if self.separator != Separator::None {
+                    out.write_char('\n')?;
+                }
+                self.separator = Separator::Line;
+            }
+            Container::TableCell { head, .. } => out.write_str(if *head { "||" } else { "|" })?,
+            Container::Paragraph => self.block(&mut out)?,
+            Container::Heading { level, .. } => {
+                self.block(&mut out)?;
+                write!(out, "h{}. ", level)?;
+            }
+            Container::Caption | Container::LinkDefinition { .. } => self.ignore = 1,
+            Container::RawBlock { format } | Container::RawInline { format } => {
+                if *format == "jira" {
+                    if c.is_block() {
+                        self.block(&mut out)?;
+                    }
+                    self.verbatim = true;
+                } else {
+                    self.ignore = 1;
+                }
+            }
+            Container::CodeBlock { language } => {
+                self.block(&mut out)?;
+                if language.is_empty() {
+                    out.write_str("{code}\n")?;
+                } else {
+                    write!(out, "{{code:{}}}", language)?;
+                    out.write_char('\n')?;
+                }
+                self.verbatim = true;
+            }
+            Container::Link(dst, ty) => match ty {
+                LinkType::Span(SpanLinkType::Unresolved) => {}
+                LinkType::AutoLink => {
+                    out.write_char('[')?;
+                    write_url(dst, &mut out)?;
+                    out.write_char(']')?;
+                    self.ignore = 1;
+                }
+                LinkType::Email => {
+                    out.write_str("[mailto:")?;
+                    write_url(dst, &mut out)?;
+                    out.write_char(']')?;
+                    self.ignore = 1;
+                }
+                LinkType::Span(_) => out.write_char('[')?,
+            },
+            Container::Image(src, _) => {
+                out.write_char('!')?;
+                write_url(src, &mut out)?;
+                out.write_char('!')?;
+                self.ignore = 1;
+            }
+            Container::Verbatim | Container::Math { .. } => out.write_str("{{")?,
+            Container::Subscript => out.write_char('~')?,
+            Container::Superscript => out.write_char('^')?,
+            Container::Insert => out.write_char('+')?,
+            Container::Delete => out.write_char('-')?,
+            Container::Strong => out.write_char('*')?,
+            Container::Emphasis => out.write_char('_')?,
+            Container::DescriptionList
+            | Container::DescriptionDetails
+            | Container::Section { .. }
+            | Container::Div { .. }
+            | Container::CustomBlock { .. }
+            | Container::Span
+            | Container::CustomInline { .. }
+            | Container::Mark => {}
+        }
+        if !c.is_block() {
+            self.line_start = false;
+        }
+        Ok(())
+    }
+
+    fn render_end<W>(&mut self, c: &Container<'s>, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        match c {
+            Container::Blockquote => out.write_str("\n{quote}")?,
+            Container::List { .. } => {
+                self.list.pop();
+            }
+            Container::DescriptionTerm | Container::Strong => out.write_char('*')?,
+            Container::TableRow { head } => out.write_str(if *head { "||" } else { "|" })?,
+            Container::RawBlock { .. } | Container::RawInline { .. } => self.verbatim = false,
+            Container::CodeBlock { .. } => {
+                out.write_str("{code}")?;
+                self.verbatim = false;
+            }
+            Container::Link(
+                dst,
+                LinkType::Span(SpanLinkType::Inline | SpanLinkType::Reference),
+            ) => {
+                out.write_char('|')?;
+                write_url(dst, &mut out)?;
+                out.write_char(']')?;
+            }
+            Container::Verbatim | Container::Math { .. } => out.write_str("}}")?,
+            Container::Subscript => out.write_char('~')?,
+            Container::Superscript => out.write_char('^')?,
+            Container::Insert => out.write_char('+')?,
+            Container::Delete => out.write_char('-')?,
+            Container::Emphasis => out.write_char('_')?,
+            _ => {}
+        }
+        if c.is_block() {
+            self.separator = Separator::Blank;
+        }
+        Ok(())
+    }
+
+    fn render_epilogue<W>(&mut self, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        if !self.references.is_empty() {
+            self.block(&mut out)?;
+            out.write_str("----")?;
+            let references = std::mem::take(&mut self.references);
+            for (i, label) in references.iter().enumerate() {
+                let events = self
+                    .footnotes
+                    .iter()
+                    .position(|(l, _)| l == label)
+                    .map(|i| self.footnotes.remove(i).1)
+                    .unwrap_or_default();
+                write!(out, "\n^{}^ ", i + 1)?;
+                self.separator = Separator::None;
+                for e in &events {
+                    self.render_event(e, &mut out)?;
+                }
+            }
+            self.separator = Separator::Blank;
+        }
+        if self.separator != Separator::None {
+            out.write_char('\n')?;
+        }
+        Ok(())
+    }
+
+    /// Start a block, separating it from the previous one.
+    fn block<W>(&mut self, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        match self.separator {
+            Separator::None => {}
+            Separator::Line => out.write_char('\n')?,
+            // lines within a list item are not supported, so continue after a line break
+            Separator::Blank if !self.list.is_empty() => out.write_str(" \\\\ ")?,
+            Separator::Blank => out.write_str("\n\n")?,
+        }
+        self.separator = Separator::Blank;
+        self.line_start = true;
+        Ok(())
+    }
+
+    /// Write text, with characters that would be interpreted as markup escaped.
+    fn write_text<W>(&mut self, s: &str, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        let mut chars = s.chars().peekable();
+        let mut prev = None;
+        let mut rest = s;
+        while let Some(c) = chars.next() {
+            rest = &rest[c.len_utf8()..];
+            let line_start = std::mem::replace(&mut self.line_start, false);
+            let escape = match c {
+                '\\' | '{' | '}' | '[' | ']' | '|' | '!' | '*' | '_' | '+' | '^' | '~' => true,
+                // hyphens within words do not strike through text
+                '-' => {
+                    !prev.map_or(false, char::is_alphanumeric)
+                        || !chars.peek().map_or(false, |c| c.is_alphanumeric())
+                }
+                '?' => chars.peek() == Some(&'?'),
+                // e.g. `h1.` for headings
+                'h' => line_start && matches!(rest.as_bytes(), [b'1'..=b'6', b'.', ..]),
+                '#' => line_start,
+                _ => false,
+            };
+            if escape {
+                out.write_char('\\')?;
+            }
+            out.write_char(c)?;
+            prev = Some(c);
+        }
+        Ok(())
+    }
+}
+
+/// Write a URL, with characters that would end a link or an image percent-encoded.
+fn write_url<W>(url: &str, mut out: W) -> std::fmt::Result
+where
+    W: std::fmt::Write,
+{
+    for c in url.chars() {
+        match c {
+            '|' => out.write_str("%7C")?,
+            ']' => out.write_str("%5D")?,
+            '!' => out.write_str("%21")?,
+            c => out.write_char(c)?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::render_to_string;
+
+    #[test]
+    fn blocks() {
+        let src = concat!(
+            "> a\n",
+            "\n",
+            "- b\n",
+            "\n",
+            "  c\n",
+            "\n",
+            "  1. d\n",
+            "- [x] e\n",
+            "\n",
+            "| f | g |\n",
+            "|---|---|\n",
+            "| h | i |\n",
+            "\n",
+            ": j\n",
+            "\n",
+            "  k\n",
+            "\n",
+            "***\n",
+        );
+        assert_eq!(
+            render_to_string(crate::Parser::new(src)),
+            concat!(
+                "{quote}\n",
+                "a\n",
+                "{quote}\n",
+                "\n",
+                "* b \\\\ c\n",
+                "*# d\n",
+                "\n",
+                "* ☑ e\n",
+                "\n",
+                "||f||g||\n",
+                "|h|i|\n",
+                "\n",
+                "*j*\n",
+                "\n",
+                "k\n",
+                "\n",
+                "----\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn inline() {
+        let src = concat!(
+            "h1. x-y -z [a](b.html?c|d) <c@d> ![e](f!.png) {+g+}[^h] ~i~ $`j`\n",
+            "\n",
+            "h2\n",
+            "\n",
+            "[^h]: k\n",
+        );
+        assert_eq!(
+            render_to_string(crate::Parser::new(src)),
+            concat!(
+                "\\h1. x-y \\-z [a|b.html?c%7Cd] [mailto:c@d] !f%21.png! +g+^1^ ~i~ {{j}}\n",
+                "\n",
+                "h2\n",
+                "\n",
+                "----\n",
+                "^1^ k\n",
+            ),
+        );
+    }
+}
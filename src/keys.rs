@@ -0,0 +1,122 @@
+//! Stable keys of blocks.
+//!
+//! Frontends that keep a virtual DOM of the preview patch it with the changes of each render,
+//! rather than replacing all of it. The [`Keys`] adapter helps matching the elements of
+//! consecutive renders by adding a `data-key` attribute to each block element, except sections.
+//! The key is derived from the content of the block, so that it is unchanged as long as the
+//! block is, regardless of edits elsewhere in the document. Identical blocks are distinguished by
+//! a suffix with their number among them, e.g. `-1`.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! use jotdown::keys::Keys;
+//!
+//! let key = |src: &str, i: usize| {
+//!     let html = jotdown::html::render_to_string(Keys::new(jotdown::Parser::new(src)));
+//!     html.split("data-key=\"").nth(i + 1).unwrap().split('"').next().unwrap().to_string()
+//! };
+//! let para = key("a\n\nb\n", 1);
+//! assert_eq!(key("b\n", 0), para);
+//! assert_eq!(key("b\n\nb\n", 1), format!("{}-1", para));
+//! assert_ne!(key("c\n\na\n", 1), para);
+//! # }
+//! ```
+
+use crate::AttributeKind;
+use crate::Container;
+use crate::Event;
+use crate::Map;
+
+/// An iterator adapter that adds stable keys of blocks as attributes.
+///
+/// The events are buffered, as the key of a block depends on all of its content.
+pub struct Keys<'s> {
+    events: std::vec::IntoIter<Event<'s>>,
+    /// Keys of the blocks, in order.
+    keys: std::vec::IntoIter<String>,
+}
+
+impl<'s> Keys<'s> {
+    /// Collect the events of a document and compute the keys of its blocks.
+    pub fn new<I: IntoIterator<Item = Event<'s>>>(events: I) -> Self {
+        let events: Vec<Event<'s>> = events.into_iter().collect();
+        let mut keys = Vec::new();
+        let mut open: Vec<(usize, usize)> = Vec::new();
+        let mut count: Map<u64, usize> = Map::new();
+        for (i, e) in events.iter().enumerate() {
+            match e {
+                Event::Start(c, _) if is_keyed(c) => {
+                    open.push((i, keys.len()));
+                    keys.push(String::new());
+                }
+                Event::End(c) if is_keyed(c) => {
+                    let (start, k) = open.pop().unwrap();
+                    let hash = crate::record::hash(&events[start..=i]);
+                    let n = count.entry(hash).or_insert(0);
+                    keys[k] = if *n == 0 {
+                        format!("{:016x}", hash)
+                    } else {
+                        format!("{:016x}-{}", hash, n)
+                    };
+                    *n += 1;
+                }
+                _ => {}
+            }
+        }
+        Self {
+            events: events.into_iter(),
+            keys: keys.into_iter(),
+        }
+    }
+}
+
+impl<'s> Iterator for Keys<'s> {
+    type Item = Event<'s>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(match self.events.next()? {
+            Event::Start(c, mut attrs) if is_keyed(&c) => {
+                let key = self.keys.next().unwrap_or_default();
+                attrs.push((AttributeKind::Pair { key: "data-key" }, key.into()));
+                Event::Start(c, attrs)
+            }
+            e => e,
+        })
+    }
+}
+
+fn is_keyed(c: &Container) -> bool {
+    c.is_block() && !matches!(c, Container::Section { .. })
+}
+
+#[cfg(test)]
+mod test {
+    use super::Keys;
+    use crate::Event;
+
+    fn keys(src: &str) -> Vec<String> {
+        Keys::new(crate::Parser::new(src))
+            .filter_map(|e| match e {
+                Event::Start(_, attrs) => attrs.get_value("data-key").map(|v| v.to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn stable() {
+        let a = keys("# x\n\n- y\n- z\n\nw\n");
+        let b = keys("# x\n\n- y\n- z!\n\nv\n\nw\n");
+        assert_eq!((a.len(), b.len()), (7, 8));
+        // the heading, the first item and the last paragraph are unchanged
+        assert_eq!(a[0], b[0]);
+        assert_eq!(a[2..4], b[2..4]);
+        assert_eq!(a[6], b[7]);
+        // the list and its second item are changed
+        assert_ne!(a[1], b[1]);
+        assert_ne!(a[4], b[4]);
+    }
+}
@@ -1,64 +1,168 @@
+//! Tokens of inline markup.
+//!
+//! The parser splits the content of leaf blocks, e.g. paragraphs and headings, into tokens
+//! before matching them into inline elements. The tokens are exposed for tools that operate
+//! below the level of events, e.g. formatters, syntax highlighters and linters. A token only
+//! describes a sequence of characters that may be significant, e.g. an asterisk that may or may
+//! not start strong emphasis. Block structure, e.g. list markers or fences, is not tokenized.
+//!
+//! # Examples
+//!
+//! ```
+//! use jotdown::lex::{self, Delimiter, Kind, Symbol};
+//!
+//! let tokens: Vec<_> = lex::tokens("*a* {-b-}").collect();
+//! assert_eq!(
+//!     tokens,
+//!     &[
+//!         (Kind::Sym(Symbol::Asterisk), 0..1),
+//!         (Kind::Text, 1..2),
+//!         (Kind::Sym(Symbol::Asterisk), 2..3),
+//!         (Kind::Text, 3..4),
+//!         (Kind::Open(Delimiter::BraceHyphen), 4..6),
+//!         (Kind::Text, 6..7),
+//!         (Kind::Close(Delimiter::BraceHyphen), 7..9),
+//!     ],
+//! );
+//! ```
+
+use std::ops::Range;
+
 use Delimiter::*;
 use Kind::*;
 use Sequence::*;
 use Symbol::*;
 
+/// A token, i.e. a kind and the length of its source.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct Token {
+    /// Kind of the token.
     pub kind: Kind,
+    /// Length of the token in bytes.
     pub len: usize,
 }
 
+/// Kind of a token.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Kind {
+    /// Characters without special meaning, including escaped characters.
     Text,
+    /// A line break.
     Newline,
+    /// An escaped space, i.e. a non-breaking space.
     Nbsp,
+    /// An escaped line break, including any whitespace before it.
     Hardbreak,
+    /// A backslash that escapes the following character.
     Escape,
+    /// An opening delimiter, e.g. `[` or `{-`.
     Open(Delimiter),
+    /// A closing delimiter, e.g. `]` or `-}`.
     Close(Delimiter),
+    /// A symbol, e.g. `*` or `|`.
     Sym(Symbol),
+    /// A sequence of one or more of the same character, e.g. ```` ``` ````.
     Seq(Sequence),
 }
 
+/// A delimiter that may open or close an inline element.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Delimiter {
+    /// `{` or `}`.
     Brace,
+    /// `{*` or `*}`.
     BraceAsterisk,
+    /// `{^` or `^}`.
     BraceCaret,
+    /// `{=` or `=}`.
     BraceEqual,
+    /// `{-` or `-}`.
     BraceHyphen,
+    /// `{+` or `+}`.
     BracePlus,
+    /// `{~` or `~}`.
     BraceTilde,
+    /// `{_` or `_}`.
     BraceUnderscore,
+    /// `[` or `]`.
     Bracket,
+    /// `{'` or `'}`.
     BraceQuote1,
+    /// `{"` or `"}`.
     BraceQuote2,
+    /// `(` or `)`.
     Paren,
 }
 
+/// A symbol that may delimit or start an inline element.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Symbol {
+    /// `*`.
     Asterisk,
+    /// `^`.
     Caret,
+    /// `![`.
     ExclaimBracket,
+    /// `<`.
     Lt,
+    /// `|`.
     Pipe,
+    /// `'`.
     Quote1,
+    /// `"`.
     Quote2,
+    /// `~`.
     Tilde,
+    /// `_`.
     Underscore,
+    /// `:`.
     Colon,
 }
 
+/// The character of a [`Kind::Seq`] token.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Sequence {
+    /// `` ` ``.
     Backtick,
+    /// `-`.
     Hyphen,
+    /// `.`.
     Period,
 }
 
+/// Tokenize inline markup.
+///
+/// Each token is yielded with its location in `src`. The tokens are contiguous and cover all
+/// of `src`.
+pub fn tokens(src: &str) -> Tokens<'_> {
+    Tokens {
+        lexer: Lexer::new(src.as_bytes()),
+        pos: 0,
+    }
+}
+
+/// An iterator over the tokens of inline markup, see [`tokens`].
+#[derive(Clone)]
+pub struct Tokens<'s> {
+    lexer: Lexer<'s>,
+    pos: usize,
+}
+
+impl Iterator for Tokens<'_> {
+    type Item = (Kind, Range<usize>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Token { kind, len } = self.lexer.next()?;
+        let start = self.pos;
+        self.pos += len;
+        Some((kind, start..self.pos))
+    }
+}
+
 impl Sequence {
     fn ch(self) -> u8 {
         match self {
@@ -406,6 +510,16 @@ mod test {
         );
     }
 
+    #[test]
+    fn tokens() {
+        let src = "a\\ *b*\\\n{=c=} ```";
+        let tokens: Vec<_> = super::tokens(src).collect();
+        assert_eq!(tokens.last().map(|(_, r)| r.end), Some(src.len()));
+        assert!(tokens.windows(2).all(|w| w[0].1.end == w[1].1.start));
+        assert_eq!(tokens[1], (Escape, 1..2));
+        assert_eq!(tokens[2], (Nbsp, 2..3));
+    }
+
     #[test]
     fn seq() {
         test_lex!("`", Seq(Backtick).l(1));
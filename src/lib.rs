@@ -6,7 +6,14 @@
 //!
 //! # Feature flags
 //!
+//! - `bbcode`: build the [`bbcode`] module, a renderer of BBCode.
+//! - `emoji`: build the [`emoji`] module, with a table of emoji shortcodes.
 //! - `html` (default): build the html module and a binary that converts djot to HTML.
+//! - `jira`: build the [`jira`] module, a renderer of Jira and Confluence wiki markup.
+//! - `mediawiki`: build the [`mediawiki`] module, a renderer of MediaWiki markup.
+//! - `serde`: implement deserialization of [`citation::Reference`] from e.g. CSL-JSON, and build
+//!   the [`front_matter`] module.
+//! - `testing`: build the `testing` module, with helpers for snapshot tests.
 //!
 //! # Examples
 //!
@@ -48,13 +55,67 @@ use std::fmt::Write as FmtWrite;
 use std::io;
 use std::ops::Range;
 
+#[cfg(feature = "bbcode")]
+pub mod bbcode;
+#[cfg(feature = "emoji")]
+pub mod emoji;
 #[cfg(feature = "html")]
 pub mod html;
+#[cfg(feature = "jira")]
+pub mod jira;
+#[cfg(feature = "mediawiki")]
+pub mod mediawiki;
+
+pub mod abbr;
+pub mod alerts;
+pub mod assets;
+pub mod batch;
+pub mod book;
+#[cfg(feature = "html")]
+pub mod cache;
+pub mod check;
+pub mod citation;
+pub mod conditional;
+pub mod diff;
+pub mod excerpt;
+pub mod extension;
+#[cfg(feature = "serde")]
+pub mod front_matter;
+pub mod ids;
+pub mod keys;
+pub mod lex;
+pub mod line_block;
+#[cfg(feature = "html")]
+pub mod notebook;
+pub mod outline;
+#[cfg(feature = "html")]
+pub mod page;
+pub mod positions;
+pub mod prose;
+pub mod readability;
+pub mod record;
+pub mod ruby;
+pub mod search;
+pub mod search_index;
+pub mod shortcode;
+#[cfg(feature = "html")]
+pub mod slides;
+pub mod snippet;
+pub mod social;
+pub mod split;
+pub mod stats;
+pub mod tangle;
+pub mod tasks;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod validate;
+pub mod variables;
+pub mod wiki;
+pub mod xref;
 
 mod attr;
 mod block;
 mod inline;
-mod lex;
 
 pub use attr::{
     AttributeKind, AttributeValue, AttributeValueParts, Attributes, ParseAttributesError,
@@ -623,6 +684,46 @@ pub enum Event<'s> {
     Attributes(Attributes<'s>),
 }
 
+impl Event<'_> {
+    /// A number that identifies the kind of the event, e.g. for serialization or foreign
+    /// function interfaces.
+    ///
+    /// The tags are stable, a kind of event keeps its tag across versions of the crate and new
+    /// kinds of events are given new tags.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// assert_eq!(Event::Str("a".into()).tag(), 2);
+    /// assert_eq!(Event::Softbreak.tag(), 13);
+    /// ```
+    #[must_use]
+    pub fn tag(&self) -> u8 {
+        match self {
+            Self::Start(..) => 0,
+            Self::End(..) => 1,
+            Self::Str(..) => 2,
+            Self::FootnoteReference(..) => 3,
+            Self::Symbol(..) => 4,
+            Self::LeftSingleQuote => 5,
+            Self::RightSingleQuote => 6,
+            Self::LeftDoubleQuote => 7,
+            Self::RightDoubleQuote => 8,
+            Self::Ellipsis => 9,
+            Self::EnDash => 10,
+            Self::EmDash => 11,
+            Self::NonBreakingSpace => 12,
+            Self::Softbreak => 13,
+            Self::Hardbreak => 14,
+            Self::Escape => 15,
+            Self::Blankline => 16,
+            Self::ThematicBreak(..) => 17,
+            Self::Attributes(..) => 18,
+        }
+    }
+}
+
 /// A container that may contain other elements.
 ///
 /// There are three types of containers:
@@ -1722,9 +1823,121 @@ pub enum Container<'s> {
     /// assert_eq!(&html::render_to_string(events.into_iter()), html);
     /// ```
     Mark,
+    /// An inline element recognized by a syntax extension, see the [`extension`] module.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::extension::Extensions;
+    /// let src = "++SPOILER++";
+    /// let extensions = Extensions::new().with_delimited("spoiler", "++", "++");
+    /// let events: Vec<_> = Parser::with_extensions(src, &extensions).collect();
+    /// assert_eq!(
+    ///     &events,
+    ///     &[
+    ///         Event::Start(Container::Paragraph, Attributes::new()),
+    ///         Event::Start(Container::CustomInline { name: "spoiler" }, Attributes::new()),
+    ///         Event::Str("SPOILER".into()),
+    ///         Event::End(Container::CustomInline { name: "spoiler" }),
+    ///         Event::End(Container::Paragraph),
+    ///     ],
+    /// );
+    /// let html = "<p><span class=\"spoiler\">SPOILER</span></p>\n";
+    /// assert_eq!(&html::render_to_string(events.into_iter()), html);
+    /// ```
+    CustomInline { name: &'s str },
+    /// A block element recognized by a syntax extension, see the [`extension`] module.
+    ///
+    /// The argument is the rest of the first line of the block. The content of a fenced block is
+    /// text, like that of a code block, while a directive has no content. Without a hook, the
+    /// HTML renderer writes the block as a div with the name as class and the argument, if any,
+    /// as a `data-argument` attribute.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::extension::Extensions;
+    /// let src = concat!(
+    ///     ".. warning:: Beware\n",
+    ///     "\n",
+    ///     "text\n",
+    /// );
+    /// let extensions = Extensions::new().with_directive("warning", ".. warning::");
+    /// let events: Vec<_> = Parser::with_extensions(src, &extensions).collect();
+    /// assert_eq!(
+    ///     &events[..2],
+    ///     &[
+    ///         Event::Start(
+    ///             Container::CustomBlock { name: "warning", argument: "Beware" },
+    ///             Attributes::new(),
+    ///         ),
+    ///         Event::End(Container::CustomBlock { name: "warning", argument: "Beware" }),
+    ///     ],
+    /// );
+    /// let html = concat!(
+    ///     "<div class=\"warning\" data-argument=\"Beware\"></div>\n",
+    ///     "<p>text</p>\n",
+    /// );
+    /// assert_eq!(&html::render_to_string(events.into_iter()), html);
+    /// ```
+    CustomBlock { name: &'s str, argument: &'s str },
 }
 
 impl Container<'_> {
+    /// A number that identifies the kind of the container, regardless of its fields.
+    ///
+    /// Like the tags of events, see [`Event::tag`], the tags are stable across versions of the
+    /// crate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// assert_eq!(Container::Paragraph.tag(), 11);
+    /// assert_eq!(Container::Div { class: "note" }.tag(), Container::Div { class: "" }.tag());
+    /// ```
+    #[must_use]
+    pub fn tag(&self) -> u8 {
+        match self {
+            Self::Blockquote => 0,
+            Self::List { .. } => 1,
+            Self::ListItem => 2,
+            Self::TaskListItem { .. } => 3,
+            Self::DescriptionList => 4,
+            Self::DescriptionDetails => 5,
+            Self::Footnote { .. } => 6,
+            Self::Table => 7,
+            Self::TableRow { .. } => 8,
+            Self::Section { .. } => 9,
+            Self::Div { .. } => 10,
+            Self::Paragraph => 11,
+            Self::Heading { .. } => 12,
+            Self::TableCell { .. } => 13,
+            Self::Caption => 14,
+            Self::DescriptionTerm => 15,
+            Self::LinkDefinition { .. } => 16,
+            Self::RawBlock { .. } => 17,
+            Self::CodeBlock { .. } => 18,
+            Self::Span => 19,
+            Self::Link(..) => 20,
+            Self::Image(..) => 21,
+            Self::Verbatim => 22,
+            Self::Math { .. } => 23,
+            Self::RawInline { .. } => 24,
+            Self::Subscript => 25,
+            Self::Superscript => 26,
+            Self::Insert => 27,
+            Self::Delete => 28,
+            Self::Strong => 29,
+            Self::Emphasis => 30,
+            Self::Mark => 31,
+            Self::CustomInline { .. } => 32,
+            Self::CustomBlock { .. } => 33,
+        }
+    }
+
     /// Is a block element.
     #[must_use]
     pub fn is_block(&self) -> bool {
@@ -1747,7 +1960,8 @@ impl Container<'_> {
             | Self::DescriptionTerm
             | Self::LinkDefinition { .. }
             | Self::RawBlock { .. }
-            | Self::CodeBlock { .. } => true,
+            | Self::CodeBlock { .. }
+            | Self::CustomBlock { .. } => true,
             Self::Span
             | Self::Link(..)
             | Self::Image(..)
@@ -1760,7 +1974,8 @@ impl Container<'_> {
             | Self::Delete
             | Self::Strong
             | Self::Emphasis
-            | Self::Mark => false,
+            | Self::Mark
+            | Self::CustomInline { .. } => false,
         }
     }
 
@@ -1787,6 +2002,7 @@ impl Container<'_> {
             | Self::LinkDefinition { .. }
             | Self::RawBlock { .. }
             | Self::CodeBlock { .. }
+            | Self::CustomBlock { .. }
             | Self::Span
             | Self::Link(..)
             | Self::Image(..)
@@ -1799,7 +2015,8 @@ impl Container<'_> {
             | Self::Delete
             | Self::Strong
             | Self::Emphasis
-            | Self::Mark => false,
+            | Self::Mark
+            | Self::CustomInline { .. } => false,
         }
     }
 }
@@ -1996,6 +2213,17 @@ type Set<T> = std::collections::HashSet<T>;
 #[cfg(feature = "deterministic")]
 type Set<T> = std::collections::BTreeSet<T>;
 
+/// Check if a URL starts with a scheme, e.g. `https:`.
+pub(crate) fn has_scheme(url: &str) -> bool {
+    url.find(':').map_or(false, |i| {
+        let scheme = &url[..i];
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
 /// A parser that generates [`Event`]s from a Djot document.
 ///
 /// When created, it will perform an initial pass and build up a tree of the document's block
@@ -2240,8 +2468,18 @@ impl<'s> PrePass<'s> {
 impl<'s> Parser<'s> {
     #[must_use]
     pub fn new(src: &'s str) -> Self {
-        let blocks = block::parse(src);
+        Self::with_extensions(src, &extension::Extensions::default())
+    }
+
+    /// Create a parser that also recognizes the syntax of the provided extensions.
+    ///
+    /// See the [`extension`] module for more information.
+    #[must_use]
+    pub fn with_extensions(src: &'s str, extensions: &extension::Extensions) -> Self {
+        let blocks = block::parse(src, &extensions.block);
         let mut inline_parser = inline::Parser::new(src);
+        inline_parser.extensions = extensions.inline.clone();
+        inline_parser.disabled = extensions.disabled.clone();
         let pre_pass = PrePass::new(src, blocks.iter(), &mut inline_parser);
 
         Self {
@@ -2363,6 +2601,48 @@ impl<'s> Parser<'s> {
         OffsetIter { parser: self }
     }
 
+    /// Parse events into `events` until the provided budget is exhausted, in order to bound the
+    /// work of each call, e.g. to keep an interactive application responsive while parsing a
+    /// large document.
+    ///
+    /// The parser itself is the continuation: another call resumes where the previous one
+    /// paused. At least one event is parsed per call, unless the end of the input has been
+    /// reached. Note that the block structure of the document is parsed up front by
+    /// [`Parser::new`], the budget only bounds the remaining inline parsing and the emission of
+    /// events.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// let mut parser = Parser::new("a\n\nb\n");
+    /// let budget = Budget::new().with_events(4);
+    /// let mut events = Vec::new();
+    /// assert_eq!(parser.parse_within(budget, &mut events), Progress::Paused);
+    /// assert_eq!(events.len(), 4);
+    /// assert_eq!(parser.parse_within(budget, &mut events), Progress::Done);
+    /// assert_eq!(events, Parser::new("a\n\nb\n").collect::<Vec<_>>());
+    /// ```
+    pub fn parse_within(&mut self, budget: Budget, events: &mut Vec<Event<'s>>) -> Progress {
+        let (mut n, mut bytes) = (0, 0);
+        loop {
+            // always parse at least one event, so that each call makes progress
+            if n > 0
+                && (budget.events.map_or(false, |max| n >= max)
+                    || budget.bytes.map_or(false, |max| bytes >= max))
+            {
+                return Progress::Paused;
+            }
+            if let Some((e, span)) = self.next_span() {
+                events.push(e);
+                n += 1;
+                bytes += span.len();
+            } else {
+                return Progress::Done;
+            }
+        }
+    }
+
     fn inline(&mut self) -> Option<(Event<'s>, Range<usize>)> {
         let next = self.inline_parser.next()?;
 
@@ -2430,6 +2710,7 @@ impl<'s> Parser<'s> {
                                 Container::Image(url_or_tag, ty)
                             }
                         }
+                        inline::Container::Custom { name } => Container::CustomInline { name },
                         inline::Container::Autolink(url) => {
                             let ty = if url.contains('@') {
                                 LinkType::Email
@@ -2561,6 +2842,10 @@ impl<'s> Parser<'s> {
                                     self.verbatim = enter;
                                     Container::LinkDefinition { label }
                                 }
+                                block::Leaf::Custom { name, argument } => {
+                                    self.verbatim = enter;
+                                    Container::CustomBlock { name, argument }
+                                }
                             }
                         }
                         block::Node::Container(c) => match c {
@@ -2676,6 +2961,47 @@ impl<'s> Iterator for Parser<'s> {
     }
 }
 
+/// A limit on the work of a call to [`Parser::parse_within`].
+///
+/// An empty budget is unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Budget {
+    events: Option<usize>,
+    bytes: Option<usize>,
+}
+
+impl Budget {
+    /// Create an unlimited budget.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse at most `n` events, or a single event if `n` is zero.
+    #[must_use]
+    pub fn with_events(mut self, n: usize) -> Self {
+        self.events = Some(n.max(1));
+        self
+    }
+
+    /// Stop parsing when the events cover at least `n` bytes of the input, or after a single
+    /// event if `n` is zero.
+    #[must_use]
+    pub fn with_bytes(mut self, n: usize) -> Self {
+        self.bytes = Some(n);
+        self
+    }
+}
+
+/// The state of a [`Parser`] after a call to [`Parser::parse_within`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Progress {
+    /// The budget was exhausted, there may be more events.
+    Paused,
+    /// All events have been parsed.
+    Done,
+}
+
 /// An iterator that is identical to a [`Parser`], except that it also emits the location of each
 /// event within the input.
 ///
@@ -4347,4 +4673,24 @@ mod test {
         assert_eq!(AlphaUpper.parse_number("Z"), 26);
         assert_eq!(AlphaLower.parse_number("aa"), 27);
     }
+
+    #[test]
+    fn budget_bytes() {
+        let src = "abc\n\ndef\n";
+        let mut parser = super::Parser::new(src);
+        let budget = super::Budget::new().with_bytes(3);
+        let mut events = Vec::new();
+        let mut calls = 0;
+        while parser.parse_within(budget, &mut events) == super::Progress::Paused {
+            calls += 1;
+        }
+        assert_eq!(calls, 2);
+        assert_eq!(events, super::Parser::new(src).collect::<Vec<_>>());
+
+        let mut parser = super::Parser::new(src);
+        let budget = super::Budget::new().with_bytes(0);
+        let mut events = Vec::new();
+        while parser.parse_within(budget, &mut events) == super::Progress::Paused {}
+        assert_eq!(events, super::Parser::new(src).collect::<Vec<_>>());
+    }
 }
@@ -0,0 +1,154 @@
+//! Preservation of line breaks.
+//!
+//! Line breaks within paragraphs are soft breaks, which are usually rendered as spaces. For
+//! poetry, addresses and similar content, the [`LineBlocks`] adapter turns soft breaks into hard
+//! breaks within elements with the class `line-block`, or with any other registered class. It
+//! may also be applied to a whole document.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! use jotdown::line_block::LineBlocks;
+//!
+//! let src = concat!(
+//!     "::: line-block\n",
+//!     "Roses are red,\n",
+//!     "violets are blue.\n",
+//!     ":::\n",
+//!     "\n",
+//!     "Soft\n",
+//!     "break.\n",
+//! );
+//! let events = LineBlocks::new(jotdown::Parser::new(src));
+//! assert_eq!(
+//!     jotdown::html::render_to_string(events),
+//!     concat!(
+//!         "<div class=\"line-block\">\n",
+//!         "<p>Roses are red,<br>\n",
+//!         "violets are blue.</p>\n",
+//!         "</div>\n",
+//!         "<p>Soft\n",
+//!         "break.</p>\n",
+//!     ),
+//! );
+//! # }
+//! ```
+
+use crate::Attributes;
+use crate::Container;
+use crate::Event;
+
+/// An iterator adapter that preserves line breaks within line blocks.
+pub struct LineBlocks<I> {
+    events: I,
+    classes: Vec<String>,
+    all: bool,
+    /// Nesting depth of containers within the outermost line block.
+    depth: usize,
+}
+
+impl<'s, I> LineBlocks<I>
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    /// Create an adapter that preserves line breaks within elements with the class
+    /// `line-block`.
+    pub fn new(events: I) -> Self {
+        Self {
+            events,
+            classes: vec!["line-block".to_string()],
+            all: false,
+            depth: 0,
+        }
+    }
+
+    /// Also preserve line breaks within elements with the specified class, e.g. `poem`.
+    #[must_use]
+    pub fn with_class(mut self, class: &str) -> Self {
+        self.classes.push(class.to_string());
+        self
+    }
+
+    /// Preserve line breaks within the whole document.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::line_block::LineBlocks;
+    /// # use jotdown::Event;
+    /// let events: Vec<_> = LineBlocks::new(jotdown::Parser::new("a\nb")).with_all().collect();
+    /// assert_eq!(events[2], Event::Hardbreak);
+    /// ```
+    #[must_use]
+    pub fn with_all(mut self) -> Self {
+        self.all = true;
+        self
+    }
+
+    /// Returns `true` if the container has one of the classes of line blocks.
+    fn is_line_block(&self, c: &Container, attrs: &Attributes) -> bool {
+        let div_class = if let Container::Div { class } = c {
+            class
+        } else {
+            ""
+        };
+        let classes = attrs
+            .get_value("class")
+            .map_or_else(String::new, |c| c.to_string());
+        self.classes
+            .iter()
+            .any(|class| class == div_class || classes.split(' ').any(|c| c == class))
+    }
+}
+
+impl<'s, I> Iterator for LineBlocks<I>
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    type Item = Event<'s>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let e = self.events.next()?;
+        match &e {
+            Event::Start(c, attrs) if self.depth > 0 || self.is_line_block(c, attrs) => {
+                self.depth += 1;
+            }
+            Event::End(..) => self.depth = self.depth.saturating_sub(1),
+            Event::Softbreak if self.all || self.depth > 0 => return Some(Event::Hardbreak),
+            _ => {}
+        }
+        Some(e)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LineBlocks;
+    use crate::Event;
+
+    fn breaks(events: impl Iterator<Item = Event<'static>>) -> String {
+        events
+            .filter_map(|e| match e {
+                Event::Softbreak => Some('s'),
+                Event::Hardbreak => Some('h'),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn classes() {
+        let src = "{.poem}\na\nb\n\n::: x\n[c\nd]{.line-block}\ne\nf\n:::\n\ng\nh\n";
+        assert_eq!(breaks(LineBlocks::new(crate::Parser::new(src))), "shsss");
+        assert_eq!(
+            breaks(LineBlocks::new(crate::Parser::new(src)).with_class("poem")),
+            "hhsss"
+        );
+        assert_eq!(
+            breaks(LineBlocks::new(crate::Parser::new(src)).with_class("x")),
+            "shhhs"
+        );
+    }
+}
@@ -1,7 +1,11 @@
+#[cfg(feature = "serde")]
+use std::collections::BTreeMap;
 use std::ffi::OsString;
 use std::fs::File;
 use std::io::BufWriter;
 use std::io::Read;
+use std::path::Path;
+use std::path::PathBuf;
 use std::process::exit;
 
 use jotdown::Render;
@@ -13,6 +17,15 @@ struct App {
     minified: bool,
     start_indent: usize,
     indent_string: String,
+    template: Option<OsString>,
+    /// Convert files and directories with the `build` subcommand.
+    build: Option<Build>,
+}
+
+#[derive(Default)]
+struct Build {
+    inputs: Vec<OsString>,
+    jobs: Option<usize>,
 }
 
 fn parse_args() -> App {
@@ -20,6 +33,11 @@ fn parse_args() -> App {
 
     let mut args = std::env::args_os().skip(1).peekable();
 
+    if args.peek().map_or(false, |a| a == "build") {
+        app.build = Some(Build::default());
+        args.next();
+    }
+
     while let Some(arg) = args.next() {
         match (arg.to_string_lossy().as_ref(), args.peek()) {
             ("-h" | "--help", _) => {
@@ -41,6 +59,16 @@ fn parse_args() -> App {
                 }
             },
             ("--minified", _) => app.minified = true,
+            (flag @ "--template", t) => match t {
+                Some(t) => {
+                    app.template = Some(t.into());
+                    args.next();
+                }
+                None => {
+                    eprintln!("please supply an argument to {}", flag);
+                    exit(1);
+                }
+            },
             (flag @ "--indent-string", s) => {
                 if let Some(s) = s {
                     app.indent_string = s.to_string_lossy().into_owned();
@@ -50,6 +78,24 @@ fn parse_args() -> App {
                     exit(1);
                 }
             }
+            (flag @ ("-j" | "--jobs"), n) if app.build.is_some() => {
+                if let Some(n) = n {
+                    if let Ok(n) = n.to_string_lossy().parse() {
+                        app.build.as_mut().unwrap().jobs = Some(n);
+                    } else {
+                        eprintln!(
+                            "{} expected a non-negative integer, got '{}'",
+                            flag,
+                            n.to_string_lossy(),
+                        );
+                        exit(1);
+                    }
+                    args.next();
+                } else {
+                    eprintln!("please supply an argument to {}", flag);
+                    exit(1);
+                }
+            }
             (flag @ "--start-indent", s) => {
                 if let Some(s) = s {
                     if let Ok(n) = s.to_string_lossy().parse() {
@@ -68,7 +114,10 @@ fn parse_args() -> App {
                     exit(1);
                 }
             }
-            ("-", _) => {}
+            ("-", _) if app.build.is_none() => {}
+            (file, _) if !file.starts_with('-') && app.build.is_some() => {
+                app.build.as_mut().unwrap().inputs.push(file.into());
+            }
             (file, _) if !file.starts_with('-') => {
                 if app.input.is_some() {
                     eprint!("too many arguments\n\n{}", include_str!("./help.txt"));
@@ -86,10 +135,350 @@ fn parse_args() -> App {
     app
 }
 
+fn renderer(app: &App) -> jotdown::html::Renderer {
+    if app.minified {
+        jotdown::html::Renderer::minified()
+    } else {
+        jotdown::html::Renderer::indented(jotdown::html::Indentation {
+            string: app.indent_string.clone(),
+            initial_level: app.start_indent,
+        })
+    }
+}
+
+/// A value of the front matter, as text.
+#[cfg(feature = "serde")]
+struct Field(String);
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Field {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = Field;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a value or a list of values")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Field, E> {
+                Ok(Field(v.to_string()))
+            }
+
+            fn visit_unit<E>(self) -> Result<Field, E> {
+                Ok(Field(String::new()))
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> Result<Field, A::Error> {
+                let mut items = Vec::new();
+                while let Some(Field(item)) = seq.next_element()? {
+                    items.push(item);
+                }
+                Ok(Field(items.join(", ")))
+            }
+        }
+
+        // scalars are given as written, lists and null values fall back to their own types
+        d.deserialize_str(Visitor)
+    }
+}
+
+/// Values of the front matter, by key.
+#[cfg(feature = "serde")]
+type Fields = BTreeMap<String, String>;
+#[cfg(not(feature = "serde"))]
+type Fields = Vec<(String, String)>;
+
+/// Split a document into its front matter, if any, and its body.
+#[cfg(feature = "serde")]
+fn front_matter(src: &str) -> Result<(Fields, &str), std::io::Error> {
+    let doc = jotdown::front_matter::Document::<BTreeMap<String, Field>>::parse(src)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    let fields = doc
+        .front_matter
+        .into_iter()
+        .map(|(k, Field(v))| (k, v))
+        .collect();
+    Ok((fields, doc.body))
+}
+
+/// Front matter is only supported with the `serde` feature, the document is used as is.
+#[cfg(not(feature = "serde"))]
+fn front_matter(src: &str) -> Result<(Fields, &str), std::io::Error> {
+    Ok((Vec::new(), src))
+}
+
+/// Render a document to a page by filling in the placeholders of a template.
+///
+/// `{{body}}` and `{{toc}}` are replaced by the rendered document and table of contents, other
+/// placeholders by the values of the front matter. `{{title}}` and `{{lang}}` default to the
+/// first heading and the document language. Unknown placeholders are removed.
+fn page(
+    template: &str,
+    src: &str,
+    renderer: &jotdown::html::Renderer,
+) -> Result<String, std::io::Error> {
+    let (fields, body) = front_matter(src)?;
+    let field = |name: &str| {
+        fields
+            .iter()
+            .find(|(k, _)| k.as_str() == name)
+            .map(|(_, v)| v.as_str())
+    };
+    let page = jotdown::page::Template::new(|page: &jotdown::page::Page<()>| {
+        let mut out = String::new();
+        let mut rest = template;
+        while let Some((i, len)) = rest
+            .find("{{")
+            .and_then(|i| rest[i..].find("}}").map(|len| (i, len)))
+        {
+            out.push_str(&rest[..i]);
+            let name = rest[i + 2..i + len].trim();
+            match name {
+                "body" => out.push_str(&page.body),
+                "toc" => out.push_str(&page.toc),
+                _ => {
+                    let value = field(name).or(match name {
+                        "title" => page.title.as_deref(),
+                        "lang" => page.lang.as_deref(),
+                        _ => None,
+                    });
+                    escape(value.unwrap_or(""), &mut out);
+                }
+            }
+            rest = &rest[i + len + 2..];
+        }
+        out.push_str(rest);
+        out
+    })
+    .with_renderer(renderer.clone());
+    Ok(page.render_with_lang(jotdown::Parser::new(body), (), field("lang")))
+}
+
+fn escape(s: &str, out: &mut String) {
+    for c in s.chars() {
+        match c {
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '&' => out.push_str("&amp;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+}
+
+/// Returns `true` if an input is a glob pattern rather than a path.
+fn is_pattern(s: &std::ffi::OsStr) -> bool {
+    s.to_string_lossy().contains(|c| c == '*' || c == '?')
+}
+
+/// Returns `true` if a name matches a pattern, where `*` matches any sequence of characters and
+/// `?` matches a single character.
+fn matches_name(pattern: &[char], name: &[char]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some('*'), _) => {
+            matches_name(&pattern[1..], name)
+                || (!name.is_empty() && matches_name(pattern, &name[1..]))
+        }
+        (Some('?'), Some(_)) => matches_name(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) if p == n => matches_name(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+
+/// Returns `true` if the components of a path match those of a pattern, where `**` matches any
+/// number of components.
+fn matches_path(pattern: &[Vec<char>], path: &[Vec<char>]) -> bool {
+    match (pattern.first(), path.first()) {
+        (None, None) => true,
+        (Some(p), _) if p.iter().collect::<String>() == "**" => {
+            matches_path(&pattern[1..], path)
+                || (!path.is_empty() && matches_path(pattern, &path[1..]))
+        }
+        (Some(p), Some(n)) => matches_name(p, n) && matches_path(&pattern[1..], &path[1..]),
+        _ => false,
+    }
+}
+
+/// Find all djot files of the inputs, paired with their paths relative to the output directory.
+///
+/// Files found in a directory or by a glob pattern are relative to the directory, or the
+/// directory of the pattern up to its first wildcard. Other files are relative to their common
+/// directory.
+fn sources(inputs: &[OsString]) -> Result<Vec<(PathBuf, PathBuf)>, std::io::Error> {
+    fn walk(
+        dir: &Path,
+        rel: &Path,
+        files: &mut Vec<(PathBuf, PathBuf)>,
+    ) -> Result<(), std::io::Error> {
+        let mut entries = std::fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+        entries.sort_by_key(std::fs::DirEntry::file_name);
+        for entry in entries {
+            let path = entry.path();
+            let rel = rel.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                walk(&path, &rel, files)?;
+            } else if path.extension().map_or(false, |e| e == "dj") {
+                files.push((path, rel));
+            }
+        }
+        Ok(())
+    }
+
+    fn chars(s: &std::ffi::OsStr) -> Vec<char> {
+        s.to_string_lossy().chars().collect()
+    }
+
+    let mut files = Vec::new();
+    let mut explicit = Vec::new();
+    for input in inputs {
+        let path = Path::new(input);
+        if path.is_dir() {
+            walk(path, Path::new(""), &mut files)?;
+        } else if is_pattern(input) {
+            let components = path.components().collect::<Vec<_>>();
+            let wildcard = components
+                .iter()
+                .position(|c| is_pattern(c.as_os_str()))
+                .unwrap();
+            let dir = components[..wildcard].iter().collect::<PathBuf>();
+            let dir = if dir.as_os_str().is_empty() {
+                PathBuf::from(".")
+            } else {
+                dir
+            };
+            let pattern = components[wildcard..]
+                .iter()
+                .map(|c| chars(c.as_os_str()))
+                .collect::<Vec<_>>();
+            let mut found = Vec::new();
+            if dir.is_dir() {
+                walk(&dir, Path::new(""), &mut found)?;
+            }
+            let n = files.len();
+            files.extend(found.into_iter().filter(|(_, rel)| {
+                let rel = rel
+                    .components()
+                    .map(|c| chars(c.as_os_str()))
+                    .collect::<Vec<_>>();
+                matches_path(&pattern, &rel)
+            }));
+            if files.len() == n {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("no djot files match {}", path.display()),
+                ));
+            }
+        } else {
+            explicit.push(path.to_path_buf());
+        }
+    }
+
+    let canonical = explicit
+        .iter()
+        .map(std::fs::canonicalize)
+        .collect::<Result<Vec<_>, _>>()?;
+    let root = canonical.iter().filter_map(|path| path.parent()).fold(
+        None,
+        |root: Option<PathBuf>, dir| {
+            Some(root.map_or_else(
+                || dir.to_path_buf(),
+                |root| {
+                    root.components()
+                        .zip(dir.components())
+                        .take_while(|(a, b)| a == b)
+                        .map(|(a, _)| a)
+                        .collect()
+                },
+            ))
+        },
+    );
+    for (path, canonical) in explicit.into_iter().zip(&canonical) {
+        let rel = root
+            .as_ref()
+            .and_then(|root| canonical.strip_prefix(root).ok())
+            .unwrap_or(canonical)
+            .to_path_buf();
+        files.push((path, rel));
+    }
+
+    let mut outputs = std::collections::HashMap::new();
+    for (path, rel) in &files {
+        if let Some(other) = outputs.insert(rel.with_extension("html"), path) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "{} and {} would be written to the same output file",
+                    other.display(),
+                    path.display(),
+                ),
+            ));
+        }
+    }
+
+    Ok(files)
+}
+
+/// Convert all djot files of the inputs to HTML files within the output directory.
+fn build(app: &App, build: &Build) -> Result<(), std::io::Error> {
+    let out_dir = if let Some(out) = &app.output {
+        Path::new(out)
+    } else {
+        eprintln!("please supply an output directory to build with -o");
+        exit(1);
+    };
+    if build.inputs.is_empty() {
+        eprintln!("please supply files or directories to build");
+        exit(1);
+    }
+
+    let files = sources(&build.inputs)?;
+    let contents = files
+        .iter()
+        .map(|(path, _)| std::fs::read_to_string(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut batch = jotdown::batch::Batch::new();
+    if let Some(n) = build.jobs {
+        batch = batch.with_threads(n);
+    }
+    let pages = if let Some(template) = &app.template {
+        let template = std::fs::read_to_string(template)?;
+        let renderer = renderer(app);
+        batch.map(contents, move |src| page(&template, src, &renderer))
+    } else {
+        batch
+            .render(contents, renderer(app))
+            .into_iter()
+            .map(Ok)
+            .collect()
+    };
+
+    for ((src, rel), html) in files.iter().zip(pages) {
+        let html =
+            html.map_err(|e| std::io::Error::new(e.kind(), format!("{}: {}", src.display(), e)))?;
+        let path = out_dir.join(rel).with_extension("html");
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, html)?;
+    }
+
+    Ok(())
+}
+
 fn run() -> Result<(), std::io::Error> {
     let app = parse_args();
 
-    let content = match app.input {
+    if let Some(b) = &app.build {
+        return build(&app, b);
+    }
+
+    let content = match &app.input {
         Some(path) => std::fs::read_to_string(path)?,
         None => {
             let mut s = String::new();
@@ -98,15 +487,17 @@ fn run() -> Result<(), std::io::Error> {
         }
     };
 
+    let renderer = renderer(&app);
+    if let Some(template) = &app.template {
+        let html = page(&std::fs::read_to_string(template)?, &content, &renderer)?;
+        match app.output {
+            Some(path) => std::fs::write(path, html)?,
+            None => print!("{}", html),
+        }
+        return Ok(());
+    }
+
     let parser = jotdown::Parser::new(&content);
-    let renderer = if app.minified {
-        jotdown::html::Renderer::minified()
-    } else {
-        jotdown::html::Renderer::indented(jotdown::html::Indentation {
-            string: app.indent_string,
-            initial_level: app.start_indent,
-        })
-    };
 
     match app.output {
         Some(path) => renderer.write(parser, File::create(path)?)?,
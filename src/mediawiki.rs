@@ -0,0 +1,717 @@
+//! A MediaWiki renderer that takes an iterator of [`Event`]s and emits wikitext.
+//!
+//! The output may be used as the content of a page of a MediaWiki installation, e.g. when edited
+//! through its API. Elements without a wikitext equivalent use the HTML tags that MediaWiki
+//! accepts, e.g. `<del>` and `<blockquote>`. Footnotes become references of the Cite extension,
+//! listed in a `<references>` element at the end of the page.
+//!
+//! Links with a URL scheme are external links, while other links, e.g. `[Main Page](Main_Page)`,
+//! are rendered as links to pages of the wiki. Images become links to files uploaded to the
+//! wiki, by the file name of their source.
+//!
+//! # Examples
+//!
+//! ```
+//! let src = concat!(
+//!     "# Usage\n",
+//!     "\n",
+//!     "Run _jotdown_ on a [file](https://djot.net)[^1].\n",
+//!     "\n",
+//!     "- one\n",
+//!     "- two\n",
+//!     "\n",
+//!     "[^1]: Or standard input.\n",
+//! );
+//! let events = jotdown::Parser::new(src);
+//! assert_eq!(
+//!     jotdown::mediawiki::render_to_string(events),
+//!     concat!(
+//!         "= Usage =\n",
+//!         "\n",
+//!         "Run ''jotdown'' on a [https://djot.net file]<ref name=\"1\" />.\n",
+//!         "\n",
+//!         "* one\n",
+//!         "* two\n",
+//!         "\n",
+//!         "<references>\n",
+//!         "<ref name=\"1\">Or standard input.</ref>\n",
+//!         "</references>\n",
+//!     ),
+//! );
+//! ```
+
+use crate::has_scheme;
+use crate::Alignment;
+use crate::Container;
+use crate::Event;
+use crate::LinkType;
+use crate::ListKind;
+use crate::Render;
+use crate::SpanLinkType;
+
+/// Render events into a string.
+///
+/// This is a convenience function for using [`Renderer::push`] with fewer imports and without an
+/// intermediate variable.
+///
+/// # Examples
+///
+/// ```
+/// let events = jotdown::Parser::new("hello *world*");
+/// assert_eq!(
+///     jotdown::mediawiki::render_to_string(events),
+///     "hello '''world'''\n",
+/// );
+/// ```
+pub fn render_to_string<'s, I>(events: I) -> String
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    let mut s = String::new();
+    Renderer::default().push(events, &mut s).unwrap();
+    s
+}
+
+/// [`Render`] implementor that writes MediaWiki markup.
+#[derive(Debug, Clone)]
+pub struct Renderer {
+    table_class: Option<String>,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self {
+            table_class: Some("wikitable".to_string()),
+        }
+    }
+}
+
+impl Renderer {
+    /// Set the class of tables, `wikitable` by default, or use no class if `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::*;
+    /// # use jotdown::mediawiki::*;
+    /// let renderer = Renderer::default().with_table_class(Some("sortable"));
+    /// let mut wiki = String::new();
+    /// renderer.push(Parser::new("|a|b|\n"), &mut wiki).unwrap();
+    /// assert_eq!(wiki, "{| class=\"sortable\"\n|-\n| a\n| b\n|}\n");
+    /// ```
+    #[must_use]
+    pub fn with_table_class(mut self, class: Option<&str>) -> Self {
+        self.table_class = class.map(ToString::to_string);
+        self
+    }
+}
+
+impl Render for Renderer {
+    fn push<'s, I, W>(&self, mut events: I, mut out: W) -> std::fmt::Result
+    where
+        I: Iterator<Item = Event<'s>>,
+        W: std::fmt::Write,
+    {
+        let mut w = Writer::new(self);
+        events.try_for_each(|e| w.render_event(&e, &mut out))?;
+        w.render_epilogue(&mut out)
+    }
+}
+
+/// Separator to write before the next block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Separator {
+    /// Nothing, at the start of the output or of a list item.
+    None,
+    /// A line break, at the start of a container.
+    Line,
+    /// A blank line, after a block.
+    Blank,
+}
+
+struct Writer<'s, 'f> {
+    renderer: &'f Renderer,
+    separator: Separator,
+    /// Prefixes of the open lists, e.g. `*` or `#`.
+    list: String,
+    /// Whether the next character is at the start of a line.
+    line_start: bool,
+    /// Depth of images, whose content is written as alt text.
+    image: usize,
+    /// Whether text is written as is, and not as wikitext.
+    verbatim: bool,
+    /// Whether content is omitted.
+    ignore: bool,
+    /// Labels of footnotes in order of their first reference.
+    references: Vec<&'s str>,
+    /// Events of footnotes, by label.
+    footnotes: Vec<(&'s str, Vec<Event<'s>>)>,
+    /// Footnote that is being collected.
+    footnote: Option<(&'s str, Vec<Event<'s>>)>,
+}
+
+impl<'s, 'f> Writer<'s, 'f> {
+    fn new(renderer: &'f Renderer) -> Self {
+        Self {
+            renderer,
+            separator: Separator::None,
+            list: String::new(),
+            line_start: true,
+            image: 0,
+            verbatim: false,
+            ignore: false,
+            references: Vec::new(),
+            footnotes: Vec::new(),
+            footnote: None,
+        }
+    }
+
+    fn render_event<W>(&mut self, e: &Event<'s>, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        if let Some((label, events)) = &mut self.footnote {
+            if let Event::End(Container::Footnote { .. }) = e {
+                self.footnotes.push((label, std::mem::take(events)));
+                self.footnote = None;
+            } else {
+                events.push(e.clone());
+            }
+            return Ok(());
+        }
+
+        if self.ignore {
+            if let Event::End(
+                Container::LinkDefinition { .. }
+                | Container::RawBlock { .. }
+                | Container::RawInline { .. },
+            ) = e
+            {
+                self.ignore = false;
+            }
+            return Ok(());
+        }
+
+        if self.image > 0 {
+            match e {
+                Event::Start(Container::Image(..), _) => self.image += 1,
+                Event::End(Container::Image(..)) => {
+                    self.image -= 1;
+                    if self.image == 0 {
+                        out.write_str("]]")?;
+                    }
+                }
+                Event::Str(s) => self.write_text(s, &mut out)?,
+                Event::Start(..) | Event::End(..) => {}
+                e => self.render_atom(e, &mut out)?,
+            }
+            return Ok(());
+        }
+
+        match e {
+            Event::Start(c, _) => self.render_start(c, &mut out),
+            Event::End(c) => self.render_end(c, &mut out),
+            Event::Str(s) if self.verbatim => write_verbatim(s, &mut out),
+            Event::Str(s) => self.write_text(s, &mut out),
+            Event::FootnoteReference(label) => {
+                if !self.references.contains(label) {
+                    self.references.push(label);
+                }
+                out.write_str("<ref name=\"")?;
+                write_attr(label, &mut out)?;
+                out.write_str("\" />")
+            }
+            Event::Softbreak => {
+                if self.list.is_empty() {
+                    self.line_start = true;
+                    out.write_char('\n')
+                } else {
+                    out.write_char(' ')
+                }
+            }
+            Event::Hardbreak => {
+                out.write_str("<br />")?;
+                if self.list.is_empty() {
+                    self.line_start = true;
+                    out.write_char('\n')?;
+                }
+                Ok(())
+            }
+            Event::ThematicBreak(_) => {
+                self.block(&mut out)?;
+                out.write_str("----")
+            }
+            e => self.render_atom(e, &mut out),
+        }
+    }
+
+    /// Render an event that is written the same way in alt text.
+    fn render_atom<W>(&mut self, e: &Event<'s>, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        self.line_start = false;
+        match e {
+            Event::Symbol(sym) => {
+                out.write_char(':')?;
+                self.write_text(sym, &mut out)?;
+                out.write_char(':')
+            }
+            Event::LeftSingleQuote => out.write_char('‘'),
+            Event::RightSingleQuote => out.write_char('’'),
+            Event::LeftDoubleQuote => out.write_char('“'),
+            Event::RightDoubleQuote => out.write_char('”'),
+            Event::Ellipsis => out.write_char('…'),
+            Event::EnDash => out.write_char('–'),
+            Event::EmDash => out.write_char('—'),
+            Event::NonBreakingSpace => out.write_str("&nbsp;"),
+            Event::Softbreak | Event::Hardbreak => out.write_char(' '),
+            _ => Ok(()),
+        }
+    }
+
+    fn render_start<W>(&mut self, c: &Container<'s>, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        match c {
+            Container::Blockquote => {
+                self.block(&mut out)?;
+                out.write_str("<blockquote>")?;
+                self.separator = Separator::Line;
+            }
+            Container::List { kind, .. } => {
+                self.list_start(&mut out)?;
+                self.list.push(match kind {
+                    ListKind::Ordered { .. } => '#',
+                    ListKind::Unordered(..) | ListKind::Task(..) => '*',
+                });
+            }
+            Container::ListItem | Container::TaskListItem { .. } => {
+                self.item(&mut out)?;
+                if let Container::TaskListItem { checked } = c {
+                    out.write_str(if *checked { "☑ " } else { "☐ " })?;
+                }
+            }
+            Container::DescriptionList => {
+                self.list_start(&mut out)?;
+                self.list.push(';');
+            }
+            Container::DescriptionTerm => self.item(&mut out)?,
+            Container::DescriptionDetails => {
+                self.list.pop();
+                self.list.push(':');
+                self.item(&mut out)?;
+            }
+            Container::Footnote { label } => self.footnote = Some((label, Vec::new())),
+            Container::Table => {
+                self.block(&mut out)?;
+                out.write_str("{|")?;
+                if let Some(class) = &self.renderer.table_class {
+                    out.write_str(" class=\"")?;
+                    write_attr(class, &mut out)?;
+                    out.write_char('"')?;
+                }
+            }
+            Container::Caption => out.write_str("\n|+ ")?,
+            Container::TableRow { .. } => out.write_str("\n|-")?,
+            Container::TableCell { alignment, head } => {
+                out.write_str(if *head { "\n! " } else { "\n| " })?;
+                let align = match alignment {
+                    Alignment::Unspecified => None,
+                    Alignment::Left => Some("left"),
+                    Alignment::Center => Some("center"),
+                    Alignment::Right => Some("right"),
+                };
+                if let Some(align) = align {
+                    write!(out, "style=\"text-align: {};\" | ", align)?;
+                }
+            }
+            Container::Section { .. } => {}
+            Container::Div { class } | Container::CustomBlock { name: class, .. } => {
+                if !class.is_empty() {
+                    self.block(&mut out)?;
+                    out.write_str("<div class=\"")?;
+                    write_attr(class, &mut out)?;
+                    out.write_str("\">")?;
+                    self.separator = Separator::Line;
+                }
+            }
+            Container::Paragraph => self.block(&mut out)?,
+            Container::Heading { level, .. } => {
+                self.block(&mut out)?;
+                write!(out, "{} ", "=".repeat((*level).into()))?;
+            }
+            Container::LinkDefinition { .. } => self.ignore = true,
+            Container::RawBlock { format } | Container::RawInline { format } => {
+                if *format != "mediawiki" {
+                    self.ignore = true;
+                    return Ok(());
+                }
+                if c.is_block() {
+                    self.block(&mut out)?;
+                }
+                self.verbatim = true;
+            }
+            Container::CodeBlock { language } => {
+                self.block(&mut out)?;
+                if language.is_empty() {
+                    out.write_str("<pre>")?;
+                } else {
+                    out.write_str("<syntaxhighlight lang=\"")?;
+                    write_attr(language, &mut out)?;
+                    out.write_str("\">\n")?;
+                }
+                self.verbatim = true;
+            }
+            Container::Span => {}
+            Container::CustomInline { name } => {
+                out.write_str("<span class=\"")?;
+                write_attr(name, &mut out)?;
+                out.write_str("\">")?;
+            }
+            Container::Link(dst, ty) => match ty {
+                LinkType::Span(SpanLinkType::Unresolved) => {}
+                LinkType::Email => {
+                    out.write_str("[mailto:")?;
+                    write_url(dst, &mut out)?;
+                    out.write_char(' ')?;
+                }
+                _ if has_scheme(dst) => {
+                    out.write_char('[')?;
+                    write_url(dst, &mut out)?;
+                    out.write_char(' ')?;
+                }
+                _ => {
+                    out.write_str("[[")?;
+                    self.write_text(dst, &mut out)?;
+                    out.write_char('|')?;
+                }
+            },
+            Container::Image(src, _) => {
+                let name = src.rsplit('/').next().unwrap_or(src);
+                out.write_str("[[File:")?;
+                self.write_text(name, &mut out)?;
+                out.write_str("|alt=")?;
+                self.image = 1;
+            }
+            Container::Verbatim => {
+                out.write_str("<code><nowiki>")?;
+                self.verbatim = true;
+            }
+            Container::Math { display } => {
+                out.write_str(if *display {
+                    "<math display=\"block\">"
+                } else {
+                    "<math>"
+                })?;
+                self.verbatim = true;
+            }
+            Container::Subscript => out.write_str("<sub>")?,
+            Container::Superscript => out.write_str("<sup>")?,
+            Container::Insert => out.write_str("<ins>")?,
+            Container::Delete => out.write_str("<del>")?,
+            Container::Strong => out.write_str("'''")?,
+            Container::Emphasis => out.write_str("''")?,
+            Container::Mark => out.write_str("<mark>")?,
+        }
+        self.line_start = false;
+        Ok(())
+    }
+
+    fn render_end<W>(&mut self, c: &Container<'s>, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        match c {
+            Container::Blockquote => {
+                out.write_str("\n</blockquote>")?;
+                self.separator = Separator::Blank;
+            }
+            Container::List { .. } | Container::DescriptionList => {
+                self.list.pop();
+                self.separator = Separator::Blank;
+            }
+            Container::ListItem | Container::TaskListItem { .. } => {
+                // an empty item still ends its line
+                self.separator = Separator::Blank;
+            }
+            Container::DescriptionTerm => self.separator = Separator::Blank,
+            Container::DescriptionDetails => {
+                self.list.pop();
+                self.list.push(';');
+                self.separator = Separator::Blank;
+            }
+            Container::Table => {
+                out.write_str("\n|}")?;
+                self.separator = Separator::Blank;
+            }
+            Container::Div { class } | Container::CustomBlock { name: class, .. }
+                if !class.is_empty() =>
+            {
+                out.write_str("\n</div>")?;
+                self.separator = Separator::Blank;
+            }
+            Container::Heading { level, .. } => {
+                write!(out, " {}", "=".repeat((*level).into()))?;
+            }
+            Container::RawBlock { .. } | Container::RawInline { .. } => self.verbatim = false,
+            Container::CodeBlock { language } => {
+                out.write_str(if language.is_empty() {
+                    "</pre>"
+                } else {
+                    "</syntaxhighlight>"
+                })?;
+                self.verbatim = false;
+            }
+            Container::CustomInline { .. } => out.write_str("</span>")?,
+            Container::Link(dst, ty) => match ty {
+                LinkType::Span(SpanLinkType::Unresolved) => {}
+                LinkType::Email => out.write_char(']')?,
+                _ if has_scheme(dst) => out.write_char(']')?,
+                _ => out.write_str("]]")?,
+            },
+            Container::Verbatim => {
+                out.write_str("</nowiki></code>")?;
+                self.verbatim = false;
+            }
+            Container::Math { .. } => {
+                out.write_str("</math>")?;
+                self.verbatim = false;
+            }
+            Container::Subscript => out.write_str("</sub>")?,
+            Container::Superscript => out.write_str("</sup>")?,
+            Container::Insert => out.write_str("</ins>")?,
+            Container::Delete => out.write_str("</del>")?,
+            Container::Strong => out.write_str("'''")?,
+            Container::Emphasis => out.write_str("''")?,
+            Container::Mark => out.write_str("</mark>")?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn render_epilogue<W>(&mut self, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        if !self.references.is_empty() {
+            self.block(&mut out)?;
+            out.write_str("<references>")?;
+            for label in std::mem::take(&mut self.references) {
+                out.write_str("\n<ref name=\"")?;
+                write_attr(label, &mut out)?;
+                out.write_str("\">")?;
+                let events = self
+                    .footnotes
+                    .iter()
+                    .position(|(l, _)| *l == label)
+                    .map(|i| self.footnotes.remove(i).1)
+                    .unwrap_or_default();
+                self.separator = Separator::None;
+                for e in &events {
+                    self.render_event(e, &mut out)?;
+                }
+                out.write_str("</ref>")?;
+            }
+            out.write_str("\n</references>")?;
+            self.separator = Separator::Blank;
+        }
+        if self.separator != Separator::None {
+            out.write_char('\n')?;
+        }
+        Ok(())
+    }
+
+    /// Start a block, separating it from the previous one.
+    fn block<W>(&mut self, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        match self.separator {
+            Separator::None => {}
+            Separator::Line => out.write_char('\n')?,
+            Separator::Blank if !self.list.is_empty() => {
+                // continue the list item on a new line
+                write!(out, "\n{}: ", self.list)?;
+            }
+            Separator::Blank => out.write_str("\n\n")?,
+        }
+        self.separator = Separator::Blank;
+        self.line_start = true;
+        Ok(())
+    }
+
+    /// Start a list, separating it from the previous block unless it is nested.
+    fn list_start<W>(&mut self, out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        if self.list.is_empty() {
+            self.block(out)?;
+            self.separator = Separator::None;
+        }
+        Ok(())
+    }
+
+    /// Start an item of the innermost list.
+    fn item<W>(&mut self, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        if self.separator != Separator::None {
+            out.write_char('\n')?;
+        }
+        write!(out, "{} ", self.list)?;
+        self.separator = Separator::None;
+        Ok(())
+    }
+
+    /// Write text, with characters that would be interpreted as markup escaped.
+    fn write_text<W>(&mut self, s: &str, mut out: W) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            let line_start = std::mem::replace(&mut self.line_start, false);
+            match c {
+                '&' => out.write_str("&amp;")?,
+                '<' => out.write_str("&lt;")?,
+                '>' => out.write_str("&gt;")?,
+                '[' | ']' | '{' | '}' | '|' | '\'' | '~' => write!(out, "&#{};", u32::from(c))?,
+                '_' if chars.peek() == Some(&'_') => out.write_str("&#95;")?,
+                '*' | '#' | ':' | ';' | '=' | '-' | ' ' if line_start => {
+                    write!(out, "&#{};", u32::from(c))?;
+                }
+                c => out.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Write text that is not interpreted as wikitext, e.g. within `<pre>` or `<nowiki>`.
+fn write_verbatim<W>(s: &str, mut out: W) -> std::fmt::Result
+where
+    W: std::fmt::Write,
+{
+    let mut rest = s;
+    while let Some(i) = rest.find(|c| matches!(c, '&' | '<')) {
+        out.write_str(&rest[..i])?;
+        out.write_str(if rest.as_bytes()[i] == b'&' {
+            "&amp;"
+        } else {
+            "&lt;"
+        })?;
+        rest = &rest[i + 1..];
+    }
+    out.write_str(rest)
+}
+
+/// Write the value of an HTML attribute.
+fn write_attr<W>(s: &str, mut out: W) -> std::fmt::Result
+where
+    W: std::fmt::Write,
+{
+    s.split('"').enumerate().try_for_each(|(i, part)| {
+        if i > 0 {
+            out.write_str("&quot;")?;
+        }
+        write_verbatim(part, &mut out)
+    })
+}
+
+/// Write the URL of an external link, which may not contain spaces or closing brackets.
+fn write_url<W>(url: &str, mut out: W) -> std::fmt::Result
+where
+    W: std::fmt::Write,
+{
+    url.chars().try_for_each(|c| match c {
+        ' ' => out.write_str("%20"),
+        ']' => out.write_str("%5D"),
+        c => out.write_char(c),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::render_to_string;
+
+    #[test]
+    fn blocks() {
+        let src = concat!(
+            "> a\n",
+            ">\n",
+            "> b\n",
+            "\n",
+            "1. c\n",
+            "\n",
+            "   d\n",
+            "\n",
+            "   - [x] e\n",
+            "2. f\n",
+            "\n",
+            "``` rust\n",
+            "fn g() {}\n",
+            "```\n",
+            "\n",
+            ": h\n",
+            "\n",
+            "  i\n",
+            "\n",
+            "| j | k |\n",
+            "|--:|---|\n",
+            "| l | m |\n",
+            "^ n\n",
+        );
+        assert_eq!(
+            render_to_string(crate::Parser::new(src)),
+            concat!(
+                "<blockquote>\n",
+                "a\n",
+                "\n",
+                "b\n",
+                "</blockquote>\n",
+                "\n",
+                "# c\n",
+                "#: d\n",
+                "#* ☑ e\n",
+                "# f\n",
+                "\n",
+                "<syntaxhighlight lang=\"rust\">\n",
+                "fn g() {}\n",
+                "</syntaxhighlight>\n",
+                "\n",
+                "; h\n",
+                ": i\n",
+                "\n",
+                "{| class=\"wikitable\"\n",
+                "|+ n\n",
+                "|-\n",
+                "! style=\"text-align: right;\" | j\n",
+                "! k\n",
+                "|-\n",
+                "| style=\"text-align: right;\" | l\n",
+                "| m\n",
+                "|}\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn inline() {
+        let src = concat!(
+            "[a](Main_Page) ![b _c_](img/d.png) `e<f` <g@h.i> {-j-}\n",
+            "* k [[l]] 'm' x^2^ $`n`\n",
+        );
+        assert_eq!(
+            render_to_string(crate::Parser::new(src)),
+            concat!(
+                "[[Main_Page|a]] [[File:d.png|alt=b c]] <code><nowiki>e&lt;f</nowiki></code> ",
+                "[mailto:g@h.i g@h.i] <del>j</del>\n",
+                "&#42; k &#91;&#91;l&#93;&#93; ‘m’ x<sup>2</sup> <math>n</math>\n",
+            ),
+        );
+    }
+}
@@ -0,0 +1,225 @@
+//! Conversion of documents to Jupyter notebooks.
+//!
+//! Code blocks of a specified language, outside of other blocks, become code cells, while the
+//! prose between them becomes markdown cells. The prose is rendered to HTML, which Jupyter
+//! displays as is within markdown cells. Footnotes are placed in each cell they are referenced
+//! from.
+//!
+//! The notebook is written in the JSON format of `.ipynb` files, version 4.
+//!
+//! # Examples
+//!
+//! ```
+//! use jotdown::notebook::*;
+//!
+//! let src = concat!(
+//!     "# Analysis\n",
+//!     "\n",
+//!     "Load the *data*:\n",
+//!     "\n",
+//!     "``` python\n",
+//!     "import pandas as pd\n",
+//!     "df = pd.read_csv(\"data.csv\")\n",
+//!     "```\n",
+//! );
+//! let notebook = Notebook::new("python").with_renderer(jotdown::html::Renderer::minified());
+//! assert_eq!(
+//!     notebook.cells(jotdown::Parser::new(src)),
+//!     &[
+//!         Cell::Markdown(
+//!             "<h1 id=\"Analysis\">Analysis</h1><p>Load the <strong>data</strong>:</p>".into()
+//!         ),
+//!         Cell::Code("import pandas as pd\ndf = pd.read_csv(\"data.csv\")".into()),
+//!     ],
+//! );
+//! assert!(notebook
+//!     .render(jotdown::Parser::new(src))
+//!     .starts_with(r#"{"cells":[{"cell_type":"markdown","metadata":{},"source":["#));
+//! ```
+
+use crate::html::Renderer;
+use crate::search_index::json_string;
+use crate::slides::unsectioned;
+use crate::split::Footnotes;
+use crate::Container;
+use crate::Event;
+use crate::Render;
+
+/// A single cell of a notebook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cell {
+    /// A markdown cell, containing the HTML of the prose.
+    Markdown(String),
+    /// A code cell, containing the content of a code block.
+    Code(String),
+}
+
+/// A converter of documents to Jupyter notebooks.
+#[derive(Clone)]
+pub struct Notebook {
+    language: String,
+    renderer: Renderer,
+}
+
+impl Notebook {
+    /// Create a converter whose code cells are the code blocks of the specified language, e.g.
+    /// `python`.
+    #[must_use]
+    pub fn new(language: &str) -> Self {
+        Self {
+            language: language.to_string(),
+            renderer: Renderer::default(),
+        }
+    }
+
+    /// Render the prose of markdown cells with the provided renderer.
+    #[must_use]
+    pub fn with_renderer(mut self, renderer: Renderer) -> Self {
+        self.renderer = renderer;
+        self
+    }
+
+    /// Divide a document into cells.
+    pub fn cells<'s, I>(&self, events: I) -> Vec<Cell>
+    where
+        I: IntoIterator<Item = Event<'s>>,
+    {
+        // footnotes may be defined after the cells that reference them
+        let mut footnotes = Footnotes::new();
+        let body: Vec<_> = events
+            .into_iter()
+            .filter_map(|e| footnotes.collect(e))
+            .collect();
+
+        let mut cells = Vec::new();
+        let mut prose = Vec::new();
+        let mut code: Option<String> = None;
+        // depth of blocks other than sections
+        let mut depth = 0;
+
+        for e in body {
+            if let Some(s) = &mut code {
+                match e {
+                    Event::Str(t) => s.push_str(&t),
+                    Event::End(_) => {
+                        let s = code.take().unwrap();
+                        cells.push(Cell::Code(s.trim_end_matches('\n').to_string()));
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            if let Event::Start(Container::CodeBlock { language }, _) = e {
+                if depth == 0 && language == self.language {
+                    self.push_prose(&mut cells, std::mem::take(&mut prose), &footnotes);
+                    code = Some(String::new());
+                    continue;
+                }
+            }
+            if let Some(e) = unsectioned(e, &mut depth) {
+                prose.push(e);
+            }
+        }
+        self.push_prose(&mut cells, prose, &footnotes);
+
+        cells
+    }
+
+    /// Convert a document to the JSON of a notebook.
+    pub fn render<'s, I>(&self, events: I) -> String
+    where
+        I: IntoIterator<Item = Event<'s>>,
+    {
+        let mut out = String::from("{\"cells\":[");
+        for (i, cell) in self.cells(events).iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let source = match cell {
+                Cell::Markdown(s) => {
+                    out.push_str("{\"cell_type\":\"markdown\",\"metadata\":{},");
+                    s
+                }
+                Cell::Code(s) => {
+                    out.push_str(concat!(
+                        "{\"cell_type\":\"code\",\"execution_count\":null,",
+                        "\"metadata\":{},\"outputs\":[],",
+                    ));
+                    s
+                }
+            };
+            out.push_str("\"source\":[");
+            for (j, line) in source.split_inclusive('\n').enumerate() {
+                if j > 0 {
+                    out.push(',');
+                }
+                json_string(&mut out, line);
+            }
+            out.push_str("]}");
+        }
+        out.push_str("],\"metadata\":{\"language_info\":{\"name\":");
+        json_string(&mut out, &self.language);
+        out.push_str("}},\"nbformat\":4,\"nbformat_minor\":4}\n");
+        out
+    }
+
+    /// Render prose to a markdown cell, unless it is empty.
+    fn push_prose<'s>(
+        &self,
+        cells: &mut Vec<Cell>,
+        mut prose: Vec<Event<'s>>,
+        footnotes: &Footnotes<'s>,
+    ) {
+        if prose
+            .iter()
+            .all(|e| matches!(e, Event::Blankline | Event::Attributes(_)))
+        {
+            return;
+        }
+        footnotes.append(&mut prose);
+        let mut html = String::new();
+        self.renderer.push(prose.into_iter(), &mut html).unwrap();
+        cells.push(Cell::Markdown(html.trim_end().to_string()));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Cell;
+    use super::Notebook;
+
+    #[test]
+    fn cells() {
+        let src = concat!(
+            "a[^n]\n\n",
+            "``` python\nx = 1\n```\n\n",
+            "> ``` python\n> y = 2\n> ```\n\n",
+            "``` r\nz <- 3\n```\n\n",
+            "``` python\n```\n\n",
+            "[^n]: b\n",
+        );
+        let notebook = Notebook::new("python").with_renderer(crate::html::Renderer::minified());
+        let cells = notebook.cells(crate::Parser::new(src));
+        assert_eq!(cells.len(), 4);
+        assert!(matches!(&cells[0], Cell::Markdown(s) if s.contains("<p>b")));
+        assert_eq!(cells[1], Cell::Code("x = 1".to_string()));
+        assert!(matches!(&cells[2], Cell::Markdown(s) if s.contains("y = 2") && s.contains("z")));
+        assert_eq!(cells[3], Cell::Code(String::new()));
+    }
+
+    #[test]
+    fn json() {
+        let src = "a\n\nb\n\n``` python\nx = \"1\"\ny = 2\n```\n";
+        assert_eq!(
+            Notebook::new("python").render(crate::Parser::new(src)),
+            concat!(
+                r#"{"cells":["#,
+                r#"{"cell_type":"markdown","metadata":{},"source":["<p>a</p>\n","<p>b</p>"]},"#,
+                r#"{"cell_type":"code","execution_count":null,"metadata":{},"outputs":[],"#,
+                r#""source":["x = \"1\"\n","y = 2"]}],"#,
+                r#""metadata":{"language_info":{"name":"python"}},"nbformat":4,"nbformat_minor":4}"#,
+                "\n",
+            ),
+        );
+    }
+}
@@ -0,0 +1,808 @@
+//! Outlines of documents.
+//!
+//! An [`Outline`] is the tree of the headings of a document, e.g. for a table of contents. Each
+//! heading is nested within the closest preceding heading of a lower level. Headings within
+//! footnotes are not part of the outline.
+//!
+//! Headings may be numbered hierarchically, e.g. 1, 1.1, 1.1.2, according to a [`Numbering`].
+//! The numbers are available in the outline, see [`Outline::with_numbering`], and may be
+//! prefixed to the headings of the document with the [`Numbered`] adapter.
+//!
+//! The [`Toc`] adapter places a table of contents where the author wants it, in place of a div
+//! with the class `toc` or a paragraph that only contains `[[toc]]`.
+//!
+//! Similarly, a [`Captions`] list is a list of figures or a list of tables, e.g. for a report.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! use jotdown::outline::Outline;
+//!
+//! let src = "# Intro\n\n## *Why*\n\n# Usage\n";
+//! let outline = Outline::new(jotdown::Parser::new(src));
+//! assert_eq!(outline.title(), Some("Intro"));
+//! assert_eq!(outline.headings()[0].children[0].text, "Why");
+//! assert_eq!(
+//!     jotdown::html::render_to_string(outline.events().into_iter()),
+//!     concat!(
+//!         "<ul>\n",
+//!         "<li>\n",
+//!         "<a href=\"#Intro\">Intro</a>\n",
+//!         "<ul>\n",
+//!         "<li>\n",
+//!         "<a href=\"#Why\">Why</a>\n",
+//!         "</li>\n",
+//!         "</ul>\n",
+//!         "</li>\n",
+//!         "<li>\n",
+//!         "<a href=\"#Usage\">Usage</a>\n",
+//!         "</li>\n",
+//!         "</ul>\n",
+//!     ),
+//! );
+//! # }
+//! ```
+//!
+//! Numbered headings, where the top level is not numbered:
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! use jotdown::outline::*;
+//!
+//! let src = "# Title\n\n## Intro\n\n## Usage\n\n### Install\n";
+//! let numbering = Numbering::new()
+//!     .with_format(1, "")
+//!     .with_format(2, "{2}.")
+//!     .with_format(3, "{2}.{3}");
+//! let outline = Outline::new(jotdown::Parser::new(src)).with_numbering(&numbering);
+//! let usage = &outline.headings()[0].children[1];
+//! assert_eq!(usage.number.as_deref(), Some("2."));
+//! assert_eq!(usage.children[0].number.as_deref(), Some("2.1"));
+//! let events = Numbered::new(jotdown::Parser::new(src), &numbering);
+//! assert_eq!(
+//!     jotdown::html::render_to_string(events),
+//!     concat!(
+//!         "<section id=\"Title\">\n",
+//!         "<h1>Title</h1>\n",
+//!         "<section id=\"Intro\">\n",
+//!         "<h2>1. Intro</h2>\n",
+//!         "</section>\n",
+//!         "<section id=\"Usage\">\n",
+//!         "<h2>2. Usage</h2>\n",
+//!         "<section id=\"Install\">\n",
+//!         "<h3>2.1 Install</h3>\n",
+//!         "</section>\n",
+//!         "</section>\n",
+//!         "</section>\n",
+//!     ),
+//! );
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+
+use crate::Attributes;
+use crate::Container;
+use crate::Event;
+use crate::LinkType;
+use crate::ListBulletType;
+use crate::ListKind;
+use crate::OrderedListNumbering;
+use crate::OrderedListStyle;
+use crate::SpanLinkType;
+
+/// A heading of a document, with the headings of its subsections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heading {
+    /// Level of the heading, 1 for the top level.
+    pub level: u16,
+    /// Id of the heading, explicit or generated.
+    pub id: String,
+    /// Text of the heading, without any markup.
+    pub text: String,
+    /// Number of the heading, if the outline is numbered, see [`Outline::with_numbering`].
+    pub number: Option<String>,
+    /// Headings nested within this one.
+    pub children: Vec<Heading>,
+}
+
+/// The tree of headings of a document.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Outline {
+    headings: Vec<Heading>,
+}
+
+impl Outline {
+    /// Create the outline of a document.
+    #[must_use]
+    pub fn new<'s, I: IntoIterator<Item = Event<'s>>>(events: I) -> Self {
+        let mut outline = Self::default();
+        let mut current: Option<Heading> = None;
+        let mut footnote = 0;
+        for e in events {
+            match e {
+                Event::Start(Container::Footnote { .. }, _) => footnote += 1,
+                Event::End(Container::Footnote { .. }) => footnote -= 1,
+                _ if footnote > 0 => {}
+                Event::Start(Container::Heading { level, id, .. }, _) => {
+                    current = Some(Heading {
+                        level,
+                        id: id.to_string(),
+                        text: String::new(),
+                        number: None,
+                        children: Vec::new(),
+                    });
+                }
+                Event::End(Container::Heading { .. }) => {
+                    if let Some(mut h) = current.take() {
+                        h.text = h.text.trim().to_string();
+                        insert(&mut outline.headings, h);
+                    }
+                }
+                e => {
+                    if let Some(h) = &mut current {
+                        push_text(&mut h.text, &e);
+                    }
+                }
+            }
+        }
+        outline
+    }
+
+    /// Number the headings according to the provided numbering.
+    #[must_use]
+    pub fn with_numbering(mut self, numbering: &Numbering) -> Self {
+        fn number(headings: &mut [Heading], numbering: &Numbering, counters: &mut Counters) {
+            for h in headings {
+                h.number = numbering.format(counters.next(h.level));
+                number(&mut h.children, numbering, counters);
+            }
+        }
+        number(&mut self.headings, numbering, &mut Counters::default());
+        self
+    }
+
+    /// Headings at the top of the tree.
+    #[must_use]
+    pub fn headings(&self) -> &[Heading] {
+        &self.headings
+    }
+
+    /// Text of the first heading of the document, if it has any headings.
+    #[must_use]
+    pub fn title(&self) -> Option<&str> {
+        self.headings.first().map(|h| h.text.as_str())
+    }
+
+    /// Create the events of a table of contents, nested bullet lists with links to the headings.
+    ///
+    /// The text of each link is preceded by the number of the heading, if it is numbered. No
+    /// events are created if the document has no headings.
+    #[must_use]
+    pub fn events(&self) -> Vec<Event<'static>> {
+        let mut events = Vec::new();
+        push_list(&self.headings, &mut events);
+        events
+    }
+}
+
+/// Append the text of an inline event, without any markup.
+pub(crate) fn push_text(text: &mut String, e: &Event) {
+    match e {
+        Event::Str(s) => text.push_str(s),
+        Event::LeftSingleQuote => text.push('‘'),
+        Event::RightSingleQuote => text.push('’'),
+        Event::LeftDoubleQuote => text.push('“'),
+        Event::RightDoubleQuote => text.push('”'),
+        Event::Ellipsis => text.push('…'),
+        Event::EnDash => text.push('–'),
+        Event::EmDash => text.push('—'),
+        Event::NonBreakingSpace => text.push('\u{a0}'),
+        Event::Softbreak | Event::Hardbreak => text.push(' '),
+        _ => {}
+    }
+}
+
+/// Insert a heading as the last heading of the tree.
+fn insert(headings: &mut Vec<Heading>, h: Heading) {
+    match headings.last_mut() {
+        Some(last) if last.level < h.level => insert(&mut last.children, h),
+        _ => headings.push(h),
+    }
+}
+
+fn push_list(headings: &[Heading], events: &mut Vec<Event<'static>>) {
+    if headings.is_empty() {
+        return;
+    }
+    let list = Container::List {
+        kind: ListKind::Unordered(ListBulletType::Dash),
+        tight: true,
+    };
+    events.push(Event::Start(list.clone(), Attributes::new()));
+    for h in headings {
+        let link = Container::Link(
+            format!("#{}", h.id).into(),
+            LinkType::Span(SpanLinkType::Inline),
+        );
+        events.push(Event::Start(Container::ListItem, Attributes::new()));
+        events.push(Event::Start(Container::Paragraph, Attributes::new()));
+        events.push(Event::Start(link.clone(), Attributes::new()));
+        events.push(Event::Str(match &h.number {
+            Some(number) => format!("{} {}", number, h.text).into(),
+            None => h.text.clone().into(),
+        }));
+        events.push(Event::End(link));
+        events.push(Event::End(Container::Paragraph));
+        push_list(&h.children, events);
+        events.push(Event::End(Container::ListItem));
+    }
+    events.push(Event::End(list));
+}
+
+/// Formats of the numbers of headings, for each level.
+///
+/// A format may refer to the number of the heading within its parent at level `n` with `{n}`,
+/// where the top level is 1. By default, the numbers of all levels up to the level of the
+/// heading are joined by periods, e.g. `{1}.{2}.{3}` for level 3. Headings whose level has an
+/// empty format are not numbered, but still counted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Numbering {
+    formats: Vec<String>,
+}
+
+impl Default for Numbering {
+    fn default() -> Self {
+        Self {
+            formats: (1..=6)
+                .map(|level| {
+                    (1..=level)
+                        .map(|l| format!("{{{}}}", l))
+                        .collect::<Vec<_>>()
+                        .join(".")
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Numbering {
+    /// Create a numbering with the default formats.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use the provided format for headings at the specified level.
+    #[must_use]
+    pub fn with_format(mut self, level: u16, format: &str) -> Self {
+        let i = usize::from(level.max(1)) - 1;
+        if self.formats.len() <= i {
+            self.formats.resize(i + 1, String::new());
+        }
+        self.formats[i] = format.to_string();
+        self
+    }
+
+    /// Format the number of a heading, given the counters of all levels up to its own.
+    pub(crate) fn format(&self, counters: &[u32]) -> Option<String> {
+        let format = self.formats.get(counters.len() - 1)?;
+        if format.is_empty() {
+            return None;
+        }
+        let mut number = format.clone();
+        for (l, n) in counters.iter().enumerate().rev() {
+            number = number.replace(&format!("{{{}}}", l + 1), &n.to_string());
+        }
+        Some(number)
+    }
+}
+
+/// Counters of the headings at each level.
+#[derive(Default)]
+pub(crate) struct Counters(Vec<u32>);
+
+impl Counters {
+    /// Count a heading, returns the counters of all levels up to its own.
+    pub(crate) fn next(&mut self, level: u16) -> &[u32] {
+        let level = usize::from(level.max(1));
+        self.0.resize(level, 0);
+        self.0[level - 1] += 1;
+        &self.0
+    }
+}
+
+/// An iterator adapter that prefixes headings with their numbers.
+pub struct Numbered<'n, 's, I> {
+    events: I,
+    numbering: &'n Numbering,
+    counters: Counters,
+    footnote: usize,
+    /// Number of the heading that was just started.
+    pending: Option<Event<'s>>,
+}
+
+impl<'n, 's, I> Numbered<'n, 's, I>
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    /// Create an adapter that numbers the headings according to the provided numbering.
+    pub fn new(events: I, numbering: &'n Numbering) -> Self {
+        Self {
+            events,
+            numbering,
+            counters: Counters::default(),
+            footnote: 0,
+            pending: None,
+        }
+    }
+}
+
+impl<'n, 's, I> Iterator for Numbered<'n, 's, I>
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    type Item = Event<'s>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.pending.take() {
+            return Some(e);
+        }
+        let e = self.events.next()?;
+        match &e {
+            Event::Start(Container::Footnote { .. }, _) => self.footnote += 1,
+            Event::End(Container::Footnote { .. }) => self.footnote -= 1,
+            Event::Start(Container::Heading { level, .. }, _) if self.footnote == 0 => {
+                let counters = self.counters.next(*level);
+                self.pending = self
+                    .numbering
+                    .format(counters)
+                    .map(|number| Event::Str(format!("{} ", number).into()));
+            }
+            _ => {}
+        }
+        Some(e)
+    }
+}
+
+/// An iterator adapter that replaces placeholders with a table of contents.
+///
+/// The content of a placeholder div is replaced, while its attributes are kept. A `[[toc]]`
+/// paragraph is replaced by a div with the class `toc` and the attributes of the paragraph.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "html")]
+/// # {
+/// use jotdown::outline::Toc;
+///
+/// let src = "[[toc]]\n\n# Intro\n";
+/// assert_eq!(
+///     jotdown::html::render_to_string(Toc::new(jotdown::Parser::new(src))),
+///     concat!(
+///         "<div class=\"toc\">\n",
+///         "<ul>\n",
+///         "<li>\n",
+///         "<a href=\"#Intro\">Intro</a>\n",
+///         "</li>\n",
+///         "</ul>\n",
+///         "</div>\n",
+///         "<section id=\"Intro\">\n",
+///         "<h1>Intro</h1>\n",
+///         "</section>\n",
+///     ),
+/// );
+/// # }
+/// ```
+pub struct Toc<'s> {
+    events: std::vec::IntoIter<Event<'s>>,
+    outline: Outline,
+    /// Events of a table of contents that have not yet been emitted.
+    pending: VecDeque<Event<'s>>,
+}
+
+impl<'s> Toc<'s> {
+    /// Create an adapter that inserts the outline of the document.
+    ///
+    /// The events are buffered, as the outline depends on the whole document.
+    pub fn new<I: IntoIterator<Item = Event<'s>>>(events: I) -> Self {
+        let events: Vec<_> = events.into_iter().collect();
+        Self {
+            outline: Outline::new(events.iter().cloned()),
+            events: events.into_iter(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Number the headings within the table of contents.
+    #[must_use]
+    pub fn with_numbering(mut self, numbering: &Numbering) -> Self {
+        self.outline = self.outline.with_numbering(numbering);
+        self
+    }
+
+    /// Queue a table of contents within a div.
+    fn insert(&mut self, div: Container<'s>, attrs: Attributes<'s>) {
+        self.pending.push_back(Event::Start(div.clone(), attrs));
+        self.pending.extend(self.outline.events());
+        self.pending.push_back(Event::End(div));
+    }
+}
+
+impl<'s> Iterator for Toc<'s> {
+    type Item = Event<'s>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.pending.pop_front() {
+            return Some(e);
+        }
+        match self.events.next()? {
+            Event::Start(c @ Container::Div { .. }, attrs) if has_class(&c, &attrs, "toc") => {
+                let mut depth = 0;
+                for e in self.events.by_ref() {
+                    match e {
+                        Event::Start(..) => depth += 1,
+                        Event::End(..) if depth == 0 => break,
+                        Event::End(..) => depth -= 1,
+                        _ => {}
+                    }
+                }
+                self.insert(c, attrs);
+                self.pending.pop_front()
+            }
+            Event::Start(Container::Paragraph, attrs)
+                if matches!(
+                    self.events.as_slice(),
+                    [Event::Str(s), Event::End(Container::Paragraph), ..] if s.trim() == "[[toc]]"
+                ) =>
+            {
+                self.events.nth(1);
+                self.insert(Container::Div { class: "toc" }, attrs);
+                self.pending.pop_front()
+            }
+            e => Some(e),
+        }
+    }
+}
+
+/// Returns `true` if the div has the specified class.
+pub(crate) fn has_class(div: &Container, attrs: &Attributes, class: &str) -> bool {
+    matches!(div, Container::Div { class: c } if *c == class)
+        || attrs
+            .get_value("class")
+            .map_or(false, |c| c.to_string().split(' ').any(|c| c == class))
+}
+
+/// A figure or table of a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Captioned {
+    /// Number of the element, in order of appearance starting at 1.
+    pub number: usize,
+    /// Explicit id of the element, if any.
+    pub id: Option<String>,
+    /// Text of the caption, without any markup, empty if the element has no caption.
+    pub caption: String,
+}
+
+/// A list of the figures or of the tables of a document.
+///
+/// Like with [cross-references](crate::xref), figures are divs with the `figure` class, and
+/// all tables are listed. The caption of a table is its `^ ` caption, while the caption of a
+/// figure is a paragraph within it that starts with `^ `, or else the alt text of its first
+/// image. Figures and tables within footnotes are not listed.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "html")]
+/// # {
+/// use jotdown::outline::Captions;
+///
+/// let src = concat!(
+///     "{#results}\n",
+///     "| 1 | 2 |\n",
+///     "^ Results of the _first_ run\n",
+///     "\n",
+///     "| 3 |\n",
+/// );
+/// let tables = Captions::tables(jotdown::Parser::new(src));
+/// assert_eq!(tables.entries()[0].caption, "Results of the first run");
+/// assert_eq!(
+///     jotdown::html::render_to_string(tables.events().into_iter()),
+///     concat!(
+///         "<ol>\n",
+///         "<li>\n",
+///         "<a href=\"#results\">Results of the first run</a>\n",
+///         "</li>\n",
+///         "<li>\n",
+///         "Table 2\n",
+///         "</li>\n",
+///         "</ol>\n",
+///     ),
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Captions {
+    /// Kind of the elements, used to label elements without a caption.
+    kind: crate::xref::Kind,
+    entries: Vec<Captioned>,
+}
+
+impl Captions {
+    fn new(kind: crate::xref::Kind) -> Self {
+        Self {
+            kind,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Create the list of figures of a document.
+    #[must_use]
+    pub fn figures<'s, I: IntoIterator<Item = Event<'s>>>(events: I) -> Self {
+        let mut list = Self::new(crate::xref::Kind::Figure);
+        let mut footnote = 0;
+        // events of the current figure, with its id and the nesting depth of its content
+        let mut figure: Option<(Option<String>, usize, Vec<Event<'s>>)> = None;
+        for e in events {
+            if let Some((id, depth, evs)) = &mut figure {
+                match e {
+                    Event::Start(..) => *depth += 1,
+                    Event::End(..) if *depth == 0 => {
+                        list.entries.push(Captioned {
+                            number: list.entries.len() + 1,
+                            id: id.take(),
+                            caption: figure_caption(evs),
+                        });
+                        figure = None;
+                        continue;
+                    }
+                    Event::End(..) => *depth -= 1,
+                    _ => {}
+                }
+                evs.push(e);
+                continue;
+            }
+            match e {
+                Event::Start(Container::Footnote { .. }, _) => footnote += 1,
+                Event::End(Container::Footnote { .. }) => footnote -= 1,
+                Event::Start(c @ Container::Div { .. }, attrs)
+                    if footnote == 0 && has_class(&c, &attrs, "figure") =>
+                {
+                    let id = attrs.get_value("id").map(|id| id.to_string());
+                    figure = Some((id, 0, Vec::new()));
+                }
+                _ => {}
+            }
+        }
+        list
+    }
+
+    /// Create the list of tables of a document.
+    #[must_use]
+    pub fn tables<'s, I: IntoIterator<Item = Event<'s>>>(events: I) -> Self {
+        let mut list = Self::new(crate::xref::Kind::Table);
+        let mut footnote = 0;
+        let mut in_caption = false;
+        for e in events {
+            match e {
+                Event::Start(Container::Footnote { .. }, _) => footnote += 1,
+                Event::End(Container::Footnote { .. }) => footnote -= 1,
+                _ if footnote > 0 => {}
+                Event::Start(Container::Table, attrs) => list.entries.push(Captioned {
+                    number: list.entries.len() + 1,
+                    id: attrs.get_value("id").map(|id| id.to_string()),
+                    caption: String::new(),
+                }),
+                Event::Start(Container::Caption, _) => in_caption = true,
+                Event::End(Container::Caption) => {
+                    in_caption = false;
+                    if let Some(t) = list.entries.last_mut() {
+                        t.caption = t.caption.trim().to_string();
+                    }
+                }
+                e if in_caption => {
+                    if let Some(t) = list.entries.last_mut() {
+                        push_text(&mut t.caption, &e);
+                    }
+                }
+                _ => {}
+            }
+        }
+        list
+    }
+
+    /// The figures or tables, in order of appearance.
+    #[must_use]
+    pub fn entries(&self) -> &[Captioned] {
+        &self.entries
+    }
+
+    /// Create the events of the list, an ordered list with the captions of the elements.
+    ///
+    /// The captions of elements with an id link to the element. Elements without a caption are
+    /// labeled with their kind and number instead, e.g. "Table 2". No events are created if the
+    /// document has no figures or tables.
+    #[must_use]
+    pub fn events(&self) -> Vec<Event<'static>> {
+        let mut events = Vec::new();
+        if self.entries.is_empty() {
+            return events;
+        }
+        let list = Container::List {
+            kind: ListKind::Ordered {
+                numbering: OrderedListNumbering::Decimal,
+                style: OrderedListStyle::Period,
+                start: 1,
+            },
+            tight: true,
+        };
+        events.push(Event::Start(list.clone(), Attributes::new()));
+        for entry in &self.entries {
+            events.push(Event::Start(Container::ListItem, Attributes::new()));
+            events.push(Event::Start(Container::Paragraph, Attributes::new()));
+            let text = if entry.caption.is_empty() {
+                format!("{} {}", self.kind.default_name(), entry.number)
+            } else {
+                entry.caption.clone()
+            };
+            let text = Event::Str(text.into());
+            if let Some(id) = &entry.id {
+                let link = Container::Link(
+                    format!("#{}", id).into(),
+                    LinkType::Span(SpanLinkType::Inline),
+                );
+                events.push(Event::Start(link.clone(), Attributes::new()));
+                events.push(text);
+                events.push(Event::End(link));
+            } else {
+                events.push(text);
+            }
+            events.push(Event::End(Container::Paragraph));
+            events.push(Event::End(Container::ListItem));
+        }
+        events.push(Event::End(list));
+        events
+    }
+}
+
+/// Caption of a figure, given the events of its content.
+fn figure_caption(events: &[Event]) -> String {
+    // alt text of the first image, and whether it is being read
+    let mut alt = String::new();
+    let mut in_alt: Option<bool> = None;
+    let mut paragraph: Option<String> = None;
+    for e in events {
+        match e {
+            Event::Start(Container::Paragraph, _) => paragraph = Some(String::new()),
+            Event::End(Container::Paragraph) => {
+                if let Some(caption) = paragraph.take() {
+                    if let Some(caption) = caption.strip_prefix("^ ") {
+                        return caption.trim().to_string();
+                    }
+                }
+            }
+            Event::Start(Container::Image(..), _) if in_alt.is_none() => in_alt = Some(true),
+            Event::End(Container::Image(..)) => in_alt = Some(false),
+            e => {
+                if let Some(p) = &mut paragraph {
+                    push_text(p, e);
+                }
+                if in_alt == Some(true) {
+                    push_text(&mut alt, e);
+                }
+            }
+        }
+    }
+    alt.trim().to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::Outline;
+
+    fn levels(src: &str) -> String {
+        fn push(headings: &[super::Heading], out: &mut String) {
+            for h in headings {
+                out.push_str(&h.level.to_string());
+                if !h.children.is_empty() {
+                    out.push('(');
+                    push(&h.children, out);
+                    out.push(')');
+                }
+            }
+        }
+        let mut out = String::new();
+        push(Outline::new(crate::Parser::new(src)).headings(), &mut out);
+        out
+    }
+
+    #[test]
+    fn nesting() {
+        assert_eq!(levels("# a\n\n### b\n\n## c\n\n# d\n"), "1(32)1");
+        assert_eq!(levels("## a\n\n# b\n\n## c\n"), "21(2)");
+        assert_eq!(levels("::: x\n# a\n:::\n\n# b\n"), "11");
+    }
+
+    #[test]
+    fn numbering() {
+        let src = "# a\n\n### b\n\n## c\n\n# d\n\n## e\n";
+        let numbering = super::Numbering::new().with_format(2, "{1}-{2}");
+        let numbers: Vec<_> = super::Numbered::new(crate::Parser::new(src), &numbering)
+            .filter_map(|e| match e {
+                crate::Event::Str(s) if s.ends_with(' ') => Some(s.trim().to_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(numbers, &["1", "1.0.1", "1-1", "2", "2-1"]);
+        let outline = Outline::new(crate::Parser::new(src)).with_numbering(&numbering);
+        assert_eq!(
+            outline.headings()[0].children[1].number.as_deref(),
+            Some("1-1")
+        );
+    }
+
+    #[test]
+    fn toc() {
+        let src = "{.x .toc}\n:::\nold\n:::\n\n# a\n\n## b\n\n[[toc]]{.y}\n";
+        let numbering = super::Numbering::new();
+        let events: Vec<_> = super::Toc::new(crate::Parser::new(src))
+            .with_numbering(&numbering)
+            .collect();
+        let text: Vec<_> = events
+            .iter()
+            .filter_map(|e| match e {
+                crate::Event::Str(s) => Some(s.as_ref()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(text, &["1 a", "1.1 b", "a", "b", "[toc]"]);
+    }
+
+    #[test]
+    fn figures() {
+        let src = concat!(
+            "{#a}\n::: figure\n![x _y_](x.png) ![z](z.png)\n:::\n\n",
+            "[^n]\n\n",
+            "{.figure}\n:::\n![w](w.png)\n\n^ The *w*\n:::\n\n",
+            "[^n]:\n  ::: figure\n  ![v](v.png)\n  :::\n",
+        );
+        let figures = super::Captions::figures(crate::Parser::new(src));
+        assert_eq!(
+            figures.entries(),
+            &[
+                super::Captioned {
+                    number: 1,
+                    id: Some("a".to_string()),
+                    caption: "x y".to_string(),
+                },
+                super::Captioned {
+                    number: 2,
+                    id: None,
+                    caption: "The w".to_string(),
+                },
+            ],
+        );
+        assert_eq!(
+            figures
+                .events()
+                .iter()
+                .filter(|e| matches!(e, crate::Event::Start(crate::Container::Link(..), _)))
+                .count(),
+            1,
+        );
+    }
+
+    #[test]
+    fn text() {
+        let src = "# \"A\" _b_ `c`{#d}\n\ne[^f]\n\n[^f]: # g\n";
+        let outline = Outline::new(crate::Parser::new(src));
+        assert_eq!(outline.headings().len(), 1);
+        assert_eq!(outline.headings()[0].text, "“A” b c");
+        assert_eq!(outline.headings()[0].id, "A-b-c");
+    }
+}
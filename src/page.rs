@@ -0,0 +1,223 @@
+//! Rendering of complete pages with templates.
+//!
+//! The HTML renderer only produces the body of a document. A [`Template`] renders the body and
+//! extracts the metadata of a document, i.e. its title and table of contents, and hands them
+//! together with any front matter to a callback that produces the final page. The callback may
+//! e.g. fill in a template of a template engine, or simply format a string.
+//!
+//! The language of a document, which is used by browsers for hyphenation and by screen readers,
+//! belongs on the root element of the page. It may be set for all pages with
+//! [`Template::with_lang`], or for a single page with [`Template::render_with_lang`], e.g. from
+//! its front matter. It is added as a `lang` attribute to the `<html>` element produced by the
+//! callback, unless the element already has one, and is also available as [`Page::lang`]. Parts of a document in other languages are marked with a `lang` attribute,
+//! e.g. `[hola]{lang=es}`, which is rendered on the span or div as is.
+//!
+//! # Examples
+//!
+//! ```
+//! use jotdown::page::*;
+//!
+//! struct Meta {
+//!     lang: Option<&'static str>,
+//! }
+//!
+//! let template = Template::new(|page: &Page<&Meta>| {
+//!     format!(
+//!         "<html><title>{}</title><nav>{}</nav><main>{}</main></html>",
+//!         page.title.as_deref().unwrap_or("Untitled"),
+//!         page.toc,
+//!         page.body,
+//!     )
+//! })
+//! .with_renderer(jotdown::html::Renderer::minified())
+//! .with_lang("en");
+//! let src = "# Hello\n\n[mundo]{lang=es}\n";
+//! let meta = Meta { lang: Some("en-GB") };
+//! let html = template.render_with_lang(jotdown::Parser::new(src), &meta, meta.lang);
+//! assert_eq!(
+//!     html,
+//!     concat!(
+//!         "<html lang=\"en-GB\"><title>Hello</title>",
+//!         "<nav><ul><li><a href=\"#Hello\">Hello</a></li></ul></nav>",
+//!         "<main><section id=\"Hello\"><h1>Hello</h1>",
+//!         "<p><span lang=\"es\">mundo</span></p></section></main></html>",
+//!     ),
+//! );
+//! ```
+
+use crate::html::Renderer;
+use crate::outline::Outline;
+use crate::Event;
+use crate::Render;
+
+/// A rendered document with its metadata, passed to the callback of a [`Template`].
+#[derive(Debug, Clone)]
+pub struct Page<M> {
+    /// Text of the first heading of the document, if any.
+    pub title: Option<String>,
+    /// Headings of the document.
+    pub outline: Outline,
+    /// Rendered table of contents, empty if the document has no headings.
+    pub toc: String,
+    /// Rendered body of the document.
+    pub body: String,
+    /// Language of the document, if known, e.g. `en` or `pt-BR`.
+    pub lang: Option<String>,
+    /// Front matter, or other metadata, provided by the caller.
+    pub front_matter: M,
+}
+
+/// A callback that produces complete pages from rendered documents.
+#[derive(Clone)]
+pub struct Template<F> {
+    template: F,
+    renderer: Renderer,
+    lang: Option<String>,
+}
+
+impl<F> Template<F> {
+    /// Create a template that renders documents with the default HTML renderer.
+    pub fn new(template: F) -> Self {
+        Self {
+            template,
+            renderer: Renderer::default(),
+            lang: None,
+        }
+    }
+
+    /// Render the body and table of contents with the provided renderer.
+    #[must_use]
+    pub fn with_renderer(mut self, renderer: Renderer) -> Self {
+        self.renderer = renderer;
+        self
+    }
+
+    /// Set the language of documents that do not specify one.
+    #[must_use]
+    pub fn with_lang(mut self, lang: impl Into<String>) -> Self {
+        self.lang = Some(lang.into());
+        self
+    }
+
+    /// Render a document, along with its front matter, to a complete page.
+    ///
+    /// The front matter is passed to the callback as is, e.g. the metadata of a
+    /// `front_matter::Document` when the `serde` feature is enabled.
+    pub fn render<'s, I, M>(&self, events: I, front_matter: M) -> String
+    where
+        I: IntoIterator<Item = Event<'s>>,
+        F: Fn(&Page<M>) -> String,
+    {
+        self.render_with_lang(events, front_matter, None)
+    }
+
+    /// Render a document in the specified language to a complete page.
+    ///
+    /// If no language is specified, the language of the template is used, if any.
+    pub fn render_with_lang<'s, I, M>(
+        &self,
+        events: I,
+        front_matter: M,
+        lang: Option<&str>,
+    ) -> String
+    where
+        I: IntoIterator<Item = Event<'s>>,
+        F: Fn(&Page<M>) -> String,
+    {
+        let events: Vec<_> = events.into_iter().collect();
+        let outline = Outline::new(events.iter().cloned());
+        let mut toc = String::new();
+        if !outline.headings().is_empty() {
+            self.renderer
+                .push(outline.events().into_iter(), &mut toc)
+                .unwrap();
+        }
+        let mut body = String::new();
+        self.renderer.push(events.into_iter(), &mut body).unwrap();
+        let lang = lang.map(ToString::to_string).or_else(|| self.lang.clone());
+        let html = (self.template)(&Page {
+            title: outline.title().map(ToString::to_string),
+            outline,
+            toc,
+            body,
+            lang: lang.clone(),
+            front_matter,
+        });
+        match lang {
+            Some(lang) => with_lang(html, &lang),
+            None => html,
+        }
+    }
+}
+
+/// Add a `lang` attribute to the `<html>` element of a page, unless it already has one.
+fn with_lang(mut html: String, lang: &str) -> String {
+    let lower = html.to_ascii_lowercase();
+    let start = lower.match_indices("<html").map(|(i, _)| i).find(|i| {
+        lower[i + 5..]
+            .chars()
+            .next()
+            .map_or(false, |c| c == '>' || c == '/' || c.is_ascii_whitespace())
+    });
+    if let Some(start) = start {
+        let tag = &lower[start..lower[start..].find('>').map_or(lower.len(), |i| start + i)];
+        let has_lang = tag.match_indices("lang").any(|(i, _)| {
+            tag[..i].ends_with(char::is_whitespace) && tag[i + 4..].trim_start().starts_with('=')
+        });
+        if !has_lang {
+            let mut attr = String::from(" lang=\"");
+            crate::html::write_escape(lang, true, false, &mut attr).unwrap();
+            attr.push('"');
+            html.insert_str(start + 5, &attr);
+        }
+    }
+    html
+}
+
+#[cfg(test)]
+mod test {
+    use super::Page;
+    use super::Template;
+
+    #[test]
+    fn empty() {
+        let template = Template::new(|page: &Page<u8>| {
+            assert_eq!(page.title, None);
+            assert!(page.outline.headings().is_empty());
+            format!("{}|{}|{}", page.front_matter, page.toc, page.body)
+        });
+        assert_eq!(template.render(crate::Parser::new("a"), 1), "1||<p>a</p>\n");
+    }
+
+    #[test]
+    fn root_lang() {
+        let template = Template::new(|page: &Page<&str>| page.front_matter.to_string());
+        let render = |page, lang| template.render_with_lang(crate::Parser::new(""), page, lang);
+        assert_eq!(
+            render("<!DOCTYPE html>\n<HTML>", Some("en\"")),
+            "<!DOCTYPE html>\n<HTML lang=\"en&quot;\">"
+        );
+        assert_eq!(
+            render("<html class=\"a\" xml:lang=\"fr\">", Some("en")),
+            "<html lang=\"en\" class=\"a\" xml:lang=\"fr\">"
+        );
+        assert_eq!(render("<html lang=fr>", Some("en")), "<html lang=fr>");
+        assert_eq!(
+            render("<htmlx><html/>", Some("en")),
+            "<htmlx><html lang=\"en\"/>"
+        );
+        assert_eq!(render("<html>", None), "<html>");
+    }
+
+    #[test]
+    fn lang() {
+        let template = Template::new(|page: &Page<()>| format!("{:?}", page.lang));
+        assert_eq!(template.render(crate::Parser::new(""), ()), "None");
+        let template = template.with_lang("en");
+        assert_eq!(template.render(crate::Parser::new(""), ()), "Some(\"en\")");
+        assert_eq!(
+            template.render_with_lang(crate::Parser::new(""), (), Some("fr")),
+            "Some(\"fr\")"
+        );
+    }
+}
@@ -0,0 +1,339 @@
+//! Source positions of blocks.
+//!
+//! Live preview editors synchronize the scroll position of the preview with the editor, and
+//! let users jump to the source of a clicked element. The [`Positions`] adapter supports this by
+//! adding a `data-pos` attribute to each block element, except sections, with the span of the
+//! block in the source as `start:end`. The span is given in byte offsets by default, or in line
+//! numbers, starting at 1, with [`Positions::with_lines`].
+//!
+//! The adapter takes events with their spans, as provided by [`Parser::into_offset_iter`].
+//!
+//! Alternatively, a [`SourceMap`] maps lines of the source to blocks and vice versa, without
+//! modifying the output. Blocks are identified by their index in the order of the elements with a
+//! `data-pos` attribute in the HTML rendered with footnotes at the end, the default. Link
+//! definitions are not rendered, and footnotes follow the rest of the document in the order of
+//! their first reference.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! use jotdown::positions::Positions;
+//!
+//! let src = "# Title\n\n> a\n> b\n";
+//! let events = jotdown::Parser::new(src).into_offset_iter();
+//! assert_eq!(
+//!     jotdown::html::render_to_string(Positions::new(src, events).with_lines()),
+//!     concat!(
+//!         "<section id=\"Title\">\n",
+//!         "<h1 data-pos=\"1:1\">Title</h1>\n",
+//!         "<blockquote data-pos=\"3:4\">\n",
+//!         "<p data-pos=\"3:4\">a\n",
+//!         "b</p>\n",
+//!         "</blockquote>\n",
+//!         "</section>\n",
+//!     ),
+//! );
+//! # }
+//! ```
+//!
+//! [`Parser::into_offset_iter`]: crate::Parser::into_offset_iter
+
+use std::ops::Range;
+use std::ops::RangeInclusive;
+
+use crate::AttributeKind;
+use crate::Container;
+use crate::Event;
+use crate::Map;
+
+/// An iterator adapter that adds the source positions of blocks as attributes.
+///
+/// The events are buffered, as the end of a block is only known after its content.
+pub struct Positions<'s> {
+    src: &'s str,
+    /// Events with their spans, the span of a start event covers the whole container.
+    events: std::vec::IntoIter<(Event<'s>, Range<usize>)>,
+    /// Offsets of the start of each line, if positions are given in lines.
+    lines: Option<Vec<usize>>,
+}
+
+impl<'s> Positions<'s> {
+    /// Collect the events of a document and the spans of their containers.
+    pub fn new<I>(src: &'s str, events: I) -> Self
+    where
+        I: IntoIterator<Item = (Event<'s>, Range<usize>)>,
+    {
+        Self {
+            src,
+            events: spans(events).into_iter(),
+            lines: None,
+        }
+    }
+
+    /// Give positions as line numbers, starting at 1, instead of byte offsets.
+    #[must_use]
+    pub fn with_lines(mut self) -> Self {
+        self.lines = Some(line_starts(self.src));
+        self
+    }
+}
+
+impl<'s> Iterator for Positions<'s> {
+    type Item = Event<'s>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(match self.events.next()? {
+            (Event::Start(c, mut attrs), span) if is_positioned(&c) => {
+                let pos = match &self.lines {
+                    Some(lines) => {
+                        let (start, end) = lines_of(lines, &span);
+                        format!("{}:{}", start, end)
+                    }
+                    None => format!("{}:{}", span.start, span.end),
+                };
+                attrs.push((AttributeKind::Pair { key: "data-pos" }, pos.into()));
+                Event::Start(c, attrs)
+            }
+            (e, _) => e,
+        })
+    }
+}
+
+/// A block element of a [`SourceMap`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    /// Lines of the block in the source, starting at 1.
+    pub lines: RangeInclusive<usize>,
+    /// Number of enclosing blocks, other than sections.
+    pub depth: usize,
+}
+
+/// A mapping between lines of a document and its blocks, e.g. for synchronizing the scroll
+/// positions of an editor and a preview.
+///
+/// # Examples
+///
+/// ```
+/// use jotdown::positions::SourceMap;
+///
+/// let src = "para\ngraph\n\n- a\n- b\n";
+/// let map = SourceMap::new(src);
+/// assert_eq!(map.blocks().len(), 6);
+/// assert_eq!(map.block_at_line(2), Some(0));
+/// assert_eq!(map.block_at_line(5), Some(5));
+/// assert_eq!(map.block_at_line(3), None);
+/// assert_eq!(map.lines(1), Some(4..=5));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    blocks: Vec<Block>,
+}
+
+impl SourceMap {
+    /// Parse a document and find the lines of its blocks.
+    #[must_use]
+    pub fn new(src: &str) -> Self {
+        let line_starts = line_starts(src);
+        let mut blocks = Vec::new();
+        let mut depth = 0;
+        for (e, span) in rendered(spans(crate::Parser::new(src).into_offset_iter())) {
+            match e {
+                Event::Start(c, _) if is_positioned(&c) => {
+                    let (start, end) = lines_of(&line_starts, &span);
+                    blocks.push(Block {
+                        lines: start..=end,
+                        depth,
+                    });
+                    depth += 1;
+                }
+                Event::End(c) if is_positioned(&c) => depth -= 1,
+                _ => {}
+            }
+        }
+        Self { blocks }
+    }
+
+    /// All blocks of the document, in order.
+    #[must_use]
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks
+    }
+
+    /// Index of the innermost block that contains a line, if any.
+    #[must_use]
+    pub fn block_at_line(&self, line: usize) -> Option<usize> {
+        self.blocks
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.lines.contains(&line))
+            .max_by_key(|(_, b)| b.depth)
+            .map(|(i, _)| i)
+    }
+
+    /// Lines of the block with the specified index.
+    #[must_use]
+    pub fn lines(&self, index: usize) -> Option<RangeInclusive<usize>> {
+        self.blocks.get(index).map(|b| b.lines.clone())
+    }
+}
+
+/// Collect events, extending the span of each start event to the end of its container.
+pub(crate) fn spans<'s, I>(events: I) -> Vec<(Event<'s>, Range<usize>)>
+where
+    I: IntoIterator<Item = (Event<'s>, Range<usize>)>,
+{
+    let mut events: Vec<(Event<'s>, Range<usize>)> = events.into_iter().collect();
+    let mut open = Vec::new();
+    for i in 0..events.len() {
+        match &events[i].0 {
+            Event::Start(..) => open.push(i),
+            Event::End(..) => {
+                if let Some(j) = open.pop() {
+                    events[j].1.end = events[i].1.end.max(events[j].1.end);
+                }
+            }
+            _ => {}
+        }
+    }
+    events
+}
+
+/// Offsets of the start of each line.
+pub(crate) fn line_starts(src: &str) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(src.match_indices('\n').map(|(i, _)| i + 1))
+        .collect()
+}
+
+/// Lines of the first and last character of a span, starting at 1.
+pub(crate) fn lines_of(line_starts: &[usize], span: &Range<usize>) -> (usize, usize) {
+    let line = |pos: usize| match line_starts.binary_search(&pos) {
+        Ok(i) => i + 1,
+        Err(i) => i,
+    };
+    let last = if span.end > span.start {
+        span.end - 1
+    } else {
+        span.start
+    };
+    (line(span.start), line(last))
+}
+
+/// Reorder events as they are rendered: footnotes are moved to the end in the order of their
+/// first reference, and unreferenced footnotes are removed.
+fn rendered<'s>(events: Vec<(Event<'s>, Range<usize>)>) -> Vec<(Event<'s>, Range<usize>)> {
+    let mut main = Vec::new();
+    let mut footnotes = Map::new();
+    let mut open: Vec<(&str, Vec<_>)> = Vec::new();
+    for (e, span) in events {
+        match e {
+            Event::Start(Container::Footnote { label }, _) => open.push((label, Vec::new())),
+            Event::End(Container::Footnote { .. }) => {
+                if let Some((label, evs)) = open.pop() {
+                    footnotes.insert(label, evs);
+                }
+            }
+            e => match open.last_mut() {
+                Some((_, evs)) => evs.push((e, span)),
+                None => main.push((e, span)),
+            },
+        }
+    }
+    let mut labels = Vec::new();
+    let mut i = 0;
+    while i < main.len() {
+        if let Event::FootnoteReference(label) = main[i].0 {
+            if !labels.contains(&label) {
+                labels.push(label);
+                if let Some(evs) = footnotes.get(label) {
+                    main.extend(evs.iter().cloned());
+                }
+            }
+        }
+        i += 1;
+    }
+    main
+}
+
+/// Returns `true` if the container is rendered as an element with a position.
+fn is_positioned(c: &Container) -> bool {
+    c.is_block()
+        && !matches!(
+            c,
+            Container::Section { .. }
+                | Container::Footnote { .. }
+                | Container::LinkDefinition { .. }
+        )
+}
+
+#[cfg(test)]
+mod test {
+    use super::Positions;
+    use super::SourceMap;
+    use crate::Event;
+
+    fn positions(src: &str, lines: bool) -> Vec<String> {
+        let positions = Positions::new(src, crate::Parser::new(src).into_offset_iter());
+        let positions = if lines {
+            positions.with_lines()
+        } else {
+            positions
+        };
+        positions
+            .filter_map(|e| match e {
+                Event::Start(_, attrs) => attrs.get_value("data-pos").map(|v| v.to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn blocks() {
+        let src = "- a\n\n  b _c_\n\n```\nd\n```\n";
+        assert_eq!(
+            positions(src, false),
+            &["0:14", "0:14", "2:4", "7:13", "14:24"],
+        );
+        assert_eq!(positions(src, true), &["1:4", "1:4", "1:1", "3:3", "5:7"],);
+    }
+
+    #[cfg(feature = "html")]
+    #[test]
+    fn source_map_order() {
+        let src = "a[^n]\n\n[l]: http://x\n\n[^m]: unused\n\n[^n]: note[^o]\n\n[^o]: o\n\nb\n";
+        let events = Positions::new(src, crate::Parser::new(src).into_offset_iter()).with_lines();
+        let html = crate::html::render_to_string(events);
+        let rendered: Vec<&str> = html
+            .split("data-pos=\"")
+            .skip(1)
+            .map(|s| &s[..s.find('"').unwrap()])
+            .collect();
+        let map: Vec<String> = SourceMap::new(src)
+            .blocks()
+            .iter()
+            .map(|b| format!("{}:{}", b.lines.start(), b.lines.end()))
+            .collect();
+        assert_eq!(rendered, &["1:1", "11:11", "7:7", "9:9"]);
+        assert_eq!(map, rendered);
+    }
+
+    #[test]
+    fn source_map() {
+        let src = "# a\n\n> b\n>\n> c\n\nd\n";
+        let map = SourceMap::new(src);
+        let lines: Vec<_> = map
+            .blocks()
+            .iter()
+            .map(|b| (b.lines.clone(), b.depth))
+            .collect();
+        assert_eq!(
+            lines,
+            &[(1..=1, 0), (3..=5, 0), (3..=3, 1), (5..=5, 1), (7..=7, 0)],
+        );
+        assert_eq!(map.block_at_line(4), Some(1));
+        assert_eq!(map.block_at_line(5), Some(3));
+        assert_eq!(map.lines(5), None);
+    }
+}
@@ -0,0 +1,139 @@
+//! Human-readable text of a document.
+//!
+//! Tools such as spellcheckers and grammar checkers are only interested in the prose of a
+//! document, and need to know where in the source each piece of text is located in order to
+//! annotate it. The [`Prose`] iterator provides the text runs of a document along with their
+//! source spans, skipping any content that is not prose:
+//!
+//! - code blocks and verbatim,
+//! - math,
+//! - raw blocks and raw inlines,
+//! - link definitions,
+//! - autolinks and email links.
+//!
+//! # Examples
+//!
+//! ```
+//! use jotdown::prose::Prose;
+//!
+//! let src = "Teh `cdoe` is at <https://exampel.com> and $`x^2`, _see_ [ths](url).";
+//! let runs = Prose::new(src).collect::<Vec<_>>();
+//! assert_eq!(
+//!     runs,
+//!     &[
+//!         ("Teh ", 0..4),
+//!         (" is at ", 10..17),
+//!         (" and ", 38..43),
+//!         (", ", 49..51),
+//!         ("see", 52..55),
+//!         (" ", 56..57),
+//!         ("ths", 58..61),
+//!         (".", 67..68),
+//!     ],
+//! );
+//! ```
+
+use std::ops::Range;
+
+use crate::Container;
+use crate::Event;
+use crate::LinkType;
+use crate::OffsetIter;
+
+/// An iterator over the prose text runs of a document, with their location in the source.
+///
+/// Each item is the text as it appears in the source, i.e. the source at the span.
+pub struct Prose<'s> {
+    src: &'s str,
+    events: OffsetIter<'s>,
+    /// Nesting depth of containers that are not prose.
+    skip: usize,
+}
+
+impl<'s> Prose<'s> {
+    /// Create an iterator over the prose of a document.
+    #[must_use]
+    pub fn new(src: &'s str) -> Self {
+        Self {
+            src,
+            events: crate::Parser::new(src).into_offset_iter(),
+            skip: 0,
+        }
+    }
+}
+
+impl<'s> Iterator for Prose<'s> {
+    type Item = (&'s str, Range<usize>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (e, span) in self.events.by_ref() {
+            match e {
+                Event::Start(c, _) if is_skipped(&c) => self.skip += 1,
+                Event::End(c) if is_skipped(&c) => self.skip -= 1,
+                Event::Str(_) if self.skip == 0 && !span.is_empty() => {
+                    return Some((&self.src[span.clone()], span));
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+/// Containers whose content is not prose.
+pub(crate) fn is_skipped(c: &Container) -> bool {
+    matches!(
+        c,
+        Container::CodeBlock { .. }
+            | Container::CustomBlock { .. }
+            | Container::RawBlock { .. }
+            | Container::RawInline { .. }
+            | Container::Verbatim
+            | Container::Math { .. }
+            | Container::LinkDefinition { .. }
+            | Container::Link(_, LinkType::AutoLink | LinkType::Email)
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::Prose;
+
+    fn texts(src: &str) -> Vec<&str> {
+        Prose::new(src).map(|(s, _)| s).collect()
+    }
+
+    #[test]
+    fn blocks() {
+        assert_eq!(
+            texts(concat!(
+                "# Head\n",
+                "\n",
+                "```rust\n",
+                "let x;\n",
+                "```\n",
+                "\n",
+                "> quoted\n",
+                "> text\n",
+                "\n",
+                "``` =html\n",
+                "<br>\n",
+                "```\n",
+                "\n",
+                "[link]: https://a.b\n",
+                "\n",
+                "- item <a@b.c>\n",
+            )),
+            &["Head", "quoted", "text", "item "]
+        );
+    }
+
+    #[test]
+    fn spans() {
+        let src = "a `b`{=html} c";
+        for (s, span) in Prose::new(src) {
+            assert_eq!(&src[span], s);
+        }
+        assert_eq!(texts(src), &["a ", " c"]);
+    }
+}
@@ -0,0 +1,292 @@
+//! Readability metrics.
+//!
+//! Readability formulas estimate how difficult a text is to read from the lengths of its
+//! sentences and words. [`Readability`] computes them over the prose of a document, i.e.
+//! skipping code, math, raw content and headings, for the whole document as well as for each
+//! section of its [`Outline`].
+//!
+//! The counts are based on heuristics for English: a sentence ends with a word ending in `.`,
+//! `!` or `?`, or at the end of a block, and syllables are groups of vowels, excluding a silent
+//! final `e`.
+//!
+//! # Examples
+//!
+//! ```
+//! use jotdown::readability::Readability;
+//!
+//! let src = concat!(
+//!     "# Intro\n",
+//!     "\n",
+//!     "The cat sat on the mat. It was happy.\n",
+//!     "\n",
+//!     "## Details\n",
+//!     "\n",
+//!     "Unquestionably, the extraordinarily comfortable accommodation contributed.\n",
+//! );
+//! let readability = Readability::new(jotdown::Parser::new(src));
+//! let document = readability.document();
+//! assert_eq!((document.words, document.sentences), (15, 3));
+//!
+//! let sections = readability.sections();
+//! assert_eq!(sections[1].heading.text, "Details");
+//! let intro = sections[0].counts.flesch_reading_ease().unwrap();
+//! let details = sections[1].counts.flesch_reading_ease().unwrap();
+//! assert!(details < intro);
+//! ```
+//!
+//! [`Outline`]: crate::outline::Outline
+
+use crate::outline::push_text;
+use crate::outline::Heading;
+use crate::outline::Outline;
+use crate::prose::is_skipped;
+use crate::Container;
+use crate::Event;
+
+/// Counts of a text, from which readability scores are computed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    /// Number of words.
+    pub words: usize,
+    /// Number of sentences.
+    pub sentences: usize,
+    /// Number of syllables of the words.
+    pub syllables: usize,
+    /// Number of letters and digits of the words.
+    pub characters: usize,
+}
+
+impl Counts {
+    /// Count the words, sentences, syllables and characters of a block of text.
+    fn new(text: &str) -> Self {
+        let mut counts = Self::default();
+        // whether the current sentence has any words
+        let mut open = false;
+        for word in text.split_whitespace() {
+            if word.chars().any(char::is_alphanumeric) {
+                counts.words += 1;
+                counts.syllables += syllables(word);
+                counts.characters += word.chars().filter(|c| c.is_alphanumeric()).count();
+                open = true;
+            }
+            let end = word.trim_end_matches(['"', '\'', ')', ']', '”', '’'].as_ref());
+            if open && end.ends_with(['.', '!', '?'].as_ref()) {
+                counts.sentences += 1;
+                open = false;
+            }
+        }
+        if open {
+            counts.sentences += 1;
+        }
+        counts
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.words += other.words;
+        self.sentences += other.sentences;
+        self.syllables += other.syllables;
+        self.characters += other.characters;
+    }
+
+    /// The Flesch reading ease, roughly between 0 and 100 where higher is easier to read, or
+    /// `None` if there are no words.
+    #[must_use]
+    pub fn flesch_reading_ease(&self) -> Option<f64> {
+        let (w, s, y) = self.ratios()?;
+        Some(206.835 - 1.015 * w / s - 84.6 * y / w)
+    }
+
+    /// The Flesch-Kincaid grade level, i.e. the US school grade that is needed to understand
+    /// the text, or `None` if there are no words.
+    #[must_use]
+    pub fn flesch_kincaid_grade(&self) -> Option<f64> {
+        let (w, s, y) = self.ratios()?;
+        Some(0.39 * w / s + 11.8 * y / w - 15.59)
+    }
+
+    /// The automated readability index, a grade level based on characters rather than
+    /// syllables, or `None` if there are no words.
+    #[must_use]
+    pub fn automated_readability_index(&self) -> Option<f64> {
+        let (w, s, _) = self.ratios()?;
+        Some(4.71 * self.characters as f64 / w + 0.5 * w / s - 21.43)
+    }
+
+    /// Words, sentences and syllables as floats.
+    fn ratios(&self) -> Option<(f64, f64, f64)> {
+        if self.words == 0 {
+            return None;
+        }
+        Some((
+            self.words as f64,
+            self.sentences.max(1) as f64,
+            self.syllables as f64,
+        ))
+    }
+}
+
+/// Readability of a section of a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    /// Heading of the section, from the outline, without its children.
+    pub heading: Heading,
+    /// Counts of the prose within the section, including its subsections.
+    pub counts: Counts,
+}
+
+/// Readability of a document and its sections.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Readability {
+    document: Counts,
+    sections: Vec<Section>,
+}
+
+impl Readability {
+    /// Count the prose of a document.
+    #[must_use]
+    pub fn new<'s, I: IntoIterator<Item = Event<'s>>>(events: I) -> Self {
+        let events: Vec<Event<'s>> = events.into_iter().collect();
+        let mut document = Counts::default();
+        // counts of each section, by id, and the indices of the open sections
+        let mut sections: Vec<(&str, Counts)> = Vec::new();
+        let mut open = Vec::new();
+        let mut text = String::new();
+        let mut skip = 0;
+        for e in &events {
+            match e {
+                Event::Start(Container::Section { id }, _) => {
+                    open.push(sections.len());
+                    sections.push((id, Counts::default()));
+                }
+                Event::End(Container::Section { .. }) => {
+                    open.pop();
+                }
+                Event::Start(c, _) if is_skipped(c) || matches!(c, Container::Heading { .. }) => {
+                    skip += 1;
+                }
+                Event::End(c) if is_skipped(c) || matches!(c, Container::Heading { .. }) => {
+                    skip -= 1;
+                }
+                Event::End(c) if c.is_block() => {
+                    let counts = Counts::new(&text);
+                    document.add(&counts);
+                    for i in &open {
+                        sections[*i].1.add(&counts);
+                    }
+                    text.clear();
+                }
+                e if skip == 0 => push_text(&mut text, e),
+                _ => {}
+            }
+        }
+
+        fn flatten(headings: &[Heading], out: &mut Vec<Heading>) {
+            for h in headings {
+                out.push(Heading {
+                    children: Vec::new(),
+                    ..h.clone()
+                });
+                flatten(&h.children, out);
+            }
+        }
+        let mut headings = Vec::new();
+        flatten(
+            Outline::new(events.iter().cloned()).headings(),
+            &mut headings,
+        );
+        let sections = headings
+            .into_iter()
+            .map(|heading| {
+                let counts = sections
+                    .iter()
+                    .find(|(id, _)| *id == heading.id)
+                    .map_or_else(Counts::default, |(_, c)| *c);
+                Section { heading, counts }
+            })
+            .collect();
+
+        Self { document, sections }
+    }
+
+    /// Counts of the whole document.
+    #[must_use]
+    pub fn document(&self) -> &Counts {
+        &self.document
+    }
+
+    /// Readability of each section, in the order of the document.
+    #[must_use]
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+}
+
+/// Estimate the number of syllables of an English word.
+fn syllables(word: &str) -> usize {
+    let word: Vec<char> = word
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .flat_map(char::to_lowercase)
+        .collect();
+    let is_vowel = |c: &char| matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y');
+    let mut n = 0;
+    let mut prev = false;
+    for c in &word {
+        let vowel = is_vowel(c);
+        if vowel && !prev {
+            n += 1;
+        }
+        prev = vowel;
+    }
+    // silent final e, as in "mate" but not "table" or "free"
+    if n > 1
+        && word.ends_with(&['e'])
+        && !word.ends_with(&['l', 'e'])
+        && !word.ends_with(&['e', 'e'])
+    {
+        n -= 1;
+    }
+    n.max(1)
+}
+
+#[cfg(test)]
+mod test {
+    use super::syllables;
+    use super::Counts;
+    use super::Readability;
+
+    #[test]
+    fn counts() {
+        assert_eq!(
+            Counts::new("Hello, world! Is it \"over\"? (Yes.) Not-quite 42"),
+            Counts {
+                words: 8,
+                sentences: 4,
+                syllables: 11,
+                characters: 31,
+            },
+        );
+        assert_eq!(
+            ["the", "cat", "table", "make", "free", "beautiful", "rhythm"].map(syllables),
+            [1, 1, 2, 1, 1, 3, 1],
+        );
+    }
+
+    #[test]
+    fn prose() {
+        let src = concat!(
+            "# A\n\nOne `two three`.\n\n## B\n\n- Four\n- five six\n\n```\nseven\n```\n\n",
+            "# C\n\n$`x`\n",
+        );
+        let readability = Readability::new(crate::Parser::new(src));
+        assert_eq!(readability.document().words, 4);
+        assert_eq!(readability.document().sentences, 3);
+        let sections: Vec<_> = readability
+            .sections()
+            .iter()
+            .map(|s| (s.heading.text.as_str(), s.counts.words))
+            .collect();
+        assert_eq!(sections, &[("A", 4), ("B", 3), ("C", 0)]);
+        assert_eq!(readability.sections()[2].counts.flesch_reading_ease(), None);
+    }
+}
@@ -0,0 +1,481 @@
+//! Recording and replaying of events.
+//!
+//! Parsing a large document is considerably more expensive than rendering its events. The events
+//! may be recorded to a compact binary format with [`encode`], e.g. to be cached on disk, and
+//! replayed later with [`decode`]. Decoding does not allocate any strings, the decoded events
+//! borrow them from the recording.
+//!
+//! A recording starts with a header containing the version of the format. Recordings of another
+//! version are rejected rather than misinterpreted. Events and containers are identified by their
+//! stable tags, see [`Event::tag`](crate::Event::tag) and
+//! [`Container::tag`](crate::Container::tag).
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! use jotdown::record;
+//!
+//! let src = "# Title\n\nSome _text_[^a].\n\n[^a]: A note.\n";
+//! let mut recording = Vec::new();
+//! record::encode(jotdown::Parser::new(src), &mut recording);
+//!
+//! let events = record::decode(&recording).unwrap();
+//! assert_eq!(events, jotdown::Parser::new(src).collect::<Vec<_>>());
+//! assert_eq!(
+//!     jotdown::html::render_to_string(events.into_iter()),
+//!     jotdown::html::render_to_string(jotdown::Parser::new(src)),
+//! );
+//! # }
+//! ```
+
+use std::hash::Hash;
+use std::hash::Hasher;
+
+use crate::Alignment;
+use crate::AttributeKind;
+use crate::AttributeValue;
+use crate::Attributes;
+use crate::Container;
+use crate::Event;
+use crate::LinkType;
+use crate::ListBulletType;
+use crate::ListKind;
+use crate::OrderedListNumbering;
+use crate::OrderedListStyle;
+use crate::SpanLinkType;
+
+/// Identifier and version of the format, at the start of each recording.
+const HEADER: &[u8] = b"djev\x01";
+
+/// Error returned by [`decode`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodeError {
+    /// Location in the recording where it became invalid.
+    pub pos: usize,
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "invalid event recording at {}", self.pos)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Record events, appending them to `out`.
+pub fn encode<'s, I>(events: I, out: &mut Vec<u8>)
+where
+    I: IntoIterator<Item = Event<'s>>,
+{
+    out.extend_from_slice(HEADER);
+    for e in events {
+        encode_event(&e, out);
+    }
+}
+
+/// Replay recorded events.
+pub fn decode(recording: &[u8]) -> Result<Vec<Event<'_>>, DecodeError> {
+    if !recording.starts_with(HEADER) {
+        return Err(DecodeError { pos: 0 });
+    }
+    let mut decoder = Decoder {
+        bytes: recording,
+        pos: HEADER.len(),
+    };
+    let mut events = Vec::new();
+    while decoder.pos < recording.len() {
+        events.push(decoder.event()?);
+    }
+    Ok(events)
+}
+
+/// Hash of the recording of events, e.g. to identify blocks by their content.
+pub(crate) fn hash(events: &[Event]) -> u64 {
+    let mut recording = Vec::new();
+    encode(events.iter().cloned(), &mut recording);
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    recording.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn encode_event(e: &Event, out: &mut Vec<u8>) {
+    out.push(e.tag());
+    match e {
+        Event::Start(c, attrs) => {
+            encode_container(c, out);
+            encode_attributes(attrs, out);
+        }
+        Event::End(c) => encode_container(c, out),
+        Event::Str(s) | Event::Symbol(s) => encode_str(s, out),
+        Event::FootnoteReference(label) => encode_str(label, out),
+        Event::ThematicBreak(attrs) | Event::Attributes(attrs) => encode_attributes(attrs, out),
+        _ => {}
+    }
+}
+
+fn encode_container(c: &Container, out: &mut Vec<u8>) {
+    out.push(c.tag());
+    match c {
+        Container::List { kind, tight } => {
+            match kind {
+                ListKind::Unordered(bullet) => {
+                    out.push(0);
+                    out.push(*bullet as u8);
+                }
+                ListKind::Ordered {
+                    numbering,
+                    style,
+                    start,
+                } => {
+                    out.push(1);
+                    out.push(*numbering as u8);
+                    out.push(*style as u8);
+                    encode_int(*start, out);
+                }
+                ListKind::Task(bullet) => {
+                    out.push(2);
+                    out.push(*bullet as u8);
+                }
+            }
+            out.push((*tight).into());
+        }
+        Container::TaskListItem { checked: b }
+        | Container::TableRow { head: b }
+        | Container::Math { display: b } => out.push((*b).into()),
+        Container::Footnote { label: s }
+        | Container::Div { class: s }
+        | Container::LinkDefinition { label: s }
+        | Container::RawBlock { format: s }
+        | Container::CodeBlock { language: s }
+        | Container::RawInline { format: s }
+        | Container::CustomInline { name: s } => encode_str(s, out),
+        Container::Section { id } => encode_str(id, out),
+        Container::Heading {
+            level,
+            has_section,
+            id,
+        } => {
+            encode_int((*level).into(), out);
+            out.push((*has_section).into());
+            encode_str(id, out);
+        }
+        Container::TableCell { alignment, head } => {
+            out.push(*alignment as u8);
+            out.push((*head).into());
+        }
+        Container::Link(dst, ty) => {
+            encode_str(dst, out);
+            match ty {
+                LinkType::Span(ty) => {
+                    out.push(0);
+                    out.push(*ty as u8);
+                }
+                LinkType::AutoLink => out.push(1),
+                LinkType::Email => out.push(2),
+            }
+        }
+        Container::Image(src, ty) => {
+            encode_str(src, out);
+            out.push(*ty as u8);
+        }
+        Container::CustomBlock { name, argument } => {
+            encode_str(name, out);
+            encode_str(argument, out);
+        }
+        _ => {}
+    }
+}
+
+fn encode_attributes(attrs: &Attributes, out: &mut Vec<u8>) {
+    encode_int(attrs.len() as u64, out);
+    for (kind, value) in attrs.iter() {
+        match kind {
+            AttributeKind::Class => out.push(0),
+            AttributeKind::Id => out.push(1),
+            AttributeKind::Pair { key } => {
+                out.push(2);
+                encode_str(key, out);
+            }
+            AttributeKind::Comment => out.push(3),
+        }
+        encode_str(value.raw(), out);
+    }
+}
+
+fn encode_str(s: &str, out: &mut Vec<u8>) {
+    encode_int(s.len() as u64, out);
+    out.extend_from_slice(s.as_bytes());
+}
+
+/// Write an integer with 7 bits per byte, least significant first.
+fn encode_int(mut n: u64, out: &mut Vec<u8>) {
+    while n >= 0x80 {
+        out.push((n & 0x7f) as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+struct Decoder<'b> {
+    bytes: &'b [u8],
+    pos: usize,
+}
+
+impl<'b> Decoder<'b> {
+    /// Fail at the previously read byte.
+    fn error<T>(&self) -> Result<T, DecodeError> {
+        Err(DecodeError { pos: self.pos - 1 })
+    }
+
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        let b = *self
+            .bytes
+            .get(self.pos)
+            .ok_or(DecodeError { pos: self.pos })?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn bool(&mut self) -> Result<bool, DecodeError> {
+        match self.byte()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => self.error(),
+        }
+    }
+
+    fn int(&mut self) -> Result<u64, DecodeError> {
+        let start = self.pos;
+        let mut n = 0;
+        for shift in (0..64).step_by(7) {
+            let b = self.byte()?;
+            n |= u64::from(b & 0x7f) << shift;
+            if b & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        Err(DecodeError { pos: start })
+    }
+
+    fn str(&mut self) -> Result<&'b str, DecodeError> {
+        let start = self.pos;
+        let len = self.int()?;
+        let end = usize::try_from(len)
+            .ok()
+            .and_then(|len| self.pos.checked_add(len))
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(DecodeError { pos: start })?;
+        let s = std::str::from_utf8(&self.bytes[self.pos..end])
+            .map_err(|_| DecodeError { pos: start })?;
+        self.pos = end;
+        Ok(s)
+    }
+
+    fn event(&mut self) -> Result<Event<'b>, DecodeError> {
+        Ok(match self.byte()? {
+            0 => Event::Start(self.container()?, self.attributes()?),
+            1 => Event::End(self.container()?),
+            2 => Event::Str(self.str()?.into()),
+            3 => Event::FootnoteReference(self.str()?),
+            4 => Event::Symbol(self.str()?.into()),
+            5 => Event::LeftSingleQuote,
+            6 => Event::RightSingleQuote,
+            7 => Event::LeftDoubleQuote,
+            8 => Event::RightDoubleQuote,
+            9 => Event::Ellipsis,
+            10 => Event::EnDash,
+            11 => Event::EmDash,
+            12 => Event::NonBreakingSpace,
+            13 => Event::Softbreak,
+            14 => Event::Hardbreak,
+            15 => Event::Escape,
+            16 => Event::Blankline,
+            17 => Event::ThematicBreak(self.attributes()?),
+            18 => Event::Attributes(self.attributes()?),
+            _ => return self.error(),
+        })
+    }
+
+    fn container(&mut self) -> Result<Container<'b>, DecodeError> {
+        Ok(match self.byte()? {
+            0 => Container::Blockquote,
+            1 => {
+                let kind = match self.byte()? {
+                    0 => ListKind::Unordered(self.bullet()?),
+                    1 => ListKind::Ordered {
+                        numbering: match self.byte()? {
+                            0 => OrderedListNumbering::Decimal,
+                            1 => OrderedListNumbering::AlphaLower,
+                            2 => OrderedListNumbering::AlphaUpper,
+                            3 => OrderedListNumbering::RomanLower,
+                            4 => OrderedListNumbering::RomanUpper,
+                            _ => return self.error(),
+                        },
+                        style: match self.byte()? {
+                            0 => OrderedListStyle::Period,
+                            1 => OrderedListStyle::Paren,
+                            2 => OrderedListStyle::ParenParen,
+                            _ => return self.error(),
+                        },
+                        start: self.int()?,
+                    },
+                    2 => ListKind::Task(self.bullet()?),
+                    _ => return self.error(),
+                };
+                Container::List {
+                    kind,
+                    tight: self.bool()?,
+                }
+            }
+            2 => Container::ListItem,
+            3 => Container::TaskListItem {
+                checked: self.bool()?,
+            },
+            4 => Container::DescriptionList,
+            5 => Container::DescriptionDetails,
+            6 => Container::Footnote { label: self.str()? },
+            7 => Container::Table,
+            8 => Container::TableRow { head: self.bool()? },
+            9 => Container::Section {
+                id: self.str()?.into(),
+            },
+            10 => Container::Div { class: self.str()? },
+            11 => Container::Paragraph,
+            12 => {
+                let start = self.pos;
+                Container::Heading {
+                    level: u16::try_from(self.int()?).map_err(|_| DecodeError { pos: start })?,
+                    has_section: self.bool()?,
+                    id: self.str()?.into(),
+                }
+            }
+            13 => Container::TableCell {
+                alignment: match self.byte()? {
+                    0 => Alignment::Unspecified,
+                    1 => Alignment::Left,
+                    2 => Alignment::Center,
+                    3 => Alignment::Right,
+                    _ => return self.error(),
+                },
+                head: self.bool()?,
+            },
+            14 => Container::Caption,
+            15 => Container::DescriptionTerm,
+            16 => Container::LinkDefinition { label: self.str()? },
+            17 => Container::RawBlock {
+                format: self.str()?,
+            },
+            18 => Container::CodeBlock {
+                language: self.str()?,
+            },
+            19 => Container::Span,
+            20 => {
+                let dst = self.str()?.into();
+                let ty = match self.byte()? {
+                    0 => LinkType::Span(self.span_link_type()?),
+                    1 => LinkType::AutoLink,
+                    2 => LinkType::Email,
+                    _ => return self.error(),
+                };
+                Container::Link(dst, ty)
+            }
+            21 => Container::Image(self.str()?.into(), self.span_link_type()?),
+            22 => Container::Verbatim,
+            23 => Container::Math {
+                display: self.bool()?,
+            },
+            24 => Container::RawInline {
+                format: self.str()?,
+            },
+            25 => Container::Subscript,
+            26 => Container::Superscript,
+            27 => Container::Insert,
+            28 => Container::Delete,
+            29 => Container::Strong,
+            30 => Container::Emphasis,
+            31 => Container::Mark,
+            32 => Container::CustomInline { name: self.str()? },
+            33 => Container::CustomBlock {
+                name: self.str()?,
+                argument: self.str()?,
+            },
+            _ => return self.error(),
+        })
+    }
+
+    fn bullet(&mut self) -> Result<ListBulletType, DecodeError> {
+        match self.byte()? {
+            0 => Ok(ListBulletType::Dash),
+            1 => Ok(ListBulletType::Star),
+            2 => Ok(ListBulletType::Plus),
+            _ => self.error(),
+        }
+    }
+
+    fn span_link_type(&mut self) -> Result<SpanLinkType, DecodeError> {
+        match self.byte()? {
+            0 => Ok(SpanLinkType::Inline),
+            1 => Ok(SpanLinkType::Reference),
+            2 => Ok(SpanLinkType::Unresolved),
+            _ => self.error(),
+        }
+    }
+
+    fn attributes(&mut self) -> Result<Attributes<'b>, DecodeError> {
+        let len = self.int()?;
+        let mut attrs = Attributes::new();
+        for _ in 0..len {
+            let kind = match self.byte()? {
+                0 => AttributeKind::Class,
+                1 => AttributeKind::Id,
+                2 => AttributeKind::Pair { key: self.str()? },
+                3 => AttributeKind::Comment,
+                _ => return self.error(),
+            };
+            attrs.push((kind, AttributeValue::from(self.str()?)));
+        }
+        Ok(attrs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::decode;
+    use super::encode;
+    use super::DecodeError;
+
+    #[test]
+    fn roundtrip() {
+        let src = concat!(
+            "{#a .b c=\"d\\\"e\" %f%}\n",
+            "# Heading\n",
+            "\n",
+            "3) [link](url){.x} <a@b.c> ![img][ref] 'q' --- ... :sym:\n",
+            "\n",
+            "- [x] task\n",
+            "\n",
+            "| `v` | $`m` |\n",
+            "|:---|---:|\n",
+            "\n",
+            "``` =html\n",
+            "<br>\n",
+            "```\n",
+            "\n",
+            "[ref]: /img.png\n",
+        );
+        let events: Vec<_> = crate::Parser::new(src).collect();
+        let mut recording = Vec::new();
+        encode(events.iter().cloned(), &mut recording);
+        assert_eq!(decode(&recording).unwrap(), events);
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(decode(b"djev\x02"), Err(DecodeError { pos: 0 }));
+        assert_eq!(decode(b"djev\x01\x02\x05ab"), Err(DecodeError { pos: 6 }));
+        assert_eq!(decode(b"djev\x01\x13"), Err(DecodeError { pos: 5 }));
+        assert_eq!(decode(b"djev\x01"), Ok(Vec::new()));
+    }
+}
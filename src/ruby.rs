@@ -0,0 +1,180 @@
+//! Ruby annotations.
+//!
+//! Ruby annotations, e.g. furigana or pinyin, are small annotations placed above or beside the
+//! base text, typically to show the pronunciation of CJK characters. A span with a `ruby`
+//! attribute is annotated with the value of the attribute, e.g. `[漢字]{ruby="かんじ"}`. If the
+//! span only contains text and the annotation consists of one part per character, separated by
+//! spaces, each character is annotated separately, e.g. `[漢字]{ruby="かん じ"}`.
+//!
+//! The markup of the `<ruby>` elements is emitted as raw HTML, with the other attributes of the
+//! span, while the annotations themselves are regular text. They are enclosed in parentheses within `<rp>` elements, which are hidden
+//! by browsers that support ruby, so that renderers of other formats output e.g. `漢字(かんじ)`.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! use jotdown::ruby::Ruby;
+//!
+//! let src = "[漢字]{ruby=\"かん じ\"} and [北京]{ruby=\"Běijīng\"}";
+//! assert_eq!(
+//!     jotdown::html::render_to_string(Ruby::new(jotdown::Parser::new(src))),
+//!     concat!(
+//!         "<p><ruby>",
+//!         "漢<rp>(</rp><rt>かん</rt><rp>)</rp>",
+//!         "字<rp>(</rp><rt>じ</rt><rp>)</rp>",
+//!         "</ruby> and <ruby>",
+//!         "北京<rp>(</rp><rt>Běijīng</rt><rp>)</rp>",
+//!         "</ruby></p>\n",
+//!     ),
+//! );
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+
+use crate::abbr::push_escaped;
+use crate::abbr::raw;
+use crate::Attributes;
+use crate::Container;
+use crate::Event;
+
+/// An iterator adapter that renders spans with a `ruby` attribute as ruby annotations.
+pub struct Ruby<'s, I> {
+    events: I,
+    /// Events to emit before the next event.
+    pending: VecDeque<Event<'s>>,
+}
+
+impl<'s, I> Ruby<'s, I>
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    /// Create an adapter that renders ruby annotations.
+    pub fn new(events: I) -> Self {
+        Self {
+            events,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Queue an annotated span, given its attributes, annotation and content.
+    fn annotate(&mut self, attrs: &Attributes, annotation: &str, content: Vec<Event<'s>>) {
+        let mut open = String::from("<ruby");
+        for (key, value) in attrs.unique_pairs() {
+            if key != "ruby" {
+                open.push(' ');
+                open.push_str(key);
+                open.push_str("=\"");
+                push_escaped(&mut open, &value.to_string());
+                open.push('"');
+            }
+        }
+        open.push('>');
+        self.pending.extend(raw(open));
+        let parts: Vec<&str> = annotation.split_whitespace().collect();
+        match content.as_slice() {
+            [Event::Str(s)] if parts.len() > 1 && s.chars().count() == parts.len() => {
+                for (c, part) in s.chars().zip(parts) {
+                    self.pending.push_back(Event::Str(c.to_string().into()));
+                    self.push_annotation(part);
+                }
+            }
+            _ => {
+                self.pending.extend(content);
+                self.push_annotation(annotation.trim());
+            }
+        }
+        self.pending.extend(raw("</ruby>"));
+    }
+
+    /// Queue the annotation of the preceding base text.
+    fn push_annotation(&mut self, annotation: &str) {
+        self.pending.extend(raw("<rp>"));
+        self.pending.push_back(Event::Str("(".into()));
+        self.pending.extend(raw("</rp><rt>"));
+        self.pending
+            .push_back(Event::Str(annotation.to_string().into()));
+        self.pending.extend(raw("</rt><rp>"));
+        self.pending.push_back(Event::Str(")".into()));
+        self.pending.extend(raw("</rp>"));
+    }
+}
+
+impl<'s, I> Iterator for Ruby<'s, I>
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    type Item = Event<'s>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.pending.pop_front() {
+            return Some(e);
+        }
+        match self.events.next()? {
+            Event::Start(Container::Span, attrs) if attrs.get_value("ruby").is_some() => {
+                let annotation = attrs.get_value("ruby").unwrap().to_string();
+                let mut content = Vec::new();
+                let mut depth = 0;
+                for e in self.events.by_ref() {
+                    match e {
+                        Event::Start(..) => depth += 1,
+                        Event::End(..) if depth == 0 => break,
+                        Event::End(..) => depth -= 1,
+                        _ => {}
+                    }
+                    content.push(e);
+                }
+                let content = Ruby::new(content.into_iter()).collect();
+                self.annotate(&attrs, &annotation, content);
+                self.next()
+            }
+            e => Some(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Ruby;
+    use crate::Container;
+    use crate::Event;
+
+    /// Text of the events, as rendered by a renderer that omits raw HTML.
+    fn text(src: &str) -> String {
+        let mut raw = false;
+        Ruby::new(crate::Parser::new(src))
+            .filter_map(|e| match e {
+                Event::Start(Container::RawInline { .. }, _) => {
+                    raw = true;
+                    None
+                }
+                Event::End(Container::RawInline { .. }) => {
+                    raw = false;
+                    None
+                }
+                Event::Str(s) if !raw => Some(s.to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fallback() {
+        assert_eq!(text("[漢字]{ruby=\"かん じ\"}"), "漢(かん)字(じ)");
+        assert_eq!(text("[漢字]{ruby=\"a b c\"}"), "漢字(a b c)");
+        assert_eq!(text("[_漢_字]{ruby=\"かん じ\"}"), "漢字(かん じ)");
+        assert_eq!(text("[a]{.b}"), "a");
+    }
+
+    #[test]
+    fn attributes() {
+        let src = "[漢字]{ruby=\"かんじ\" .a lang=\"ja&\"}";
+        let open = Ruby::new(crate::Parser::new(src)).nth(2);
+        assert_eq!(
+            open,
+            Some(Event::Str("<ruby class=\"a\" lang=\"ja&amp;\">".into()))
+        );
+    }
+}
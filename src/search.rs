@@ -0,0 +1,228 @@
+//! Search within a document.
+//!
+//! Occurrences of a query are found in the source of a document, and each is reported along
+//! with its structural context: the headings of the sections it is located within, and the kind
+//! of content it is part of, e.g. prose or code.
+//!
+//! # Examples
+//!
+//! ```
+//! use jotdown::search::*;
+//!
+//! let src = "# Setup\n\n## Install\n\nRun `cargo install`, then install it.\n";
+//! let matches = search(src, "install");
+//! assert_eq!(
+//!     matches,
+//!     &[
+//!         Match {
+//!             span: 32..39,
+//!             context: Context::Code,
+//!             headings: vec!["Setup".to_string(), "Install".to_string()],
+//!         },
+//!         Match {
+//!             span: 47..54,
+//!             context: Context::Prose,
+//!             headings: vec!["Setup".to_string(), "Install".to_string()],
+//!         },
+//!     ],
+//! );
+//! ```
+
+use std::ops::Range;
+
+use crate::Container;
+use crate::Event;
+
+/// The kind of content that a match is part of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Context {
+    /// Regular text, e.g. in a paragraph.
+    Prose,
+    /// Text of a heading.
+    Heading,
+    /// Content of a code block or verbatim.
+    Code,
+    /// Content of math.
+    Math,
+    /// Content of a raw block or raw inline.
+    Raw,
+    /// Any other part of the source, e.g. a link destination or attributes.
+    Markup,
+}
+
+/// An occurrence of a query within a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    /// Location of the occurrence in the source.
+    pub span: Range<usize>,
+    /// The kind of content that the occurrence is part of.
+    pub context: Context,
+    /// Text of the headings of all sections that contain the occurrence, from the outermost
+    /// section to the innermost.
+    pub headings: Vec<String>,
+}
+
+/// Find all occurrences of a query within the source of a document.
+///
+/// The query must match the source exactly. Occurrences that are only partially within text,
+/// e.g. across an emphasis boundary, are reported with the [`Context::Markup`] context.
+#[must_use]
+pub fn search(src: &str, query: &str) -> Vec<Match> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    // text events, with their context and the index of their innermost section
+    let mut texts: Vec<(Range<usize>, Context, Option<usize>)> = Vec::new();
+    // sections with their heading text and parent section
+    let mut sections: Vec<(Range<usize>, String, Option<usize>)> = Vec::new();
+    let mut open: Vec<usize> = Vec::new();
+    let mut contexts: Vec<Context> = Vec::new();
+    for (e, span) in crate::Parser::new(src).into_offset_iter() {
+        match e {
+            Event::Start(c, _) => {
+                if let Container::Section { .. } = c {
+                    sections.push((span.start..src.len(), String::new(), open.last().copied()));
+                    open.push(sections.len() - 1);
+                }
+                if let Some(ctx) = context(&c) {
+                    contexts.push(ctx);
+                }
+            }
+            Event::End(c) => {
+                if let Container::Section { .. } = c {
+                    if let Some(i) = open.pop() {
+                        sections[i].0.end = span.end;
+                    }
+                }
+                if context(&c).is_some() {
+                    contexts.pop();
+                }
+            }
+            Event::Str(s) => {
+                let ctx = contexts.last().copied().unwrap_or(Context::Prose);
+                if ctx == Context::Heading {
+                    if let Some(i) = open.last() {
+                        sections[*i].1.push_str(&s);
+                    }
+                }
+                texts.push((span, ctx, open.last().copied()));
+            }
+            e => {
+                if contexts.last() == Some(&Context::Heading) {
+                    if let Some(i) = open.last() {
+                        crate::outline::push_text(&mut sections[*i].1, &e);
+                    }
+                }
+            }
+        }
+    }
+
+    let headings = |mut section: Option<usize>| {
+        let mut headings = Vec::new();
+        while let Some(i) = section {
+            headings.push(sections[i].1.clone());
+            section = sections[i].2;
+        }
+        headings.reverse();
+        headings
+    };
+
+    src.match_indices(query)
+        .map(|(start, _)| {
+            let span = start..start + query.len();
+            let text = texts
+                .iter()
+                .find(|(t, _, _)| t.start <= span.start && span.end <= t.end);
+            let (context, section) = if let Some((_, ctx, section)) = text {
+                (*ctx, *section)
+            } else {
+                let section = sections
+                    .iter()
+                    .rposition(|(s, _, _)| s.start <= span.start && span.end <= s.end);
+                (Context::Markup, section)
+            };
+            Match {
+                span,
+                context,
+                headings: headings(section),
+            }
+        })
+        .collect()
+}
+
+/// Context of the content of a container, if it differs from its parent.
+fn context(c: &Container) -> Option<Context> {
+    match c {
+        Container::Heading { .. } => Some(Context::Heading),
+        Container::CodeBlock { .. } | Container::Verbatim => Some(Context::Code),
+        Container::Math { .. } => Some(Context::Math),
+        Container::RawBlock { .. } | Container::RawInline { .. } => Some(Context::Raw),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::search;
+    use super::Context::*;
+
+    fn contexts(src: &str, query: &str) -> Vec<(super::Context, Vec<String>)> {
+        search(src, query)
+            .into_iter()
+            .map(|m| {
+                assert_eq!(&src[m.span], query);
+                (m.context, m.headings)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn contexts_of_matches() {
+        let src = concat!(
+            "x\n",
+            "\n",
+            "# a x\n",
+            "\n",
+            "$`x` [x](x) `x`{=html}\n",
+            "\n",
+            "## b\n",
+            "\n",
+            "```\n",
+            "x\n",
+            "```\n",
+            "\n",
+            "# c\n",
+            "\n",
+            "*x*\n",
+        );
+        let a = || vec!["a x".to_string()];
+        let ab = || vec!["a x".to_string(), "b".to_string()];
+        assert_eq!(
+            contexts(src, "x"),
+            &[
+                (Prose, vec![]),
+                (Heading, a()),
+                (Math, a()),
+                (Prose, a()),
+                (Markup, a()),
+                (Raw, a()),
+                (Code, ab()),
+                (Prose, vec!["c".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn smart_punctuation() {
+        assert_eq!(
+            contexts("# \"a\" -- b...\n\nx\n", "x"),
+            &[(Prose, vec!["“a” – b…".to_string()])]
+        );
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(search("abc", ""), &[]);
+    }
+}
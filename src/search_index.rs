@@ -0,0 +1,199 @@
+//! Search indices for static sites.
+//!
+//! A document is divided into a record for each section, containing the title of the section,
+//! its anchor and the text of its body, excluding any subsections. The records may be
+//! serialized to a JSON array of documents, in the form expected by client-side search
+//! libraries such as lunr or elasticlunr:
+//!
+//! ```json
+//! [{"id": "page.html#Usage", "title": "Usage", "body": "Run it."}]
+//! ```
+//!
+//! # Examples
+//!
+//! ```
+//! use jotdown::search_index::SearchIndex;
+//!
+//! let src = "Intro.\n\n# Usage\n\nRun \"it\".\n\n## Options\n\nNone.\n";
+//! let mut index = SearchIndex::new();
+//! index.add("page.html", jotdown::Parser::new(src));
+//! assert_eq!(
+//!     index.to_json(),
+//!     concat!(
+//!         "[",
+//!         r#"{"id":"page.html","title":"","body":"Intro."},"#,
+//!         r#"{"id":"page.html#Usage","title":"Usage","body":"Run “it”."},"#,
+//!         r#"{"id":"page.html#Options","title":"Options","body":"None."}"#,
+//!         "]",
+//!     ),
+//! );
+//! ```
+
+use crate::Container;
+use crate::Event;
+
+/// A searchable part of a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// Location of the part, i.e. the URL of the document followed by the id of the section.
+    pub id: String,
+    /// Text of the heading of the section, empty for content before the first section.
+    pub title: String,
+    /// Text content of the section, excluding subsections.
+    pub body: String,
+}
+
+/// A collection of records of one or more documents.
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+    records: Vec<Record>,
+}
+
+impl SearchIndex {
+    /// Create an empty index.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add records for each section of a document, located at the provided URL.
+    pub fn add<'s, I: IntoIterator<Item = Event<'s>>>(&mut self, url: &str, events: I) {
+        let mut current = Record {
+            id: url.to_string(),
+            title: String::new(),
+            body: String::new(),
+        };
+        let mut in_heading = false;
+        let mut skip = 0;
+        for e in events {
+            match e {
+                Event::Start(c, _) if is_skipped(&c) => skip += 1,
+                Event::End(c) if is_skipped(&c) => skip -= 1,
+                _ if skip > 0 => {}
+                Event::Start(Container::Heading { id, .. }, _) => {
+                    self.push(std::mem::replace(
+                        &mut current,
+                        Record {
+                            id: format!("{}#{}", url, id),
+                            title: String::new(),
+                            body: String::new(),
+                        },
+                    ));
+                    in_heading = true;
+                }
+                Event::End(Container::Heading { .. }) => in_heading = false,
+                Event::End(c) if c.is_block() => separate(&mut current.body),
+                e => {
+                    let text = if in_heading {
+                        &mut current.title
+                    } else {
+                        &mut current.body
+                    };
+                    match e {
+                        Event::Softbreak
+                        | Event::Hardbreak
+                        | Event::NonBreakingSpace
+                        | Event::ThematicBreak(..) => separate(text),
+                        e => crate::outline::push_text(text, &e),
+                    }
+                }
+            }
+        }
+        self.push(current);
+    }
+
+    /// Add a record, unless it is empty.
+    fn push(&mut self, mut record: Record) {
+        record.title = record.title.trim().to_string();
+        record.body = record.body.trim().to_string();
+        if !(record.title.is_empty() && record.body.is_empty()) {
+            self.records.push(record);
+        }
+    }
+
+    /// Records of all added documents.
+    #[must_use]
+    pub fn records(&self) -> &[Record] {
+        &self.records
+    }
+
+    /// Serialize the records as a JSON array of objects with the fields `id`, `title` and
+    /// `body`.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("[");
+        for (i, r) in self.records.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str("{\"id\":");
+            json_string(&mut out, &r.id);
+            out.push_str(",\"title\":");
+            json_string(&mut out, &r.title);
+            out.push_str(",\"body\":");
+            json_string(&mut out, &r.body);
+            out.push('}');
+        }
+        out.push(']');
+        out
+    }
+}
+
+/// Separate words with a single space.
+fn separate(s: &mut String) {
+    if !s.is_empty() && !s.ends_with(' ') {
+        s.push(' ');
+    }
+}
+
+/// Write a string as a JSON string literal.
+pub(crate) fn json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Containers whose content is not searchable.
+fn is_skipped(c: &Container) -> bool {
+    matches!(
+        c,
+        Container::RawBlock { .. } | Container::RawInline { .. } | Container::LinkDefinition { .. }
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::SearchIndex;
+
+    #[test]
+    fn escape() {
+        let mut index = SearchIndex::new();
+        index.add(
+            "a\"b",
+            crate::Parser::new("``` =html\n<x>\n```\n\n`\\ \u{1}`"),
+        );
+        assert_eq!(
+            index.to_json(),
+            r#"[{"id":"a\"b","title":"","body":"\\ \u0001"}]"#
+        );
+    }
+
+    #[test]
+    fn blocks() {
+        let mut index = SearchIndex::new();
+        index.add("", crate::Parser::new("# a *b*\n\n- c\n- d\n\n> e\nf\n"));
+        assert_eq!(index.records().len(), 1);
+        assert_eq!(index.records()[0].title, "a b");
+        assert_eq!(index.records()[0].body, "c d e f");
+    }
+}
@@ -0,0 +1,260 @@
+//! Expansion of shortcodes.
+//!
+//! A shortcode is a div or span with a class that has been registered with a handler, similar
+//! to the shortcodes of e.g. Hugo. The [`Shortcodes`] adapter replaces each such element with the
+//! events or HTML returned by its handler, which is given the attributes and the content of the
+//! element:
+//!
+//! ```djot
+//! ::: youtube
+//! dQw4w9WgXcQ
+//! :::
+//!
+//! Press [Ctrl+C]{.kbd} to copy.
+//! ```
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! use jotdown::shortcode::*;
+//! use jotdown::Event;
+//!
+//! let src = "::: youtube\nabc\n:::\n\nPress [Ctrl+C]{.kbd} to copy.\n";
+//! let events = Shortcodes::new(jotdown::Parser::new(src))
+//!     .with("youtube", |s: &Shortcode| {
+//!         Expansion::Html(format!(
+//!             "<iframe src=\"https://www.youtube.com/embed/{}\"></iframe>",
+//!             s.text().trim(),
+//!         ))
+//!     })
+//!     .with("kbd", |s: &Shortcode| {
+//!         Expansion::Html(format!("<kbd>{}</kbd>", s.text()))
+//!     });
+//! assert_eq!(
+//!     jotdown::html::render_to_string(events),
+//!     concat!(
+//!         "<iframe src=\"https://www.youtube.com/embed/abc\"></iframe>\n",
+//!         "<p>Press <kbd>Ctrl+C</kbd> to copy.</p>\n",
+//!     ),
+//! );
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+
+use crate::Attributes;
+use crate::Container;
+use crate::Event;
+
+/// An element to be expanded by a handler.
+#[derive(Debug)]
+pub struct Shortcode<'a, 's> {
+    /// The registered class of the element.
+    pub name: &'a str,
+    /// Attributes of the element.
+    pub attributes: &'a Attributes<'s>,
+    /// Events within the element, excluding its start and end.
+    pub content: &'a [Event<'s>],
+    /// Whether the element is a div, as opposed to a span.
+    pub block: bool,
+}
+
+impl<'a, 's> Shortcode<'a, 's> {
+    /// Text content of the element, i.e. without any markup.
+    #[must_use]
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for e in self.content {
+            match e {
+                Event::Str(s) => text.push_str(s),
+                Event::Softbreak | Event::Hardbreak => text.push('\n'),
+                Event::NonBreakingSpace => text.push(' '),
+                _ => {}
+            }
+        }
+        text
+    }
+}
+
+/// Replacement of a shortcode, returned by a handler.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expansion<'s> {
+    /// Events to emit in place of the element.
+    ///
+    /// For divs, the events should form block elements, and for spans, inline elements.
+    Events(Vec<Event<'s>>),
+    /// HTML to emit as a raw block or raw inline, in place of the element.
+    Html(String),
+}
+
+type Handler<'h, 's> = Box<dyn FnMut(&Shortcode<'_, 's>) -> Expansion<'s> + 'h>;
+
+/// An iterator adapter that expands shortcodes.
+///
+/// The content of a shortcode is passed to its handler as is, and the returned events are not
+/// searched for further shortcodes.
+pub struct Shortcodes<'h, 's, I> {
+    events: I,
+    handlers: Vec<(String, Handler<'h, 's>)>,
+    /// Events of an expansion that have not yet been emitted.
+    pending: VecDeque<Event<'s>>,
+}
+
+impl<'h, 's, I> Shortcodes<'h, 's, I>
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    /// Create an adapter without any registered shortcodes.
+    pub fn new(events: I) -> Self {
+        Self {
+            events,
+            handlers: Vec::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Expand divs and spans with the class `name` using the provided handler.
+    ///
+    /// If an element has multiple registered classes, the handler that was registered first is
+    /// used.
+    #[must_use]
+    pub fn with<F>(mut self, name: &str, handler: F) -> Self
+    where
+        F: FnMut(&Shortcode<'_, 's>) -> Expansion<'s> + 'h,
+    {
+        self.handlers.push((name.to_string(), Box::new(handler)));
+        self
+    }
+
+    /// Index of the handler of a container, if it is a shortcode.
+    fn handler(&self, c: &Container, attrs: &Attributes) -> Option<usize> {
+        let div_class = match c {
+            Container::Div { class } => class,
+            Container::Span => "",
+            _ => return None,
+        };
+        let classes = attrs
+            .get_value("class")
+            .map_or_else(String::new, |c| c.to_string());
+        self.handlers
+            .iter()
+            .position(|(name, _)| name == div_class || classes.split(' ').any(|c| c == name))
+    }
+
+    /// Expand the shortcode starting with the provided event.
+    fn expand(&mut self, i: usize, start: Container<'s>, attrs: Attributes<'s>) {
+        let mut content = Vec::new();
+        let mut depth = 0;
+        for e in self.events.by_ref() {
+            match e {
+                Event::Start(..) => depth += 1,
+                Event::End(..) if depth == 0 => break,
+                Event::End(..) => depth -= 1,
+                _ => {}
+            }
+            content.push(e);
+        }
+        let block = start.is_block();
+        let (name, handler) = &mut self.handlers[i];
+        let expansion = handler(&Shortcode {
+            name,
+            attributes: &attrs,
+            content: &content,
+            block,
+        });
+        match expansion {
+            Expansion::Events(events) => self.pending.extend(events),
+            Expansion::Html(html) => {
+                let raw = if block {
+                    Container::RawBlock { format: "html" }
+                } else {
+                    Container::RawInline { format: "html" }
+                };
+                self.pending
+                    .push_back(Event::Start(raw.clone(), Attributes::new()));
+                self.pending.push_back(Event::Str(html.into()));
+                self.pending.push_back(Event::End(raw));
+            }
+        }
+    }
+}
+
+impl<'h, 's, I> Iterator for Shortcodes<'h, 's, I>
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    type Item = Event<'s>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            match self.events.next()? {
+                Event::Start(c, attrs) => {
+                    if let Some(i) = self.handler(&c, &attrs) {
+                        self.expand(i, c, attrs);
+                    } else {
+                        return Some(Event::Start(c, attrs));
+                    }
+                }
+                e => return Some(e),
+            }
+        }
+        self.pending.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Expansion;
+    use super::Shortcode;
+    use super::Shortcodes;
+    use crate::Container::*;
+    use crate::Event::*;
+
+    #[test]
+    fn events() {
+        let events: Vec<_> = Shortcodes::new(crate::Parser::new("a [b *c*]{.x #y} d"))
+            .with("x", |s: &Shortcode| {
+                assert!(!s.block);
+                assert_eq!(s.name, "x");
+                assert_eq!(s.content.len(), 4);
+                Expansion::Events(vec![Str(s.text().to_uppercase().into())])
+            })
+            .collect();
+        assert_eq!(
+            events,
+            &[
+                Start(Paragraph, Default::default()),
+                Str("a ".into()),
+                Str("B C".into()),
+                Str(" d".into()),
+                End(Paragraph),
+            ]
+        );
+    }
+
+    #[test]
+    fn nested() {
+        let src = "{.x}\n:::: y\n::: z\na\n:::\n::::\n\nb\n";
+        let mut calls = 0;
+        let events: Vec<_> = Shortcodes::new(crate::Parser::new(src))
+            .with("z", |_: &Shortcode| unreachable!())
+            .with("x", |s: &Shortcode| {
+                calls += 1;
+                assert!(s.block);
+                Expansion::Events(Vec::new())
+            })
+            .collect();
+        assert_eq!(calls, 1);
+        assert_eq!(
+            events,
+            &[
+                Blankline,
+                Start(Paragraph, Default::default()),
+                Str("b".into()),
+                End(Paragraph),
+            ]
+        );
+    }
+}
@@ -0,0 +1,344 @@
+//! Rendering of documents as slides.
+//!
+//! A document is split into slides before each heading at or above a specified level, 2 by
+//! default, and at each thematic break outside of other blocks. The thematic breaks themselves
+//! are removed. Each slide is rendered as a `<section>` element, as expected by presentation
+//! frameworks such as [reveal.js](https://revealjs.com), which may be placed within the
+//! `<div class="slides">` element of a reveal.js page.
+//!
+//! Footnotes are placed on each slide they are referenced from, with ids that are prefixed by
+//! the number of the slide, e.g. `slide-2-fn1`. Divs with the class `notes` are speaker notes,
+//! which are not shown on the slide. They are rendered within an `<aside class="notes">` element
+//! at the end of the slide, where reveal.js shows them in its speaker view, and are also
+//! available separately as [`Slide::notes`]. Footnotes referenced within speaker notes are
+//! placed within the notes.
+//!
+//! # Examples
+//!
+//! ```
+//! use jotdown::slides::*;
+//!
+//! let src = concat!(
+//!     "# Djot\n",
+//!     "\n",
+//!     "A light markup language.\n",
+//!     "\n",
+//!     "***\n",
+//!     "\n",
+//!     "It has *strong* text.\n",
+//!     "\n",
+//!     "::: notes\n",
+//!     "Mention the _emphasis_ too.\n",
+//!     ":::\n",
+//!     "\n",
+//!     "## Syntax\n",
+//!     "\n",
+//!     "- easy to parse\n",
+//! );
+//! let slides = Slides::new().with_renderer(jotdown::html::Renderer::minified());
+//! assert_eq!(
+//!     slides.render(jotdown::Parser::new(src)),
+//!     concat!(
+//!         "<section><h1 id=\"Djot\">Djot</h1><p>A light markup language.</p></section>\n",
+//!         "<section><p>It has <strong>strong</strong> text.</p>",
+//!         "<aside class=\"notes\"><p>Mention the <em>emphasis</em> too.</p></aside></section>\n",
+//!         "<section><h2 id=\"Syntax\">Syntax</h2><ul><li>easy to parse</li></ul></section>\n",
+//!     ),
+//! );
+//! ```
+
+use crate::html::Renderer;
+use crate::split::Footnotes;
+use crate::Container;
+use crate::Event;
+use crate::Render;
+
+/// A single slide of a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slide<'s> {
+    /// Events of the slide, without any sections or speaker notes.
+    pub events: Vec<Event<'s>>,
+    /// Events of the speaker notes of the slide, i.e. the content of its `notes` divs, with the
+    /// footnotes referenced within them.
+    pub notes: Vec<Event<'s>>,
+}
+
+impl<'s> Slide<'s> {
+    fn new() -> Self {
+        Self {
+            events: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+}
+
+/// Split a document into slides before each heading whose level is at most `level`, and at each
+/// thematic break outside of other blocks.
+///
+/// Sections are removed, so that each slide only contains complete elements. Headings are given
+/// the id of their section instead.
+///
+/// # Examples
+///
+/// ```
+/// # use jotdown::slides::*;
+/// let src = "# a\n\nb\n\n## c\n\n***\n\nd\n";
+/// assert_eq!(slides(jotdown::Parser::new(src), 1).len(), 2);
+/// assert_eq!(slides(jotdown::Parser::new(src), 2).len(), 3);
+/// ```
+pub fn slides<'s, I>(events: I, level: u16) -> Vec<Slide<'s>>
+where
+    I: IntoIterator<Item = Event<'s>>,
+{
+    let mut slides = vec![Slide::new()];
+    let mut footnotes = Footnotes::new();
+    // depth of blocks other than sections
+    let mut depth = 0;
+    // depth of containers within the current speaker notes
+    let mut notes: Option<usize> = None;
+    for e in events {
+        let e = match footnotes.collect(e) {
+            Some(e) => e,
+            None => continue,
+        };
+        if let Some(d) = &mut notes {
+            match e {
+                Event::Start(..) => *d += 1,
+                Event::End(..) if *d == 0 => {
+                    notes = None;
+                    continue;
+                }
+                Event::End(..) => *d -= 1,
+                _ => {}
+            }
+            slides.last_mut().unwrap().notes.push(e);
+            continue;
+        }
+        if let Event::Start(Container::Div { class: "notes" }, _) = e {
+            notes = Some(0);
+            continue;
+        }
+        match e {
+            Event::ThematicBreak(_) if depth == 0 => {
+                slides.push(Slide::new());
+                continue;
+            }
+            Event::Start(Container::Heading { level: l, .. }, _) if l <= level && depth == 0 => {
+                slides.push(Slide::new());
+            }
+            _ => {}
+        }
+        if let Some(e) = unsectioned(e, &mut depth) {
+            slides.last_mut().unwrap().events.push(e);
+        }
+    }
+
+    slides.retain(|s| {
+        !(s.events.iter().chain(&s.notes))
+            .all(|e| matches!(e, Event::Blankline | Event::Attributes(_)))
+    });
+
+    for slide in &mut slides {
+        footnotes.append(&mut slide.events);
+        footnotes.append(&mut slide.notes);
+    }
+
+    slides
+}
+
+/// Remove the sections of a document that is rendered in parts, returning `None` for the events
+/// of sections.
+///
+/// Headings are given the id of their section instead. The depth of the blocks that enclose the
+/// next event, other than sections, is updated.
+pub(crate) fn unsectioned<'s>(e: Event<'s>, depth: &mut usize) -> Option<Event<'s>> {
+    Some(match e {
+        Event::Start(Container::Section { .. }, _) | Event::End(Container::Section { .. }) => {
+            return None;
+        }
+        Event::Start(Container::Heading { level, id, .. }, attrs) => {
+            *depth += 1;
+            Event::Start(
+                Container::Heading {
+                    level,
+                    has_section: false,
+                    id,
+                },
+                attrs,
+            )
+        }
+        Event::End(Container::Heading { level, id, .. }) => {
+            *depth -= 1;
+            Event::End(Container::Heading {
+                level,
+                has_section: false,
+                id,
+            })
+        }
+        Event::Start(ref c, _) if c.is_block() => {
+            *depth += 1;
+            e
+        }
+        Event::End(ref c) if c.is_block() => {
+            *depth -= 1;
+            e
+        }
+        e => e,
+    })
+}
+
+/// A renderer of documents as HTML slides.
+#[derive(Clone)]
+pub struct Slides {
+    renderer: Renderer,
+    level: u16,
+}
+
+impl Default for Slides {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Slides {
+    /// Create a renderer that splits documents at headings of level 1 and 2, and renders the
+    /// slides with the default HTML renderer.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            renderer: Renderer::default(),
+            level: 2,
+        }
+    }
+
+    /// Render the content of the slides with the provided renderer.
+    #[must_use]
+    pub fn with_renderer(mut self, renderer: Renderer) -> Self {
+        self.renderer = renderer;
+        self
+    }
+
+    /// Start a new slide at each heading whose level is at most `level`.
+    #[must_use]
+    pub fn with_level(mut self, level: u16) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Render a document to a sequence of `<section>` elements, one for each slide.
+    pub fn render<'s, I>(&self, events: I) -> String
+    where
+        I: IntoIterator<Item = Event<'s>>,
+    {
+        let mut html = String::new();
+        for (i, slide) in slides(events, self.level).into_iter().enumerate() {
+            html.push_str("<section>");
+            let prefix = format!("slide-{}-", i + 1);
+            self.renderer
+                .clone()
+                .with_footnote_id_prefix(&prefix)
+                .push(slide.events.into_iter(), &mut html)
+                .unwrap();
+            if !slide.notes.is_empty() {
+                html.push_str("<aside class=\"notes\">");
+                self.renderer
+                    .clone()
+                    .with_footnote_id_prefix(&format!("{}notes-", prefix))
+                    .push(slide.notes.into_iter(), &mut html)
+                    .unwrap();
+                html.push_str("</aside>");
+            }
+            html.push_str("</section>\n");
+        }
+        html
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::slides;
+    use super::Slides;
+    use crate::Container::*;
+    use crate::Event::*;
+
+    #[test]
+    fn breaks() {
+        let src = "***\n\n> a\n>\n> ***\n\n***\n\n***\n\nb\n";
+        let slides = slides(crate::Parser::new(src), 2);
+        assert_eq!(slides.len(), 2);
+        assert!(slides[0]
+            .events
+            .iter()
+            .any(|e| matches!(e, ThematicBreak(_))));
+    }
+
+    #[test]
+    fn nested_headings() {
+        for src in [
+            "::: x\n# a\n\nb\n\n# c\n:::\n",
+            "> # a\n>\n> # b\n",
+            "- # a\n\n  # b\n",
+        ] {
+            let slides = slides(crate::Parser::new(src), 2);
+            assert_eq!(slides.len(), 1, "{}", src);
+            let starts = slides[0]
+                .events
+                .iter()
+                .filter(|e| matches!(e, Start(..)))
+                .count();
+            let ends = slides[0]
+                .events
+                .iter()
+                .filter(|e| matches!(e, End(..)))
+                .count();
+            assert_eq!(starts, ends, "{}", src);
+        }
+    }
+
+    #[test]
+    fn notes() {
+        let src = "# a\n\n:::: notes\n::: x\nb\n:::\n::::\n\n::: notes\n***\n:::\n\n# c\n";
+        let slides = slides(crate::Parser::new(src), 1);
+        assert_eq!(slides.len(), 2);
+        assert!(slides[0]
+            .notes
+            .contains(&ThematicBreak(crate::Attributes::new())));
+        assert!(slides[0]
+            .notes
+            .iter()
+            .any(|e| matches!(e, End(Div { class: "x" }))));
+        assert!(!slides[0]
+            .events
+            .iter()
+            .any(|e| matches!(e, Start(Div { .. }, _))));
+        assert!(slides[1].notes.is_empty());
+    }
+
+    #[test]
+    fn footnotes() {
+        let src = "# a\n\nx[^n]\n\n# b\n\ny[^n]\n\n[^n]: note\n";
+        let renderer = crate::html::Renderer::minified();
+        let html = Slides::new()
+            .with_renderer(renderer)
+            .render(crate::Parser::new(src));
+        assert_eq!(html.matches("role=\"doc-endnotes\"").count(), 2);
+        assert!(
+            html.contains(r##"<a id="slide-1-fnref1" href="#slide-1-fn1" role="doc-noteref">"##)
+        );
+        assert!(html.contains(r#"<li id="slide-2-fn1">"#));
+    }
+
+    #[test]
+    fn notes_footnotes() {
+        let src = "# a\n\nx[^n]\n\n::: notes\ny[^m]\n:::\n\n[^n]: n\n\n[^m]: m\n";
+        let html = Slides::new()
+            .with_renderer(crate::html::Renderer::minified())
+            .render(crate::Parser::new(src));
+        assert!(html.contains(r#"<li id="slide-1-fn1"><p>n<a"#));
+        assert!(html.contains(r#"<li id="slide-1-notes-fn1"><p>m<a"#));
+        assert!(slides(crate::Parser::new(src), 1).iter().all(|s| !s
+            .events
+            .iter()
+            .any(|e| matches!(e, Start(Section { .. }, _)))));
+    }
+}
@@ -0,0 +1,147 @@
+//! Extraction of parts of a document.
+//!
+//! A single element of a larger document, e.g. a section, may be extracted in order to embed it
+//! elsewhere. The element is identified either by its id or, for sections, by the text of its
+//! heading. The extracted events form a complete subtree, from the start of the element to its
+//! end.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! use jotdown::snippet;
+//!
+//! let src = "# Usage\n\nRun it.\n\n# License\n\n{#mit}\nMIT\n";
+//! let usage = snippet::by_heading(jotdown::Parser::new(src), "Usage").unwrap();
+//! assert_eq!(
+//!     jotdown::html::render_to_string(usage.into_iter()),
+//!     "<section id=\"Usage\">\n<h1>Usage</h1>\n<p>Run it.</p>\n</section>\n",
+//! );
+//! let mit = snippet::by_id(jotdown::Parser::new(src), "mit").unwrap();
+//! assert_eq!(
+//!     jotdown::html::render_to_string(mit.into_iter()),
+//!     "<p id=\"mit\">MIT</p>\n",
+//! );
+//! # }
+//! ```
+
+use crate::Container;
+use crate::Event;
+
+/// Extract the element with the specified id, if it exists.
+///
+/// Ids of sections, headings and all elements with an explicit id are considered.
+pub fn by_id<'s, I: IntoIterator<Item = Event<'s>>>(events: I, id: &str) -> Option<Vec<Event<'s>>> {
+    extract(events, |e, _| match e {
+        Event::Start(Container::Section { id: i } | Container::Heading { id: i, .. }, _) => {
+            i.as_ref() == id
+        }
+        Event::Start(_, attrs) | Event::ThematicBreak(attrs) => {
+            attrs.get_value("id").map_or(false, |i| i.to_string() == id)
+        }
+        _ => false,
+    })
+}
+
+/// Extract the section whose heading has the specified text, if it exists.
+///
+/// The text is compared to the text content of the heading, i.e. without any markup. If the
+/// heading is not part of a section, only the heading is extracted.
+pub fn by_heading<'s, I: IntoIterator<Item = Event<'s>>>(
+    events: I,
+    title: &str,
+) -> Option<Vec<Event<'s>>> {
+    extract(events, |e, rest| {
+        // events that follow the start of the heading
+        let heading = match e {
+            Event::Start(Container::Section { .. }, _) => match rest.first() {
+                Some(Event::Start(Container::Heading { .. }, _)) => &rest[1..],
+                _ => return false,
+            },
+            Event::Start(
+                Container::Heading {
+                    has_section: false, ..
+                },
+                _,
+            ) => rest,
+            _ => return false,
+        };
+        let mut text = String::new();
+        for e in heading {
+            match e {
+                Event::End(Container::Heading { .. }) => break,
+                Event::Str(s) => text.push_str(s),
+                Event::Softbreak | Event::NonBreakingSpace => text.push(' '),
+                _ => {}
+            }
+        }
+        text.trim() == title
+    })
+}
+
+/// Extract the first element whose start matches the predicate, which is passed the event and
+/// all events that follow it.
+fn extract<'s, I, F>(events: I, is_match: F) -> Option<Vec<Event<'s>>>
+where
+    I: IntoIterator<Item = Event<'s>>,
+    F: Fn(&Event<'s>, &[Event<'s>]) -> bool,
+{
+    let events: Vec<Event<'s>> = events.into_iter().collect();
+    let start = (0..events.len()).find(|i| is_match(&events[*i], &events[i + 1..]))?;
+    let mut depth = 0;
+    let mut end = start;
+    for (i, e) in events.iter().enumerate().skip(start) {
+        match e {
+            Event::Start(..) => depth += 1,
+            Event::End(..) => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            end = i;
+            break;
+        }
+    }
+    Some(events.into_iter().take(end + 1).skip(start).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::by_heading;
+    use super::by_id;
+    use crate::Container::*;
+    use crate::Event;
+    use crate::Event::*;
+
+    fn parse(src: &str) -> Vec<Event<'_>> {
+        crate::Parser::new(src).collect()
+    }
+
+    #[test]
+    fn nested_section() {
+        let src = "# a\n\n## b *c*\n\nd\n\n## e\n";
+        let b = by_heading(parse(src), "b c").unwrap();
+        assert_eq!(
+            b.first(),
+            Some(&Start(Section { id: "b-c".into() }, Default::default()))
+        );
+        assert_eq!(b.last(), Some(&End(Section { id: "b-c".into() })));
+        assert!(b.contains(&Str("d".into())));
+        assert!(!b.contains(&Str("e".into())));
+        assert_eq!(by_id(parse(src), "b-c"), Some(b));
+    }
+
+    #[test]
+    fn heading_without_section() {
+        let src = "> # a\n>\n> b\n";
+        let a = by_heading(parse(src), "a").unwrap();
+        assert_eq!(a.len(), 3);
+    }
+
+    #[test]
+    fn leaf() {
+        let src = "{#t}\n***\n";
+        assert_eq!(by_id(parse(src), "t").unwrap().len(), 1);
+        assert_eq!(by_id(parse(src), "u"), None);
+    }
+}
@@ -0,0 +1,188 @@
+//! Hashtags and mentions.
+//!
+//! Hashtags, e.g. `#rust`, and mentions, e.g. `@alice`, are recognized by the syntax extensions
+//! [`Extensions::with_hashtags`] and [`Extensions::with_mentions`], which emit them as custom
+//! inline containers named `hashtag` and `mention`. Their content is the text of the element,
+//! including the `#` or `@`, so that they are rendered as e.g. `<span class="hashtag">#rust</span>`
+//! by default.
+//!
+//! The [`Tags`] adapter turns them into links, with destinations provided by a resolver. The
+//! links have the class `hashtag` or `mention`, and elements whose destination is not resolved
+//! are left as is.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! use jotdown::extension::Extensions;
+//! use jotdown::social::{Kind, Tags};
+//!
+//! let extensions = Extensions::new().with_hashtags().with_mentions();
+//! let src = "Thanks @alice and @bob for #rust-1, mail bob@example.com";
+//! let events = jotdown::Parser::with_extensions(src, &extensions);
+//! let events = Tags::new(events, |kind, name| match kind {
+//!     Kind::Hashtag => Some(format!("/tags/{}", name)),
+//!     Kind::Mention => (name == "alice").then(|| format!("/users/{}", name)),
+//! });
+//! assert_eq!(
+//!     jotdown::html::render_to_string(events),
+//!     concat!(
+//!         "<p>Thanks <a href=\"/users/alice\" class=\"mention\">@alice</a> and ",
+//!         "<span class=\"mention\">@bob</span> for ",
+//!         "<a href=\"/tags/rust-1\" class=\"hashtag\">#rust-1</a>, ",
+//!         "mail bob@example.com</p>\n",
+//!     ),
+//! );
+//! # }
+//! ```
+//!
+//! [`Extensions::with_hashtags`]: crate::extension::Extensions::with_hashtags
+//! [`Extensions::with_mentions`]: crate::extension::Extensions::with_mentions
+
+use std::collections::VecDeque;
+
+use crate::AttributeKind;
+use crate::Container;
+use crate::Event;
+use crate::LinkType;
+use crate::SpanLinkType;
+
+/// Kind of a tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// A hashtag, e.g. `#rust`.
+    Hashtag,
+    /// A mention of a user, e.g. `@alice`.
+    Mention,
+}
+
+impl Kind {
+    /// Name of the custom container and class of the link.
+    fn name(self) -> &'static str {
+        match self {
+            Self::Hashtag => "hashtag",
+            Self::Mention => "mention",
+        }
+    }
+}
+
+/// An iterator adapter that turns hashtags and mentions into links.
+///
+/// The resolver is given the kind of the tag and its name, without the `#` or `@`, and returns
+/// the destination of the link, if any.
+pub struct Tags<'s, I, F> {
+    events: I,
+    resolve: F,
+    /// Events to emit before the next event.
+    pending: VecDeque<Event<'s>>,
+}
+
+impl<'s, I, F> Tags<'s, I, F>
+where
+    I: Iterator<Item = Event<'s>>,
+    F: FnMut(Kind, &str) -> Option<String>,
+{
+    /// Create an adapter that resolves tags with the provided function.
+    pub fn new(events: I, resolve: F) -> Self {
+        Self {
+            events,
+            resolve,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<'s, I, F> Iterator for Tags<'s, I, F>
+where
+    I: Iterator<Item = Event<'s>>,
+    F: FnMut(Kind, &str) -> Option<String>,
+{
+    type Item = Event<'s>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.pending.pop_front() {
+            return Some(e);
+        }
+        let (kind, mut attrs) = match self.events.next()? {
+            Event::Start(Container::CustomInline { name: "hashtag" }, attrs) => {
+                (Kind::Hashtag, attrs)
+            }
+            Event::Start(Container::CustomInline { name: "mention" }, attrs) => {
+                (Kind::Mention, attrs)
+            }
+            e => return Some(e),
+        };
+        let container = Container::CustomInline { name: kind.name() };
+        let text = match self.events.next() {
+            Some(Event::Str(s)) => s,
+            e => {
+                self.pending.extend(e);
+                return Some(Event::Start(container, attrs));
+            }
+        };
+        let name = text.get(1..).unwrap_or_default();
+        let link = match (self.resolve)(kind, name) {
+            Some(dst) => Container::Link(dst.into(), LinkType::Span(SpanLinkType::Inline)),
+            None => {
+                self.pending.push_back(Event::Str(text));
+                return Some(Event::Start(container, attrs));
+            }
+        };
+        // the end of the custom container
+        self.events.next();
+        attrs.push((AttributeKind::Class, kind.name().into()));
+        self.pending.push_back(Event::Str(text));
+        self.pending.push_back(Event::End(link.clone()));
+        Some(Event::Start(link, attrs))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Kind;
+    use super::Tags;
+    use crate::extension::Extensions;
+    use crate::Container;
+    use crate::Event;
+
+    /// Tags of a document, with their destinations if resolved.
+    fn tags(src: &str) -> Vec<String> {
+        let extensions = Extensions::new().with_hashtags().with_mentions();
+        let events = crate::Parser::with_extensions(src, &extensions);
+        Tags::new(events, |kind, name| {
+            (kind == Kind::Mention && name != "nobody").then(|| format!("/{}", name))
+        })
+        .filter_map(|e| match e {
+            Event::Start(Container::Link(dst, _), _) => Some(format!("{}:", dst)),
+            Event::Start(Container::CustomInline { name }, _) => Some(format!("{}:", name)),
+            Event::Str(s) => Some(s.to_string()),
+            _ => None,
+        })
+        .collect()
+    }
+
+    #[test]
+    fn syntax() {
+        assert_eq!(
+            tags("#a_b-. #123 a#b #1x \\#c `#d` x@y @a.b. @nobody @"),
+            &[
+                "hashtag:",
+                "#a_b",
+                "-. #123 a#b ",
+                "hashtag:",
+                "#1x",
+                " ",
+                "#c ",
+                "#d",
+                " x@y ",
+                "/a.b:",
+                "@a.b",
+                ". ",
+                "mention:",
+                "@nobody",
+                " @",
+            ],
+        );
+    }
+}
@@ -0,0 +1,266 @@
+//! Splitting of a document into multiple pages.
+//!
+//! A document is split before each section whose heading is at or above a specified level. The
+//! content before the first such section forms a separate page, if it is not empty.
+//!
+//! Links to an id on another page, e.g. `[](#Install)`, are rewritten to point at that page,
+//! e.g. `Install.html#Install`. Footnotes are placed on each page they are referenced from.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! let src = "Start at [](#Install).\n\n# Install\n\nSee [](#Usage).\n\n# Usage\n";
+//! let pages = jotdown::split::split(jotdown::Parser::new(src), 1);
+//! assert_eq!(
+//!     pages.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(),
+//!     &["index", "Install", "Usage"],
+//! );
+//! assert_eq!(
+//!     jotdown::html::render_to_string(pages[1].events.clone().into_iter()),
+//!     concat!(
+//!         "<section id=\"Install\">\n",
+//!         "<h1>Install</h1>\n",
+//!         "<p>See <a href=\"Usage.html#Usage\"></a>.</p>\n",
+//!         "</section>\n",
+//!     ),
+//! );
+//! # }
+//! ```
+
+use crate::Container;
+use crate::Event;
+use crate::LinkType;
+use crate::Map;
+
+/// A single page of a split document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page<'s> {
+    /// Name of the page, i.e. the id of its section or `index` for the content before the
+    /// first section.
+    pub name: String,
+    /// Events of the page.
+    pub events: Vec<Event<'s>>,
+}
+
+impl Page<'_> {
+    /// Output path of the page, e.g. `Install.html`.
+    #[must_use]
+    pub fn path(&self) -> String {
+        format!("{}.html", self.name)
+    }
+}
+
+/// Split a document before each section whose heading level is at most `level`.
+pub fn split<'s, I: IntoIterator<Item = Event<'s>>>(events: I, level: u16) -> Vec<Page<'s>> {
+    let mut pages = vec![Page {
+        name: "index".to_string(),
+        events: Vec::new(),
+    }];
+    let mut footnotes = Footnotes::new();
+    // open sections, and whether they have been closed before a split
+    let mut sections: Vec<bool> = Vec::new();
+
+    let mut events = events.into_iter().peekable();
+    while let Some(e) = events.next() {
+        let e = match footnotes.collect(e) {
+            Some(e) => e,
+            None => continue,
+        };
+        match &e {
+            Event::Start(Container::Section { id }, _) => {
+                let is_split = matches!(
+                    events.peek(),
+                    Some(Event::Start(Container::Heading { level: l, .. }, _)) if *l <= level
+                );
+                if is_split {
+                    let page = pages.last_mut().unwrap();
+                    for closed in sections.iter_mut().filter(|c| !**c) {
+                        page.events
+                            .push(Event::End(Container::Section { id: "".into() }));
+                        *closed = true;
+                    }
+                    pages.push(Page {
+                        name: id.to_string(),
+                        events: Vec::new(),
+                    });
+                }
+                sections.push(false);
+            }
+            Event::End(Container::Section { .. }) if sections.pop() == Some(true) => continue,
+            _ => {}
+        }
+        pages.last_mut().unwrap().events.push(e);
+    }
+
+    if pages[0]
+        .events
+        .iter()
+        .all(|e| matches!(e, Event::Blankline | Event::End(..)))
+    {
+        pages.remove(0);
+    }
+
+    // close sections with the matching id, as the ends were pushed before the id was known
+    for page in &mut pages {
+        let mut open = Vec::new();
+        for e in &mut page.events {
+            match e {
+                Event::Start(Container::Section { id }, _) => open.push(id.clone()),
+                Event::End(Container::Section { id }) => {
+                    if let Some(open) = open.pop() {
+                        *id = open;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut locations = Map::new();
+    for (i, page) in pages.iter().enumerate() {
+        for e in &page.events {
+            let id = match e {
+                Event::Start(Container::Section { id } | Container::Heading { id, .. }, _) => {
+                    Some(id.to_string())
+                }
+                Event::Start(_, attrs) => attrs.get_value("id").map(|id| id.to_string()),
+                _ => None,
+            };
+            if let Some(id) = id {
+                locations.entry(id).or_insert(i);
+            }
+        }
+    }
+
+    let paths = pages.iter().map(Page::path).collect::<Vec<_>>();
+    for (i, page) in pages.iter_mut().enumerate() {
+        for e in &mut page.events {
+            if let Event::Start(Container::Link(dst, LinkType::Span(_)), _)
+            | Event::End(Container::Link(dst, LinkType::Span(_))) = e
+            {
+                let target = dst.strip_prefix('#').and_then(|id| locations.get(id));
+                if let Some(&j) = target.filter(|j| **j != i) {
+                    *dst = format!("{}{}", paths[j], dst).into();
+                }
+            }
+        }
+        footnotes.append(&mut page.events);
+    }
+
+    pages
+}
+
+/// Footnote definitions of a document that is divided into parts, e.g. pages or slides.
+///
+/// The definitions are removed from the document, and placed on each part they are referenced
+/// from, as they may be defined after the parts that reference them.
+pub(crate) struct Footnotes<'s> {
+    definitions: Map<&'s str, Vec<Event<'s>>>,
+    /// Label and events of the current definition.
+    open: Option<(&'s str, Vec<Event<'s>>)>,
+}
+
+impl<'s> Footnotes<'s> {
+    pub(crate) fn new() -> Self {
+        Self {
+            definitions: Map::new(),
+            open: None,
+        }
+    }
+
+    /// Collect the event if it is part of a footnote definition, or else return it.
+    pub(crate) fn collect(&mut self, e: Event<'s>) -> Option<Event<'s>> {
+        if let Some((label, evs)) = &mut self.open {
+            let end = matches!(e, Event::End(Container::Footnote { .. }));
+            evs.push(e);
+            if end {
+                let (label, evs) = (*label, std::mem::take(evs));
+                self.definitions.insert(label, evs);
+                self.open = None;
+            }
+            None
+        } else if let Event::Start(Container::Footnote { label }, _) = e {
+            self.open = Some((label, vec![e]));
+            None
+        } else {
+            Some(e)
+        }
+    }
+
+    /// Append the definitions of the footnotes that are referenced within the events.
+    pub(crate) fn append(&self, events: &mut Vec<Event<'s>>) {
+        let mut labels = Vec::new();
+        for e in events.iter() {
+            if let Event::FootnoteReference(label) = e {
+                if !labels.contains(label) {
+                    labels.push(*label);
+                }
+            }
+        }
+        for label in labels {
+            if let Some(evs) = self.definitions.get(label) {
+                events.extend(evs.iter().cloned());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::split;
+    use crate::Container::*;
+    use crate::Event::*;
+
+    fn names(src: &str, level: u16) -> Vec<String> {
+        split(crate::Parser::new(src), level)
+            .into_iter()
+            .map(|p| p.name)
+            .collect()
+    }
+
+    #[test]
+    fn levels() {
+        let src = "# a\n\n## b\n\n### c\n\n## d\n\n# e\n";
+        assert_eq!(names(src, 1), &["a", "e"]);
+        assert_eq!(names(src, 2), &["a", "b", "d", "e"]);
+        assert_eq!(names(src, 3), &["a", "b", "c", "d", "e"]);
+    }
+
+    #[test]
+    fn balanced() {
+        let pages = split(crate::Parser::new("# a\n\n## b\n\n## c\n"), 2);
+        for page in pages {
+            let starts = page
+                .events
+                .iter()
+                .filter(|e| matches!(e, Start(Section { .. }, _)))
+                .count();
+            let ends = page
+                .events
+                .iter()
+                .filter(|e| matches!(e, End(Section { .. })))
+                .count();
+            assert_eq!(starts, ends, "{}", page.name);
+            assert_eq!(
+                page.events.last(),
+                Some(&End(Section {
+                    id: page.name.clone().into()
+                }))
+            );
+        }
+    }
+
+    #[test]
+    fn footnotes() {
+        let src = "# a\n\nx[^n]\n\n# b\n\ny[^n]\n\n[^n]: note\n";
+        let pages = split(crate::Parser::new(src), 1);
+        for page in &pages {
+            assert!(page
+                .events
+                .iter()
+                .any(|e| matches!(e, Start(Footnote { label: "n" }, _))));
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Statistics of documents.
+//!
+//! Content dashboards and heuristics, e.g. for detecting spam or choosing a layout, often need
+//! an overview of the structure of a document. [`Statistics`] summarizes the events of a
+//! document: the number of elements of each kind, how deeply they are nested, and some counts of
+//! particular interest.
+//!
+//! # Examples
+//!
+//! ```
+//! use jotdown::stats::Statistics;
+//!
+//! let src = concat!(
+//!     "# Title\n",
+//!     "\n",
+//!     "> A [link](https://example.com) and a note[^a].\n",
+//!     "\n",
+//!     "```\n",
+//!     "one\n",
+//!     "two\n",
+//!     "```\n",
+//!     "\n",
+//!     "[^a]: ![image](a.png)\n",
+//! );
+//! let stats = Statistics::new(jotdown::Parser::new(src));
+//! assert_eq!(stats.elements["Paragraph"], 2);
+//! assert_eq!(stats.elements["Heading"], 1);
+//! assert_eq!(stats.max_depth, 4);
+//! assert_eq!((stats.links, stats.images, stats.footnotes), (1, 1, 1));
+//! assert_eq!(stats.longest_code_block, 2);
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::Container;
+use crate::Event;
+
+/// Statistics of a document.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Statistics {
+    /// Number of elements of each kind, by the name of their container or event, e.g.
+    /// `Paragraph` or `Softbreak`.
+    pub elements: BTreeMap<String, usize>,
+    /// Maximum nesting depth of containers, including sections.
+    pub max_depth: usize,
+    /// Number of links.
+    pub links: usize,
+    /// Number of images.
+    pub images: usize,
+    /// Number of footnote definitions.
+    pub footnotes: usize,
+    /// Number of lines of the longest code block.
+    pub longest_code_block: usize,
+}
+
+impl Statistics {
+    /// Compute the statistics of a document.
+    pub fn new<'s, I: IntoIterator<Item = Event<'s>>>(events: I) -> Self {
+        let mut stats = Self::default();
+        let mut depth = 0;
+        let mut code: Option<usize> = None;
+        for e in events {
+            match &e {
+                Event::Start(c, _) => {
+                    depth += 1;
+                    stats.max_depth = stats.max_depth.max(depth);
+                    *stats.elements.entry(name(c)).or_insert(0) += 1;
+                    match c {
+                        Container::Link(..) => stats.links += 1,
+                        Container::Image(..) => stats.images += 1,
+                        Container::Footnote { .. } => stats.footnotes += 1,
+                        Container::CodeBlock { .. } => code = Some(0),
+                        _ => {}
+                    }
+                }
+                Event::End(c) => {
+                    depth -= 1;
+                    if let (Container::CodeBlock { .. }, Some(lines)) = (c, code.take()) {
+                        stats.longest_code_block = stats.longest_code_block.max(lines);
+                    }
+                }
+                e => {
+                    if let (Event::Str(s), Some(lines)) = (e, &mut code) {
+                        *lines += s.lines().count();
+                    }
+                    *stats.elements.entry(name(e)).or_insert(0) += 1;
+                }
+            }
+        }
+        stats
+    }
+}
+
+/// Name of the variant of a container or event, e.g. `Heading`.
+fn name<T: std::fmt::Debug>(value: &T) -> String {
+    format!("{:?}", value)
+        .chars()
+        .take_while(char::is_ascii_alphanumeric)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::Statistics;
+
+    #[test]
+    fn elements() {
+        let src = "- a\n\n  - b *c*\n\n```\nd\n\ne\n```\n\n```\nf\n```\n";
+        let stats = Statistics::new(crate::Parser::new(src));
+        assert_eq!(
+            stats.elements.iter().collect::<Vec<_>>(),
+            &[
+                (&"Blankline".to_string(), &3),
+                (&"CodeBlock".to_string(), &2),
+                (&"List".to_string(), &2),
+                (&"ListItem".to_string(), &2),
+                (&"Paragraph".to_string(), &2),
+                (&"Str".to_string(), &7),
+                (&"Strong".to_string(), &1),
+            ],
+        );
+        assert_eq!(stats.max_depth, 6);
+        assert_eq!(stats.longest_code_block, 3);
+        assert_eq!(
+            Statistics::new(crate::Parser::new("")),
+            Statistics::default()
+        );
+    }
+}
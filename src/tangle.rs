@@ -0,0 +1,238 @@
+//! Extraction of source files from literate programs.
+//!
+//! A literate program is a document whose code blocks together form the source code of a
+//! program. Each code block with a `file` attribute, e.g. `{file="src/main.rs"}`, is appended to
+//! the specified file, in the order of the document. Code blocks may also be selected by their
+//! language, and written to a default file if they have no `file` attribute.
+//!
+//! The span in the document of each line of a file is retained, so that errors reported for the
+//! generated files, e.g. by a compiler, can be mapped back to the document.
+//!
+//! # Examples
+//!
+//! ```
+//! use jotdown::tangle::Tangle;
+//!
+//! let src = concat!(
+//!     "{file=\"src/main.rs\"}\n",
+//!     "``` rust\n",
+//!     "fn main() {\n",
+//!     "```\n",
+//!     "\n",
+//!     "It prints a greeting:\n",
+//!     "\n",
+//!     "{file=\"src/main.rs\"}\n",
+//!     "``` rust\n",
+//!     "    println!(\"hello\");\n",
+//!     "}\n",
+//!     "```\n",
+//! );
+//! let files = Tangle::new().files(src);
+//! assert_eq!(files.len(), 1);
+//! assert_eq!(files[0].path, "src/main.rs");
+//! assert_eq!(files[0].content, "fn main() {\n    println!(\"hello\");\n}\n");
+//! assert_eq!(&src[files[0].lines[1].clone()], "    println!(\"hello\");\n");
+//! ```
+
+use std::ops::Range;
+
+use crate::Attributes;
+use crate::Container;
+use crate::Event;
+use crate::Parser;
+
+/// A code block of a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block<'s> {
+    /// Language of the code block, empty if not specified.
+    pub language: &'s str,
+    /// Attributes of the code block.
+    pub attributes: Attributes<'s>,
+    /// Content of the code block.
+    pub code: String,
+    /// Span in the document of each line of the content.
+    pub lines: Vec<Range<usize>>,
+}
+
+impl Block<'_> {
+    /// Value of the `file` attribute, if any.
+    #[must_use]
+    pub fn file(&self) -> Option<String> {
+        self.attributes.get_value("file").map(|v| v.to_string())
+    }
+}
+
+/// Extract all code blocks of a document.
+pub fn blocks(src: &str) -> Vec<Block<'_>> {
+    let mut blocks = Vec::new();
+    let mut current: Option<Block> = None;
+    for (e, span) in Parser::new(src).into_offset_iter() {
+        match e {
+            Event::Start(Container::CodeBlock { language }, attributes) => {
+                current = Some(Block {
+                    language,
+                    attributes,
+                    code: String::new(),
+                    lines: Vec::new(),
+                });
+            }
+            Event::Str(s) => {
+                if let Some(block) = &mut current {
+                    block.code.push_str(&s);
+                    block.lines.push(span);
+                }
+            }
+            Event::End(Container::CodeBlock { .. }) => blocks.extend(current.take()),
+            _ => {}
+        }
+    }
+    blocks
+}
+
+/// A source file assembled from code blocks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct File {
+    /// Path of the file, relative to the output directory.
+    pub path: String,
+    /// Concatenated content of the code blocks.
+    pub content: String,
+    /// Span in the document of each line of the content.
+    pub lines: Vec<Range<usize>>,
+}
+
+impl File {
+    /// Write the file relative to the specified directory, creating any missing parent
+    /// directories.
+    ///
+    /// Absolute paths and paths with `..` components are rejected, so that a document cannot
+    /// write files outside of the directory.
+    pub fn write<P: AsRef<std::path::Path>>(&self, dir: P) -> std::io::Result<()> {
+        use std::path::Component;
+
+        let path = std::path::Path::new(&self.path);
+        if !path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("invalid path of tangled file: {}", self.path),
+            ));
+        }
+        let path = dir.as_ref().join(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, &self.content)
+    }
+}
+
+/// Options for extracting source files from documents.
+#[derive(Debug, Clone, Default)]
+pub struct Tangle {
+    language: Option<String>,
+    default_file: Option<String>,
+}
+
+impl Tangle {
+    /// Extract code blocks of any language with a `file` attribute.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only extract code blocks of the specified language.
+    #[must_use]
+    pub fn with_language(mut self, language: &str) -> Self {
+        self.language = Some(language.to_string());
+        self
+    }
+
+    /// Append code blocks without a `file` attribute to the specified file, instead of
+    /// ignoring them.
+    #[must_use]
+    pub fn with_default_file(mut self, path: &str) -> Self {
+        self.default_file = Some(path.to_string());
+        self
+    }
+
+    /// Assemble the files of a document, in the order they are first referred to.
+    pub fn files(&self, src: &str) -> Vec<File> {
+        let mut files: Vec<File> = Vec::new();
+        for block in blocks(src) {
+            if self
+                .language
+                .as_ref()
+                .map_or(false, |l| l != block.language)
+            {
+                continue;
+            }
+            let path = match block.file().or_else(|| self.default_file.clone()) {
+                Some(path) => path,
+                None => continue,
+            };
+            let i = match files.iter().position(|f| f.path == path) {
+                Some(i) => i,
+                None => {
+                    files.push(File {
+                        path,
+                        content: String::new(),
+                        lines: Vec::new(),
+                    });
+                    files.len() - 1
+                }
+            };
+            files[i].content.push_str(&block.code);
+            files[i].lines.extend(block.lines);
+        }
+        files
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::File;
+    use super::Tangle;
+
+    #[test]
+    fn select() {
+        let src = concat!(
+            "``` rust\nfn a() {}\n```\n\n",
+            "> {file=\"b.py\"}\n> ``` python\n> b = 1\n> ```\n\n",
+            "{file=\"c.rs\"}\n``` rust\nfn c() {}\n```\n\n",
+            "``` rust\nfn d() {}\n```\n",
+        );
+        let files = Tangle::new().files(src);
+        assert_eq!(
+            files.iter().map(|f| f.path.as_str()).collect::<Vec<_>>(),
+            &["b.py", "c.rs"],
+        );
+        assert_eq!(&src[files[0].lines[0].clone()], "b = 1\n");
+
+        let files = Tangle::new()
+            .with_language("rust")
+            .with_default_file("lib.rs")
+            .files(src);
+        assert_eq!(
+            files.iter().map(|f| f.path.as_str()).collect::<Vec<_>>(),
+            &["lib.rs", "c.rs"],
+        );
+        assert_eq!(files[0].content, "fn a() {}\nfn d() {}\n");
+        assert_eq!(files[0].lines.len(), 2);
+    }
+
+    #[test]
+    fn write() {
+        let dir = std::env::temp_dir().join(format!("jotdown-tangle-{}", std::process::id()));
+        let file = |path: &str| File {
+            path: path.to_string(),
+            content: "x\n".to_string(),
+            lines: Vec::new(),
+        };
+        file("a/b.txt").write(&dir).unwrap();
+        assert_eq!(std::fs::read_to_string(dir.join("a/b.txt")).unwrap(), "x\n");
+        assert!(file("../c.txt").write(&dir).is_err());
+        assert!(file("/c.txt").write(&dir).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
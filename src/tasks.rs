@@ -0,0 +1,99 @@
+//! Checkboxes of task lists.
+//!
+//! Interactive previews let users check off tasks by clicking on the checkboxes of task list
+//! items. The change is applied to the source by replacing the marker of the checkbox, e.g.
+//! `[ ]` with `[x]`, at the span reported by [`checkboxes`]. The checkboxes are listed in the
+//! order of the document, which is also the order of the `<input>` elements of the rendered
+//! HTML, so that the index of a clicked checkbox identifies its marker.
+//!
+//! # Examples
+//!
+//! ```
+//! use jotdown::tasks;
+//!
+//! let src = "- [ ] write\n- [x] test\n";
+//! let boxes = tasks::checkboxes(src);
+//! assert_eq!(boxes.len(), 2);
+//! assert_eq!(&src[boxes[1].span.clone()], "[x]");
+//! assert_eq!(
+//!     tasks::toggle(src, 0).unwrap(),
+//!     "- [x] write\n- [x] test\n",
+//! );
+//! ```
+
+use std::ops::Range;
+
+use crate::Container;
+use crate::Event;
+use crate::Parser;
+
+/// The checkbox of a task list item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkbox {
+    /// Whether the task is checked.
+    pub checked: bool,
+    /// Span in the document of the marker, e.g. `[ ]`.
+    pub span: Range<usize>,
+}
+
+impl Checkbox {
+    /// The marker of the checkbox with its state inverted, i.e. `[x]` or `[ ]`.
+    #[must_use]
+    pub fn toggled(&self) -> &'static str {
+        if self.checked {
+            "[ ]"
+        } else {
+            "[x]"
+        }
+    }
+}
+
+/// Find the checkboxes of all task list items of a document.
+pub fn checkboxes(src: &str) -> Vec<Checkbox> {
+    Parser::new(src)
+        .into_offset_iter()
+        .filter_map(|(e, span)| match e {
+            Event::Start(Container::TaskListItem { checked }, _) => {
+                // the span of the item is its list marker followed by the checkbox
+                let start = span.start + src[span.clone()].rfind('[')?;
+                Some(Checkbox {
+                    checked,
+                    span: start..span.end,
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Invert the state of the checkbox with the specified index, returns the modified document or
+/// `None` if there is no such checkbox.
+pub fn toggle(src: &str, index: usize) -> Option<String> {
+    let checkbox = checkboxes(src).into_iter().nth(index)?;
+    let mut out = String::with_capacity(src.len());
+    out.push_str(&src[..checkbox.span.start]);
+    out.push_str(checkbox.toggled());
+    out.push_str(&src[checkbox.span.end..]);
+    Some(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::checkboxes;
+    use super::toggle;
+
+    #[test]
+    fn nested() {
+        let src = "> - [X] a\n>\n>   + [ ] b\n\n- [ ]\n- c\n";
+        let spans: Vec<_> = checkboxes(src)
+            .into_iter()
+            .map(|c| (c.checked, &src[c.span]))
+            .collect();
+        assert_eq!(spans, &[(true, "[X]"), (false, "[ ]"), (false, "[ ]")]);
+        assert_eq!(
+            toggle(src, 0).unwrap(),
+            "> - [ ] a\n>\n>   + [ ] b\n\n- [ ]\n- c\n",
+        );
+        assert_eq!(toggle(src, 3), None);
+    }
+}
@@ -0,0 +1,159 @@
+//! Helpers for snapshot tests.
+//!
+//! Crates that extend jotdown, e.g. with filters or renderers, may test them the way the crate
+//! itself is tested: by comparing a normalized snapshot of the output with the expected one. The
+//! snapshots are plain text, so that they may be written inline or stored in files, and
+//! mismatches are reported line by line.
+//!
+//! This module is only available with the `testing` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use jotdown::testing::*;
+//!
+//! let events = jotdown::Parser::new("a _b_");
+//! assert_snapshot(
+//!     &events_snapshot(events),
+//!     concat!(
+//!         "Start(Paragraph, {})\n",
+//!         "  Str(\"a \")\n",
+//!         "  Start(Emphasis, {})\n",
+//!         "    Str(\"b\")\n",
+//!         "  End(Emphasis)\n",
+//!         "End(Paragraph)\n",
+//!     ),
+//! );
+//! ```
+
+use crate::Event;
+
+/// A snapshot of events, with one event per line and the content of each container indented.
+#[must_use]
+pub fn events_snapshot<'s, I>(events: I) -> String
+where
+    I: IntoIterator<Item = Event<'s>>,
+{
+    let mut snapshot = String::new();
+    let mut depth: usize = 0;
+    for e in events {
+        if let Event::End(..) = e {
+            depth = depth.saturating_sub(1);
+        }
+        snapshot.push_str(&"  ".repeat(depth));
+        snapshot.push_str(&format!("{:?}\n", e));
+        if let Event::Start(..) = e {
+            depth += 1;
+        }
+    }
+    snapshot
+}
+
+/// A snapshot of the HTML output of the default renderer.
+///
+/// # Examples
+///
+/// ```
+/// use jotdown::testing::*;
+///
+/// let html = html_snapshot(jotdown::Parser::new("> a"));
+/// assert_snapshot(&html, "<blockquote>\n<p>a</p>\n</blockquote>");
+/// ```
+#[cfg(feature = "html")]
+#[must_use]
+pub fn html_snapshot<'s, I>(events: I) -> String
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    crate::html::render_to_string(events).trim().to_string()
+}
+
+/// Compare two snapshots, returning a report of the lines that differ, or `None` if they are
+/// equal apart from trailing whitespace.
+///
+/// The report shows the actual and expected lines side by side, with `=` between equal lines
+/// and `!` between differing lines.
+///
+/// # Examples
+///
+/// ```
+/// use jotdown::testing::*;
+///
+/// assert_eq!(compare("a\nb\n", "a\nb"), None);
+/// assert_eq!(compare("a\nb", "a\nc").unwrap(), "a    =    a\nb    !    c\n");
+/// ```
+#[must_use]
+pub fn compare(actual: &str, expected: &str) -> Option<String> {
+    let (actual, expected) = (actual.trim_end(), expected.trim_end());
+    if actual == expected {
+        return None;
+    }
+    let a = actual.split('\n');
+    let b = expected.split('\n');
+    let max = a.clone().count().max(b.clone().count());
+    let a_width = a.clone().map(|a| a.chars().count()).max().unwrap_or(0);
+    Some(
+        a.chain(std::iter::repeat(""))
+            .zip(b.chain(std::iter::repeat("")))
+            .take(max)
+            .map(|(a, b)| {
+                format!(
+                    "{:a_width$}    {}    {}\n",
+                    a,
+                    if a == b { '=' } else { '!' },
+                    b,
+                    a_width = a_width,
+                )
+            })
+            .collect(),
+    )
+}
+
+/// Assert that two snapshots are equal, see [`compare`].
+///
+/// # Panics
+///
+/// Panics with a report of the differing lines if the snapshots are not equal.
+#[track_caller]
+pub fn assert_snapshot(actual: &str, expected: &str) {
+    if let Some(report) = compare(actual, expected) {
+        panic!(
+            "snapshots differ, actual (left) vs expected (right):\n{}",
+            report
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::assert_snapshot;
+    use super::events_snapshot;
+
+    #[test]
+    fn nested() {
+        assert_snapshot(
+            &events_snapshot(crate::Parser::new("- a\n\n  > b")),
+            concat!(
+                "Start(List { kind: Unordered(Dash), tight: false }, {})\n",
+                "  Start(ListItem, {})\n",
+                "    Start(Paragraph, {})\n",
+                "      Str(\"a\")\n",
+                "    End(Paragraph)\n",
+                "    Blankline\n",
+                "    Start(Blockquote, {})\n",
+                "      Start(Paragraph, {})\n",
+                "        Str(\"b\")\n",
+                "      End(Paragraph)\n",
+                "    End(Blockquote)\n",
+                "  End(ListItem)\n",
+                "End(List { kind: Unordered(Dash), tight: false })\n",
+            ),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "b    !    c")]
+    fn mismatch() {
+        assert_snapshot("a\nb", "a\nc");
+    }
+}
@@ -0,0 +1,258 @@
+//! Validation of event streams.
+//!
+//! The events of a [`Parser`](crate::Parser) are always well-formed, but events that have been
+//! modified by a filter, or created from scratch, may not be. A renderer given malformed events
+//! may panic or produce invalid output, so filters can be checked with [`validate`] before their
+//! output is rendered, e.g. in their tests.
+//!
+//! An event stream is well-formed if each container is closed by an end event for the same
+//! container, and block elements are only placed within block containers, e.g. not within a
+//! paragraph or a span. Events with locations are additionally checked by [`validate_offsets`].
+//!
+//! # Examples
+//!
+//! ```
+//! use jotdown::validate::*;
+//! use jotdown::*;
+//!
+//! let events: Vec<_> = Parser::new("a *b*").collect();
+//! assert_eq!(validate(events.iter().cloned()), Ok(()));
+//!
+//! // a filter that forgot to replace the end of a container
+//! let filtered = events.into_iter().map(|e| match e {
+//!     Event::Start(Container::Strong, attrs) => Event::Start(Container::Emphasis, attrs),
+//!     e => e,
+//! });
+//! assert_eq!(
+//!     validate(filtered),
+//!     Err(Malformed {
+//!         index: 4,
+//!         kind: MalformedKind::Mismatched(Container::Emphasis.tag()),
+//!     }),
+//! );
+//! ```
+
+use std::ops::Range;
+
+use crate::Container;
+use crate::Event;
+
+/// An error returned by [`validate`] and [`validate_offsets`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Malformed {
+    /// Index of the offending event.
+    pub index: usize,
+    /// The kind of error.
+    pub kind: MalformedKind,
+}
+
+/// A kind of error within an event stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MalformedKind {
+    /// An end event without an open container.
+    UnexpectedEnd,
+    /// An end event for another container than the innermost open container, with the tag of
+    /// the open container, see [`Container::tag`].
+    Mismatched(u8),
+    /// A block element within an inline element or a leaf block, e.g. a paragraph.
+    BlockInInline,
+    /// A container that is still open at the end of the stream, with its tag.
+    Unclosed(u8),
+    /// A location that is reversed or extends beyond the end of the source.
+    OutOfBounds,
+    /// A location that begins before the end of the location of the previous event.
+    Overlapping,
+}
+
+impl std::fmt::Display for Malformed {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let msg = match self.kind {
+            MalformedKind::UnexpectedEnd => "end of container that is not open",
+            MalformedKind::Mismatched(..) => "end of another container than the open one",
+            MalformedKind::BlockInInline => "block element within inline content",
+            MalformedKind::Unclosed(..) => "container is never closed",
+            MalformedKind::OutOfBounds => "location outside of the source",
+            MalformedKind::Overlapping => "location overlaps the previous event",
+        };
+        write!(f, "malformed event at {}: {}", self.index, msg)
+    }
+}
+
+impl std::error::Error for Malformed {}
+
+/// Check that an event stream is well-formed.
+pub fn validate<'s, I>(events: I) -> Result<(), Malformed>
+where
+    I: IntoIterator<Item = Event<'s>>,
+{
+    let mut stack = Vec::new();
+    let mut n = 0;
+    for (index, e) in events.into_iter().enumerate() {
+        check(&mut stack, &e).map_err(|kind| Malformed { index, kind })?;
+        n = index + 1;
+    }
+    end(&stack, n)
+}
+
+/// Check that an event stream with locations is well-formed, and that the locations are in order
+/// and within a source of length `len`.
+///
+/// Each location must begin at or after the end of the previous location, like those of an
+/// [`OffsetIter`](crate::OffsetIter). The only exception are table captions, which are located
+/// after the rows of their table but precede them in the event stream, so the rows are compared
+/// with the location before the caption.
+///
+/// # Examples
+///
+/// ```
+/// use jotdown::validate::*;
+/// use jotdown::*;
+///
+/// let src = "> a\n";
+/// let events: Vec<_> = Parser::new(src).into_offset_iter().collect();
+/// assert_eq!(validate_offsets(events.iter().cloned(), src.len()), Ok(()));
+/// assert_eq!(
+///     validate_offsets(events.iter().cloned(), 2),
+///     Err(Malformed { index: 2, kind: MalformedKind::OutOfBounds }),
+/// );
+/// ```
+pub fn validate_offsets<'s, I>(events: I, len: usize) -> Result<(), Malformed>
+where
+    I: IntoIterator<Item = (Event<'s>, Range<usize>)>,
+{
+    let mut stack = Vec::new();
+    let mut prev_end = 0;
+    // end of the location before the current caption
+    let mut before_caption = None;
+    let mut n = 0;
+    for (index, (e, span)) in events.into_iter().enumerate() {
+        let kind = if span.start > span.end || span.end > len {
+            Some(MalformedKind::OutOfBounds)
+        } else if span.start < prev_end {
+            Some(MalformedKind::Overlapping)
+        } else {
+            check(&mut stack, &e).err()
+        };
+        if let Some(kind) = kind {
+            return Err(Malformed { index, kind });
+        }
+        prev_end = match e {
+            Event::Start(Container::Caption, _) => {
+                before_caption = Some(prev_end);
+                span.end
+            }
+            Event::End(Container::Caption) => before_caption.take().unwrap_or(span.end),
+            _ => span.end,
+        };
+        n = index + 1;
+    }
+    end(&stack, n)
+}
+
+/// Check a single event, given the containers it is within.
+fn check<'s>(stack: &mut Vec<Container<'s>>, e: &Event<'s>) -> Result<(), MalformedKind> {
+    let in_inline = stack.last().map_or(false, |c| !c.is_block_container());
+    match e {
+        Event::Start(c, _) => {
+            if in_inline && c.is_block() {
+                return Err(MalformedKind::BlockInInline);
+            }
+            stack.push(c.clone());
+        }
+        Event::End(c) => match stack.pop() {
+            None => return Err(MalformedKind::UnexpectedEnd),
+            Some(open) if open != *c => return Err(MalformedKind::Mismatched(open.tag())),
+            Some(_) => {}
+        },
+        Event::ThematicBreak(..) if in_inline => return Err(MalformedKind::BlockInInline),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn end(stack: &[Container], n: usize) -> Result<(), Malformed> {
+    stack.last().map_or(Ok(()), |c| {
+        Err(Malformed {
+            index: n,
+            kind: MalformedKind::Unclosed(c.tag()),
+        })
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::validate;
+    use super::Malformed;
+    use super::MalformedKind;
+    use crate::Attributes;
+    use crate::Container;
+    use crate::Event;
+
+    #[test]
+    fn parser() {
+        let src = concat!(
+            "{#a}\n",
+            "# h\n",
+            "\n",
+            "> - [x] a[^b]\n",
+            ">\n",
+            ">   | c | *d* |\n",
+            "\n",
+            "[^b]: e\n",
+            "\n",
+            "    ***\n",
+            "\n",
+            "f\n",
+            ": g\n",
+        );
+        assert_eq!(validate(crate::Parser::new(src)), Ok(()));
+        assert_eq!(
+            super::validate_offsets(crate::Parser::new(src).into_offset_iter(), src.len()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn caption() {
+        let src = "| a | b |\n|---|:-:|\n| c | d |\n^ cap\n";
+        assert_eq!(
+            super::validate_offsets(crate::Parser::new(src).into_offset_iter(), src.len()),
+            Ok(())
+        );
+        let mut events: Vec<_> = crate::Parser::new(src).into_offset_iter().collect();
+        events[6].1 = 0..1;
+        assert_eq!(
+            super::validate_offsets(events, src.len()),
+            Err(Malformed {
+                index: 6,
+                kind: MalformedKind::Overlapping
+            }),
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        let para = || Event::Start(Container::Paragraph, Attributes::new());
+        assert_eq!(
+            validate(vec![Event::End(Container::Span)]),
+            Err(Malformed {
+                index: 0,
+                kind: MalformedKind::UnexpectedEnd
+            })
+        );
+        assert_eq!(
+            validate(vec![para(), para()]),
+            Err(Malformed {
+                index: 1,
+                kind: MalformedKind::BlockInInline
+            })
+        );
+        assert_eq!(
+            validate(vec![para()]),
+            Err(Malformed {
+                index: 1,
+                kind: MalformedKind::Unclosed(Container::Paragraph.tag())
+            })
+        );
+    }
+}
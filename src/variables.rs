@@ -0,0 +1,276 @@
+//! Substitution of variables.
+//!
+//! Placeholders of the form `{{name}}` within text are replaced by the values of variables,
+//! provided by e.g. a [`HashMap`]. Names consist of alphanumeric characters, `_`, `-` and `.`,
+//! and may be surrounded by spaces. Placeholders are not replaced within code, verbatim, math,
+//! raw content or link definitions, or when the first brace is escaped, `\{{name}}`.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! use std::collections::HashMap;
+//! use jotdown::variables::*;
+//!
+//! let src = "Version {{ version }} of *{{name}}*, see `{{name}}`.\n";
+//! let mut vars = HashMap::new();
+//! vars.insert("name", "jotdown");
+//! vars.insert("version", "0.8.0");
+//! let events = Variables::new(jotdown::Parser::new(src), &vars);
+//! assert_eq!(
+//!     jotdown::html::render_to_string(events),
+//!     "<p>Version 0.8.0 of <strong>jotdown</strong>, see <code>{{name}}</code>.</p>\n",
+//! );
+//! # }
+//! ```
+
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::iter::Peekable;
+
+use crate::Container;
+use crate::CowStr;
+use crate::Event;
+
+/// A collection of variables that may be looked up by name.
+pub trait Lookup {
+    /// Value of the variable with the specified name, if it exists.
+    fn lookup(&self, name: &str) -> Option<&str>;
+}
+
+impl<K, V, S> Lookup for HashMap<K, V, S>
+where
+    K: Borrow<str> + Hash + Eq,
+    V: AsRef<str>,
+    S: BuildHasher,
+{
+    fn lookup(&self, name: &str) -> Option<&str> {
+        self.get(name).map(AsRef::as_ref)
+    }
+}
+
+impl<K, V> Lookup for BTreeMap<K, V>
+where
+    K: Borrow<str> + Ord,
+    V: AsRef<str>,
+{
+    fn lookup(&self, name: &str) -> Option<&str> {
+        self.get(name).map(AsRef::as_ref)
+    }
+}
+
+/// Treatment of placeholders of variables that do not exist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unknown {
+    /// Leave the placeholder as is.
+    Keep,
+    /// Remove the placeholder.
+    Remove,
+}
+
+impl Default for Unknown {
+    fn default() -> Self {
+        Self::Keep
+    }
+}
+
+/// An iterator adapter that substitutes variables.
+///
+/// Names of variables that do not exist are recorded, and may be obtained with
+/// [`Variables::missing`], e.g. in order to report an error after rendering.
+pub struct Variables<'v, 's, I: Iterator<Item = Event<'s>>> {
+    events: Peekable<I>,
+    variables: &'v dyn Lookup,
+    unknown: Unknown,
+    missing: Vec<String>,
+    /// Nesting depth of containers whose content is not substituted.
+    skip: usize,
+    /// The previous event was an escape.
+    escaped: bool,
+}
+
+impl<'v, 's, I> Variables<'v, 's, I>
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    /// Create an adapter that substitutes the provided variables.
+    pub fn new<L: Lookup>(events: I, variables: &'v L) -> Self {
+        Self {
+            events: events.peekable(),
+            variables,
+            unknown: Unknown::default(),
+            missing: Vec::new(),
+            skip: 0,
+            escaped: false,
+        }
+    }
+
+    /// Treat placeholders of variables that do not exist as specified, instead of keeping them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use jotdown::variables::*;
+    /// # use jotdown::Event;
+    /// let vars: HashMap<String, String> = HashMap::new();
+    /// let mut events =
+    ///     Variables::new(jotdown::Parser::new("a{{b}}c"), &vars).with_unknown(Unknown::Remove);
+    /// assert_eq!(events.nth(1), Some(Event::Str("ac".into())));
+    /// assert_eq!(events.missing(), &["b"]);
+    /// ```
+    #[must_use]
+    pub fn with_unknown(mut self, unknown: Unknown) -> Self {
+        self.unknown = unknown;
+        self
+    }
+
+    /// Names of variables that have been referred to but do not exist, in order of first
+    /// occurrence.
+    #[must_use]
+    pub fn missing(&self) -> &[String] {
+        &self.missing
+    }
+
+    /// Replace placeholders in text, optionally ignoring a placeholder at the start.
+    fn substitute(&mut self, s: CowStr<'s>, escaped: bool) -> CowStr<'s> {
+        let mut out = String::new();
+        // end of the part of the text that has been written to the output
+        let mut last = 0;
+        let mut pos = usize::from(escaped);
+        while let Some(i) = s[pos..].find("{{").map(|i| pos + i) {
+            let len = if let Some(len) = s[i + 2..].find("}}") {
+                len
+            } else {
+                break;
+            };
+            let name = s[i + 2..i + 2 + len].trim();
+            let end = i + len + 4;
+            if !is_name(name) {
+                pos = i + 1;
+                continue;
+            }
+            let value = self.variables.lookup(name);
+            if value.is_none() && !self.missing.iter().any(|m| m == name) {
+                self.missing.push(name.to_string());
+            }
+            if let Some(v) = value.or(match self.unknown {
+                Unknown::Keep => None,
+                Unknown::Remove => Some(""),
+            }) {
+                out.push_str(&s[last..i]);
+                out.push_str(v);
+                last = end;
+            }
+            pos = end;
+        }
+        if last == 0 {
+            s
+        } else {
+            out.push_str(&s[last..]);
+            out.into()
+        }
+    }
+}
+
+impl<'v, 's, I> Iterator for Variables<'v, 's, I>
+where
+    I: Iterator<Item = Event<'s>>,
+{
+    type Item = Event<'s>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let e = self.events.next()?;
+        let escaped = std::mem::replace(&mut self.escaped, matches!(e, Event::Escape));
+        match e {
+            Event::Start(c, attrs) => {
+                if is_skipped(&c) {
+                    self.skip += 1;
+                }
+                Some(Event::Start(c, attrs))
+            }
+            Event::End(c) => {
+                if is_skipped(&c) {
+                    self.skip -= 1;
+                }
+                Some(Event::End(c))
+            }
+            Event::Str(mut s) if self.skip == 0 => {
+                // placeholders may span multiple text events
+                while let Some(Event::Str(t)) = self.events.peek() {
+                    s = format!("{}{}", s, t).into();
+                    self.events.next();
+                }
+                Some(Event::Str(self.substitute(s, escaped)))
+            }
+            e => Some(e),
+        }
+    }
+}
+
+fn is_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// Containers whose content is not substituted.
+fn is_skipped(c: &Container) -> bool {
+    matches!(
+        c,
+        Container::CodeBlock { .. }
+            | Container::CustomBlock { .. }
+            | Container::RawBlock { .. }
+            | Container::RawInline { .. }
+            | Container::Verbatim
+            | Container::Math { .. }
+            | Container::LinkDefinition { .. }
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::Unknown;
+    use super::Variables;
+    use crate::Event;
+
+    fn text(src: &str, unknown: Unknown) -> String {
+        let mut vars = std::collections::BTreeMap::new();
+        vars.insert("a", "x");
+        vars.insert("b.c", "y");
+        Variables::new(crate::Parser::new(src), &vars)
+            .with_unknown(unknown)
+            .filter_map(|e| match e {
+                Event::Str(s) => Some(s.to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn substitute() {
+        assert_eq!(text("{{a}}{{ b.c }}", Unknown::Keep), "xy");
+        assert_eq!(text("{{a}", Unknown::Keep), "{{a}");
+        assert_eq!(text("{{{a}}}", Unknown::Keep), "{x}");
+        assert_eq!(text("{{a b}} {{d}}", Unknown::Keep), "{{a b}} {{d}}");
+        assert_eq!(text("{{a b}} {{d}}", Unknown::Remove), "{{a b}} ");
+    }
+
+    #[test]
+    fn escaped() {
+        assert_eq!(text("\\{{a}} {{a}}", Unknown::Keep), "{{a}} x");
+    }
+
+    #[test]
+    fn skipped() {
+        assert_eq!(
+            text("``` =html\n{{a}}\n```\n\n$`{{a}}`", Unknown::Keep),
+            "{{a}}{{a}}"
+        );
+    }
+}
@@ -0,0 +1,151 @@
+//! Wiki links.
+//!
+//! Wiki links refer to other pages by their name, e.g. `[[Page Name]]`, optionally followed by
+//! the text of the link, e.g. `[[Page Name|the page]]`. They are recognized by the syntax
+//! extension [`Extensions::with_wiki_links`], which emits them as custom inline containers named
+//! `wikilink`.
+//!
+//! The [`WikiLinks`] adapter turns them into links, with destinations provided by a resolver
+//! that maps page names to URLs. The content of each link is its text, or the page name if it
+//! has no text. Links to pages that are not resolved, e.g. missing pages, are left as custom
+//! containers with the same content, so that they are rendered as e.g.
+//! `<span class="wikilink">Page Name</span>`.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! use jotdown::extension::Extensions;
+//! use jotdown::wiki::WikiLinks;
+//!
+//! let extensions = Extensions::new().with_wiki_links();
+//! let src = "See [[Getting Started]], [[Zettelkasten|the method]] and [[Missing]].";
+//! let events = jotdown::Parser::with_extensions(src, &extensions);
+//! let events = WikiLinks::new(events, |page| {
+//!     (page != "Missing").then(|| format!("/{}.html", page.replace(' ', "-")))
+//! });
+//! assert_eq!(
+//!     jotdown::html::render_to_string(events),
+//!     concat!(
+//!         "<p>See <a href=\"/Getting-Started.html\">Getting Started</a>, ",
+//!         "<a href=\"/Zettelkasten.html\">the method</a> and ",
+//!         "<span class=\"wikilink\">Missing</span>.</p>\n",
+//!     ),
+//! );
+//! # }
+//! ```
+//!
+//! [`Extensions::with_wiki_links`]: crate::extension::Extensions::with_wiki_links
+
+use std::collections::VecDeque;
+
+use crate::Container;
+use crate::Event;
+use crate::LinkType;
+use crate::SpanLinkType;
+
+/// An iterator adapter that turns wiki links into links.
+///
+/// The resolver is given the name of the page, with surrounding whitespace removed, and returns
+/// the destination of the link, if any.
+pub struct WikiLinks<'s, I, F> {
+    events: I,
+    resolve: F,
+    /// Events to emit before the next event.
+    pending: VecDeque<Event<'s>>,
+}
+
+impl<'s, I, F> WikiLinks<'s, I, F>
+where
+    I: Iterator<Item = Event<'s>>,
+    F: FnMut(&str) -> Option<String>,
+{
+    /// Create an adapter that resolves wiki links with the provided function.
+    pub fn new(events: I, resolve: F) -> Self {
+        Self {
+            events,
+            resolve,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<'s, I, F> Iterator for WikiLinks<'s, I, F>
+where
+    I: Iterator<Item = Event<'s>>,
+    F: FnMut(&str) -> Option<String>,
+{
+    type Item = Event<'s>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.pending.pop_front() {
+            return Some(e);
+        }
+        let attrs = match self.events.next()? {
+            Event::Start(Container::CustomInline { name: "wikilink" }, attrs) => attrs,
+            e => return Some(e),
+        };
+        let container = Container::CustomInline { name: "wikilink" };
+        let content = match self.events.next() {
+            Some(Event::Str(s)) => s,
+            e => {
+                self.pending.extend(e);
+                return Some(Event::Start(container, attrs));
+            }
+        };
+        let (page, text) = match content.split_once('|') {
+            Some((page, text)) if !text.trim().is_empty() => (page.trim(), text.trim()),
+            Some((page, _)) => (page.trim(), page.trim()),
+            None => (content.trim(), content.trim()),
+        };
+        self.pending.push_back(Event::Str(text.to_string().into()));
+        match (self.resolve)(page) {
+            Some(dst) => {
+                // the end of the custom container
+                self.events.next();
+                let link = Container::Link(dst.into(), LinkType::Span(SpanLinkType::Inline));
+                self.pending.push_back(Event::End(link.clone()));
+                Some(Event::Start(link, attrs))
+            }
+            None => Some(Event::Start(container, attrs)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::WikiLinks;
+    use crate::extension::Extensions;
+    use crate::Container;
+    use crate::Event;
+
+    #[test]
+    fn links() {
+        let extensions = Extensions::new().with_wiki_links();
+        let src = "[[ a | b ]] [[c|]] [[]] [[d]] \\[[f]] [g](h)";
+        let mut pages = Vec::new();
+        let events: Vec<String> = WikiLinks::new(
+            crate::Parser::with_extensions(src, &extensions),
+            |page: &str| {
+                pages.push(page.to_string());
+                (page != "d").then(|| page.to_uppercase())
+            },
+        )
+        .filter_map(|e| match e {
+            Event::Start(Container::Link(dst, _), _) => Some(format!("<{}>", dst)),
+            Event::Start(Container::CustomInline { .. }, _) => Some("<?>".to_string()),
+            Event::End(Container::Link(..) | Container::CustomInline { .. }) => {
+                Some("</>".to_string())
+            }
+            Event::Str(s) => Some(s.to_string()),
+            _ => None,
+        })
+        .collect();
+        assert_eq!(
+            events.concat(),
+            "<A>b</> <C>c</> [[]] <?>d</> [[f]] <h>g</>",
+        );
+        assert_eq!(pages, &["a", "c", "d"]);
+    }
+}
@@ -0,0 +1,364 @@
+//! Numbered cross-references.
+//!
+//! Sections, figures, tables and equations are numbered in order of appearance. A link to one
+//! of them without any link text, e.g. `[](#intro)`, is given a text such as "Section 2.1" or
+//! "Figure 3".
+//!
+//! - Sections are numbered hierarchically by their headings, relative to the highest heading
+//!   level of the document.
+//! - Figures are divs with the `figure` class, e.g. `::: figure`.
+//! - Tables are all tables of the document.
+//! - Equations are display math with an id, e.g. `$$`E = mc^2`{#energy}`.
+//!
+//! As links may refer to elements that appear later in the document, all events are collected
+//! before any of them are emitted by the [`CrossReferences`] adapter.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! use jotdown::xref::CrossReferences;
+//!
+//! let src = "# Intro\n\nSee [](#Details).\n\n## Details\n";
+//! let events = CrossReferences::new(jotdown::Parser::new(src));
+//! assert_eq!(
+//!     jotdown::html::render_to_string(events),
+//!     concat!(
+//!         "<section id=\"Intro\">\n",
+//!         "<h1>Intro</h1>\n",
+//!         "<p>See <a href=\"#Details\">Section 1.1</a>.</p>\n",
+//!         "<section id=\"Details\">\n",
+//!         "<h2>Details</h2>\n",
+//!         "</section>\n",
+//!         "</section>\n",
+//!     ),
+//! );
+//! # }
+//! ```
+
+use crate::outline::has_class;
+use crate::outline::Counters;
+use crate::outline::Numbering;
+use crate::Attributes;
+use crate::Container;
+use crate::Event;
+use crate::LinkType;
+use crate::Map;
+use crate::SpanLinkType;
+
+/// A kind of element that may be referenced.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Kind {
+    /// A section, started by a heading.
+    Section,
+    /// A div with the `figure` class.
+    Figure,
+    /// A table.
+    Table,
+    /// Display math with an id.
+    Equation,
+}
+
+impl Kind {
+    /// Default name used in the text of a reference, e.g. "Figure".
+    pub(crate) fn default_name(self) -> &'static str {
+        match self {
+            Self::Section => "Section",
+            Self::Figure => "Figure",
+            Self::Table => "Table",
+            Self::Equation => "Equation",
+        }
+    }
+}
+
+/// An iterator adapter that fills in the text of empty links to numbered elements.
+pub struct CrossReferences<'s> {
+    events: std::vec::IntoIter<Event<'s>>,
+    /// Kind and number of each element, by id.
+    targets: Map<String, (Kind, String)>,
+    /// Names of kinds that differ from the default.
+    names: Vec<(Kind, String)>,
+    /// Number equations in parentheses, e.g. "(3)".
+    equation_numbers: bool,
+    /// Events to emit before the next event, in reverse order.
+    pending: Vec<Event<'s>>,
+    /// Number of the current equation, if it is displayed.
+    equation: Option<String>,
+}
+
+impl<'s> CrossReferences<'s> {
+    /// Collect all events and number all referenceable elements.
+    pub fn new<I: IntoIterator<Item = Event<'s>>>(events: I) -> Self {
+        let events: Vec<Event<'s>> = events.into_iter().collect();
+        let targets = number(&events);
+        Self {
+            events: events.into_iter(),
+            targets,
+            names: Vec::new(),
+            equation_numbers: false,
+            pending: Vec::new(),
+            equation: None,
+        }
+    }
+
+    /// Use a different name for a kind of element, e.g. "Fig." instead of "Figure".
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use jotdown::xref::*;
+    /// # use jotdown::*;
+    /// let src = "{#a}\n::: figure\n![img](a.png)\n:::\n\n[](#a)";
+    /// let events = CrossReferences::new(Parser::new(src)).with_name(Kind::Figure, "Fig.");
+    /// assert!(events
+    ///     .collect::<Vec<_>>()
+    ///     .contains(&Event::Str("Fig. 1".into())));
+    /// ```
+    #[must_use]
+    pub fn with_name(mut self, kind: Kind, name: &str) -> Self {
+        self.names.retain(|(k, _)| *k != kind);
+        self.names.push((kind, name.to_string()));
+        self
+    }
+
+    /// Display the number of each equation with an id after it, in a span with the class
+    /// `equation-number`, and refer to equations by their number alone, e.g. "(3)".
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "html")]
+    /// # {
+    /// # use jotdown::xref::*;
+    /// let src = "$$`a`{#x}\n\nBy [](#x).";
+    /// let events = CrossReferences::new(jotdown::Parser::new(src)).with_equation_numbers();
+    /// assert_eq!(
+    ///     jotdown::html::render_to_string(events),
+    ///     concat!(
+    ///         r#"<p><span id="x" class="math display">\[a\]</span>"#,
+    ///         r#"<span class="equation-number">(1)</span></p>"#,
+    ///         "\n",
+    ///         r##"<p>By <a href="#x">(1)</a>.</p>"##,
+    ///         "\n",
+    ///     ),
+    /// );
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_equation_numbers(mut self) -> Self {
+        self.equation_numbers = true;
+        self
+    }
+
+    /// Text of a reference to an element, e.g. "Figure 3".
+    fn text(&self, kind: Kind, number: &str) -> String {
+        if kind == Kind::Equation && self.equation_numbers {
+            return format!("({})", number);
+        }
+        let name = self
+            .names
+            .iter()
+            .find(|(k, _)| *k == kind)
+            .map_or(kind.default_name(), |(_, name)| name.as_str());
+        format!("{} {}", name, number)
+    }
+}
+
+impl<'s> Iterator for CrossReferences<'s> {
+    type Item = Event<'s>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(event) = self.pending.pop() {
+            return Some(event);
+        }
+        let event = self.events.next()?;
+        match &event {
+            Event::Start(Container::Link(dst, LinkType::Span(SpanLinkType::Inline)), _) => {
+                let target = dst.strip_prefix('#').and_then(|id| self.targets.get(id));
+                if let (Some((kind, number)), Some(Event::End(Container::Link(..)))) =
+                    (target, self.events.as_slice().first())
+                {
+                    let text = self.text(*kind, number);
+                    self.pending.push(Event::Str(text.into()));
+                }
+            }
+            Event::Start(Container::Math { display: true }, attrs) if self.equation_numbers => {
+                self.equation = id(attrs).and_then(|id| match self.targets.get(&id) {
+                    Some((Kind::Equation, number)) => Some(number.clone()),
+                    _ => None,
+                });
+            }
+            Event::End(Container::Math { display: true }) => {
+                if let Some(number) = self.equation.take() {
+                    self.pending.extend([
+                        Event::End(Container::Span),
+                        Event::Str(format!("({})", number).into()),
+                        Event::Start(
+                            Container::Span,
+                            Attributes::try_from("{.equation-number}").expect("should be valid"),
+                        ),
+                    ]);
+                }
+            }
+            _ => {}
+        }
+        Some(event)
+    }
+}
+
+/// Determine the kind and number of each referenceable element.
+fn number(events: &[Event]) -> Map<String, (Kind, String)> {
+    let top = events
+        .iter()
+        .filter_map(|e| match e {
+            Event::Start(Container::Heading { level, .. }, _) => Some(*level),
+            _ => None,
+        })
+        .min()
+        .unwrap_or(1);
+
+    let numbering = Numbering::new();
+    let mut targets = Map::new();
+    let mut sections = Counters::default();
+    let mut figures = 0;
+    let mut tables = 0;
+    let mut equations = 0;
+    for e in events {
+        let (kind, id, number) = match e {
+            Event::Start(Container::Heading { level, id, .. }, _) => {
+                match numbering.format(sections.next(*level - top + 1)) {
+                    Some(number) => (Kind::Section, id.to_string(), number),
+                    None => continue,
+                }
+            }
+            Event::Start(c @ Container::Div { .. }, attrs) if has_class(c, attrs, "figure") => {
+                figures += 1;
+                match id(attrs) {
+                    Some(id) => (Kind::Figure, id, figures.to_string()),
+                    None => continue,
+                }
+            }
+            Event::Start(Container::Table, attrs) => {
+                tables += 1;
+                match id(attrs) {
+                    Some(id) => (Kind::Table, id, tables.to_string()),
+                    None => continue,
+                }
+            }
+            Event::Start(Container::Math { display: true }, attrs) => match id(attrs) {
+                Some(id) => {
+                    equations += 1;
+                    (Kind::Equation, id, equations.to_string())
+                }
+                None => continue,
+            },
+            _ => continue,
+        };
+        targets.entry(id).or_insert((kind, number));
+    }
+    targets
+}
+
+fn id(attrs: &Attributes) -> Option<String> {
+    attrs.get_value("id").map(|id| id.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::CrossReferences;
+    use crate::Event;
+
+    fn texts_numbered(src: &str) -> Vec<String> {
+        CrossReferences::new(crate::Parser::new(src))
+            .with_equation_numbers()
+            .filter_map(|e| match e {
+                Event::Str(s) => Some(s.to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn texts(src: &str) -> Vec<String> {
+        CrossReferences::new(crate::Parser::new(src))
+            .filter_map(|e| match e {
+                Event::Str(s) => Some(s.to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sections() {
+        assert_eq!(
+            texts(concat!(
+                "## A\n",
+                "\n",
+                "### B\n",
+                "\n",
+                "## C\n",
+                "\n",
+                "### D\n",
+                "\n",
+                "[](#A) [](#B) [](#D) [](#E)\n",
+            )),
+            &[
+                "A",
+                "B",
+                "C",
+                "D",
+                "Section 1",
+                " ",
+                "Section 1.1",
+                " ",
+                "Section 2.1",
+                " "
+            ]
+        );
+    }
+
+    #[test]
+    fn equation_numbers() {
+        assert_eq!(
+            texts_numbered("$$`x`{#a} $$`y` $$`z`{#b}\n\n[](#b) [](#a)"),
+            &["x", "(1)", " ", "y", " ", "z", "(2)", "(2)", " ", "(1)"]
+        );
+    }
+
+    #[test]
+    fn figures_tables_equations() {
+        assert_eq!(
+            texts(concat!(
+                "[](#f) [](#t) [](#eq) [text](#t)\n",
+                "\n",
+                "::: figure\n",
+                ":::\n",
+                "\n",
+                "{#f}\n",
+                "::: figure\n",
+                ":::\n",
+                "\n",
+                "|a|\n",
+                "\n",
+                "{#t}\n",
+                "|b|\n",
+                "\n",
+                "$$`x` $$`y`{#eq}\n",
+            )),
+            &[
+                "Figure 2",
+                " ",
+                "Table 2",
+                " ",
+                "Equation 1",
+                " ",
+                "text",
+                "a",
+                "b",
+                "x",
+                " ",
+                "y"
+            ]
+        );
+    }
+}